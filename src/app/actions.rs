@@ -3,9 +3,10 @@
 //! This module contains the core actions that modify application state,
 //! including query execution, schema loading, and other async operations.
 
-use crate::app::{App, ActivePanel, InputMode, SchemaNode, SchemaNodeType, ColumnCache};
+use crate::app::{App, ActivePanel, ConfirmPrompt, InputMode, SchemaNode, SchemaNodeType, ColumnCache};
+use crate::app::editor::{motions, operations};
 use crate::db::{DatabaseBackend, DatabaseDriver, ColumnDef};
-use crate::sql::format_sql_query;
+use crate::sql::{format_sql_query, statements::{self, WriteKind}};
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -305,6 +306,30 @@ impl App {
             return;
         }
 
+        // Connection safeguards: read-only blocks writes, destructive
+        // statements wait for the connection name to be typed
+        if let Some(conn) = &self.current_connection {
+            let writes = statements::write_statements(&query_text, conn.backend);
+            if conn.read_only && !writes.is_empty() {
+                let key = if writes.iter().any(|w| w.kind == WriteKind::Exec) { "read_only_exec_blocked" } else { "read_only_blocked" };
+                self.error = Some(t!(key, name = conn.name, verbs = statements::verbs(&writes).join(", ")).to_string());
+                return;
+            }
+            let destructive: Vec<String> = statements::verbs(writes.iter().filter(|w| w.is_destructive()))
+                .into_iter()
+                .map(str::to_string)
+                .collect();
+            if conn.confirms_destructive() && !destructive.is_empty() {
+                self.confirm_prompt = Some(ConfirmPrompt { query: query_text, verbs: destructive, typed: String::new() });
+                return;
+            }
+        }
+
+        self.execute_query_text(query_text);
+    }
+
+    /// Run `query_text` in the background (safeguards already checked)
+    pub fn execute_query_text(&mut self, query_text: String) {
         self.is_loading = true;
         self.error = None;
         self.message = None;
@@ -344,12 +369,12 @@ impl App {
             DatabaseBackend::Sqlite => {
                 let sqlite: &crate::db::sqlite::SqliteDriver =
                     unsafe { &*(db.as_ref() as *const dyn DatabaseDriver as *const crate::db::sqlite::SqliteDriver) };
-                let path = sqlite.path.clone();
+                let (path, read_only) = (sqlite.path.clone(), sqlite.read_only);
 
                 tokio::spawn(async move {
                     // Open a new connection for the background query
                     let result = async {
-                        let driver = crate::db::sqlite::SqliteDriver::new(path).await?;
+                        let driver = crate::db::sqlite::SqliteDriver::open(path, read_only).await?;
                         driver.execute_query(&query_text).await
                     }.await;
 
@@ -442,12 +467,12 @@ impl App {
             }
             *current_idx += 1;
 
-            if node.expanded {
-                if let Some(mut child_path) = Self::find_node_path(&node.children, target_idx, current_idx) {
-                    let mut path = vec![i];
-                    path.append(&mut child_path);
-                    return Some(path);
-                }
+            if node.expanded
+                && let Some(mut child_path) = Self::find_node_path(&node.children, target_idx, current_idx)
+            {
+                let mut path = vec![i];
+                path.append(&mut child_path);
+                return Some(path);
             }
         }
         None
@@ -490,9 +515,8 @@ impl App {
                 let name = node.name.clone();
                 let db = self.db.as_ref().unwrap();
                 if let Ok(definition) = db.get_procedure_definition(&schema, &name).await {
-                    self.save_undo_state();
-                    self.query = definition;
-                    self.cursor_pos = 0;
+                    // Opened in its own tab so the current query is kept
+                    self.open_in_buffer(definition);
                     self.active_panel = ActivePanel::QueryEditor;
                 }
             }
@@ -507,39 +531,58 @@ impl App {
             .map(|e| e.query.clone());
 
         if let Some(query) = entry_query {
-            self.save_undo_state();
-            self.query = query;
+            self.open_in_buffer(query);
             self.cursor_pos = self.query.chars().count();
             self.active_panel = ActivePanel::QueryEditor;
         }
     }
 
+    /// Remove all history entries
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+        self.history_selected = 0;
+        self.history_scroll_offset = 0;
+        self.message = Some(t!("history_cleared").to_string());
+    }
+
     /// Format SQL query with proper indentation and line breaks
     pub fn format_sql(&mut self) {
-        self.save_undo_state();
-        let formatted = format_sql_query(&self.query);
+        let formatted = format_sql_query(&self.query, self.sql_dialect(), &self.app_config.format);
         self.query = formatted;
         self.cursor_pos = self.query.chars().count();
         self.query_scroll_x = 0;
         self.query_scroll_y = 0;
     }
 
-    /// Delete selected text in visual mode
-    pub fn delete_selection(&mut self) {
-        let (start, end) = self.get_visual_selection();
-        let char_count = self.query.chars().count();
-        let end_inclusive = (end + 1).min(char_count);
-        let byte_start = Self::char_to_byte_index(&self.query, start);
-        let byte_end = Self::char_to_byte_index(&self.query, end_inclusive);
-        self.query.drain(byte_start..byte_end);
-        self.cursor_pos = start.min(self.query.chars().count().saturating_sub(1));
-        self.input_mode = InputMode::Normal;
-    }
+    /// Format the chars `start..end` (`=` and Visual `=`). Whitespace around
+    /// the SQL is kept, and the lines after the first are indented to where
+    /// it starts. Returns whether the text changed.
+    pub fn format_range(&mut self, start: usize, end: usize) -> bool {
+        let text: String = self.query.chars().skip(start).take(end - start).collect();
+        let sql = text.trim();
+        if sql.is_empty() {
+            return false;
+        }
+        let leading = &text[..text.len() - text.trim_start().len()];
+        let trailing = &text[text.trim_end().len()..];
+        let indent = match leading.rfind('\n') {
+            Some(newline) => leading[newline + 1..].to_string(),
+            None => " ".repeat(start - motions::line_start(&self.query, start) + leading.chars().count()),
+        };
 
-    /// Yank (copy) selected text to clipboard
-    pub fn yank_selection(&mut self) -> Option<String> {
-        let text = self.get_selected_text();
-        self.input_mode = InputMode::Normal;
-        Some(text)
+        let formatted = format_sql_query(sql, self.sql_dialect(), &self.app_config.format)
+            .lines()
+            .enumerate()
+            .map(|(i, line)| if i == 0 || line.is_empty() { line.to_string() } else { format!("{}{}", indent, line) })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let replacement = format!("{}{}{}", leading, formatted, trailing);
+        if replacement == text {
+            return false;
+        }
+        operations::delete_range(&mut self.query, start, end - 1);
+        operations::insert_text(&mut self.query, start, &replacement);
+        self.cursor_pos = motions::first_non_whitespace(&self.query, start);
+        true
     }
 }
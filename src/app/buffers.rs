@@ -0,0 +1,217 @@
+//! Editor buffers (tabs)
+//!
+//! The active buffer lives directly on `App` (`query`, `cursor_pos`, ...) so the
+//! editor code keeps working on plain fields. Background buffers are parked in
+//! `App::buffers` and swapped in when their tab is selected.
+
+use crate::app::editor::registers::Marks;
+use crate::app::{App, InputMode, UndoManager};
+use crate::db::QueryResult;
+use rust_i18n::t;
+use std::path::PathBuf;
+
+/// Maximum undo steps kept per buffer
+pub const UNDO_HISTORY_LIMIT: usize = 1000;
+
+/// Everything that belongs to one editor tab
+pub struct EditorBuffer {
+    pub query: String,
+    pub cursor_pos: usize,
+    pub query_scroll_x: usize,
+    pub query_scroll_y: usize,
+    pub undo_manager: UndoManager,
+    /// Last result produced by this buffer
+    pub result: QueryResult,
+    pub results_scroll: usize,
+    pub results_selected: usize,
+    pub results_col_selected: usize,
+    pub results_col_scroll: usize,
+    pub query_path: Option<PathBuf>,
+    pub saved_query: String,
+    pub marks: Marks,
+}
+
+impl Default for EditorBuffer {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            cursor_pos: 0,
+            query_scroll_x: 0,
+            query_scroll_y: 0,
+            undo_manager: UndoManager::new(UNDO_HISTORY_LIMIT),
+            result: QueryResult::empty(),
+            results_scroll: 0,
+            results_selected: 0,
+            results_col_selected: 0,
+            results_col_scroll: 0,
+            query_path: None,
+            saved_query: String::new(),
+            marks: Marks::new(),
+        }
+    }
+}
+
+impl EditorBuffer {
+    fn is_dirty(&self) -> bool {
+        self.query != self.saved_query
+    }
+}
+
+/// What the tab bar shows for one buffer
+pub struct BufferTab {
+    pub label: String,
+    pub dirty: bool,
+    pub active: bool,
+}
+
+impl App {
+    /// Number of open buffers
+    pub fn buffer_count(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Tabs for the tab bar, in order
+    pub fn buffer_tabs(&self) -> Vec<BufferTab> {
+        self.buffers
+            .iter()
+            .enumerate()
+            .map(|(i, buf)| {
+                let active = i == self.active_buffer;
+                let path = if active { self.query_path.as_ref() } else { buf.query_path.as_ref() };
+                let label = path
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| t!("buffer_unnamed").to_string());
+                BufferTab {
+                    label,
+                    dirty: if active { self.is_dirty() } else { buf.is_dirty() },
+                    active,
+                }
+            })
+            .collect()
+    }
+
+    /// Whether any buffer has unsaved changes
+    pub fn any_buffer_dirty(&self) -> bool {
+        self.is_dirty()
+            || self.buffers
+                .iter()
+                .enumerate()
+                .any(|(i, buf)| i != self.active_buffer && buf.is_dirty())
+    }
+
+    /// Open a new empty buffer after the current one and switch to it
+    pub fn new_buffer(&mut self) {
+        let index = self.active_buffer + 1;
+        self.buffers.insert(index, EditorBuffer::default());
+        self.switch_buffer(index);
+    }
+
+    /// Open `text` in a new buffer unless the current one is an empty scratch
+    /// buffer (where it can be undone)
+    pub fn open_in_buffer(&mut self, text: String) {
+        let new_tab = !self.query.trim().is_empty() || self.query_path.is_some();
+        if new_tab {
+            self.new_buffer();
+        }
+        self.query = text;
+        self.cursor_pos = 0;
+        self.query_scroll_x = 0;
+        self.query_scroll_y = 0;
+        if new_tab {
+            self.undo_manager.reset(&self.query);
+        }
+    }
+
+    /// Make buffer `index` the active one
+    pub fn switch_buffer(&mut self, index: usize) {
+        if index == self.active_buffer || index >= self.buffers.len() {
+            return;
+        }
+        let current = self.take_active_buffer();
+        self.buffers[self.active_buffer] = current;
+        let next = std::mem::take(&mut self.buffers[index]);
+        self.load_active_buffer(next);
+        self.active_buffer = index;
+    }
+
+    /// Go to the next tab (`gt`), wrapping around
+    pub fn next_buffer(&mut self) {
+        let count = self.buffers.len();
+        self.switch_buffer((self.active_buffer + 1) % count);
+    }
+
+    /// Go to the previous tab (`gT`), wrapping around
+    pub fn prev_buffer(&mut self) {
+        let count = self.buffers.len();
+        self.switch_buffer((self.active_buffer + count - 1) % count);
+    }
+
+    /// Close the active buffer. Refuses unsaved changes unless forced.
+    /// Returns false when this is the last buffer (nothing to switch to).
+    pub fn close_buffer(&mut self, force: bool) -> bool {
+        if self.buffers.len() <= 1 {
+            return false;
+        }
+        if self.is_dirty() && !force {
+            self.error = Some(t!("file_unsaved_changes").to_string());
+            return true;
+        }
+        self.remember_marks();
+        let closing = self.active_buffer;
+        let next = if closing + 1 < self.buffers.len() { closing + 1 } else { closing - 1 };
+        self.switch_buffer(next);
+        self.buffers.remove(closing);
+        if self.active_buffer > closing {
+            self.active_buffer -= 1;
+        }
+        true
+    }
+
+    /// Move the active buffer's state out of `App`, leaving empty fields
+    fn take_active_buffer(&mut self) -> EditorBuffer {
+        self.undo_manager.commit(&self.query);
+        EditorBuffer {
+            query: std::mem::take(&mut self.query),
+            cursor_pos: self.cursor_pos,
+            query_scroll_x: self.query_scroll_x,
+            query_scroll_y: self.query_scroll_y,
+            undo_manager: std::mem::replace(&mut self.undo_manager, UndoManager::new(UNDO_HISTORY_LIMIT)),
+            result: std::mem::replace(&mut self.result, QueryResult::empty()),
+            results_scroll: self.results_scroll,
+            results_selected: self.results_selected,
+            results_col_selected: self.results_col_selected,
+            results_col_scroll: self.results_col_scroll,
+            query_path: self.query_path.take(),
+            saved_query: std::mem::take(&mut self.saved_query),
+            marks: std::mem::take(&mut self.marks),
+        }
+    }
+
+    /// Install a parked buffer as the active one
+    fn load_active_buffer(&mut self, buf: EditorBuffer) {
+        self.query = buf.query;
+        self.cursor_pos = buf.cursor_pos;
+        self.query_scroll_x = buf.query_scroll_x;
+        self.query_scroll_y = buf.query_scroll_y;
+        self.undo_manager = buf.undo_manager;
+        self.result = buf.result;
+        self.results_scroll = buf.results_scroll;
+        self.results_selected = buf.results_selected;
+        self.results_col_selected = buf.results_col_selected;
+        self.results_col_scroll = buf.results_col_scroll;
+        self.query_path = buf.query_path;
+        self.saved_query = buf.saved_query;
+        self.marks = buf.marks;
+        self.completion.hide();
+        if self.input_mode == InputMode::Visual {
+            self.input_mode = InputMode::Normal;
+        }
+        self.pending_g = false;
+        self.pending_char_search = None;
+        self.pending_text_object = None;
+        self.normal_pending.reset();
+        self.insert_session = None;
+        self.block_insert = None;
+    }
+}
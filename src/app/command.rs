@@ -0,0 +1,531 @@
+//! Ex command line parsing (`:e`, `:w`, `:q`, ...)
+//!
+//! Parsing is kept separate from execution so it can be tested without an App.
+
+use crate::app::ExportFormat;
+use crate::app::options::OPTION_NAMES;
+use crate::app::registry::Action;
+use crate::config::LayoutMode;
+use crate::ui::Theme;
+use regex::RegexBuilder;
+use rust_i18n::t;
+
+/// Built-in command names offered by tab completion
+const BUILTIN_COMMANDS: &[&str] = &[
+    "edit", "write", "saveas", "quit", "wq", "qall", "recent", "set", "connect", "use", "export", "substitute",
+    "tabnew", "tabnext", "tabprevious", "tabclose", "layout", "nohlsearch", "colorscheme",
+];
+
+/// Maximum number of entries kept in the command history
+const MAX_COMMAND_HISTORY: usize = 100;
+
+/// A parsed `:` command
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExCommand {
+    /// `:e[dit][!] [path]` - open a file (reload current file without a path)
+    Edit { path: Option<String>, force: bool },
+    /// `:w[rite] [path]` - save the buffer, optionally to a new path
+    Write { path: Option<String> },
+    /// `:sav[eas] path` - save to a new path and switch the buffer to it
+    SaveAs { path: String },
+    /// `:q[uit][!]` - quit, `!` discards unsaved changes
+    Quit { force: bool },
+    /// `:wq` / `:x` - save and quit
+    WriteQuit,
+    /// `:qa[ll][!]` - quit with every buffer, `!` discards unsaved changes
+    QuitAll { force: bool },
+    /// `:tabnew [path]` - open a new buffer, optionally with a file
+    TabNew { path: Option<String> },
+    /// `:tabn[ext]` - go to the next buffer
+    TabNext,
+    /// `:tabp[revious]` - go to the previous buffer
+    TabPrev,
+    /// `:tabc[lose][!]` - close the current buffer
+    TabClose { force: bool },
+    /// `:recent [n]` - list recent files, or open the n-th one
+    Recent { index: Option<usize> },
+    /// `:se[t] [args...]` - change or show editor options
+    Set { args: Vec<String> },
+    /// `:conn[ect] [name]` - connect to a saved connection (modal without a name)
+    Connect { name: Option<String> },
+    /// `:use db` - switch database on the current connection
+    Use { database: String },
+    /// `:export <csv|json|table> [path]` - export the current results
+    Export { format: ExportFormat, path: Option<String> },
+    /// `:s/pat/rep/flags` or `:%s/pat/rep/flags`
+    Substitute(Substitute),
+    /// `:layout [auto|wide|stacked]` - show or change the panel arrangement
+    Layout { mode: Option<LayoutMode> },
+    /// `:noh[lsearch]` - hide search highlighting until the next search
+    NoHighlight,
+    /// `:colo[rscheme] [name]` - show or switch the colour theme
+    ColorScheme { name: Option<String> },
+    /// Any registered action by name (`:format`, `:execute`, ...)
+    Action(Action),
+}
+
+/// A `:s` substitution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Substitute {
+    /// `%` range: every line instead of only the cursor line
+    pub whole_buffer: bool,
+    /// Regular expression to search for
+    pub pattern: String,
+    /// Replacement text in vim syntax (`&`, `\1`)
+    pub replacement: String,
+    /// `g` flag: replace every match on a line, not just the first
+    pub global: bool,
+    /// `i` flag: ignore case
+    pub ignore_case: bool,
+}
+
+/// Parse a command line (without the leading `:`)
+pub fn parse_command(input: &str) -> Result<ExCommand, String> {
+    let input = input.trim();
+    if let Some(sub) = parse_substitute(input)? {
+        return Ok(ExCommand::Substitute(sub));
+    }
+
+    let (name, arg) = match input.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim()).filter(|a| !a.is_empty())),
+        None => (input, None),
+    };
+    let (name, force) = match name.strip_suffix('!') {
+        Some(name) => (name, true),
+        None => (name, false),
+    };
+    let arg = arg.map(str::to_string);
+    let required = |command: &str| t!("cmd_argument_required", command = command).to_string();
+
+    match name {
+        "e" | "edit" => Ok(ExCommand::Edit { path: arg, force }),
+        "w" | "write" => Ok(ExCommand::Write { path: arg }),
+        "sav" | "saveas" => arg
+            .map(|path| ExCommand::SaveAs { path })
+            .ok_or_else(|| required("saveas")),
+        "q" | "quit" => Ok(ExCommand::Quit { force }),
+        "wq" | "x" | "xit" => Ok(ExCommand::WriteQuit),
+        "qa" | "qall" | "quitall" => Ok(ExCommand::QuitAll { force }),
+        "tabnew" | "tabe" | "tabedit" => Ok(ExCommand::TabNew { path: arg }),
+        "tabn" | "tabnext" => Ok(ExCommand::TabNext),
+        "tabp" | "tabprevious" | "tabN" | "tabNext" => Ok(ExCommand::TabPrev),
+        "tabc" | "tabclose" => Ok(ExCommand::TabClose { force }),
+        "recent" => match arg {
+            None => Ok(ExCommand::Recent { index: None }),
+            Some(n) => n
+                .parse::<usize>()
+                .ok()
+                .filter(|&n| n > 0)
+                .map(|n| ExCommand::Recent { index: Some(n) })
+                .ok_or_else(|| t!("cmd_invalid_argument", command = "recent", arg = n).to_string()),
+        },
+        "se" | "set" => Ok(ExCommand::Set {
+            args: arg.map(|a| a.split_whitespace().map(str::to_string).collect()).unwrap_or_default(),
+        }),
+        "conn" | "connect" => Ok(ExCommand::Connect { name: arg }),
+        "use" => arg
+            .map(|database| ExCommand::Use { database })
+            .ok_or_else(|| required("use")),
+        "export" => {
+            let arg = arg.ok_or_else(|| required("export"))?;
+            let (format, path) = match arg.split_once(char::is_whitespace) {
+                Some((format, path)) => (format, Some(path.trim().to_string())),
+                None => (arg.as_str(), None),
+            };
+            let format = ExportFormat::parse(format)
+                .ok_or_else(|| t!("cmd_invalid_argument", command = "export", arg = format).to_string())?;
+            Ok(ExCommand::Export { format, path })
+        }
+        "noh" | "nohl" | "nohlsearch" => Ok(ExCommand::NoHighlight),
+        "colo" | "colorscheme" => Ok(ExCommand::ColorScheme { name: arg }),
+        "layout" => match arg {
+            None => Ok(ExCommand::Layout { mode: None }),
+            Some(mode) => LayoutMode::parse(&mode)
+                .map(|mode| ExCommand::Layout { mode: Some(mode) })
+                .ok_or_else(|| t!("cmd_invalid_argument", command = "layout", arg = mode).to_string()),
+        },
+        _ => Action::from_name(name)
+            .map(ExCommand::Action)
+            .ok_or_else(|| t!("cmd_unknown", command = input).to_string()),
+    }
+}
+
+/// Parse `s/pat/rep/flags` and `%s/pat/rep/flags`. Any non-alphanumeric
+/// character can be used as the delimiter. Returns None for other commands.
+fn parse_substitute(input: &str) -> Result<Option<Substitute>, String> {
+    let (whole_buffer, rest) = match input.strip_prefix('%') {
+        Some(rest) => (true, rest),
+        None => (false, input),
+    };
+    let Some(rest) = rest.strip_prefix("substitute").or_else(|| rest.strip_prefix('s')) else {
+        return Ok(None);
+    };
+    let mut chars = rest.chars();
+    let delim = match chars.next() {
+        Some(c) if !c.is_alphanumeric() && !c.is_whitespace() && c != '\\' => c,
+        _ => return Ok(None),
+    };
+
+    // Split on unescaped delimiters; "\<delim>" becomes a literal delimiter
+    let mut parts: Vec<String> = vec![String::new()];
+    let mut escaped = false;
+    for c in chars {
+        let splits = parts.len();
+        let part = parts.last_mut().expect("parts is never empty");
+        if escaped {
+            if c != delim {
+                part.push('\\');
+            }
+            part.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delim && splits < 3 {
+            parts.push(String::new());
+        } else {
+            part.push(c);
+        }
+    }
+    if escaped {
+        parts.last_mut().expect("parts is never empty").push('\\');
+    }
+
+    let pattern = parts[0].clone();
+    if pattern.is_empty() {
+        return Err(t!("cmd_argument_required", command = "substitute").to_string());
+    }
+    let replacement = parts.get(1).cloned().unwrap_or_default();
+    let flags = parts.get(2).map(|f| f.trim()).unwrap_or("");
+
+    let mut sub = Substitute { whole_buffer, pattern, replacement, global: false, ignore_case: false };
+    for flag in flags.chars() {
+        match flag {
+            'g' => sub.global = true,
+            'i' => sub.ignore_case = true,
+            'I' => sub.ignore_case = false,
+            _ => return Err(t!("cmd_invalid_argument", command = "substitute", arg = flag).to_string()),
+        }
+    }
+    Ok(Some(sub))
+}
+
+/// Convert a vim replacement (`&`, `\1`, `\n`) to `regex` syntax (`${0}`, `${1}`)
+fn vim_replacement(replacement: &str) -> String {
+    let mut out = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => out.push_str("${0}"),
+            '$' => out.push_str("$$"),
+            '\\' => match chars.next() {
+                Some(d) if d.is_ascii_digit() => out.push_str(&format!("${{{}}}", d)),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('$') => out.push_str("$$"),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Apply a substitution to `text`. `cursor_line` is the 0-based line used
+/// when the substitution is not over the whole buffer.
+/// Returns the new text, the number of replacements and the lines changed.
+pub fn substitute(text: &str, cursor_line: usize, sub: &Substitute) -> Result<(String, usize, usize), String> {
+    let re = RegexBuilder::new(&sub.pattern)
+        .case_insensitive(sub.ignore_case)
+        .build()
+        .map_err(|e| t!("cmd_invalid_pattern", error = e.to_string()).to_string())?;
+    let replacement = vim_replacement(&sub.replacement);
+
+    let mut count = 0;
+    let mut lines_changed = 0;
+    let lines: Vec<String> = text
+        .split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            if !sub.whole_buffer && i != cursor_line {
+                return line.to_string();
+            }
+            let matches = if sub.global { re.find_iter(line).count() } else { re.find(line).map_or(0, |_| 1) };
+            if matches == 0 {
+                return line.to_string();
+            }
+            count += matches;
+            lines_changed += 1;
+            let limit = if sub.global { 0 } else { 1 };
+            re.replacen(line, limit, replacement.as_str()).into_owned()
+        })
+        .collect();
+
+    if count == 0 {
+        return Err(t!("cmd_pattern_not_found", pattern = sub.pattern).to_string());
+    }
+    Ok((lines.join("\n"), count, lines_changed))
+}
+
+/// Command line history with prefix filtering (type `:s` then Up to
+/// recall only commands starting with `s`)
+#[derive(Clone, Debug, Default)]
+pub struct CommandHistory {
+    entries: Vec<String>,
+    /// Entry currently shown (None = editing a new line)
+    position: Option<usize>,
+    /// What was typed before browsing started
+    draft: String,
+}
+
+impl CommandHistory {
+    /// Record an executed command (moved to the end if already present)
+    pub fn push(&mut self, command: &str) {
+        let command = command.trim();
+        self.reset();
+        if command.is_empty() {
+            return;
+        }
+        self.entries.retain(|e| e != command);
+        self.entries.push(command.to_string());
+        if self.entries.len() > MAX_COMMAND_HISTORY {
+            self.entries.remove(0);
+        }
+    }
+
+    /// Older entry matching the typed prefix
+    pub fn prev(&mut self, current: &str) -> Option<String> {
+        if self.position.is_none() {
+            self.draft = current.to_string();
+        }
+        let end = self.position.unwrap_or(self.entries.len());
+        let found = (0..end).rev().find(|&i| self.entries[i].starts_with(&self.draft))?;
+        self.position = Some(found);
+        Some(self.entries[found].clone())
+    }
+
+    /// Newer entry matching the typed prefix, or the draft past the newest one
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Option<String> {
+        let start = self.position? + 1;
+        match (start..self.entries.len()).find(|&i| self.entries[i].starts_with(&self.draft)) {
+            Some(found) => {
+                self.position = Some(found);
+                Some(self.entries[found].clone())
+            }
+            None => {
+                self.position = None;
+                Some(self.draft.clone())
+            }
+        }
+    }
+
+    /// Stop browsing (called when the line is edited or closed)
+    pub fn reset(&mut self) {
+        self.position = None;
+        self.draft.clear();
+    }
+}
+
+/// Tab completion candidates for a command line, as complete lines.
+/// `connections` are the saved connection names.
+pub fn complete_command_line(input: &str, connections: &[String]) -> Vec<String> {
+    let Some((command, arg)) = input.split_once(' ') else {
+        // Complete the command name itself
+        let mut names: Vec<&str> = BUILTIN_COMMANDS
+            .iter()
+            .copied()
+            .chain(Action::ALL.iter().map(|a| a.name()))
+            .filter(|n| n.starts_with(input))
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        return names.into_iter().map(str::to_string).collect();
+    };
+
+    let with_prefix = |candidates: Vec<String>| -> Vec<String> {
+        candidates.into_iter().map(|c| format!("{} {}", command, c)).collect()
+    };
+    let matching = |words: &mut dyn Iterator<Item = &str>, prefix: &str| -> Vec<String> {
+        words.filter(|w| w.starts_with(prefix)).map(str::to_string).collect()
+    };
+
+    match command {
+        "e" | "edit" | "e!" | "edit!" | "w" | "write" | "sav" | "saveas" | "tabnew" | "tabe"
+        | "tabedit" => with_prefix(complete_path(arg)),
+        "se" | "set" => {
+            // Only the last word of `:set a b c` is completed
+            let (head, last) = match arg.rsplit_once(' ') {
+                Some((head, last)) => (format!("{} ", head), last),
+                None => (String::new(), arg),
+            };
+            let (no, name) = match last.strip_prefix("no") {
+                Some(name) => ("no", name),
+                None => ("", last),
+            };
+            matching(&mut OPTION_NAMES.iter().map(|(long, _)| *long), name)
+                .into_iter()
+                .map(|n| format!("{} {}{}{}", command, head, no, n))
+                .collect()
+        }
+        "conn" | "connect" => with_prefix(matching(&mut connections.iter().map(String::as_str), arg)),
+        "layout" => with_prefix(matching(&mut LayoutMode::ALL.iter().map(|m| m.name()), arg)),
+        "colo" | "colorscheme" => with_prefix(matching(&mut Theme::available().iter().map(String::as_str), arg)),
+        "export" => match arg.split_once(' ') {
+            Some((format, path)) => complete_path(path)
+                .into_iter()
+                .map(|p| format!("{} {} {}", command, format, p))
+                .collect(),
+            None => with_prefix(matching(&mut ["csv", "json", "table"].into_iter(), arg)),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// File names completing `prefix` (directories get a trailing `/`)
+fn complete_path(prefix: &str) -> Vec<String> {
+    let (dir, name) = match prefix.rfind('/') {
+        Some(i) => (&prefix[..=i], &prefix[i + 1..]),
+        None => ("", prefix),
+    };
+    let read_from = if dir.is_empty() {
+        std::path::PathBuf::from(".")
+    } else {
+        crate::app::files::expand_path(dir)
+    };
+    let Ok(entries) = std::fs::read_dir(read_from) else {
+        return Vec::new();
+    };
+
+    let mut candidates: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            if !file_name.starts_with(name) || (file_name.starts_with('.') && !name.starts_with('.')) {
+                return None;
+            }
+            let suffix = if entry.file_type().is_ok_and(|t| t.is_dir()) { "/" } else { "" };
+            Some(format!("{}{}{}", dir, file_name, suffix))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_commands() {
+        assert_eq!(parse_command("e report.sql"), Ok(ExCommand::Edit { path: Some("report.sql".into()), force: false }));
+        assert_eq!(parse_command("e!"), Ok(ExCommand::Edit { path: None, force: true }));
+        assert_eq!(parse_command("w"), Ok(ExCommand::Write { path: None }));
+        assert_eq!(parse_command("write  out dir/a b.sql "), Ok(ExCommand::Write { path: Some("out dir/a b.sql".into()) }));
+        assert_eq!(parse_command("saveas x.sql"), Ok(ExCommand::SaveAs { path: "x.sql".into() }));
+        assert!(parse_command("saveas").is_err());
+    }
+
+    #[test]
+    fn test_parse_quit_and_recent() {
+        assert_eq!(parse_command("q"), Ok(ExCommand::Quit { force: false }));
+        assert_eq!(parse_command("q!"), Ok(ExCommand::Quit { force: true }));
+        assert_eq!(parse_command("wq"), Ok(ExCommand::WriteQuit));
+        assert_eq!(parse_command("recent 2"), Ok(ExCommand::Recent { index: Some(2) }));
+        assert!(parse_command("recent 0").is_err());
+        assert!(parse_command("bogus").is_err());
+    }
+
+    #[test]
+    fn test_parse_tab_commands() {
+        assert_eq!(parse_command("tabnew"), Ok(ExCommand::TabNew { path: None }));
+        assert_eq!(parse_command("tabe a.sql"), Ok(ExCommand::TabNew { path: Some("a.sql".into()) }));
+        assert_eq!(parse_command("tabn"), Ok(ExCommand::TabNext));
+        assert_eq!(parse_command("tabN"), Ok(ExCommand::TabPrev));
+        assert_eq!(parse_command("tabclose!"), Ok(ExCommand::TabClose { force: true }));
+        assert_eq!(parse_command("qa"), Ok(ExCommand::QuitAll { force: false }));
+        assert_eq!(parse_command("qall!"), Ok(ExCommand::QuitAll { force: true }));
+        assert_eq!(parse_command("noh"), Ok(ExCommand::NoHighlight));
+    }
+
+    #[test]
+    fn test_parse_app_commands() {
+        assert_eq!(parse_command("set ts=2 nonumber"), Ok(ExCommand::Set { args: vec!["ts=2".into(), "nonumber".into()] }));
+        assert_eq!(parse_command("connect prod"), Ok(ExCommand::Connect { name: Some("prod".into()) }));
+        assert_eq!(parse_command("use Sales"), Ok(ExCommand::Use { database: "Sales".into() }));
+        assert_eq!(
+            parse_command("export csv out.csv"),
+            Ok(ExCommand::Export { format: ExportFormat::Csv, path: Some("out.csv".into()) })
+        );
+        assert!(parse_command("export xml").is_err());
+        assert_eq!(parse_command("format"), Ok(ExCommand::Action(Action::FormatQuery)));
+        assert_eq!(parse_command("layout stacked"), Ok(ExCommand::Layout { mode: Some(LayoutMode::Stacked) }));
+        assert!(parse_command("layout tall").is_err());
+        assert_eq!(parse_command("colo light"), Ok(ExCommand::ColorScheme { name: Some("light".into()) }));
+        assert_eq!(parse_command("colorscheme"), Ok(ExCommand::ColorScheme { name: None }));
+        assert_eq!(parse_command("zoom"), Ok(ExCommand::Action(Action::Zoom)));
+    }
+
+    #[test]
+    fn test_parse_substitute() {
+        let Ok(ExCommand::Substitute(sub)) = parse_command("%s/foo/bar/gi") else { panic!("expected :s") };
+        assert!(sub.whole_buffer && sub.global && sub.ignore_case);
+        assert_eq!((sub.pattern.as_str(), sub.replacement.as_str()), ("foo", "bar"));
+
+        let Ok(ExCommand::Substitute(sub)) = parse_command(r"s#a\#b#c") else { panic!("expected :s") };
+        assert!(!sub.whole_buffer && !sub.global);
+        assert_eq!((sub.pattern.as_str(), sub.replacement.as_str()), ("a#b", "c"));
+
+        // "set" and "saveas" are not substitutions
+        assert!(matches!(parse_command("set"), Ok(ExCommand::Set { .. })));
+        assert!(parse_command("s//x/").is_err());
+    }
+
+    #[test]
+    fn test_substitute() {
+        let text = "select a, a\nfrom t where a = 1";
+        let sub = |cmd: &str| match parse_command(cmd) {
+            Ok(ExCommand::Substitute(s)) => s,
+            other => panic!("unexpected {:?}", other),
+        };
+
+        let (out, count, lines) = substitute(text, 0, &sub("s/a/b/")).unwrap();
+        assert_eq!((out.as_str(), count, lines), ("select b, a\nfrom t where a = 1", 1, 1));
+
+        let (out, count, lines) = substitute(text, 0, &sub(r"%s/\ba\b/[&]/g")).unwrap();
+        assert_eq!((out.as_str(), count, lines), ("select [a], [a]\nfrom t where [a] = 1", 3, 2));
+
+        let (out, _, _) = substitute("Foo foo", 0, &sub(r"s/(f)oo/\1x/gi")).unwrap();
+        assert_eq!(out, "Fx fx");
+
+        assert!(substitute(text, 1, &sub("s/select/x/")).is_err());
+        assert!(substitute(text, 0, &sub("s/(/x/")).is_err());
+    }
+
+    #[test]
+    fn test_command_history() {
+        let mut history = CommandHistory::default();
+        history.push("w");
+        history.push("set ts=2");
+        history.push("e a.sql");
+
+        assert_eq!(history.prev("").as_deref(), Some("e a.sql"));
+        assert_eq!(history.prev("").as_deref(), Some("set ts=2"));
+        assert_eq!(history.next().as_deref(), Some("e a.sql"));
+        assert_eq!(history.next().as_deref(), Some(""));
+
+        // Prefix filtering
+        history.reset();
+        assert_eq!(history.prev("s").as_deref(), Some("set ts=2"));
+        assert_eq!(history.prev("s"), None);
+    }
+
+    #[test]
+    fn test_complete_command_line() {
+        assert_eq!(complete_command_line("expo", &[]), vec!["export", "export-csv", "export-json"]);
+        assert_eq!(complete_command_line("set nonu", &[]), vec!["set nonumber"]);
+        assert_eq!(complete_command_line("set ts=2 a", &[]), vec!["set ts=2 autoclose"]);
+        assert_eq!(complete_command_line("connect p", &["prod".into(), "dev".into()]), vec!["connect prod"]);
+        assert_eq!(complete_command_line("export c", &[]), vec!["export csv"]);
+    }
+}
@@ -0,0 +1,168 @@
+//! Lint diagnostics of the editor buffer (background linting, navigation),
+//! and the bracket / block pairs found alongside them
+
+use crate::app::editor::registers::line_col;
+use crate::app::{ActivePanel, App, SchemaNodeType};
+use crate::sql::lint::{self, Diagnostic, LintSchema};
+use crate::sql::pairs::Pairs;
+use rust_i18n::t;
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// Pause in typing before the buffer is linted again
+const LINT_DELAY: Duration = Duration::from_millis(300);
+
+impl App {
+    /// Collect a finished lint, and start one when the buffer (or the loaded
+    /// schema) changed and typing has paused
+    pub fn update_lint(&mut self) {
+        if let Some((text, rx)) = &mut self.pending_lint {
+            match rx.try_recv() {
+                Ok(diagnostics) => {
+                    // Positions are only valid for the text that was linted
+                    if *text == self.query {
+                        self.diagnostics = diagnostics;
+                    }
+                    self.pending_lint = None;
+                }
+                Err(oneshot::error::TryRecvError::Empty) => return,
+                Err(oneshot::error::TryRecvError::Closed) => self.pending_lint = None,
+            }
+        }
+
+        if self.query != self.lint_watch.0 {
+            self.lint_watch = (self.query.clone(), Instant::now());
+            return;
+        }
+        let Some(schema_size) = self.lint_schema_size() else {
+            return;
+        };
+        if self.lint_watch.1.elapsed() < LINT_DELAY || (self.query == self.linted.0 && schema_size == self.linted.1) {
+            return;
+        }
+
+        self.linted = (self.query.clone(), schema_size);
+        let text = self.query.clone();
+        let dialect = self.sql_dialect();
+        let schema = self.lint_schema();
+        let rules = self.app_config.lint.clone();
+        let (tx, rx) = oneshot::channel();
+        self.pending_lint = Some((text.clone(), rx));
+        tokio::task::spawn_blocking(move || {
+            let mut diagnostics = lint::lint(&text, dialect, &schema);
+            diagnostics.retain(|d| !rules.is_disabled(d.kind.rule()));
+            let _ = tx.send(diagnostics);
+        });
+    }
+
+    /// Find the pairs of the buffer again when it (or the dialect) changed,
+    /// once per pass of the run loop rather than per redraw or key
+    pub fn update_pairs(&mut self) {
+        let dialect = self.sql_dialect();
+        if self.query != self.pairs.0 || dialect != self.pairs.1 {
+            self.pairs = (self.query.clone(), dialect, Pairs::find(&self.query, dialect));
+        }
+    }
+
+    /// The pairs of the buffer: the cached ones when they're current, found
+    /// afresh when a key changed the buffer since the run loop's last pass
+    pub fn pairs(&self) -> Cow<'_, Pairs> {
+        let dialect = self.sql_dialect();
+        if self.query == self.pairs.0 && dialect == self.pairs.1 {
+            Cow::Borrowed(&self.pairs.2)
+        } else {
+            Cow::Owned(Pairs::find(&self.query, dialect))
+        }
+    }
+
+    /// Number of schema objects and cached column lists, to lint again as
+    /// they load (None while the column cache is being written)
+    fn lint_schema_size(&self) -> Option<usize> {
+        let columns = self.column_cache.try_read().ok()?.len();
+        let objects: usize = self
+            .schema_tree
+            .iter()
+            .flat_map(|folder| &folder.children)
+            .map(|schema| schema.children.len())
+            .sum();
+        Some(objects + columns)
+    }
+
+    /// Tables and views of the schema explorer, with the cached columns
+    fn lint_schema(&self) -> LintSchema {
+        let mut schema = LintSchema::default();
+        for root in self.schema_tree.iter().filter(|f| f.name == "Tables" || f.name == "Views") {
+            for folder in &root.children {
+                for object in &folder.children {
+                    if matches!(object.node_type, SchemaNodeType::Table | SchemaNodeType::View) {
+                        schema.add_object(&folder.name, &object.name);
+                    }
+                }
+            }
+        }
+        if let Ok(cache) = self.column_cache.try_read() {
+            for ((schema_name, table), columns) in cache.iter().filter(|(_, c)| !c.is_empty()) {
+                schema.set_columns(schema_name, table, columns.iter().map(|c| c.name.as_str()));
+            }
+        }
+        schema
+    }
+
+    /// Diagnostics starting on each line, as (line, diagnostic)
+    pub fn diagnostic_lines(&self) -> Vec<(usize, &Diagnostic)> {
+        let mut lines = Vec::with_capacity(self.diagnostics.len());
+        let (mut line, mut pos) = (0, 0);
+        let mut chars = self.query.chars();
+        for diagnostic in &self.diagnostics {
+            // Diagnostics are sorted, so one pass over the text is enough
+            while pos < diagnostic.start {
+                match chars.next() {
+                    Some('\n') => line += 1,
+                    Some(_) => {}
+                    None => break,
+                }
+                pos += 1;
+            }
+            lines.push((line, diagnostic));
+        }
+        lines
+    }
+
+    /// Move to the `count`th diagnostic after (or before) the cursor,
+    /// wrapping around the buffer, and show its message
+    pub fn jump_to_diagnostic(&mut self, forward: bool, count: usize) {
+        let total = self.diagnostics.len();
+        if total == 0 {
+            self.message = Some(t!("lint_no_diagnostics").to_string());
+            return;
+        }
+        let steps = (count.max(1) - 1) % total;
+        let index = if forward {
+            let next = self.diagnostics.iter().position(|d| d.start > self.cursor_pos).unwrap_or(0);
+            (next + steps) % total
+        } else {
+            let previous = self.diagnostics.iter().rposition(|d| d.start < self.cursor_pos).unwrap_or(total - 1);
+            (previous + total - steps) % total
+        };
+        self.show_diagnostic(index);
+    }
+
+    /// Put the cursor on diagnostic `index` and show its message
+    pub fn show_diagnostic(&mut self, index: usize) {
+        let Some(diagnostic) = self.diagnostics.get(index) else {
+            return;
+        };
+        self.cursor_pos = diagnostic.start;
+        self.active_panel = ActivePanel::QueryEditor;
+        let (line, col) = line_col(&self.query, diagnostic.start);
+        self.message = Some(t!(
+            "lint_position",
+            index = index + 1,
+            total = self.diagnostics.len(),
+            line = line + 1,
+            col = col + 1,
+            message = diagnostic.kind.message()
+        ).to_string());
+    }
+}
@@ -7,7 +7,12 @@
 
 pub mod motions;
 pub mod operations;
+pub mod normal;
+pub mod registers;
+pub mod search;
+pub mod sql_objects;
 pub mod text_objects;
+pub mod visual;
 
 pub use motions::*;
 pub use operations::*;
@@ -4,6 +4,8 @@
 //! This module contains functions for moving the cursor within text,
 //! implementing vim-style motions like w, b, e, 0, $, g, G, etc.
 
+use super::normal::Motion;
+
 /// Character class for vim word motions.
 /// In vim, a "word" is a sequence of word chars (alphanumeric + underscore),
 /// a sequence of punctuation (non-word, non-whitespace), or whitespace.
@@ -39,33 +41,33 @@ pub fn line_start(text: &str, cursor_pos: usize) -> usize {
 /// Find the end position (char index) of the current line (before newline)
 pub fn line_end(text: &str, cursor_pos: usize) -> usize {
     let chars: Vec<char> = text.chars().collect();
-    for i in cursor_pos..chars.len() {
-        if chars[i] == '\n' {
-            return i;
-        }
-    }
-    chars.len()
+    (cursor_pos..chars.len())
+        .find(|&i| chars[i] == '\n')
+        .unwrap_or(chars.len())
 }
 
 /// Find the first non-whitespace character on the current line
 pub fn first_non_whitespace(text: &str, cursor_pos: usize) -> usize {
     let start = line_start(text, cursor_pos);
     let chars: Vec<char> = text.chars().collect();
-    for i in start..chars.len() {
-        if !chars[i].is_whitespace() || chars[i] == '\n' {
-            return i;
-        }
-    }
-    start
+    (start..chars.len())
+        .find(|&i| !chars[i].is_whitespace() || chars[i] == '\n')
+        .unwrap_or(start)
 }
 
 /// Move forward by one word (w motion).
 /// Jumps to the start of the next word. Words are sequences of word chars
 /// (alphanumeric + underscore) or sequences of punctuation.
 pub fn word_forward(text: &str, cursor_pos: usize) -> usize {
+    word_forward_unclamped(text, cursor_pos).min(text.chars().count().saturating_sub(1))
+}
+
+/// Like `word_forward`, but may return the text length when there is no next
+/// word, so `dw` on the last word deletes up to the end
+fn word_forward_unclamped(text: &str, cursor_pos: usize) -> usize {
     let chars: Vec<char> = text.chars().collect();
-    if chars.is_empty() {
-        return 0;
+    if cursor_pos >= chars.len() {
+        return chars.len();
     }
     let mut pos = cursor_pos;
     let cls = char_class(chars[pos]);
@@ -81,7 +83,7 @@ pub fn word_forward(text: &str, cursor_pos: usize) -> usize {
         pos += 1;
     }
 
-    pos.min(chars.len().saturating_sub(1))
+    pos
 }
 
 /// Move backward by one word (b motion).
@@ -131,6 +133,65 @@ pub fn word_end(text: &str, cursor_pos: usize) -> usize {
     pos.min(chars.len().saturating_sub(1))
 }
 
+/// End of the word under the cursor, counting the cursor's own word (`cw` acts like `ce`
+/// but never skips past a word that ends at the cursor)
+pub fn current_word_end(text: &str, cursor_pos: usize) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    if cursor_pos >= chars.len() {
+        return cursor_pos;
+    }
+    let cls = char_class(chars[cursor_pos]);
+    let mut pos = cursor_pos;
+    while pos + 1 < chars.len() && char_class(chars[pos + 1]) == cls {
+        pos += 1;
+    }
+    pos
+}
+
+/// Move to the end of the previous word (ge motion).
+pub fn word_end_backward(text: &str, cursor_pos: usize) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() {
+        return 0;
+    }
+    let mut pos = cursor_pos.min(chars.len() - 1);
+
+    // Leave the current word/punct class
+    let cls = char_class(chars[pos]);
+    if cls != CharClass::Whitespace {
+        while pos > 0 && char_class(chars[pos]) == cls {
+            pos -= 1;
+        }
+    }
+    // Skip whitespace backwards
+    while pos > 0 && char_class(chars[pos]) == CharClass::Whitespace {
+        pos -= 1;
+    }
+
+    pos
+}
+
+/// Find the last non-whitespace character on the current line (g_ motion)
+pub fn last_non_whitespace(text: &str, cursor_pos: usize) -> usize {
+    let start = line_start(text, cursor_pos);
+    let chars: Vec<char> = text.chars().collect();
+    (start..line_end(text, cursor_pos))
+        .rev()
+        .find(|&i| !chars[i].is_whitespace())
+        .unwrap_or(start)
+}
+
+/// First non-whitespace character of a 1-based line number (clamped to the last line)
+pub fn line_number_start(text: &str, line: usize) -> usize {
+    let start = text
+        .split('\n')
+        .take(line.max(1) - 1)
+        .map(|l| l.chars().count() + 1)
+        .sum::<usize>()
+        .min(text.chars().count());
+    first_non_whitespace(text, line_start(text, start))
+}
+
 /// Move to start of document
 pub fn document_start() -> usize {
     0
@@ -180,24 +241,14 @@ pub fn cursor_down(text: &str, cursor_pos: usize) -> usize {
 pub fn find_char_forward(text: &str, cursor_pos: usize, target: char) -> Option<usize> {
     let chars: Vec<char> = text.chars().collect();
     let end = line_end(text, cursor_pos);
-    for i in (cursor_pos + 1)..end {
-        if chars[i] == target {
-            return Some(i);
-        }
-    }
-    None
+    ((cursor_pos + 1)..end).find(|&i| chars[i] == target)
 }
 
 /// Find the previous occurrence of a character on the current line (F motion)
 pub fn find_char_backward(text: &str, cursor_pos: usize, target: char) -> Option<usize> {
     let chars: Vec<char> = text.chars().collect();
     let start = line_start(text, cursor_pos);
-    for i in (start..cursor_pos).rev() {
-        if chars[i] == target {
-            return Some(i);
-        }
-    }
-    None
+    (start..cursor_pos).rev().find(|&i| chars[i] == target)
 }
 
 /// Move to just before the next occurrence of a character (t motion)
@@ -210,6 +261,58 @@ pub fn till_char_backward(text: &str, cursor_pos: usize, target: char) -> Option
     find_char_backward(text, cursor_pos, target).map(|pos| pos + 1)
 }
 
+/// Where a motion repeated `count` times lands, or None when it can't move
+/// (`f` without a match). `;` and `,` must be resolved to a `FindChar` by the
+/// caller, which knows the last search; marks, searches and `%` are
+/// resolved by the caller too.
+/// Left/right stay on the current line
+/// and may land on the line end, which is what operators need.
+pub fn motion_target(text: &str, cursor_pos: usize, motion: Motion, count: Option<usize>) -> Option<usize> {
+    let n = count.unwrap_or(1).max(1);
+    let repeat = |step: fn(&str, usize) -> usize| (0..n).fold(cursor_pos, |pos, _| step(text, pos));
+    let down = |lines: usize| (0..lines).fold(cursor_pos, |pos, _| cursor_down(text, pos));
+
+    let target = match motion {
+        Motion::Left => cursor_pos.saturating_sub(n).max(line_start(text, cursor_pos)),
+        Motion::Right => (cursor_pos + n).min(line_end(text, cursor_pos)),
+        Motion::Up => repeat(cursor_up),
+        Motion::Down => repeat(cursor_down),
+        Motion::LineStart => line_start(text, cursor_pos),
+        Motion::FirstNonBlank => first_non_whitespace(text, cursor_pos),
+        Motion::LineEnd => line_end(text, down(n - 1)),
+        Motion::LastNonBlank => last_non_whitespace(text, down(n - 1)),
+        Motion::WordForward => repeat(word_forward_unclamped),
+        Motion::WordBackward => repeat(word_backward),
+        Motion::WordEnd => repeat(word_end),
+        Motion::WordEndBackward => repeat(word_end_backward),
+        Motion::DocumentStart => count.map_or(document_start(), |line| line_number_start(text, line)),
+        Motion::DocumentEnd => count.map_or(document_end(text), |line| line_number_start(text, line)),
+        Motion::FindChar { ch, forward, till } => {
+            // Find the n-th match, then step back for t/T
+            let mut pos = cursor_pos;
+            for _ in 0..n {
+                pos = if forward {
+                    find_char_forward(text, pos, ch)?
+                } else {
+                    find_char_backward(text, pos, ch)?
+                };
+            }
+            match (till, forward) {
+                (false, _) => pos,
+                (true, true) => pos - 1,
+                (true, false) => pos + 1,
+            }
+        }
+        Motion::RepeatFind
+        | Motion::RepeatFindReverse
+        | Motion::Mark { .. }
+        | Motion::SearchNext { .. }
+        | Motion::SearchWord { .. }
+        | Motion::MatchPair => return None,
+    };
+    Some(target)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -292,6 +395,40 @@ mod tests {
         assert_eq!(word_backward(text, 14), 9);
     }
 
+    #[test]
+    fn test_word_end_backward() {
+        let text = "SELECT * FROM pmt.Contas";
+        // C(18) -> ge -> .(17)
+        assert_eq!(word_end_backward(text, 18), 17);
+        // F(9) -> ge -> *(7)
+        assert_eq!(word_end_backward(text, 9), 7);
+        // *(7) -> ge -> T(5)
+        assert_eq!(word_end_backward(text, 7), 5);
+    }
+
+    #[test]
+    fn test_motion_target_with_counts() {
+        let text = "one two three\n  four five";
+        assert_eq!(motion_target(text, 0, Motion::WordForward, Some(2)), Some(8));
+        // Past the last word, w lands on the text end (for dw)
+        assert_eq!(motion_target(text, 21, Motion::WordForward, None), Some(25));
+        assert_eq!(motion_target(text, 0, Motion::Right, Some(50)), Some(13));
+        assert_eq!(motion_target(text, 4, Motion::Down, None), Some(18));
+        assert_eq!(motion_target(text, 0, Motion::LineEnd, Some(2)), Some(25));
+        assert_eq!(motion_target(text, 20, Motion::DocumentStart, Some(1)), Some(0));
+        assert_eq!(motion_target(text, 0, Motion::DocumentEnd, Some(2)), Some(16));
+        assert_eq!(
+            motion_target(text, 0, Motion::FindChar { ch: 'e', forward: true, till: false }, Some(2)),
+            Some(11)
+        );
+        assert_eq!(
+            motion_target(text, 0, Motion::FindChar { ch: 't', forward: true, till: true }, None),
+            Some(3)
+        );
+        assert_eq!(motion_target(text, 0, Motion::FindChar { ch: 'z', forward: true, till: false }, None), None);
+        assert_eq!(motion_target(text, 5, Motion::LastNonBlank, None), Some(12));
+    }
+
     #[test]
     fn test_line_start_with_utf8() {
         let text = "café\nwörld";
@@ -0,0 +1,638 @@
+//! Normal mode command grammar
+//!
+//! Keys are fed one at a time into a `PendingCommand`, which understands
+//! `[count]operator[count]motion`, doubled operators (`dd`, `>>`), text
+//! objects after an operator (`ci(`), a `"x` register prefix and the
+//! single-key commands. Parsing is
+//! kept free of `App` so the grammar can be tested on its own; executing the
+//! parsed commands lives in the query editor handlers.
+
+use super::registers::Registers;
+use super::visual::VisualKind;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Largest count accepted, so `99999999dd` can't stall the editor
+const MAX_COUNT: usize = 9999;
+
+/// Operators that act on a motion, text object or whole lines
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+    Indent,
+    Outdent,
+    /// `=`: run the SQL formatter
+    Format,
+}
+
+impl Operator {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'd' => Some(Operator::Delete),
+            'c' => Some(Operator::Change),
+            'y' => Some(Operator::Yank),
+            '>' => Some(Operator::Indent),
+            '<' => Some(Operator::Outdent),
+            '=' => Some(Operator::Format),
+            _ => None,
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            Operator::Delete => 'd',
+            Operator::Change => 'c',
+            Operator::Yank => 'y',
+            Operator::Indent => '>',
+            Operator::Outdent => '<',
+            Operator::Format => '=',
+        }
+    }
+}
+
+/// Cursor motions
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Motion {
+    Left,
+    Right,
+    Up,
+    Down,
+    /// `0`
+    LineStart,
+    /// `^`
+    FirstNonBlank,
+    /// `$`
+    LineEnd,
+    /// `g_`
+    LastNonBlank,
+    /// `w`
+    WordForward,
+    /// `b`
+    WordBackward,
+    /// `e`
+    WordEnd,
+    /// `ge`
+    WordEndBackward,
+    /// `gg` (or `[count]gg` for a line number)
+    DocumentStart,
+    /// `G` (or `[count]G` for a line number)
+    DocumentEnd,
+    /// `f` `F` `t` `T`
+    FindChar { ch: char, forward: bool, till: bool },
+    /// `;`
+    RepeatFind,
+    /// `,`
+    RepeatFindReverse,
+    /// `` `a `` (exact position) / `'a` (line)
+    Mark { name: char, linewise: bool },
+    /// `n` / `N` (`reverse`): next match of the last search
+    SearchNext { reverse: bool },
+    /// `*` / `#`: search the word under the cursor
+    SearchWord { forward: bool },
+    /// `%`: the partner of the bracket, `BEGIN`, `CASE` or `END` (`[count]%`
+    /// goes to that percentage of the buffer)
+    MatchPair,
+}
+
+/// How an operator treats the text between the cursor and a motion's target
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MotionKind {
+    /// The target character is not included (`w`, `b`)
+    Exclusive,
+    /// The target character is included (`e`, `f`)
+    Inclusive,
+    /// Whole lines are affected (`j`, `G`)
+    Linewise,
+}
+
+impl Motion {
+    pub fn kind(self) -> MotionKind {
+        match self {
+            Motion::Up | Motion::Down | Motion::DocumentStart | Motion::DocumentEnd => MotionKind::Linewise,
+            Motion::Mark { linewise: true, .. } => MotionKind::Linewise,
+            Motion::WordEnd | Motion::WordEndBackward | Motion::LastNonBlank | Motion::MatchPair => MotionKind::Inclusive,
+            Motion::FindChar { forward: true, .. } => MotionKind::Inclusive,
+            _ => MotionKind::Exclusive,
+        }
+    }
+}
+
+/// What an operator applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Motion(Motion),
+    /// `iw`, `a(`, `i"`, ... (`around` = the `a` form)
+    TextObject { around: bool, object: char },
+    /// Doubled operator (`dd`, `yy`, `>>`): whole lines
+    Line,
+}
+
+/// Ways of entering Insert mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InsertKind {
+    /// `i`
+    Before,
+    /// `a`
+    After,
+    /// `I`
+    LineStart,
+    /// `A`
+    LineEnd,
+    /// `o`
+    LineBelow,
+    /// `O`
+    LineAbove,
+}
+
+/// A complete Normal mode command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NormalCommand {
+    Move(Motion),
+    Operate(Operator, Target),
+    /// `r{char}`
+    ReplaceChar(char),
+    /// `p` (after) / `P` (before)
+    Paste { before: bool },
+    /// `J`
+    JoinLines,
+    Insert(InsertKind),
+    /// `v` / `V` / `Ctrl+V`
+    Visual(VisualKind),
+    Undo,
+    Redo,
+    /// `g-` / `g+`: older / newer state, across undo branches
+    UndoTravel { forward: bool },
+    /// `]d` / `[d`: next / previous lint diagnostic
+    Diagnostic { forward: bool },
+    /// `.`
+    RepeatChange,
+    /// `:`
+    CommandLine,
+    /// `gt` / `gT`
+    NextTab,
+    PrevTab,
+    /// `m{a-z}`
+    SetMark(char),
+    /// `q{a-z}` (`q{A-Z}` appends); `q` again stops, handled by the caller
+    Record(char),
+    /// `@{a-z}`, or `@@` for the last macro played
+    PlayMacro(char),
+    /// `/` (forward) / `?` (backward): open the search prompt
+    Search { forward: bool },
+}
+
+impl NormalCommand {
+    /// Whether `.` should repeat this command
+    pub fn is_change(self) -> bool {
+        match self {
+            NormalCommand::Operate(op, _) => op != Operator::Yank,
+            NormalCommand::ReplaceChar(_)
+            | NormalCommand::Paste { .. }
+            | NormalCommand::JoinLines
+            | NormalCommand::Insert(_) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The last change, replayed by `.`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LastChange {
+    pub command: NormalCommand,
+    pub count: Option<usize>,
+    pub register: Option<char>,
+    /// Text typed in Insert mode after the command (`cw`, `o`, `i`, ...)
+    pub inserted: Option<String>,
+}
+
+/// An Insert mode session started by a Normal mode command
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InsertSession {
+    /// Text when Insert mode was entered
+    pub before: String,
+    /// Cursor when Insert mode was entered
+    pub start: usize,
+    /// Number of times the typed text is inserted (`3ia<Esc>`)
+    pub count: usize,
+}
+
+/// How many times text typed after `command` is inserted
+pub fn insert_repeat(command: NormalCommand, count: Option<usize>) -> usize {
+    match command {
+        NormalCommand::Insert(InsertKind::LineBelow | InsertKind::LineAbove) => 1,
+        NormalCommand::Insert(_) => count.unwrap_or(1).max(1),
+        _ => 1,
+    }
+}
+
+/// The text typed during an insert session: what ended up at `start`, as long
+/// as nothing before or after that spot changed
+pub fn inserted_text(before: &str, start: usize, after: &str) -> Option<String> {
+    let before: Vec<char> = before.chars().collect();
+    let after: Vec<char> = after.chars().collect();
+    let suffix_len = before.len().checked_sub(start)?;
+    if after.len() < before.len()
+        || after[..start] != before[..start]
+        || after[after.len() - suffix_len..] != before[start..]
+    {
+        return None;
+    }
+    Some(after[start..after.len() - suffix_len].iter().collect())
+}
+
+/// Whether `c` names a text object after `i` / `a`: words, quotes, brackets,
+/// and the SQL statement, clause, identifier and subquery
+pub fn is_text_object(c: char) -> bool {
+    matches!(c, 'w' | '"' | '\'' | '`' | '(' | ')' | 'b' | '[' | ']' | '{' | '}' | 'B' | '<' | '>' | 's' | 'c' | 'i' | 'q')
+}
+
+/// Result of feeding one key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parse {
+    /// More keys are needed
+    Pending,
+    /// A full command, with the product of any counts typed and the register
+    /// named with `"x`
+    Complete(NormalCommand, Option<usize>, Option<char>),
+    /// The keys don't form a command; the state was reset
+    Invalid,
+}
+
+/// Keys that need a second key
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Prefix {
+    G,
+    /// `]` / `[`
+    Bracket { forward: bool },
+    Find { forward: bool, till: bool },
+    Replace,
+    TextObject { around: bool },
+    Register,
+    SetMark,
+    Mark { linewise: bool },
+    Record,
+    Play,
+}
+
+/// Partially typed Normal mode command
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PendingCommand {
+    /// Count typed before the operator
+    operator_count: Option<usize>,
+    /// Count being typed (before a command, or between operator and motion)
+    count: Option<usize>,
+    operator: Option<Operator>,
+    prefix: Option<Prefix>,
+    register: Option<char>,
+    /// Keys typed so far, for display
+    keys: String,
+}
+
+impl PendingCommand {
+    /// Whether nothing has been typed yet
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The keys typed so far (shown while a command is pending)
+    pub fn keys(&self) -> &str {
+        &self.keys
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Feed one key
+    pub fn feed(&mut self, key: KeyEvent) -> Parse {
+        let result = self.parse_key(key);
+        match result {
+            Parse::Pending => {
+                if let KeyCode::Char(c) = key.code {
+                    self.keys.push(c);
+                }
+            }
+            Parse::Complete(..) | Parse::Invalid => self.reset(),
+        }
+        result
+    }
+
+    fn parse_key(&mut self, key: KeyEvent) -> Parse {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            return match key.code {
+                KeyCode::Char('r') if self.is_empty() => self.complete(NormalCommand::Redo),
+                KeyCode::Char('v') if self.is_empty() => self.complete(NormalCommand::Visual(VisualKind::Block)),
+                _ => Parse::Invalid,
+            };
+        }
+
+        let c = match key.code {
+            KeyCode::Char(c) => c,
+            KeyCode::Left => return self.motion(Motion::Left),
+            KeyCode::Right => return self.motion(Motion::Right),
+            KeyCode::Up => return self.motion(Motion::Up),
+            KeyCode::Down => return self.motion(Motion::Down),
+            KeyCode::Home => return self.motion(Motion::LineStart),
+            KeyCode::End => return self.motion(Motion::LineEnd),
+            _ => return Parse::Invalid,
+        };
+
+        if let Some(prefix) = self.prefix.take() {
+            return self.parse_prefixed(prefix, c);
+        }
+
+        // Counts ('0' only continues a count, otherwise it is a motion)
+        if let Some(digit) = c.to_digit(10)
+            && (digit != 0 || self.count.is_some())
+        {
+            let count = self.count.unwrap_or(0) * 10 + digit as usize;
+            self.count = Some(count.min(MAX_COUNT));
+            return Parse::Pending;
+        }
+
+        if let Some(op) = self.operator {
+            return match c {
+                _ if c == op.to_char() => self.complete(NormalCommand::Operate(op, Target::Line)),
+                'i' | 'a' => {
+                    self.prefix = Some(Prefix::TextObject { around: c == 'a' });
+                    Parse::Pending
+                }
+                _ => self.parse_motion(c),
+            };
+        }
+
+        if c == '"' {
+            self.prefix = Some(Prefix::Register);
+            return Parse::Pending;
+        }
+
+        if let Some(op) = Operator::from_char(c) {
+            self.operator = Some(op);
+            self.operator_count = self.count.take();
+            return Parse::Pending;
+        }
+
+        let command = match c {
+            'x' => NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::Right)),
+            'X' => NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::Left)),
+            'D' => NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::LineEnd)),
+            'C' => NormalCommand::Operate(Operator::Change, Target::Motion(Motion::LineEnd)),
+            's' => NormalCommand::Operate(Operator::Change, Target::Motion(Motion::Right)),
+            'S' => NormalCommand::Operate(Operator::Change, Target::Line),
+            'Y' => NormalCommand::Operate(Operator::Yank, Target::Line),
+            'p' => NormalCommand::Paste { before: false },
+            'P' => NormalCommand::Paste { before: true },
+            'J' => NormalCommand::JoinLines,
+            'i' => NormalCommand::Insert(InsertKind::Before),
+            'a' => NormalCommand::Insert(InsertKind::After),
+            'I' => NormalCommand::Insert(InsertKind::LineStart),
+            'A' => NormalCommand::Insert(InsertKind::LineEnd),
+            'o' => NormalCommand::Insert(InsertKind::LineBelow),
+            'O' => NormalCommand::Insert(InsertKind::LineAbove),
+            'v' => NormalCommand::Visual(VisualKind::Char),
+            'V' => NormalCommand::Visual(VisualKind::Line),
+            'u' => NormalCommand::Undo,
+            '.' => NormalCommand::RepeatChange,
+            ':' => NormalCommand::CommandLine,
+            '/' | '?' => NormalCommand::Search { forward: c == '/' },
+            'r' | 'm' | 'q' | '@' => {
+                self.prefix = Some(match c {
+                    'r' => Prefix::Replace,
+                    'm' => Prefix::SetMark,
+                    'q' => Prefix::Record,
+                    _ => Prefix::Play,
+                });
+                return Parse::Pending;
+            }
+            _ => return self.parse_motion(c),
+        };
+        self.complete(command)
+    }
+
+    fn parse_prefixed(&mut self, prefix: Prefix, c: char) -> Parse {
+        match prefix {
+            Prefix::G => match c {
+                'g' => self.motion(Motion::DocumentStart),
+                '_' => self.motion(Motion::LastNonBlank),
+                'e' => self.motion(Motion::WordEndBackward),
+                't' if self.operator.is_none() => self.complete(NormalCommand::NextTab),
+                'T' if self.operator.is_none() => self.complete(NormalCommand::PrevTab),
+                '-' if self.operator.is_none() => self.complete(NormalCommand::UndoTravel { forward: false }),
+                '+' if self.operator.is_none() => self.complete(NormalCommand::UndoTravel { forward: true }),
+                _ => Parse::Invalid,
+            },
+            Prefix::Bracket { forward } => match c {
+                'd' if self.operator.is_none() => self.complete(NormalCommand::Diagnostic { forward }),
+                _ => Parse::Invalid,
+            },
+            Prefix::Find { forward, till } => self.motion(Motion::FindChar { ch: c, forward, till }),
+            Prefix::Replace => self.complete(NormalCommand::ReplaceChar(c)),
+            Prefix::TextObject { around } => match (self.operator, c) {
+                (Some(op), c) if is_text_object(c) => {
+                    self.complete(NormalCommand::Operate(op, Target::TextObject { around, object: c }))
+                }
+                _ => Parse::Invalid,
+            },
+            Prefix::Register if Registers::is_valid_name(c) && self.operator.is_none() => {
+                self.register = Some(c);
+                Parse::Pending
+            }
+            Prefix::SetMark if c.is_ascii_lowercase() => self.complete(NormalCommand::SetMark(c)),
+            Prefix::Mark { linewise } if c.is_ascii_lowercase() || c == '`' || c == '\'' => {
+                self.motion(Motion::Mark { name: c, linewise })
+            }
+            Prefix::Record if c.is_ascii_alphabetic() => self.complete(NormalCommand::Record(c)),
+            Prefix::Play if c.is_ascii_lowercase() || c == '@' => self.complete(NormalCommand::PlayMacro(c)),
+            _ => Parse::Invalid,
+        }
+    }
+
+    fn parse_motion(&mut self, c: char) -> Parse {
+        let motion = match c {
+            'h' => Motion::Left,
+            'l' => Motion::Right,
+            'j' => Motion::Down,
+            'k' => Motion::Up,
+            '0' => Motion::LineStart,
+            '^' => Motion::FirstNonBlank,
+            '$' => Motion::LineEnd,
+            'w' => Motion::WordForward,
+            'b' => Motion::WordBackward,
+            'e' => Motion::WordEnd,
+            'G' => Motion::DocumentEnd,
+            ';' => Motion::RepeatFind,
+            ',' => Motion::RepeatFindReverse,
+            'n' | 'N' => Motion::SearchNext { reverse: c == 'N' },
+            '*' | '#' => Motion::SearchWord { forward: c == '*' },
+            '%' => Motion::MatchPair,
+            'f' | 'F' | 't' | 'T' => {
+                self.prefix = Some(Prefix::Find {
+                    forward: c.is_lowercase(),
+                    till: c.eq_ignore_ascii_case(&'t'),
+                });
+                return Parse::Pending;
+            }
+            'g' => {
+                self.prefix = Some(Prefix::G);
+                return Parse::Pending;
+            }
+            ']' | '[' => {
+                self.prefix = Some(Prefix::Bracket { forward: c == ']' });
+                return Parse::Pending;
+            }
+            '`' | '\'' => {
+                self.prefix = Some(Prefix::Mark { linewise: c == '\'' });
+                return Parse::Pending;
+            }
+            _ => return Parse::Invalid,
+        };
+        self.motion(motion)
+    }
+
+    /// A motion completes either a move or the pending operator
+    fn motion(&mut self, motion: Motion) -> Parse {
+        match self.operator {
+            Some(op) => self.complete(NormalCommand::Operate(op, Target::Motion(motion))),
+            None => self.complete(NormalCommand::Move(motion)),
+        }
+    }
+
+    fn complete(&mut self, command: NormalCommand) -> Parse {
+        let count = match (self.operator_count, self.count) {
+            (Some(a), Some(b)) => Some((a * b).min(MAX_COUNT)),
+            (a, b) => a.or(b),
+        };
+        Parse::Complete(command, count, self.register)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(keys: &str) -> Parse {
+        let mut pending = PendingCommand::default();
+        let mut result = Parse::Pending;
+        for c in keys.chars() {
+            result = pending.feed(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+        result
+    }
+
+    #[test]
+    fn test_counts_and_motions() {
+        assert_eq!(parse("j"), Parse::Complete(NormalCommand::Move(Motion::Down), None, None));
+        assert_eq!(parse("3j"), Parse::Complete(NormalCommand::Move(Motion::Down), Some(3), None));
+        assert_eq!(parse("10w"), Parse::Complete(NormalCommand::Move(Motion::WordForward), Some(10), None));
+        assert_eq!(parse("0"), Parse::Complete(NormalCommand::Move(Motion::LineStart), None, None));
+        assert_eq!(parse("gg"), Parse::Complete(NormalCommand::Move(Motion::DocumentStart), None, None));
+        assert_eq!(parse("%"), Parse::Complete(NormalCommand::Move(Motion::MatchPair), None, None));
+        assert_eq!(parse("50%"), Parse::Complete(NormalCommand::Move(Motion::MatchPair), Some(50), None));
+        assert_eq!(
+            parse("2fx"),
+            Parse::Complete(NormalCommand::Move(Motion::FindChar { ch: 'x', forward: true, till: false }), Some(2), None)
+        );
+        assert_eq!(parse("gt"), Parse::Complete(NormalCommand::NextTab, None, None));
+        assert_eq!(parse("3g-"), Parse::Complete(NormalCommand::UndoTravel { forward: false }, Some(3), None));
+        assert_eq!(parse("dg+"), Parse::Invalid);
+        assert_eq!(parse("2]d"), Parse::Complete(NormalCommand::Diagnostic { forward: true }, Some(2), None));
+        assert_eq!(parse("[d"), Parse::Complete(NormalCommand::Diagnostic { forward: false }, None, None));
+        assert_eq!(parse("d]d"), Parse::Invalid);
+    }
+
+    #[test]
+    fn test_operators() {
+        let delete_line = NormalCommand::Operate(Operator::Delete, Target::Line);
+        assert_eq!(parse("dd"), Parse::Complete(delete_line, None, None));
+        assert_eq!(parse("5dd"), Parse::Complete(delete_line, Some(5), None));
+        assert_eq!(
+            parse("d2w"),
+            Parse::Complete(NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::WordForward)), Some(2), None)
+        );
+        // Counts before the operator and before the motion multiply
+        assert_eq!(
+            parse("2d3w"),
+            Parse::Complete(NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::WordForward)), Some(6), None)
+        );
+        assert_eq!(
+            parse("ci("),
+            Parse::Complete(NormalCommand::Operate(Operator::Change, Target::TextObject { around: false, object: '(' }), None, None)
+        );
+        assert_eq!(
+            parse("dt,"),
+            Parse::Complete(
+                NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::FindChar { ch: ',', forward: true, till: true })),
+                None,
+                None
+            )
+        );
+        assert_eq!(parse(">>"), Parse::Complete(NormalCommand::Operate(Operator::Indent, Target::Line), None, None));
+        assert_eq!(
+            parse("=is"),
+            Parse::Complete(NormalCommand::Operate(Operator::Format, Target::TextObject { around: false, object: 's' }), None, None)
+        );
+        assert_eq!(
+            parse("\"a3yy"),
+            Parse::Complete(NormalCommand::Operate(Operator::Yank, Target::Line), Some(3), Some('a'))
+        );
+        assert_eq!(
+            parse("d`a"),
+            Parse::Complete(NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::Mark { name: 'a', linewise: false })), None, None)
+        );
+        assert_eq!(parse("d\""), Parse::Invalid);
+        assert_eq!(
+            parse("yas"),
+            Parse::Complete(NormalCommand::Operate(Operator::Yank, Target::TextObject { around: true, object: 's' }), None, None)
+        );
+        assert_eq!(parse("dx"), Parse::Invalid);
+        assert_eq!(parse("dgt"), Parse::Invalid);
+        assert_eq!(parse("d2"), Parse::Pending);
+    }
+
+    #[test]
+    fn test_simple_commands() {
+        assert_eq!(
+            parse("3x"),
+            Parse::Complete(NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::Right)), Some(3), None)
+        );
+        assert_eq!(parse("ra"), Parse::Complete(NormalCommand::ReplaceChar('a'), None, None));
+        assert_eq!(parse("."), Parse::Complete(NormalCommand::RepeatChange, None, None));
+        assert_eq!(parse("o"), Parse::Complete(NormalCommand::Insert(InsertKind::LineBelow), None, None));
+        assert_eq!(parse("mx"), Parse::Complete(NormalCommand::SetMark('x'), None, None));
+        assert_eq!(parse("?"), Parse::Complete(NormalCommand::Search { forward: false }, None, None));
+        assert_eq!(
+            parse("d2n"),
+            Parse::Complete(NormalCommand::Operate(Operator::Delete, Target::Motion(Motion::SearchNext { reverse: false })), Some(2), None)
+        );
+        assert_eq!(parse("d/"), Parse::Invalid);
+        assert_eq!(parse("qa"), Parse::Complete(NormalCommand::Record('a'), None, None));
+        assert_eq!(parse("3@@"), Parse::Complete(NormalCommand::PlayMacro('@'), Some(3), None));
+        assert_eq!(parse("\"+p"), Parse::Complete(NormalCommand::Paste { before: false }, None, Some('+')));
+        assert_eq!(parse("V"), Parse::Complete(NormalCommand::Visual(VisualKind::Line), None, None));
+        assert!(NormalCommand::Operate(Operator::Delete, Target::Line).is_change());
+        assert!(!NormalCommand::Operate(Operator::Yank, Target::Line).is_change());
+        assert!(!NormalCommand::Move(Motion::Down).is_change());
+
+        let mut pending = PendingCommand::default();
+        pending.feed(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::NONE));
+        pending.feed(KeyEvent::new(KeyCode::Char('2'), KeyModifiers::NONE));
+        assert_eq!(pending.keys(), "d2");
+        assert_eq!(pending.feed(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)), Parse::Invalid);
+        assert!(pending.is_empty());
+        assert_eq!(
+            pending.feed(KeyEvent::new(KeyCode::Char('v'), KeyModifiers::CONTROL)),
+            Parse::Complete(NormalCommand::Visual(VisualKind::Block), None, None)
+        );
+    }
+
+    #[test]
+    fn test_inserted_text() {
+        assert_eq!(inserted_text("SELECT  FROM t", 7, "SELECT id FROM t"), Some("id".into()));
+        assert_eq!(inserted_text("ab", 2, "ab\n    x"), Some("\n    x".into()));
+        // Backspacing over text from before the session can't be replayed
+        assert_eq!(inserted_text("abc", 3, "abX"), None);
+        assert_eq!(insert_repeat(NormalCommand::Insert(InsertKind::Before), Some(3)), 3);
+        assert_eq!(insert_repeat(NormalCommand::Insert(InsertKind::LineBelow), Some(3)), 1);
+    }
+}
@@ -137,6 +137,57 @@ pub fn change_range(text: &mut String, start: usize, end: usize) -> usize {
     start.min(text.chars().count())
 }
 
+/// Char range (start, end exclusive) covering the whole lines from `from` to `to`,
+/// including the newline after the last one
+pub fn line_range(text: &str, from: usize, to: usize) -> (usize, usize) {
+    let start = motions::line_start(text, from.min(to));
+    let end = (motions::line_end(text, from.max(to)) + 1).min(text.chars().count());
+    (start, end)
+}
+
+/// Indent every non-empty line touching `start..end` by `width` spaces
+pub fn indent_lines(text: &mut String, start: usize, end: usize, width: usize) {
+    let indent = " ".repeat(width);
+    let mut lines = line_starts_in(text, start, end);
+    // Work from the bottom so earlier positions stay valid
+    lines.reverse();
+    for line in lines {
+        if motions::line_end(text, line) > line {
+            insert_text(text, line, &indent);
+        }
+    }
+}
+
+/// Remove up to `width` columns of leading whitespace (or one tab) from every
+/// line touching `start..end`
+pub fn outdent_lines(text: &mut String, start: usize, end: usize, width: usize) {
+    let mut lines = line_starts_in(text, start, end);
+    lines.reverse();
+    for line in lines {
+        let leading: Vec<char> = text.chars().skip(line).take(width).collect();
+        let remove = if leading.first() == Some(&'\t') {
+            1
+        } else {
+            leading.iter().take_while(|&&c| c == ' ').count()
+        };
+        if remove > 0 {
+            delete_range(text, line, line + remove - 1);
+        }
+    }
+}
+
+/// Start positions of the lines touching `start..end`
+fn line_starts_in(text: &str, start: usize, end: usize) -> Vec<usize> {
+    let mut lines = vec![motions::line_start(text, start)];
+    let last = end.saturating_sub(1).max(start);
+    for (i, c) in text.chars().enumerate().take(last).skip(start) {
+        if c == '\n' {
+            lines.push(i + 1);
+        }
+    }
+    lines
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -165,6 +216,25 @@ mod tests {
         assert_eq!(yanked, "hello");
     }
 
+    #[test]
+    fn test_line_range() {
+        let text = "one\ntwo\nthree";
+        assert_eq!(line_range(text, 5, 5), (4, 8));
+        assert_eq!(line_range(text, 10, 1), (0, 13));
+    }
+
+    #[test]
+    fn test_indent_and_outdent_lines() {
+        let mut text = String::from("a\n\n  b\nc");
+        indent_lines(&mut text, 0, 7, 4);
+        assert_eq!(text, "    a\n\n      b\nc");
+        outdent_lines(&mut text, 0, 15, 4);
+        assert_eq!(text, "a\n\n  b\nc");
+        let mut text = String::from("\tx");
+        outdent_lines(&mut text, 0, 1, 4);
+        assert_eq!(text, "x");
+    }
+
     #[test]
     fn test_insert_text() {
         let mut text = String::from("hello");
@@ -0,0 +1,261 @@
+//! Registers, marks and macro key notation
+//!
+//! Registers follow vim: `"a`–`"z` (`"A`–`"Z` append), the unnamed register
+//! `""`, the yank register `"0`, the delete ring `"1`–`"9`, the small delete
+//! register `"-` and the black hole `"_`. The system clipboard (`"+` / `"*`)
+//! is handled by the caller. Macros are stored in named registers as key
+//! notation (`<Esc>`, `<CR>`, `<C-r>`), so `"ap` shows a recorded macro.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Number of registers in the delete ring (`"1`–`"9`)
+const DELETE_RING: usize = 9;
+
+/// Marks of one buffer: name -> (line, column), both 0-based
+pub type Marks = BTreeMap<char, (usize, usize)>;
+
+/// How text got into a register
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegisterWrite {
+    Yank,
+    Delete,
+}
+
+/// Register contents
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Registers {
+    unnamed: String,
+    yank: String,
+    small_delete: String,
+    deletes: Vec<String>,
+    named: BTreeMap<char, String>,
+}
+
+impl Registers {
+    /// Whether `name` can follow `"`
+    pub fn is_valid_name(name: char) -> bool {
+        name.is_ascii_alphanumeric() || matches!(name, '"' | '-' | '_' | '+' | '*')
+    }
+
+    /// Contents of a register (`"+` / `"*` are not stored here)
+    pub fn get(&self, name: char) -> Option<&str> {
+        let text = match name {
+            '"' => &self.unnamed,
+            '0' => &self.yank,
+            '-' => &self.small_delete,
+            '1'..='9' => self.deletes.get(name as usize - '1' as usize)?,
+            'a'..='z' | 'A'..='Z' => self.named.get(&name.to_ascii_lowercase())?,
+            _ => return None,
+        };
+        (!text.is_empty()).then_some(text.as_str())
+    }
+
+    /// Store yanked or deleted text. Without a name (or with `"`, `+`, `*`)
+    /// yanks also go to `"0` and deletes to the delete ring (whole lines) or
+    /// `"-`. The unnamed register always ends up with what was written.
+    pub fn store(&mut self, name: Option<char>, text: &str, write: RegisterWrite) {
+        match name {
+            Some('_') => return,
+            Some(c @ 'a'..='z') => {
+                self.named.insert(c, text.to_string());
+            }
+            Some(c @ 'A'..='Z') => {
+                self.named.entry(c.to_ascii_lowercase()).or_default().push_str(text);
+            }
+            Some('-') => self.small_delete = text.to_string(),
+            Some(c @ '1'..='9') => {
+                let index = c as usize - '1' as usize;
+                self.deletes.resize(self.deletes.len().max(index + 1), String::new());
+                self.deletes[index] = text.to_string();
+            }
+            _ => match write {
+                RegisterWrite::Yank => self.yank = text.to_string(),
+                RegisterWrite::Delete if text.contains('\n') => {
+                    self.deletes.insert(0, text.to_string());
+                    self.deletes.truncate(DELETE_RING);
+                }
+                RegisterWrite::Delete => self.small_delete = text.to_string(),
+            },
+        }
+        self.unnamed = match name {
+            Some(c) if c.is_ascii_alphabetic() => self.named[&c.to_ascii_lowercase()].clone(),
+            _ => text.to_string(),
+        };
+    }
+
+    /// Store a recorded macro (`qa` replaces, `qA` appends; the unnamed
+    /// register is left alone)
+    pub fn store_macro(&mut self, name: char, keys: &str) {
+        let register = self.named.entry(name.to_ascii_lowercase()).or_default();
+        if !name.is_ascii_uppercase() {
+            register.clear();
+        }
+        register.push_str(keys);
+    }
+}
+
+/// Key names used in macro notation
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("CR", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("BS", KeyCode::Backspace),
+    ("Tab", KeyCode::Tab),
+    ("S-Tab", KeyCode::BackTab),
+    ("Del", KeyCode::Delete),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("lt", KeyCode::Char('<')),
+];
+
+/// Write keys in vim notation (`ciwfoo<Esc>j`)
+pub fn keys_to_notation(keys: &[KeyEvent]) -> String {
+    let mut out = String::new();
+    for key in keys {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Char(c) if ctrl => out.push_str(&format!("<C-{}>", c)),
+            KeyCode::Char('<') => out.push_str("<lt>"),
+            KeyCode::Char(c) => out.push(c),
+            KeyCode::F(n) => out.push_str(&format!("<F{}>", n)),
+            code => {
+                if let Some((name, _)) = KEY_NAMES.iter().find(|(_, k)| *k == code) {
+                    out.push_str(&format!("<{}>", name));
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Read keys written in vim notation. A `<` that doesn't start a known key
+/// name is taken literally.
+pub fn notation_to_keys(notation: &str) -> Vec<KeyEvent> {
+    let mut keys = Vec::new();
+    let mut rest = notation;
+    while let Some(c) = rest.chars().next() {
+        if c == '<'
+            && let Some(end) = rest.find('>')
+            && let Some(key) = parse_key_name(&rest[1..end])
+        {
+            keys.push(key);
+            rest = &rest[end + 1..];
+            continue;
+        }
+        keys.push(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        rest = &rest[c.len_utf8()..];
+    }
+    keys
+}
+
+fn parse_key_name(name: &str) -> Option<KeyEvent> {
+    if let Some(c) = name.strip_prefix("C-").or_else(|| name.strip_prefix("c-")) {
+        let mut chars = c.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(KeyEvent::new(KeyCode::Char(c.to_ascii_lowercase()), KeyModifiers::CONTROL)),
+            _ => None,
+        };
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse().ok()) {
+        return Some(KeyEvent::new(KeyCode::F(n), KeyModifiers::NONE));
+    }
+    KEY_NAMES
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, code)| KeyEvent::new(*code, KeyModifiers::NONE))
+}
+
+/// (line, column) of a char position
+pub fn line_col(text: &str, pos: usize) -> (usize, usize) {
+    let before: Vec<char> = text.chars().take(pos).collect();
+    let line = before.iter().filter(|&&c| c == '\n').count();
+    let col = before.iter().rev().take_while(|&&c| c != '\n').count();
+    (line, col)
+}
+
+/// Char position of (line, column), clamped to the text
+pub fn position_of(text: &str, line: usize, col: usize) -> usize {
+    let mut pos = 0;
+    for (i, l) in text.split('\n').enumerate() {
+        let len = l.chars().count();
+        if i == line {
+            return pos + col.min(len);
+        }
+        pos += len + 1;
+    }
+    pos.saturating_sub(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_writes() {
+        let mut regs = Registers::default();
+        regs.store(None, "id", RegisterWrite::Yank);
+        regs.store(None, "name\n", RegisterWrite::Delete);
+        regs.store(None, "x", RegisterWrite::Delete);
+        assert_eq!(regs.get('0'), Some("id"));
+        assert_eq!(regs.get('1'), Some("name\n"));
+        assert_eq!(regs.get('-'), Some("x"));
+        assert_eq!(regs.get('"'), Some("x"));
+
+        regs.store(Some('a'), "foo", RegisterWrite::Yank);
+        regs.store(Some('A'), "bar", RegisterWrite::Yank);
+        assert_eq!(regs.get('a'), Some("foobar"));
+        assert_eq!(regs.get('"'), Some("foobar"));
+        // Named yanks leave "0 alone
+        assert_eq!(regs.get('0'), Some("id"));
+
+        regs.store(Some('_'), "gone", RegisterWrite::Delete);
+        assert_eq!(regs.get('"'), Some("foobar"));
+        assert_eq!(regs.get('b'), None);
+    }
+
+    #[test]
+    fn test_macros() {
+        let mut regs = Registers::default();
+        regs.store(None, "kept", RegisterWrite::Yank);
+        regs.store_macro('q', "A,<Esc>j");
+        regs.store_macro('Q', "0");
+        assert_eq!(regs.get('q'), Some("A,<Esc>j0"));
+        assert_eq!(regs.get('"'), Some("kept"));
+        regs.store_macro('q', "x");
+        assert_eq!(regs.get('q'), Some("x"));
+    }
+
+    #[test]
+    fn test_key_notation_round_trip() {
+        let keys = vec![
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('<'), KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE),
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::CONTROL),
+            KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+        ];
+        let notation = keys_to_notation(&keys);
+        assert_eq!(notation, "c<lt><Esc><C-r><CR>");
+        assert_eq!(notation_to_keys(&notation), keys);
+        // Unknown names are plain text
+        assert_eq!(notation_to_keys("<x>").len(), 3);
+    }
+
+    #[test]
+    fn test_line_col() {
+        let text = "ab\ncde\n";
+        assert_eq!(line_col(text, 4), (1, 1));
+        assert_eq!(position_of(text, 1, 1), 4);
+        assert_eq!(position_of(text, 1, 10), 6);
+        assert_eq!(position_of(text, 5, 0), 7);
+        assert_eq!(line_col(text, 0), (0, 0));
+    }
+}
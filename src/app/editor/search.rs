@@ -0,0 +1,161 @@
+//! `/` and `?` search
+//!
+//! Patterns are plain text unless `:set regex` is on. With `ignorecase` and
+//! `smartcase` (the defaults) a pattern is case-insensitive until it contains
+//! an uppercase letter; `\c` / `\C` anywhere in the pattern force either way.
+
+use regex::{Regex, RegexBuilder};
+use rust_i18n::t;
+
+/// How patterns are read
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchFlags {
+    pub ignore_case: bool,
+    pub smart_case: bool,
+    pub regex: bool,
+}
+
+/// A compiled search
+#[derive(Clone, Debug)]
+pub struct Search {
+    /// The pattern as typed (shown in messages and reused by `n` / `N`)
+    pub pattern: String,
+    /// Direction of the `/` (forward) or `?` (backward) that started it
+    pub forward: bool,
+    regex: Regex,
+}
+
+impl Search {
+    /// Compile a typed pattern
+    pub fn new(pattern: &str, forward: bool, flags: SearchFlags) -> Result<Self, String> {
+        let (body, case) = strip_case_flags(pattern);
+        let ignore_case = match case {
+            Some(ignore) => ignore,
+            None => flags.ignore_case && !(flags.smart_case && body.chars().any(char::is_uppercase)),
+        };
+        let source = if flags.regex { body } else { regex::escape(&body) };
+        Self::build(pattern, &source, forward, ignore_case)
+    }
+
+    /// Whole-word search for `*` / `#` (case follows `ignorecase` only, like vim)
+    pub fn word(word: &str, forward: bool, flags: SearchFlags) -> Result<Self, String> {
+        let source = format!(r"\b{}\b", regex::escape(word));
+        Self::build(word, &source, forward, flags.ignore_case)
+    }
+
+    fn build(pattern: &str, source: &str, forward: bool, ignore_case: bool) -> Result<Self, String> {
+        let regex = RegexBuilder::new(source)
+            .case_insensitive(ignore_case)
+            .multi_line(true)
+            .build()
+            .map_err(|e| t!("cmd_invalid_pattern", error = e.to_string()).to_string())?;
+        Ok(Self { pattern: pattern.to_string(), forward, regex })
+    }
+
+    /// Every non-empty match as char ranges (start, end exclusive)
+    pub fn matches(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        // Byte offsets come in order, so the char count can be carried along
+        let mut chars_before = 0;
+        let mut last_byte = 0;
+        for m in self.regex.find_iter(text).filter(|m| !m.is_empty()) {
+            chars_before += text[last_byte..m.start()].chars().count();
+            let len = m.as_str().chars().count();
+            matches.push((chars_before, chars_before + len));
+            chars_before += len;
+            last_byte = m.end();
+        }
+        matches
+    }
+}
+
+/// Remove `\c` / `\C` from a pattern: Some(true) = ignore case, Some(false) = match case
+fn strip_case_flags(pattern: &str) -> (String, Option<bool>) {
+    let mut body = String::new();
+    let mut case = None;
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('c') => case = Some(true),
+                Some('C') => case = Some(false),
+                Some(next) => {
+                    body.push(c);
+                    body.push(next);
+                }
+                None => body.push(c),
+            }
+        } else {
+            body.push(c);
+        }
+    }
+    (body, case)
+}
+
+/// The search prompt being typed, and where the cursor was before it opened
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SearchPrompt {
+    pub forward: bool,
+    pub count: Option<usize>,
+    pub origin: usize,
+    pub origin_scroll: (usize, usize),
+}
+
+/// The `count`-th match start after (forward) or before `cursor`, wrapping
+/// around the text. Returns the match index and whether the search wrapped.
+pub fn next_match(matches: &[(usize, usize)], cursor: usize, forward: bool, count: usize) -> Option<(usize, bool)> {
+    if matches.is_empty() {
+        return None;
+    }
+    let len = matches.len() as isize;
+    let steps = count.max(1) as isize - 1;
+    let target = if forward {
+        let first = matches.iter().position(|&(start, _)| start > cursor).unwrap_or(matches.len());
+        first as isize + steps
+    } else {
+        let first = matches.iter().rposition(|&(start, _)| start < cursor).map_or(-1, |i| i as isize);
+        first - steps
+    };
+    Some((target.rem_euclid(len) as usize, !(0..len).contains(&target)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLAGS: SearchFlags = SearchFlags { ignore_case: true, smart_case: true, regex: false };
+
+    #[test]
+    fn test_smartcase_and_literal() {
+        let text = "select Id, id from t.id";
+        assert_eq!(Search::new("id", true, FLAGS).unwrap().matches(text).len(), 3);
+        assert_eq!(Search::new("Id", true, FLAGS).unwrap().matches(text), vec![(7, 9)]);
+        assert_eq!(Search::new("id\\C", true, FLAGS).unwrap().matches(text).len(), 2);
+        // Without :set regex, "." is literal
+        assert_eq!(Search::new("t.id", true, FLAGS).unwrap().matches(text), vec![(19, 23)]);
+        let regex = SearchFlags { regex: true, ..FLAGS };
+        assert_eq!(Search::new("i.,", true, regex).unwrap().matches(text).len(), 1);
+        assert!(Search::new("(", true, regex).is_err());
+    }
+
+    #[test]
+    fn test_word_search_and_chars() {
+        let text = "é id idx id";
+        let search = Search::word("id", true, FLAGS).unwrap();
+        assert_eq!(search.matches(text), vec![(2, 4), (9, 11)]);
+    }
+
+    #[test]
+    fn test_next_match() {
+        let matches = [(2, 4), (9, 11), (20, 22)];
+        assert_eq!(next_match(&matches, 0, true, 1), Some((0, false)));
+        assert_eq!(next_match(&matches, 2, true, 1), Some((1, false)));
+        assert_eq!(next_match(&matches, 20, true, 1), Some((0, true)));
+        assert_eq!(next_match(&matches, 0, true, 2), Some((1, false)));
+        assert_eq!(next_match(&matches, 10, false, 1), Some((1, false)));
+        assert_eq!(next_match(&matches, 9, false, 1), Some((0, false)));
+        assert_eq!(next_match(&matches, 2, false, 1), Some((2, true)));
+        assert_eq!(next_match(&matches, 9, false, 2), Some((2, true)));
+        assert_eq!(next_match(&[], 0, true, 1), None);
+    }
+}
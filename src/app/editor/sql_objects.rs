@@ -0,0 +1,345 @@
+//! SQL text objects
+//!
+//! - `is` / `as`: the statement under the cursor. Statements end at `;`, a
+//!   T-SQL `GO` line or a blank line; `as` also takes the `;` and the
+//!   whitespace after it.
+//! - `ic` / `ac`: the clause under the cursor (`SELECT` list, `FROM`,
+//!   `WHERE`, `ORDER BY`...) without / with its keyword. Clauses of a
+//!   subquery are found when the cursor is inside it.
+//! - `ii` / `ai`: a dotted identifier such as `[dbo].[My Table]` or `t.id`.
+//! - `iq` / `aq`: the innermost subquery, without / with its parentheses.
+//!
+//! Strings, comments and quoted names are skipped when looking for
+//! separators, keywords and parentheses.
+
+use crate::db::DatabaseBackend;
+use crate::sql::lexer::{tokenize, TokenKind};
+use crate::sql::statements::{statement_spans, StatementSpan};
+
+use super::text_objects::TextObject;
+
+/// Keywords that start a clause; pairs are matched as two words
+const CLAUSE_KEYWORDS: &[&[&str]] = &[
+    &["SELECT"],
+    &["FROM"],
+    &["WHERE"],
+    &["GROUP", "BY"],
+    &["HAVING"],
+    &["ORDER", "BY"],
+    &["UNION", "ALL"],
+    &["UNION"],
+    &["EXCEPT"],
+    &["INTERSECT"],
+    &["INSERT", "INTO"],
+    &["VALUES"],
+    &["UPDATE"],
+    &["SET"],
+    &["DELETE"],
+    &["OUTPUT"],
+    &["RETURNING"],
+    &["LIMIT"],
+    &["OFFSET"],
+];
+
+/// What a char belongs to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Code,
+    /// Inside a string or quoted name, delimiters included
+    Quoted,
+    Comment,
+}
+
+/// Classify every char of the text
+fn classify(text: &str, dialect: DatabaseBackend) -> Vec<Class> {
+    let mut classes = vec![Class::Code; text.chars().count()];
+    for token in tokenize(text, dialect) {
+        let class = match token.kind {
+            TokenKind::String | TokenKind::QuotedName => Class::Quoted,
+            TokenKind::LineComment | TokenKind::BlockComment => Class::Comment,
+            _ => continue,
+        };
+        classes[token.start..token.end()].fill(class);
+    }
+    classes
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$')
+}
+
+/// Position of the first non-whitespace char in `start..end` and one past the
+/// last, or None if the range is blank
+fn trim(chars: &[char], start: usize, end: usize) -> Option<(usize, usize)> {
+    let first = (start..end).find(|&i| !chars[i].is_whitespace())?;
+    let last = (start..end).rev().find(|&i| !chars[i].is_whitespace())?;
+    Some((first, last + 1))
+}
+
+/// The statement under the cursor; whitespace between statements belongs to
+/// the next one
+fn statement_at(text: &str, dialect: DatabaseBackend, cursor: usize) -> Option<StatementSpan> {
+    let statements = statement_spans(text, dialect);
+    statements.iter().find(|s| cursor < s.separator_end.max(s.end)).or(statements.last()).copied()
+}
+
+/// Extend `start..end` over the whitespace after it (up to `limit`), or
+/// before it when there is none (like `aw`). Returns an inclusive object.
+fn with_whitespace(chars: &[char], start: usize, end: usize, limit: usize) -> TextObject {
+    let after = (end..limit).find(|&i| !chars[i].is_whitespace()).unwrap_or(limit);
+    if after > end {
+        return TextObject::new(start, after - 1);
+    }
+    let before = (0..start).rev().find(|&i| !chars[i].is_whitespace()).map_or(0, |i| i + 1);
+    TextObject::new(before, end - 1)
+}
+
+/// `is` / `as`
+pub fn statement(text: &str, cursor_pos: usize, around: bool, dialect: DatabaseBackend) -> Option<TextObject> {
+    let chars: Vec<char> = text.chars().collect();
+    let statement = statement_at(text, dialect, cursor_pos)?;
+    if around {
+        Some(with_whitespace(&chars, statement.start, statement.separator_end.max(statement.end), chars.len()))
+    } else {
+        Some(TextObject::new(statement.start, statement.end - 1))
+    }
+}
+
+/// Parenthesised groups around the cursor as (open, close), innermost first
+fn enclosing_parens(chars: &[char], classes: &[Class], cursor: usize) -> Vec<(usize, usize)> {
+    let mut opens = Vec::new();
+    let mut depth = 0;
+    // A cursor on `)` is inside that group
+    let from = if chars.get(cursor) == Some(&')') { cursor } else { cursor + 1 };
+    for i in (0..from.min(chars.len())).rev() {
+        match chars[i] {
+            ')' if classes[i] == Class::Code => depth += 1,
+            '(' if classes[i] == Class::Code => {
+                if depth == 0 {
+                    opens.push(i);
+                } else {
+                    depth -= 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    opens
+        .into_iter()
+        .filter_map(|open| {
+            let mut depth = 0;
+            for i in open + 1..chars.len() {
+                match chars[i] {
+                    '(' if classes[i] == Class::Code => depth += 1,
+                    ')' if classes[i] == Class::Code && depth == 0 => return Some((open, i)),
+                    ')' if classes[i] == Class::Code => depth -= 1,
+                    _ => {}
+                }
+            }
+            None
+        })
+        .collect()
+}
+
+/// Uppercased word starting at `i`, if a word starts there
+fn word_at(chars: &[char], classes: &[Class], i: usize) -> Option<(String, usize)> {
+    if classes[i] != Class::Code || !chars[i].is_alphabetic() || (i > 0 && is_ident_char(chars[i - 1])) {
+        return None;
+    }
+    let end = (i..chars.len()).find(|&j| !is_ident_char(chars[j])).unwrap_or(chars.len());
+    Some((chars[i..end].iter().collect::<String>().to_uppercase(), end))
+}
+
+fn starts_query(chars: &[char], classes: &[Class], start: usize, end: usize) -> bool {
+    trim(chars, start, end)
+        .and_then(|(first, _)| word_at(chars, classes, first))
+        .is_some_and(|(word, _)| word == "SELECT" || word == "WITH")
+}
+
+/// The innermost subquery around the cursor as (open, close) parentheses
+fn subquery_at(chars: &[char], classes: &[Class], cursor: usize) -> Option<(usize, usize)> {
+    enclosing_parens(chars, classes, cursor)
+        .into_iter()
+        .find(|&(open, close)| starts_query(chars, classes, open + 1, close))
+}
+
+/// `iq` / `aq`
+pub fn subquery(text: &str, cursor_pos: usize, around: bool, dialect: DatabaseBackend) -> Option<TextObject> {
+    let chars: Vec<char> = text.chars().collect();
+    let classes = classify(text, dialect);
+    let (open, close) = subquery_at(&chars, &classes, cursor_pos)?;
+    if around {
+        return Some(TextObject::new(open, close));
+    }
+    let (start, end) = trim(&chars, open + 1, close)?;
+    Some(TextObject::new(start, end - 1))
+}
+
+/// Clause keywords in `start..end` at the top level, as (keyword start, keyword end)
+fn clause_starts(chars: &[char], classes: &[Class], start: usize, end: usize) -> Vec<(usize, usize)> {
+    let mut starts = Vec::new();
+    let mut depth = 0;
+    let mut i = start;
+    while i < end {
+        match chars[i] {
+            '(' if classes[i] == Class::Code => depth += 1,
+            ')' if classes[i] == Class::Code => depth -= 1,
+            _ if depth == 0 => {
+                if let Some(keyword_end) = match_clause_keyword(chars, classes, i, end) {
+                    starts.push((i, keyword_end));
+                    i = keyword_end;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    starts
+}
+
+/// End of the clause keyword starting at `i`, if one does
+fn match_clause_keyword(chars: &[char], classes: &[Class], i: usize, end: usize) -> Option<usize> {
+    let (first, first_end) = word_at(chars, classes, i)?;
+    CLAUSE_KEYWORDS.iter().find_map(|keyword| {
+        if keyword[0] != first {
+            return None;
+        }
+        let mut pos = first_end;
+        for word in &keyword[1..] {
+            let next = (pos..end).find(|&j| !chars[j].is_whitespace())?;
+            let (found, found_end) = word_at(chars, classes, next)?;
+            if found != *word {
+                return None;
+            }
+            pos = found_end;
+        }
+        Some(pos)
+    })
+}
+
+/// `ic` / `ac`
+pub fn clause(text: &str, cursor_pos: usize, around: bool, dialect: DatabaseBackend) -> Option<TextObject> {
+    let chars: Vec<char> = text.chars().collect();
+    let classes = classify(text, dialect);
+    let (scope_start, scope_end) = match subquery_at(&chars, &classes, cursor_pos) {
+        Some((open, close)) => (open + 1, close),
+        None => {
+            let statement = statement_at(text, dialect, cursor_pos)?;
+            (statement.start, statement.end)
+        }
+    };
+    let starts = clause_starts(&chars, &classes, scope_start, scope_end);
+    let index = starts.iter().rposition(|&(start, _)| start <= cursor_pos).unwrap_or(0);
+    let &(keyword_start, keyword_end) = starts.get(index)?;
+    let clause_end = starts.get(index + 1).map_or(scope_end, |&(next, _)| next);
+    let (_, body_end) = trim(&chars, keyword_start, clause_end)?;
+    if around {
+        return Some(with_whitespace(&chars, keyword_start, body_end, clause_end));
+    }
+    let (start, end) = trim(&chars, keyword_end, clause_end)?;
+    Some(TextObject::new(start, end - 1))
+}
+
+/// The part of a dotted name at `pos`: a quoted name or a run of name chars
+fn name_part(chars: &[char], classes: &[Class], pos: usize) -> Option<(usize, usize)> {
+    let c = *chars.get(pos)?;
+    if classes[pos] == Class::Quoted {
+        let start = (0..=pos).rev().take_while(|&i| classes[i] == Class::Quoted).last()?;
+        if !matches!(chars[start], '[' | '"' | '`') {
+            return None;
+        }
+        let end = (pos..chars.len()).take_while(|&i| classes[i] == Class::Quoted).last()?;
+        return Some((start, end));
+    }
+    if classes[pos] != Class::Code || !is_ident_char(c) {
+        return None;
+    }
+    let start = (0..=pos).rev().take_while(|&i| classes[i] == Class::Code && is_ident_char(chars[i])).last()?;
+    let end = (pos..chars.len()).take_while(|&i| classes[i] == Class::Code && is_ident_char(chars[i])).last()?;
+    Some((start, end))
+}
+
+/// `ii` / `ai`
+pub fn identifier(text: &str, cursor_pos: usize, around: bool, dialect: DatabaseBackend) -> Option<TextObject> {
+    let chars: Vec<char> = text.chars().collect();
+    let classes = classify(text, dialect);
+    // On a dot, take the name before it
+    let pos = if chars.get(cursor_pos) == Some(&'.') { cursor_pos.checked_sub(1)? } else { cursor_pos };
+    let (mut start, mut end) = name_part(&chars, &classes, pos)?;
+    while start >= 2
+        && chars[start - 1] == '.'
+        && let Some((prev, _)) = name_part(&chars, &classes, start - 2)
+    {
+        start = prev;
+    }
+    while chars.get(end + 1) == Some(&'.')
+        && let Some((_, next)) = name_part(&chars, &classes, end + 2)
+    {
+        end = next;
+    }
+    if around {
+        Some(with_whitespace(&chars, start, end + 1, chars.len()))
+    } else {
+        Some(TextObject::new(start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Object = fn(&str, usize, bool, DatabaseBackend) -> Option<TextObject>;
+
+    fn select(text: &str, cursor: usize, f: Object, around: bool) -> Option<String> {
+        f(text, cursor, around, DatabaseBackend::SqlServer).map(|obj| obj.extract(text))
+    }
+
+    #[test]
+    fn test_statement() {
+        let text = "select 1;\nselect ';' from t;\n\nupdate t\nset a = 1\nGO\ndelete t";
+        assert_eq!(select(text, 3, statement, false).as_deref(), Some("select 1"));
+        assert_eq!(select(text, 3, statement, true).as_deref(), Some("select 1;\n"));
+        // A `;` in a string doesn't end the statement
+        assert_eq!(select(text, 12, statement, false).as_deref(), Some("select ';' from t"));
+        // A blank line and GO end statements too
+        assert_eq!(select(text, 40, statement, false).as_deref(), Some("update t\nset a = 1"));
+        assert_eq!(select(text, text.len() - 1, statement, false).as_deref(), Some("delete t"));
+        assert_eq!(select(text, text.len() - 1, statement, true).as_deref(), Some("\ndelete t"));
+    }
+
+    #[test]
+    fn test_clause() {
+        let text = "SELECT a, b\nFROM t\nWHERE x IN (SELECT id FROM u WHERE y = 1)\nORDER BY a";
+        assert_eq!(select(text, 8, clause, false).as_deref(), Some("a, b"));
+        assert_eq!(select(text, 2, clause, false).as_deref(), Some("a, b"));
+        assert_eq!(select(text, 23, clause, false).as_deref(), Some("x IN (SELECT id FROM u WHERE y = 1)"));
+        assert_eq!(select(text, 70, clause, false).as_deref(), Some("a"));
+        assert_eq!(select(text, 70, clause, true).as_deref(), Some("\nORDER BY a"));
+        assert_eq!(select(text, 14, clause, true).as_deref(), Some("FROM t\n"));
+        // Inside a subquery its own clauses are used
+        let inner = text.find("y = 1").unwrap();
+        assert_eq!(select(text, inner, clause, false).as_deref(), Some("y = 1"));
+    }
+
+    #[test]
+    fn test_identifier() {
+        let text = "select [dbo].[My Table].id, \"x\".y from t";
+        assert_eq!(select(text, 16, identifier, false).as_deref(), Some("[dbo].[My Table].id"));
+        assert_eq!(select(text, 8, identifier, false).as_deref(), Some("[dbo].[My Table].id"));
+        assert_eq!(select(text, 12, identifier, false).as_deref(), Some("[dbo].[My Table].id"));
+        assert_eq!(select(text, 32, identifier, false).as_deref(), Some("\"x\".y"));
+        assert_eq!(select(text, 2, identifier, true).as_deref(), Some("select "));
+        assert_eq!(select(text, 6, identifier, false), None);
+        assert_eq!(select("where a = 'it''s'", 12, identifier, false), None);
+    }
+
+    #[test]
+    fn test_subquery() {
+        let text = "select * from (\n  select id from t where f(x) > 0\n) s";
+        let inside = text.find("f(x)").unwrap() + 2;
+        assert_eq!(select(text, inside, subquery, false).as_deref(), Some("select id from t where f(x) > 0"));
+        assert_eq!(select(text, 14, subquery, true).unwrap().len(), text.len() - 16);
+        assert_eq!(select(text, 2, subquery, false), None);
+    }
+}
@@ -21,7 +21,7 @@ impl TextObject {
     }
     
     /// Get the text content from the source
-    pub fn extract<'a>(&self, text: &'a str) -> String {
+    pub fn extract(&self, text: &str) -> String {
         text.chars().skip(self.start).take(self.end - self.start + 1).collect()
     }
 }
@@ -96,12 +96,12 @@ pub fn inner_quoted(text: &str, cursor_pos: usize, quote_char: char) -> Option<T
     
     // Search forward for closing quote
     if let Some(s) = start {
-        for i in (s + 1)..chars.len() {
-            if chars[i] == quote_char {
+        for (i, &c) in chars.iter().enumerate().skip(s + 1) {
+            if c == quote_char {
                 end = Some(i);
                 break;
             }
-            if chars[i] == '\n' {
+            if c == '\n' {
                 break; // Don't cross lines
             }
         }
@@ -131,12 +131,12 @@ pub fn a_quoted(text: &str, cursor_pos: usize, quote_char: char) -> Option<TextO
     }
     
     if let Some(s) = start {
-        for i in (s + 1)..chars.len() {
-            if chars[i] == quote_char {
+        for (i, &c) in chars.iter().enumerate().skip(s + 1) {
+            if c == quote_char {
                 end = Some(i);
                 break;
             }
-            if chars[i] == '\n' {
+            if c == '\n' {
                 break;
             }
         }
@@ -172,10 +172,10 @@ pub fn inner_bracket(text: &str, cursor_pos: usize, open: char, close: char) ->
     // Search forward for closing bracket
     if let Some(s) = start {
         depth = 0;
-        for i in (s + 1)..chars.len() {
-            if chars[i] == open {
+        for (i, &c) in chars.iter().enumerate().skip(s + 1) {
+            if c == open {
                 depth += 1;
-            } else if chars[i] == close {
+            } else if c == close {
                 if depth == 0 {
                     if i > s + 1 {
                         return Some(TextObject::new(s + 1, i - 1));
@@ -212,10 +212,10 @@ pub fn a_bracket(text: &str, cursor_pos: usize, open: char, close: char) -> Opti
     
     if let Some(s) = start {
         depth = 0;
-        for i in (s + 1)..chars.len() {
-            if chars[i] == open {
+        for (i, &c) in chars.iter().enumerate().skip(s + 1) {
+            if c == open {
                 depth += 1;
-            } else if chars[i] == close {
+            } else if c == close {
                 if depth == 0 {
                     return Some(TextObject::new(s, i));
                 }
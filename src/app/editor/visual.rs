@@ -0,0 +1,196 @@
+//! Visual mode selections: characterwise (`v`), linewise (`V`) and
+//! blockwise (`Ctrl+V`)
+//!
+//! Positions are char indices into the buffer; blocks are described by
+//! line and column so they survive edits on the lines above.
+
+use super::operations;
+use super::registers::{line_col, position_of};
+
+/// Which kind of selection Visual mode is making
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VisualKind {
+    #[default]
+    Char,
+    Line,
+    Block,
+}
+
+impl VisualKind {
+    /// Mode label shown in the editor title
+    pub fn label(self) -> &'static str {
+        match self {
+            VisualKind::Char => "[VISUAL]",
+            VisualKind::Line => "[VISUAL LINE]",
+            VisualKind::Block => "[VISUAL BLOCK]",
+        }
+    }
+}
+
+/// A rectangle of text: lines and columns, both inclusive. `right` is
+/// `usize::MAX` for a block extended to every line's end with `$`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Block {
+    pub first_line: usize,
+    pub last_line: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Block {
+    /// The block with `anchor` and `cursor` in opposite corners
+    pub fn new(text: &str, anchor: usize, cursor: usize) -> Self {
+        let (anchor_line, anchor_col) = line_col(text, anchor);
+        let (cursor_line, cursor_col) = line_col(text, cursor);
+        Self {
+            first_line: anchor_line.min(cursor_line),
+            last_line: anchor_line.max(cursor_line),
+            left: anchor_col.min(cursor_col),
+            right: anchor_col.max(cursor_col),
+        }
+    }
+
+    pub fn contains(&self, line: usize, col: usize) -> bool {
+        (self.first_line..=self.last_line).contains(&line) && (self.left..=self.right).contains(&col)
+    }
+
+    /// The part of each line inside the block as char ranges (start, end
+    /// exclusive), top to bottom. Lines too short to reach it give empty ranges.
+    pub fn ranges(&self, text: &str) -> Vec<(usize, usize)> {
+        let mut ranges = Vec::new();
+        let mut pos = 0;
+        for (i, line) in text.split('\n').enumerate() {
+            let len = line.chars().count();
+            if i > self.last_line {
+                break;
+            }
+            if i >= self.first_line {
+                let start = pos + self.left.min(len);
+                let end = pos + self.right.saturating_add(1).min(len);
+                ranges.push((start, end.max(start)));
+            }
+            pos += len + 1;
+        }
+        ranges
+    }
+
+    /// The block's text, one line per block row
+    pub fn text(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        self.ranges(text)
+            .iter()
+            .map(|&(start, end)| chars[start..end].iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Remove the block from every line
+    pub fn delete(&self, text: &mut String) {
+        for (start, end) in self.ranges(text).into_iter().rev() {
+            if end > start {
+                operations::delete_range(text, start, end - 1);
+            }
+        }
+    }
+
+    /// Position of the top-left corner
+    pub fn top_left(&self, text: &str) -> usize {
+        position_of(text, self.first_line, self.left)
+    }
+}
+
+/// Insert `content` at column `col` of lines `first..=last` (None = at each
+/// line's end). Lines shorter than `col` are skipped, or padded with spaces
+/// when `pad` is set (`A`).
+pub fn insert_at_column(text: &mut String, first: usize, last: usize, col: Option<usize>, content: &str, pad: bool) {
+    for line in (first..=last).rev() {
+        let start = position_of(text, line, 0);
+        let len = position_of(text, line, usize::MAX) - start;
+        let col = col.unwrap_or(len);
+        if len < col && !pad {
+            continue;
+        }
+        let fill = " ".repeat(col.saturating_sub(len));
+        operations::insert_text(text, start + len.min(col), &format!("{}{}", fill, content));
+    }
+}
+
+/// A block `I`, `A` or `c` in progress: what is typed on the first line is
+/// copied to the other lines when Insert mode ends
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockInsert {
+    /// Text when Insert mode was entered, and where typing started
+    pub before: String,
+    pub start: usize,
+    pub first_line: usize,
+    pub last_line: usize,
+    /// Column typed at, or None for each line's end (`$A`)
+    pub col: Option<usize>,
+    /// Pad short lines up to `col` (`A`) instead of skipping them
+    pub pad: bool,
+}
+
+/// What Visual mode has selected, for drawing it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    /// Char range, both ends inclusive
+    Range(usize, usize),
+    Block(Block),
+}
+
+impl Selection {
+    /// Whether the char at `pos` (on `line`, column `col`) is selected
+    pub fn contains(&self, pos: usize, line: usize, col: usize) -> bool {
+        match self {
+            Selection::Range(start, end) => (*start..=*end).contains(&pos),
+            Selection::Block(block) => block.contains(line, col),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "select\n  a,\n  bb\nfrom t";
+
+    #[test]
+    fn test_block_ranges_and_text() {
+        // From "e" in "select" (col 1) down to col 2 of "  bb"
+        let block = Block::new(TEXT, 1, position_of(TEXT, 2, 2));
+        assert_eq!(block, Block { first_line: 0, last_line: 2, left: 1, right: 2 });
+        assert_eq!(block.text(TEXT), "el\n a\n b");
+        // The anchor may be below and to the right of the cursor
+        assert_eq!(Block::new(TEXT, position_of(TEXT, 2, 2), 1), block);
+
+        // Lines that end inside or before the block give what they have
+        let short = Block { first_line: 0, last_line: 2, left: 3, right: 5 };
+        assert_eq!(short.text(TEXT), "ect\n,\nb");
+        let past = Block { first_line: 1, last_line: 1, left: 6, right: 7 };
+        assert_eq!(past.ranges(TEXT), vec![(11, 11)]);
+        let to_end = Block { first_line: 0, last_line: 1, left: 2, right: usize::MAX };
+        assert_eq!(to_end.text(TEXT), "lect\na,");
+    }
+
+    #[test]
+    fn test_block_delete() {
+        let mut text = TEXT.to_string();
+        Block { first_line: 1, last_line: 2, left: 0, right: 1 }.delete(&mut text);
+        assert_eq!(text, "select\na,\nbb\nfrom t");
+    }
+
+    #[test]
+    fn test_insert_at_column() {
+        let mut text = "a\nbbb\n\ncc".to_string();
+        insert_at_column(&mut text, 0, 3, Some(1), "x.", false);
+        assert_eq!(text, "ax.\nbx.bb\n\ncx.c");
+
+        let mut text = "a\nbbb".to_string();
+        insert_at_column(&mut text, 0, 1, Some(3), ";", true);
+        assert_eq!(text, "a  ;\nbbb;");
+
+        let mut text = "a\nbbb".to_string();
+        insert_at_column(&mut text, 0, 1, None, ";", false);
+        assert_eq!(text, "a;\nbbb;");
+    }
+}
@@ -0,0 +1,185 @@
+//! Registers, marks and macros on `App`
+//!
+//! Registers are shared by all buffers; marks belong to a buffer and are
+//! remembered per file. Both are saved to `editor_state.json` next to the
+//! query history when the app quits.
+
+use crate::app::editor::registers::{keys_to_notation, line_col, notation_to_keys, position_of, Marks, RegisterWrite, Registers};
+use crate::app::editor::first_non_whitespace;
+use crate::app::App;
+use anyhow::Result;
+use crossterm::event::KeyEvent;
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Upper bound on queued macro keys, so a macro calling itself stops
+const MAX_REPLAY_KEYS: usize = 100_000;
+
+/// What is kept between sessions
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EditorState {
+    pub registers: Registers,
+    /// Marks by canonical file path
+    pub marks: HashMap<String, Marks>,
+}
+
+impl EditorState {
+    fn state_file() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("alrajhi-sql-tui")
+            .join("editor_state.json")
+    }
+
+    /// Load the saved state, or an empty one
+    pub fn load() -> Self {
+        fs::read_to_string(Self::state_file())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = Self::state_file();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Key under which a file's marks are saved
+fn marks_key(path: &Path) -> String {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()).to_string_lossy().to_string()
+}
+
+fn clipboard_text() -> Option<String> {
+    arboard::Clipboard::new().ok().and_then(|mut c| c.get_text().ok())
+}
+
+impl App {
+    /// Text to paste from register `name` (None = unnamed). The unnamed
+    /// register follows the system clipboard when something was copied in
+    /// another program since our last write.
+    pub(crate) fn read_register(&mut self, name: Option<char>) -> Option<String> {
+        match name.unwrap_or('"') {
+            '+' | '*' => clipboard_text(),
+            '"' => {
+                if let Some(text) = clipboard_text()
+                    && !text.is_empty()
+                    && self.clipboard_sent.as_ref() != Some(&text)
+                {
+                    return Some(text);
+                }
+                self.editor_state.registers.get('"').map(str::to_string)
+            }
+            c => self.editor_state.registers.get(c).map(str::to_string),
+        }
+    }
+
+    /// Store yanked or deleted text in register `name` (None = unnamed). The
+    /// unnamed and clipboard registers also go to the system clipboard.
+    pub(crate) fn write_register(&mut self, name: Option<char>, text: &str, write: RegisterWrite) {
+        self.editor_state.registers.store(name, text, write);
+        if matches!(name, None | Some('"' | '+' | '*'))
+            && let Ok(mut clipboard) = arboard::Clipboard::new()
+        {
+            let _ = clipboard.set_text(text);
+            self.clipboard_sent = Some(text.to_string());
+        }
+    }
+
+    /// `m{a-z}`
+    pub(crate) fn set_mark(&mut self, name: char) {
+        self.marks.insert(name, line_col(&self.query, self.cursor_pos));
+    }
+
+    /// Position of a mark (`` ` `` and `'` are the position before the last jump)
+    pub(crate) fn mark_target(&mut self, name: char, linewise: bool) -> Option<usize> {
+        let name = if name == '`' { '\'' } else { name };
+        let Some(&(line, col)) = self.marks.get(&name) else {
+            self.error = Some(t!("mark_not_set", name = name).to_string());
+            return None;
+        };
+        let pos = position_of(&self.query, line, col);
+        Some(if linewise { first_non_whitespace(&self.query, pos) } else { pos })
+    }
+
+    /// Remember where the cursor was before a jump (`G`, `gg`, marks)
+    pub(crate) fn set_jump_mark(&mut self) {
+        self.set_mark('\'');
+    }
+
+    /// Start recording keys into register `name` (`qa`)
+    pub(crate) fn start_recording(&mut self, name: char) {
+        self.recording = Some((name, Vec::new()));
+    }
+
+    /// Stop recording (`q`), dropping the `q` itself
+    pub(crate) fn stop_recording(&mut self) {
+        if let Some((name, mut keys)) = self.recording.take() {
+            keys.pop();
+            self.editor_state.registers.store_macro(name, &keys_to_notation(&keys));
+        }
+    }
+
+    /// Record a key typed by the user while a macro is being recorded
+    pub(crate) fn record_key(&mut self, key: KeyEvent) {
+        if let Some((_, keys)) = self.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    /// Queue the keys of macro `name` (`@@` = the last one played) `count` times
+    pub(crate) fn play_macro(&mut self, name: char, count: usize) {
+        let Some(name) = (if name == '@' { self.last_macro } else { Some(name) }) else {
+            return;
+        };
+        self.last_macro = Some(name);
+        let Some(notation) = self.editor_state.registers.get(name) else {
+            return;
+        };
+        let keys = notation_to_keys(notation);
+        if self.replay_keys.len() + keys.len() * count > MAX_REPLAY_KEYS {
+            self.replay_keys.clear();
+            self.error = Some(t!("macro_too_long").to_string());
+            return;
+        }
+        // Played before whatever is still queued, so nested macros run in order
+        for _ in 0..count {
+            for key in keys.iter().rev() {
+                self.replay_keys.push_front(*key);
+            }
+        }
+    }
+
+    /// Keep the active buffer's marks under its file before it changes
+    pub(crate) fn remember_marks(&mut self) {
+        if let Some(path) = &self.query_path {
+            self.editor_state.marks.insert(marks_key(path), self.marks.clone());
+        }
+    }
+
+    /// Marks saved for a file
+    pub(crate) fn saved_marks(&self, path: &Path) -> Marks {
+        self.editor_state.marks.get(&marks_key(path)).cloned().unwrap_or_default()
+    }
+
+    /// Save registers and every open file's marks
+    pub fn save_editor_state(&mut self) {
+        self.remember_marks();
+        for buf in &self.buffers {
+            if let Some(path) = &buf.query_path
+                && !buf.marks.is_empty()
+            {
+                self.editor_state.marks.insert(marks_key(path), buf.marks.clone());
+            }
+        }
+        let _ = self.editor_state.save();
+    }
+}
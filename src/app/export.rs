@@ -1,41 +1,136 @@
 //! Export functionality for query results
 
 use crate::app::App;
+use crate::db::QueryResult;
 use anyhow::Result;
 use rust_i18n::t;
+use std::io::Write;
 
-impl App {
-    /// Export results to CSV file
-    pub fn export_results_csv(&mut self) {
-        if self.result.rows.is_empty() {
-            self.error = Some(t!("no_results_to_export").to_string());
-            return;
+/// Output format for exported results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    /// Plain-text aligned table (for terminals and pipes)
+    Table,
+}
+
+impl ExportFormat {
+    /// Parse a format name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            "table" | "txt" => Some(ExportFormat::Table),
+            _ => None,
         }
+    }
 
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let filename = format!("export_{}.csv", timestamp);
+    /// File extension used for exported files
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Table => "txt",
+        }
+    }
+}
 
-        match self.export_csv(&filename) {
-            Ok(()) => {
-                self.message = Some(t!("exported_rows", count = self.result.rows.len(), filename = filename).to_string());
+/// Write a result set to any writer in the given format
+pub fn write_results<W: Write>(result: &QueryResult, format: ExportFormat, writer: W) -> Result<()> {
+    match format {
+        ExportFormat::Csv => write_csv(result, writer),
+        ExportFormat::Json => write_json(result, writer),
+        ExportFormat::Table => write_table(result, writer),
+    }
+}
+
+/// Write results as CSV (header row + one record per row)
+fn write_csv<W: Write>(result: &QueryResult, writer: W) -> Result<()> {
+    let mut wtr = csv::Writer::from_writer(writer);
+    let headers: Vec<String> = result.columns.iter().map(|c| c.name.clone()).collect();
+    wtr.write_record(&headers)?;
+    for row in &result.rows {
+        let record: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+        wtr.write_record(&record)?;
+    }
+    wtr.flush()?;
+    Ok(())
+}
+
+/// Write results as a pretty-printed JSON array of objects
+fn write_json<W: Write>(result: &QueryResult, mut writer: W) -> Result<()> {
+    let mut rows: Vec<serde_json::Map<String, serde_json::Value>> = Vec::new();
+    for row in &result.rows {
+        let mut obj = serde_json::Map::new();
+        for (i, col) in result.columns.iter().enumerate() {
+            if let Some(cell) = row.get(i) {
+                obj.insert(col.name.clone(), serde_json::Value::String(cell.to_string()));
             }
-            Err(e) => {
-                self.error = Some(t!("export_failed", error = e.to_string()).to_string());
+        }
+        rows.push(obj);
+    }
+    serde_json::to_writer_pretty(&mut writer, &rows)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+/// Write results as an aligned plain-text table
+fn write_table<W: Write>(result: &QueryResult, mut writer: W) -> Result<()> {
+    use unicode_width::UnicodeWidthStr;
+
+    let cells: Vec<Vec<String>> = result.rows
+        .iter()
+        .map(|row| row.iter().map(|c| c.to_string().replace('\n', " ")).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = result.columns.iter().map(|c| c.name.width()).collect();
+    for row in &cells {
+        for (i, cell) in row.iter().enumerate() {
+            if let Some(w) = widths.get_mut(i) {
+                *w = (*w).max(cell.width());
             }
         }
     }
 
+    let pad = |text: &str, width: usize| format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())));
+
+    let header: Vec<String> = result.columns.iter().enumerate().map(|(i, c)| pad(&c.name, widths[i])).collect();
+    writeln!(writer, "{}", header.join(" | ").trim_end())?;
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    writeln!(writer, "{}", separator.join("-+-"))?;
+    for row in &cells {
+        let line: Vec<String> = row.iter().enumerate().map(|(i, c)| pad(c, widths.get(i).copied().unwrap_or(0))).collect();
+        writeln!(writer, "{}", line.join(" | ").trim_end())?;
+    }
+    writeln!(writer, "({} rows)", result.rows.len())?;
+    Ok(())
+}
+
+impl App {
+    /// Export results to CSV file
+    pub fn export_results_csv(&mut self) {
+        self.export_results(ExportFormat::Csv, None);
+    }
+
     /// Export results to JSON file
     pub fn export_results_json(&mut self) {
+        self.export_results(ExportFormat::Json, None);
+    }
+
+    /// Export results in the given format, to a timestamped file when no name is given
+    pub fn export_results(&mut self, format: ExportFormat, filename: Option<String>) {
         if self.result.rows.is_empty() {
             self.error = Some(t!("no_results_to_export").to_string());
             return;
         }
 
-        let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-        let filename = format!("export_{}.json", timestamp);
+        let filename = filename.unwrap_or_else(|| {
+            let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
+            format!("export_{}.{}", timestamp, format.extension())
+        });
 
-        match self.export_json(&filename) {
+        match self.export_to_file(&filename, format) {
             Ok(()) => {
                 self.message = Some(t!("exported_rows", count = self.result.rows.len(), filename = filename).to_string());
             }
@@ -45,33 +140,56 @@ impl App {
         }
     }
 
-    /// Write results to CSV file
-    fn export_csv(&self, filename: &str) -> Result<()> {
-        let mut wtr = csv::Writer::from_path(filename)?;
-        let headers: Vec<String> = self.result.columns.iter().map(|c| c.name.clone()).collect();
-        wtr.write_record(&headers)?;
-        for row in &self.result.rows {
-            let record: Vec<String> = row.iter().map(|c| c.to_string()).collect();
-            wtr.write_record(&record)?;
-        }
-        wtr.flush()?;
-        Ok(())
+    /// Write results to a file
+    fn export_to_file(&self, filename: &str, format: ExportFormat) -> Result<()> {
+        let file = std::fs::File::create(filename)?;
+        write_results(&self.result, format, std::io::BufWriter::new(file))
     }
+}
 
-    /// Write results to JSON file
-    fn export_json(&self, filename: &str) -> Result<()> {
-        let mut rows: Vec<serde_json::Map<String, serde_json::Value>> = Vec::new();
-        for row in &self.result.rows {
-            let mut obj = serde_json::Map::new();
-            for (i, col) in self.result.columns.iter().enumerate() {
-                if let Some(cell) = row.get(i) {
-                    obj.insert(col.name.clone(), serde_json::Value::String(cell.to_string()));
-                }
-            }
-            rows.push(obj);
-        }
-        let json = serde_json::to_string_pretty(&rows)?;
-        std::fs::write(filename, json)?;
-        Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{CellValue, ColumnInfo};
+
+    fn sample() -> QueryResult {
+        let mut result = QueryResult::empty();
+        result.columns = vec![
+            ColumnInfo { name: "id".into(), type_name: "int".into(), max_width: 4 },
+            ColumnInfo { name: "name".into(), type_name: "nvarchar".into(), max_width: 4 },
+        ];
+        result.rows = vec![
+            vec![CellValue::Int(1), CellValue::String("Ana".into())],
+            vec![CellValue::Int(22), CellValue::Null],
+        ];
+        result.row_count = 2;
+        result
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ExportFormat::parse("CSV"), Some(ExportFormat::Csv));
+        assert_eq!(ExportFormat::parse("table"), Some(ExportFormat::Table));
+        assert_eq!(ExportFormat::parse("xml"), None);
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut out = Vec::new();
+        write_results(&sample(), ExportFormat::Csv, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "id,name\n1,Ana\n22,NULL\n");
+    }
+
+    #[test]
+    fn test_write_table() {
+        let mut out = Vec::new();
+        write_results(&sample(), ExportFormat::Table, &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "id | name");
+        assert_eq!(lines[1], "---+-----");
+        assert_eq!(lines[2], "1  | Ana");
+        assert_eq!(lines[3], "22 | NULL");
+        assert_eq!(lines[4], "(2 rows)");
     }
 }
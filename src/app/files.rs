@@ -0,0 +1,118 @@
+//! Query files backing the editor buffer (open, save, dirty tracking)
+
+use crate::app::buffers::UNDO_HISTORY_LIMIT;
+use crate::app::{App, UndoManager};
+use anyhow::{Context, Result};
+use rust_i18n::t;
+use std::path::{Path, PathBuf};
+
+/// Expand a leading `~` to the home directory
+pub fn expand_path(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches('/')),
+            None => PathBuf::from(path),
+        },
+        _ => PathBuf::from(path),
+    }
+}
+
+impl App {
+    /// Whether the buffer has changes that were not written to disk
+    pub fn is_dirty(&self) -> bool {
+        self.query != self.saved_query
+    }
+
+    /// File name shown in the editor title (None for an unnamed buffer)
+    pub fn buffer_name(&self) -> Option<String> {
+        self.query_path.as_ref().map(|p| {
+            p.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| p.display().to_string())
+        })
+    }
+
+    /// Load a file into the editor. A path that does not exist yet starts
+    /// an empty buffer that will be created on the first write.
+    pub fn open_file(&mut self, path: PathBuf) {
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.message = Some(t!("file_new", path = path.display().to_string()).to_string());
+                String::new()
+            }
+            Err(e) => {
+                self.error = Some(t!("file_open_failed", path = path.display().to_string(), error = e.to_string()).to_string());
+                return;
+            }
+        };
+
+        self.remember_marks();
+        self.marks = self.saved_marks(&path);
+        // Undo history saved with the file, if the file hasn't changed since
+        match UndoManager::load_for(&path, &text, UNDO_HISTORY_LIMIT) {
+            Some(history) => self.undo_manager = history,
+            None => self.undo_manager.reset(&text),
+        }
+        self.query = text.clone();
+        self.cursor_pos = 0;
+        self.saved_query = text;
+        self.query_scroll_x = 0;
+        self.query_scroll_y = 0;
+        self.completion.hide();
+        self.remember_recent_file(&path);
+        self.query_path = Some(path);
+    }
+
+    /// Write the buffer to `path`, or to the current file when None.
+    /// Writing to a new path makes it the buffer's file.
+    pub fn save_file(&mut self, path: Option<PathBuf>) -> Result<()> {
+        let path = path
+            .or_else(|| self.query_path.clone())
+            .context(t!("file_no_name").to_string())?;
+
+        std::fs::write(&path, &self.query)
+            .with_context(|| t!("file_write_failed", path = path.display().to_string()).to_string())?;
+
+        self.saved_query = self.query.clone();
+        // Keep the undo history with the file; losing it is not worth an error
+        self.undo_manager.commit(&self.query);
+        let _ = self.undo_manager.save_for(&path, &self.query);
+        self.message = Some(t!("file_written", path = path.display().to_string(), lines = self.query.lines().count()).to_string());
+        self.remember_recent_file(&path);
+        self.query_path = Some(path);
+        Ok(())
+    }
+
+    /// Quit, refusing once when there are unsaved changes unless forced
+    pub fn request_quit(&mut self, force: bool) {
+        if force || self.quit_pending || !self.any_buffer_dirty() {
+            self.should_quit = true;
+        } else {
+            self.quit_pending = true;
+            self.error = Some(t!("file_unsaved_changes").to_string());
+        }
+    }
+
+    /// Record a file in the recent files list and persist it
+    fn remember_recent_file(&mut self, path: &Path) {
+        let full = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.app_config.add_recent_file(&full.to_string_lossy());
+        let _ = self.app_config.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_path() {
+        assert_eq!(expand_path("a/b.sql"), PathBuf::from("a/b.sql"));
+        assert_eq!(expand_path("~user/b.sql"), PathBuf::from("~user/b.sql"));
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_path("~/q.sql"), home.join("q.sql"));
+            assert_eq!(expand_path("~"), home);
+        }
+    }
+}
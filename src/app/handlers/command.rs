@@ -0,0 +1,333 @@
+//! Ex command line handler (`:` from Normal mode or any non-editing panel)
+
+use crate::app::command::{complete_command_line, parse_command, substitute, ExCommand, Substitute};
+use crate::app::files::expand_path;
+use crate::app::options::{option_name, SetArg};
+use crate::app::{App, InputMode};
+use crate::db::DatabaseBackend;
+use crate::ui::{ActiveTheme, Theme};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rust_i18n::t;
+
+impl App {
+    /// Open the command line
+    pub(crate) fn enter_command_mode(&mut self) {
+        self.command_buffer.clear();
+        self.command_history.reset();
+        self.command_completion = None;
+        self.completion.hide();
+        self.input_mode = InputMode::Command;
+    }
+
+    /// Close the command line and go back to Normal mode
+    fn leave_command_mode(&mut self) {
+        self.command_buffer.clear();
+        self.command_history.reset();
+        self.command_completion = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Handle keys while typing a `:` command
+    pub(crate) async fn handle_command_mode(&mut self, key: KeyEvent) -> Result<()> {
+        if self.search_prompt.is_some() {
+            self.handle_search_key(key);
+            return Ok(());
+        }
+
+        // Any key other than Tab ends a completion cycle
+        if !matches!(key.code, KeyCode::Tab | KeyCode::BackTab) {
+            self.command_completion = None;
+        }
+
+        match key.code {
+            KeyCode::Esc => self.leave_command_mode(),
+            KeyCode::Enter => {
+                let command = std::mem::take(&mut self.command_buffer);
+                self.command_history.push(&command);
+                self.leave_command_mode();
+                self.execute_command(&command).await;
+            }
+            // Backspace on an empty line leaves the command line, like vim
+            KeyCode::Backspace if self.command_buffer.is_empty() => self.leave_command_mode(),
+            KeyCode::Backspace => {
+                self.command_buffer.pop();
+                self.command_history.reset();
+            }
+            KeyCode::Up => {
+                if let Some(entry) = self.command_history.prev(&self.command_buffer) {
+                    self.command_buffer = entry;
+                }
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.command_history.next() {
+                    self.command_buffer = entry;
+                }
+            }
+            KeyCode::Tab => self.cycle_command_completion(true),
+            KeyCode::BackTab => self.cycle_command_completion(false),
+            // Ctrl+U clears the line, Ctrl+W deletes the last word
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.command_buffer.clear();
+            }
+            KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                let trimmed = self.command_buffer.trim_end();
+                let keep = trimmed.rfind(|c: char| c.is_whitespace() || c == '/').map_or(0, |i| i + 1);
+                self.command_buffer.truncate(keep);
+            }
+            KeyCode::Char(c) => {
+                self.command_buffer.push(c);
+                self.command_history.reset();
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Complete the command line, cycling through candidates on repeated Tab
+    fn cycle_command_completion(&mut self, forward: bool) {
+        if let Some((candidates, selected)) = self.command_completion.as_mut() {
+            let len = candidates.len();
+            *selected = if forward { (*selected + 1) % len } else { (*selected + len - 1) % len };
+            self.command_buffer = candidates[*selected].clone();
+            return;
+        }
+
+        let connections: Vec<String> = self.app_config.connections.iter().map(|c| c.name.clone()).collect();
+        let candidates = complete_command_line(&self.command_buffer, &connections);
+        match candidates.len() {
+            0 => {}
+            1 => self.command_buffer = candidates[0].clone(),
+            _ => {
+                let selected = if forward { 0 } else { candidates.len() - 1 };
+                self.command_buffer = candidates[selected].clone();
+                self.command_completion = Some((candidates, selected));
+            }
+        }
+    }
+
+    /// Run a command line (without the leading `:`)
+    pub(crate) async fn execute_command(&mut self, input: &str) {
+        if input.trim().is_empty() {
+            return;
+        }
+        self.error = None;
+        let command = match parse_command(input) {
+            Ok(command) => command,
+            Err(e) => {
+                self.error = Some(e);
+                return;
+            }
+        };
+
+        match command {
+            ExCommand::Edit { path, force } => {
+                if self.is_dirty() && !force {
+                    self.error = Some(t!("file_unsaved_changes").to_string());
+                    return;
+                }
+                match path.map(|p| expand_path(&p)).or_else(|| self.query_path.clone()) {
+                    Some(path) => self.open_file(path),
+                    None => self.error = Some(t!("file_no_name").to_string()),
+                }
+            }
+            ExCommand::Write { path } => {
+                self.write_or_report(path.map(|p| expand_path(&p)));
+            }
+            ExCommand::SaveAs { path } => {
+                self.write_or_report(Some(expand_path(&path)));
+            }
+            ExCommand::Quit { force } => {
+                // With several tabs open, :q closes the current one
+                if self.close_buffer(force) {
+                    return;
+                }
+                if self.is_dirty() && !force {
+                    self.error = Some(t!("file_unsaved_changes").to_string());
+                } else {
+                    self.should_quit = true;
+                }
+            }
+            ExCommand::WriteQuit => {
+                if (!self.is_dirty() || self.write_or_report(None)) && !self.close_buffer(true) {
+                    self.should_quit = true;
+                }
+            }
+            ExCommand::QuitAll { force } => {
+                if self.any_buffer_dirty() && !force {
+                    self.error = Some(t!("file_unsaved_changes").to_string());
+                } else {
+                    self.should_quit = true;
+                }
+            }
+            ExCommand::TabNew { path } => {
+                self.new_buffer();
+                if let Some(path) = path {
+                    self.open_file(expand_path(&path));
+                }
+            }
+            ExCommand::TabNext => self.next_buffer(),
+            ExCommand::TabPrev => self.prev_buffer(),
+            ExCommand::TabClose { force } => {
+                if !self.close_buffer(force) {
+                    self.error = Some(t!("buffer_last").to_string());
+                }
+            }
+            ExCommand::Recent { index: None } => {
+                if self.app_config.recent_files.is_empty() {
+                    self.message = Some(t!("file_no_recent").to_string());
+                } else {
+                    let list: Vec<String> = self.app_config.recent_files
+                        .iter()
+                        .enumerate()
+                        .map(|(i, p)| format!("{}: {}", i + 1, p))
+                        .collect();
+                    self.message = Some(list.join("  "));
+                }
+            }
+            ExCommand::Recent { index: Some(n) } => {
+                if self.is_dirty() {
+                    self.error = Some(t!("file_unsaved_changes").to_string());
+                    return;
+                }
+                match self.app_config.recent_files.get(n - 1).cloned() {
+                    Some(path) => self.open_file(path.into()),
+                    None => self.error = Some(t!("cmd_invalid_argument", command = "recent", arg = n).to_string()),
+                }
+            }
+            ExCommand::Set { args } => self.set_options(&args),
+            ExCommand::Connect { name: None } => {
+                self.show_connection_modal = true;
+                self.update_form_from_selection();
+            }
+            ExCommand::Connect { name: Some(name) } => {
+                let Some(config) = self.app_config.get_connection(&name).cloned() else {
+                    self.error = Some(t!("cli_connection_not_found", name = name).to_string());
+                    return;
+                };
+                if let Err(e) = self.connect(&config).await {
+                    self.error = Some(t!("connection_error", error = format!("{:#}", e)).to_string());
+                }
+            }
+            ExCommand::Use { database } => self.use_database(database).await,
+            ExCommand::Export { format, path } => {
+                self.export_results(format, path.map(|p| expand_path(&p).to_string_lossy().to_string()));
+            }
+            ExCommand::Substitute(sub) => self.substitute(&sub),
+            ExCommand::Layout { mode: Some(mode) } => self.set_layout_mode(mode),
+            ExCommand::Layout { mode: None } => {
+                self.message = Some(t!("layout_mode", mode = self.app_config.layout.mode.name()).to_string());
+            }
+            ExCommand::NoHighlight => self.search_highlight = false,
+            ExCommand::ColorScheme { name: Some(name) } => self.set_color_scheme(&name),
+            ExCommand::ColorScheme { name: None } => {
+                let key = if ActiveTheme::is_no_color() { "colorscheme_no_color" } else { "colorscheme_current" };
+                self.message = Some(t!(key, name = ActiveTheme::name()).to_string());
+            }
+            ExCommand::Action(action) => self.run_action(action).await,
+        }
+    }
+
+    /// Switch the colour theme and remember it in the config
+    fn set_color_scheme(&mut self, name: &str) {
+        match Theme::load(name) {
+            Ok(theme) => {
+                ActiveTheme::set(theme);
+                self.app_config.theme = Some(name.to_string());
+                if let Err(e) = self.app_config.save() {
+                    self.error = Some(e.to_string());
+                }
+            }
+            Err(e) => self.error = Some(format!("{:#}", e)),
+        }
+    }
+
+    /// Save the buffer, reporting failures in the status bar. Returns true on success.
+    fn write_or_report(&mut self, path: Option<std::path::PathBuf>) -> bool {
+        match self.save_file(path) {
+            Ok(()) => true,
+            Err(e) => {
+                self.error = Some(format!("{:#}", e));
+                false
+            }
+        }
+    }
+
+    /// Apply `:set` arguments, or show all options when there are none
+    fn set_options(&mut self, args: &[String]) {
+        if args.is_empty() {
+            self.message = Some(format!("{} locale={}", self.editor_options.describe(), &*rust_i18n::locale()));
+            return;
+        }
+
+        let mut shown = Vec::new();
+        for arg in args {
+            let arg = SetArg::parse(arg);
+            let result = match &arg {
+                SetArg::Assign(name, value) if option_name(name) == Some("locale") => {
+                    self.set_locale(value);
+                    Ok(None)
+                }
+                SetArg::Query(name) | SetArg::Enable(name) if option_name(name) == Some("locale") => {
+                    Ok(Some(format!("locale={}", &*rust_i18n::locale())))
+                }
+                _ => self.editor_options.apply(&arg),
+            };
+            match result {
+                Ok(Some(text)) => shown.push(text),
+                Ok(None) => {}
+                Err(e) => {
+                    self.error = Some(e);
+                    return;
+                }
+            }
+        }
+        if !shown.is_empty() {
+            self.message = Some(shown.join("  "));
+        }
+    }
+
+    /// Switch the UI language and remember it in the config
+    fn set_locale(&mut self, locale: &str) {
+        crate::init_locale(Some(locale));
+        self.app_config.locale = Some(locale.to_string());
+        let _ = self.app_config.save();
+        self.status = if self.is_connected() {
+            format!("{} | {}", t!("connected"), self.server_version)
+        } else {
+            t!("disconnected").to_string()
+        };
+    }
+
+    /// Reconnect the current SQL Server connection to another database
+    async fn use_database(&mut self, database: String) {
+        let Some(mut config) = self.current_connection.clone() else {
+            self.error = Some(t!("not_connected_to_database").to_string());
+            return;
+        };
+        if config.backend != DatabaseBackend::SqlServer {
+            self.error = Some(t!("cmd_use_unsupported").to_string());
+            return;
+        }
+        config.database = database;
+        match self.connect(&config).await {
+            Ok(()) => self.message = Some(t!("cmd_database_changed", database = config.database).to_string()),
+            Err(e) => self.error = Some(t!("connection_error", error = format!("{:#}", e)).to_string()),
+        }
+    }
+
+    /// Run `:s` / `:%s` on the buffer
+    fn substitute(&mut self, sub: &Substitute) {
+        let before_cursor: String = self.query.chars().take(self.cursor_pos).collect();
+        let cursor_line = before_cursor.matches('\n').count();
+
+        match substitute(&self.query, cursor_line, sub) {
+            Ok((text, count, lines)) => {
+                self.query = text;
+                self.cursor_pos = self.cursor_pos.min(self.query.chars().count().saturating_sub(1));
+                self.message = Some(t!("cmd_substituted", count = count, lines = lines).to_string());
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+}
@@ -1,8 +1,9 @@
 //! Connection modal event handler
 
-use crate::app::{App, ConnectionModalFocus};
+use crate::app::{App, ConnectionModalFocus, KeyAction, KeyChord, KeyContext};
 use crate::config::ConnectionForm;
 use crate::db::DatabaseBackend;
+use crate::secrets;
 use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use rust_i18n::t;
@@ -10,46 +11,54 @@ use rust_i18n::t;
 impl App {
     /// Handle keyboard input for the connection modal
     pub async fn handle_connection_modal(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            // Escape - close modal (only if already connected)
-            KeyCode::Esc => {
-                if self.connection_modal_focus == ConnectionModalFocus::Form {
+        // Plain characters are typed into the form fields
+        let form = self.connection_modal_focus == ConnectionModalFocus::Form;
+        let bound = if form && KeyChord::from(key).is_plain_char() {
+            None
+        } else {
+            self.keymap.key_action(KeyContext::Connections, key)
+        };
+
+        match bound {
+            // Close modal (only if already connected)
+            Some(KeyAction::Close) => {
+                if form {
                     self.connection_modal_focus = ConnectionModalFocus::List;
                 } else if self.is_connected() {
                     self.show_connection_modal = false;
                 }
             }
 
-            // Enter - either select connection or save and connect
-            KeyCode::Enter => {
+            // Either select connection or save and connect
+            Some(KeyAction::Open) => {
                 self.handle_connection_enter().await?;
             }
 
-            // Navigation and input
-            KeyCode::Up => self.handle_connection_up(),
-            KeyCode::Down => self.handle_connection_down(),
-            KeyCode::Char('k') if self.connection_modal_focus == ConnectionModalFocus::List => self.handle_connection_up(),
-            KeyCode::Char('j') if self.connection_modal_focus == ConnectionModalFocus::List => self.handle_connection_down(),
-            KeyCode::BackTab if self.connection_modal_focus == ConnectionModalFocus::Form => self.handle_connection_up(),
-            KeyCode::Tab if self.connection_modal_focus == ConnectionModalFocus::Form && key.modifiers.contains(KeyModifiers::SHIFT) => self.handle_connection_up(),
-            KeyCode::Tab if self.connection_modal_focus == ConnectionModalFocus::Form => self.handle_connection_down(),
-            // Ctrl+T toggles backend in form mode
-            KeyCode::Char('t') if key.modifiers.contains(KeyModifiers::CONTROL)
-                && self.connection_modal_focus == ConnectionModalFocus::Form =>
-            {
+            // Navigation
+            Some(KeyAction::Up) => self.handle_connection_up(),
+            Some(KeyAction::Down) => self.handle_connection_down(),
+            Some(KeyAction::PrevField) if form => self.handle_connection_up(),
+            Some(KeyAction::NextField) if form => self.handle_connection_down(),
+            Some(KeyAction::ToggleBackend) if form => {
                 self.connection_form.backend = match self.connection_form.backend {
                     DatabaseBackend::SqlServer => DatabaseBackend::Sqlite,
                     DatabaseBackend::Sqlite => DatabaseBackend::SqlServer,
                 };
                 self.connection_form_focus = 0;
             }
-            KeyCode::Char(c) => self.handle_connection_char(c),
-            KeyCode::Backspace => self.handle_connection_backspace(),
-
-            _ => {}
+            // Change the option fields (environment, flags)
+            Some(KeyAction::CycleOption) if form && self.connection_form.is_option_field(self.connection_form_focus) => {
+                self.connection_form.toggle_field(self.connection_form_focus);
+            }
+            Some(_) => {}
+            None => match key.code {
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => self.handle_connection_char(c),
+                KeyCode::Backspace => self.handle_connection_backspace(),
+                _ => {}
+            },
         }
 
-        return Ok(())
+        Ok(())
     }
 
     /// Handle Enter key in connection modal
@@ -77,7 +86,15 @@ impl App {
             }
             ConnectionModalFocus::Form => {
                 // Try to save and connect
-                if let Some(config) = self.connection_form.to_config() {
+                if let Some(mut config) = self.connection_form.to_config() {
+                    // A typed password goes into the vault when it's unlocked
+                    if config.has_cleartext_password() && secrets::is_unlocked() {
+                        match secrets::store(&config.name, &config.password) {
+                            Ok(reference) => config.password = reference,
+                            Err(e) => self.error = Some(format!("{:#}", e)),
+                        }
+                    }
+
                     // Save to config
                     self.app_config.add_connection(config.clone());
                     let _ = self.app_config.save();
@@ -141,23 +158,25 @@ impl App {
         if self.connection_modal_focus == ConnectionModalFocus::Form {
             if let Some(field) = self.connection_form.get_field_mut(self.connection_form_focus) {
                 field.push(c);
+            } else if c == ' ' && self.connection_form.is_option_field(self.connection_form_focus) {
+                self.connection_form.toggle_field(self.connection_form_focus);
             }
-        } else if c == 'e' {
-            if let Some(conn) = self.get_selected_connection().cloned() {
-                // Load existing connection into form
-                self.connection_form = ConnectionForm::from_config(&conn);
-                self.connection_form_focus = 0;
-                self.connection_modal_focus = ConnectionModalFocus::Form;
-            }
+        } else if c == 'e'
+            && let Some(conn) = self.get_selected_connection().cloned()
+        {
+            // Load existing connection into form
+            self.connection_form = ConnectionForm::from_config(&conn);
+            self.connection_form_focus = 0;
+            self.connection_modal_focus = ConnectionModalFocus::Form;
         }
     }
 
     /// Handle backspace in connection modal
     fn handle_connection_backspace(&mut self) {
-        if self.connection_modal_focus == ConnectionModalFocus::Form {
-            if let Some(field) = self.connection_form.get_field_mut(self.connection_form_focus) {
-                field.pop();
-            }
+        if self.connection_modal_focus == ConnectionModalFocus::Form
+            && let Some(field) = self.connection_form.get_field_mut(self.connection_form_focus)
+        {
+            field.pop();
         }
     }
 
@@ -1,12 +1,19 @@
 //! Event handlers for the application
 
+mod command;
 mod connection;
 mod query_editor;
 mod results;
 mod schema;
 mod history_handler;
-
-use crate::app::{App, ActivePanel, ResultsTab, SPINNER_FRAMES, InputMode};
+mod normal;
+mod search;
+mod visual;
+
+use crate::app::{
+    global_applies, Action, ActivePanel, App, Binding, InputMode, KeyAction, KeyChord, KeyContext, ResultsTab, RESIZE_STEP,
+    SPINNER_FRAMES,
+};
 use anyhow::Result;
 use crossterm::cursor::SetCursorStyle;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
@@ -33,6 +40,10 @@ impl App {
             // Check for query completion
             self.check_query_completion();
 
+            // Lint the buffer in the background
+            self.update_lint();
+            self.update_pairs();
+
             // Process smooth scroll animation
             self.process_smooth_scroll();
 
@@ -58,20 +69,30 @@ impl App {
             if event::poll(poll_duration)? {
                 match event::read()? {
                     Event::Key(key) => {
+                        self.record_key(key);
                         self.handle_key(key).await?;
+                        self.record_undo_step(Some(key));
                     }
                     Event::Mouse(mouse) => {
                         self.handle_mouse(mouse)?;
+                        self.record_undo_step(None);
                     }
                     _ => {}
                 }
             }
 
+            // Macro keys run until a query starts; the rest waits for it
+            while !self.is_loading && !self.should_quit && let Some(key) = self.replay_keys.pop_front() {
+                self.handle_key(key).await?;
+                self.record_undo_step(Some(key));
+            }
+
             if self.should_quit {
                 break;
             }
         }
 
+        self.save_editor_state();
         Ok(())
     }
 
@@ -98,16 +119,73 @@ impl App {
         }
     }
 
+    /// Binding context of the focused panel and editor mode
+    pub(crate) fn key_context(&self) -> KeyContext {
+        match self.active_panel {
+            ActivePanel::QueryEditor => match self.input_mode {
+                InputMode::Insert if self.completion.visible => KeyContext::Completion,
+                InputMode::Insert => KeyContext::Insert,
+                InputMode::Visual => KeyContext::Visual,
+                _ => KeyContext::Normal,
+            },
+            ActivePanel::Results => KeyContext::Results,
+            ActivePanel::SchemaExplorer => KeyContext::Schema,
+            ActivePanel::History => KeyContext::History,
+        }
+    }
+
+    /// What a key is bound to in the focused panel; the completion popup
+    /// falls back to the Insert mode bindings
+    pub(crate) fn bound_key(&self, key: KeyEvent) -> Option<Binding> {
+        let context = self.key_context();
+        self.keymap
+            .lookup(context, key)
+            .or_else(|| (context == KeyContext::Completion).then(|| self.keymap.lookup(KeyContext::Insert, key)).flatten())
+    }
+
+    /// The key action bound to a key in the focused panel
+    pub(crate) fn bound_key_action(&self, key: KeyEvent) -> Option<KeyAction> {
+        match self.bound_key(key) {
+            Some(Binding::Key(action)) => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Is a plain character the argument of a pending command (`f`, `"`,
+    /// `d` …) or typed into a prompt, the command line, the connection form
+    /// or the schema search, rather than a key binding?
+    fn key_is_text(&self, key: KeyEvent) -> bool {
+        if !KeyChord::from(key).is_plain_char() {
+            return false;
+        }
+        if self.confirm_prompt.is_some() || self.show_connection_modal || self.input_mode == InputMode::Command {
+            return true;
+        }
+        match self.active_panel {
+            ActivePanel::QueryEditor => {
+                !self.normal_pending.is_empty()
+                    || self.pending_char_search.is_some()
+                    || self.pending_text_object.is_some()
+                    || self.pending_g
+            }
+            ActivePanel::SchemaExplorer => self.show_search_schema,
+            _ => false,
+        }
+    }
+
     /// Handle keyboard input
     async fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        let global = self.keymap.lookup(KeyContext::Global, key);
+        let text = self.key_is_text(key);
+        // A quit key remapped to a character still types it where text goes
+        let quit = global == Some(Binding::Key(KeyAction::Quit))
+            && !text
+            && global_applies(Binding::Key(KeyAction::Quit), key.into(), self.key_context());
+
         // Don't process keys while loading (except quit)
         if self.is_loading {
-            match (key.code, key.modifiers) {
-                (KeyCode::Char('c'), KeyModifiers::CONTROL) |
-                (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
-                    self.should_quit = true;
-                }
-                _ => {}
+            if quit {
+                self.request_quit(false);
             }
             return Ok(());
         }
@@ -117,99 +195,83 @@ impl App {
             self.message = None;
         }
 
-        // Quit shortcuts - always work
-        match (key.code, key.modifiers) {
-            (KeyCode::Char('c'), KeyModifiers::CONTROL) |
-            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
-                self.should_quit = true;
-                return Ok(());
-            }
-            _ => {}
-        }
-
-        // Connection modal takes priority
-        if self.show_connection_modal {
-            return self.handle_connection_modal(key).await;
-        }
-
-        // Help toggle
-        if key.code == KeyCode::F(1) {
-            self.show_help = !self.show_help;
+        // Quit shortcuts - always work (pressed twice when there are unsaved changes)
+        if quit {
+            self.request_quit(false);
             return Ok(());
         }
+        self.quit_pending = false;
 
-        if self.show_help {
-            if key.code == KeyCode::Esc {
-                self.show_help = false;
-            }
+        // A destructive batch waiting for confirmation takes every key
+        if self.confirm_prompt.is_some() {
+            self.handle_confirm_prompt(key);
             return Ok(());
         }
 
-        // Esc no QueryEditor em modo Insert -> fecha completion primeiro, só depois volta para Normal
-        if key.code == KeyCode::Esc && self.active_panel == ActivePanel::QueryEditor && self.input_mode == InputMode::Insert {
-            if self.completion.visible {
-                self.completion.hide();
-            } else {
-                self.input_mode = InputMode::Normal;
-            }
-            return Ok(());
+        // Connection modal takes priority
+        if self.show_connection_modal {
+            return self.handle_connection_modal(key).await;
         }
 
-        // Tab in non-query panels switches panels
-        if key.code == KeyCode::Tab && (self.active_panel != ActivePanel::QueryEditor || self.input_mode == InputMode::Normal) {
-            self.active_panel = match self.active_panel {
-                ActivePanel::QueryEditor => ActivePanel::Results,
-                ActivePanel::Results => ActivePanel::SchemaExplorer,
-                ActivePanel::SchemaExplorer => ActivePanel::History,
-                ActivePanel::History => ActivePanel::QueryEditor,
-            };
+        if self.show_help {
+            self.handle_help_key(key, global == Some(Binding::Run(Action::Help)));
             return Ok(());
         }
 
-        // 'space' for command mode
-        if key.code == KeyCode::Char(' ') && (self.active_panel != ActivePanel::QueryEditor || self.input_mode != InputMode::Insert) {
-            self.command_mode = true;
-            return Ok(());
+        // The command line takes every key while open
+        if self.input_mode == InputMode::Command {
+            return self.handle_command_mode(key).await;
         }
 
+        // The key after the leader; resize keys keep the leader active so
+        // they can be repeated
         if self.command_mode {
-            match key.code {
-                KeyCode::Esc => {
-                    self.command_mode = false;
-                    return Ok(());
-                }
-                KeyCode::Char('q') => {
-                    self.command_mode = false;
-                    self.active_panel = ActivePanel::QueryEditor;
-                    return Ok(());
-                }
-                KeyCode::Char('r') => {
-                    self.command_mode = false;
-                    self.active_panel = ActivePanel::Results;
-                    return Ok(());
-                }
-                KeyCode::Char('s') => {
-                    self.command_mode = false;
-                    self.active_panel = ActivePanel::SchemaExplorer;
-                    return Ok(());
-                }
-                KeyCode::Char('h') => {
-                    self.command_mode = false;
-                    self.active_panel = ActivePanel::History;
+            match self.keymap.lookup(KeyContext::Leader, key) {
+                Some(Binding::Key(action)) => {
+                    match action {
+                        KeyAction::GrowMain => self.resize_main(RESIZE_STEP),
+                        KeyAction::ShrinkMain => self.resize_main(-RESIZE_STEP),
+                        KeyAction::GrowPanel => self.resize_active(RESIZE_STEP),
+                        KeyAction::ShrinkPanel => self.resize_active(-RESIZE_STEP),
+                        _ => self.command_mode = false,
+                    }
                     return Ok(());
                 }
-                KeyCode::Char('c') => {
+                Some(Binding::Run(action)) => {
                     self.command_mode = false;
-                    self.show_connection_modal = true;
-                    self.update_form_from_selection();
+                    self.run_action(action).await;
                     return Ok(());
                 }
-                _ => {
+                None => {
                     self.command_mode = false;
+                    if key.code == KeyCode::Esc {
+                        return Ok(());
+                    }
                 }
             }
         }
 
+        // Global bindings, where the focused panel doesn't keep the key
+        if !text
+            && let Some(binding) = global
+            && global_applies(binding, key.into(), self.key_context())
+        {
+            match binding {
+                Binding::Key(KeyAction::NextPanel) => self.focus_next_panel(),
+                Binding::Key(KeyAction::Leader) => self.command_mode = true,
+                Binding::Key(KeyAction::CommandLine) => self.enter_command_mode(),
+                Binding::Key(_) => {}
+                Binding::Run(action) => self.run_action(action).await,
+            }
+            return Ok(());
+        }
+
+        // Registered actions bound in the focused panel
+        if !text && let Some(Binding::Run(action)) = self.bound_key(key) {
+            self.run_action(action).await;
+            return Ok(());
+        }
+
         // Handle based on active panel
         match self.active_panel {
             ActivePanel::QueryEditor => self.handle_query_editor(key)?,
@@ -221,13 +283,66 @@ impl App {
         Ok(())
     }
 
-    /// Handle mouse input (scroll events)
+    /// Keys while the help is open: scrolling, and Esc or the help key closes it
+    fn handle_help_key(&mut self, key: KeyEvent, help_key: bool) {
+        match key.code {
+            _ if help_key => self.show_help = false,
+            KeyCode::Esc | KeyCode::Char('q') => self.show_help = false,
+            KeyCode::Down | KeyCode::Char('j') => self.help_scroll = self.help_scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => self.help_scroll = self.help_scroll.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.help_scroll = self.help_scroll.saturating_add(10),
+            KeyCode::PageUp => self.help_scroll = self.help_scroll.saturating_sub(10),
+            KeyCode::Home | KeyCode::Char('g') => self.help_scroll = 0,
+            KeyCode::End | KeyCode::Char('G') => self.help_scroll = u16::MAX,
+            _ => {}
+        }
+    }
+
+    /// Typing the connection name into the destructive-statement prompt;
+    /// Enter runs the batch only when the name matches
+    fn handle_confirm_prompt(&mut self, key: KeyEvent) {
+        let Some(prompt) = &mut self.confirm_prompt else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc => {
+                self.confirm_prompt = None;
+                self.error = None;
+                self.message = Some(t!("confirm_cancelled").to_string());
+            }
+            KeyCode::Enter => {
+                let name = self.current_connection.as_ref().map(|c| c.name.as_str()).unwrap_or_default();
+                if name.is_empty() {
+                    // Nothing to type: never run it on an empty prompt
+                    self.confirm_prompt = None;
+                    self.error = Some(t!("confirm_no_connection").to_string());
+                } else if prompt.typed == name {
+                    let query = prompt.query.clone();
+                    self.confirm_prompt = None;
+                    self.execute_query_text(query);
+                } else {
+                    self.error = Some(t!("confirm_mismatch", name = name).to_string());
+                }
+            }
+            KeyCode::Backspace => {
+                prompt.typed.pop();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => prompt.typed.push(c),
+            _ => {}
+        }
+    }
+
+    /// Handle mouse input (scroll, focus and border dragging)
     fn handle_mouse(&mut self, mouse: MouseEvent) -> Result<()> {
         // Don't process mouse while loading
         if self.is_loading {
             return Ok(());
         }
 
+        if !self.show_connection_modal && !self.show_help && self.handle_layout_mouse(mouse) {
+            return Ok(());
+        }
+
         match mouse.kind {
             MouseEventKind::ScrollUp => {
                 self.scroll_up(3); // Scroll 3 lines at a time
@@ -256,6 +371,9 @@ impl App {
                         // Stats view doesn't need scrolling (it's short)
                         self.results_selected = self.results_selected.saturating_sub(amount);
                     }
+                    ResultsTab::Problems => {
+                        self.results_selected = self.results_selected.saturating_sub(amount);
+                    }
                 }
             }
             ActivePanel::SchemaExplorer => {
@@ -288,6 +406,10 @@ impl App {
                         let max_cols = self.result.columns.len().saturating_sub(1);
                         self.results_selected = (self.results_selected + amount).min(max_cols);
                     }
+                    ResultsTab::Problems => {
+                        let max = self.diagnostics.len().saturating_sub(1);
+                        self.results_selected = (self.results_selected + amount).min(max);
+                    }
                 }
             }
             ActivePanel::SchemaExplorer => {
@@ -361,25 +483,15 @@ impl App {
         };
         
         // Find next newline (end of current line)
-        let mut next_newline_pos: Option<usize> = None;
-        for i in self.cursor_pos..chars.len() {
-            if chars[i] == '\n' {
-                next_newline_pos = Some(i);
-                break;
-            }
-        }
+        let next_newline_pos = (self.cursor_pos..chars.len()).find(|&i| chars[i] == '\n');
         
         if let Some(next_nl) = next_newline_pos {
             let next_line_start = next_nl + 1;
             
             // Find the end of next line
-            let mut next_line_end = chars.len();
-            for i in next_line_start..chars.len() {
-                if chars[i] == '\n' {
-                    next_line_end = i;
-                    break;
-                }
-            }
+            let next_line_end = (next_line_start..chars.len())
+                .find(|&i| chars[i] == '\n')
+                .unwrap_or(chars.len());
             
             let next_line_len = next_line_end - next_line_start;
             self.cursor_pos = next_line_start + col.min(next_line_len);
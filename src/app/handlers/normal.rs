@@ -0,0 +1,510 @@
+//! Normal mode command execution
+//!
+//! Keys are parsed by `editor::normal::PendingCommand`; this runs the complete
+//! commands against the buffer and records changes for `.`. Registers, marks
+//! and macros are kept in `app::editor_state`.
+
+use crate::app::editor::normal::{
+    insert_repeat, inserted_text, InsertKind, InsertSession, LastChange, Motion, MotionKind, NormalCommand,
+    Operator, Parse, Target,
+};
+use crate::app::editor::registers::RegisterWrite;
+use crate::app::editor::text_objects::TextObject;
+use crate::app::editor::{motions, operations, sql_objects, text_objects};
+use crate::app::{App, InputMode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rust_i18n::t;
+use std::ops::Range;
+
+impl App {
+    /// Feed a Normal mode key and run the command once it is complete
+    pub(crate) fn handle_normal_key(&mut self, key: KeyEvent) {
+        // `q` on its own ends a recording
+        if self.recording.is_some()
+            && self.normal_pending.is_empty()
+            && key.code == KeyCode::Char('q')
+            && !key.modifiers.contains(KeyModifiers::CONTROL)
+        {
+            self.stop_recording();
+            return;
+        }
+        if let Parse::Complete(command, count, register) = self.normal_pending.feed(key) {
+            self.run_normal_command(command, count, register, false);
+        }
+    }
+
+    /// Leave Insert mode, finishing the change that `.` will repeat
+    pub(crate) fn leave_insert_mode(&mut self) {
+        self.completion.hide();
+        self.input_mode = InputMode::Normal;
+        self.finish_block_insert();
+
+        let Some(session) = self.insert_session.take() else {
+            return;
+        };
+        let inserted = inserted_text(&session.before, session.start, &self.query);
+        if let Some(text) = &inserted
+            && session.count > 1
+            && !text.is_empty()
+        {
+            let copies = text.repeat(session.count - 1);
+            self.cursor_pos = operations::insert_text(&mut self.query, self.cursor_pos, &copies);
+        }
+        if let Some(change) = self.last_change.as_mut() {
+            change.inserted = inserted;
+        }
+    }
+
+    /// Run a parsed command. `repeating` is set while `.` replays a change,
+    /// so the replay doesn't record itself.
+    fn run_normal_command(&mut self, command: NormalCommand, count: Option<usize>, register: Option<char>, repeating: bool) {
+        let n = count.unwrap_or(1).max(1);
+        let changed = match command {
+            NormalCommand::Move(motion) => {
+                self.move_cursor(motion, count);
+                false
+            }
+            NormalCommand::Operate(op, target) => self.operate(op, target, count, register),
+            NormalCommand::ReplaceChar(ch) => self.replace_chars(ch, n),
+            NormalCommand::Paste { before } => self.paste(before, n, register),
+            NormalCommand::JoinLines => self.join_lines(n),
+            NormalCommand::Insert(kind) => {
+                self.start_insert(kind);
+                true
+            }
+            NormalCommand::Visual(kind) => {
+                self.visual_anchor = self.cursor_pos;
+                self.visual_kind = kind;
+                self.visual_to_line_end = false;
+                self.input_mode = InputMode::Visual;
+                false
+            }
+            NormalCommand::Undo => {
+                for _ in 0..n {
+                    if !self.undo() {
+                        break;
+                    }
+                }
+                false
+            }
+            NormalCommand::Redo => {
+                for _ in 0..n {
+                    if !self.redo() {
+                        break;
+                    }
+                }
+                false
+            }
+            NormalCommand::RepeatChange => {
+                self.repeat_last_change(count);
+                false
+            }
+            NormalCommand::CommandLine => {
+                self.enter_command_mode();
+                false
+            }
+            NormalCommand::Diagnostic { forward } => {
+                self.jump_to_diagnostic(forward, n);
+                false
+            }
+            NormalCommand::UndoTravel { forward } => {
+                for _ in 0..n {
+                    if !self.undo_travel(forward) {
+                        break;
+                    }
+                }
+                false
+            }
+            NormalCommand::NextTab => {
+                self.next_buffer();
+                false
+            }
+            NormalCommand::PrevTab => {
+                self.prev_buffer();
+                false
+            }
+            NormalCommand::SetMark(name) => {
+                self.set_mark(name);
+                false
+            }
+            NormalCommand::Record(name) => {
+                self.start_recording(name);
+                false
+            }
+            NormalCommand::PlayMacro(name) => {
+                self.play_macro(name, n);
+                false
+            }
+            NormalCommand::Search { forward } => {
+                self.start_search(forward, count);
+                false
+            }
+        };
+
+        if changed && command.is_change() && !repeating {
+            self.last_change = Some(LastChange { command, count, register, inserted: None });
+            if self.input_mode == InputMode::Insert {
+                self.insert_session = Some(InsertSession {
+                    before: self.query.clone(),
+                    start: self.cursor_pos,
+                    count: insert_repeat(command, count),
+                });
+            }
+        }
+    }
+
+    /// `.`: run the last change again, with a new count if one was given
+    fn repeat_last_change(&mut self, count: Option<usize>) {
+        let Some(change) = self.last_change.clone() else {
+            return;
+        };
+        let count = count.or(change.count);
+        // `"1p...` walks back through the delete ring
+        let register = match change.register {
+            Some(c @ '1'..='8') if matches!(change.command, NormalCommand::Paste { .. }) => {
+                char::from_digit(c.to_digit(10).unwrap_or(0) + 1, 10)
+            }
+            register => register,
+        };
+        self.run_normal_command(change.command, count, register, true);
+
+        if self.input_mode == InputMode::Insert {
+            if let Some(text) = &change.inserted {
+                let text = text.repeat(insert_repeat(change.command, count));
+                self.cursor_pos = operations::insert_text(&mut self.query, self.cursor_pos, &text);
+            }
+            self.completion.hide();
+            self.input_mode = InputMode::Normal;
+        }
+        self.last_change = Some(LastChange { count, register, ..change });
+    }
+
+    /// Resolve a motion, turning `;` / `,` into the last f/F/t/T search and
+    /// remembering new searches. Returns the target and the motion used.
+    fn resolve_motion(&mut self, motion: Motion, count: Option<usize>) -> Option<(usize, Motion)> {
+        let motion = match motion {
+            Motion::RepeatFind | Motion::RepeatFindReverse => {
+                let (ch, forward, till) = self.last_char_search?;
+                let forward = forward != (motion == Motion::RepeatFindReverse);
+                Motion::FindChar { ch, forward, till }
+            }
+            Motion::FindChar { ch, forward, till } => {
+                self.last_char_search = Some((ch, forward, till));
+                motion
+            }
+            Motion::Mark { name, linewise } => return self.mark_target(name, linewise).map(|pos| (pos, motion)),
+            Motion::SearchNext { reverse } => return self.search_target(reverse, count).map(|pos| (pos, motion)),
+            Motion::SearchWord { forward } => return self.search_word_target(forward, count).map(|pos| (pos, motion)),
+            Motion::MatchPair => {
+                return match count {
+                    // `[count]%` goes to that percentage of the buffer, linewise
+                    Some(percent) => {
+                        let line = (percent.min(100) * self.query.split('\n').count()).div_ceil(100);
+                        motions::motion_target(&self.query, self.cursor_pos, Motion::DocumentStart, Some(line))
+                            .map(|pos| (pos, Motion::DocumentStart))
+                    }
+                    None => self.pair_at_cursor().map(|(_, partner)| (partner.start, motion)),
+                };
+            }
+            _ => motion,
+        };
+        motions::motion_target(&self.query, self.cursor_pos, motion, count).map(|pos| (pos, motion))
+    }
+
+    fn move_cursor(&mut self, motion: Motion, count: Option<usize>) {
+        let Some((pos, motion)) = self.resolve_motion(motion, count) else {
+            return;
+        };
+        if matches!(
+            motion,
+            Motion::DocumentStart
+                | Motion::DocumentEnd
+                | Motion::Mark { .. }
+                | Motion::SearchNext { .. }
+                | Motion::SearchWord { .. }
+                | Motion::MatchPair
+        ) {
+            self.set_jump_mark();
+        }
+        let last = self.query.chars().count().saturating_sub(1);
+        self.cursor_pos = match motion {
+            // `l` stops on the last character instead of the line end
+            Motion::Right if pos > self.cursor_pos && pos == motions::line_end(&self.query, self.cursor_pos) => pos - 1,
+            Motion::WordForward => pos.min(last),
+            _ => pos,
+        };
+    }
+
+    /// The end of a pair (bracket, `BEGIN`, `CASE`, `END`) under the cursor
+    /// or after it on the line, and its partner
+    pub(super) fn pair_at_cursor(&self) -> Option<(Range<usize>, Range<usize>)> {
+        let line_end = motions::line_end(&self.query, self.cursor_pos);
+        self.pairs().at_or_after(self.cursor_pos, line_end)
+    }
+
+    /// Char range (start, end exclusive) an operator acts on, and whether it is linewise
+    fn operator_range(&mut self, op: Operator, target: Target, count: Option<usize>) -> Option<(usize, usize, bool)> {
+        let n = count.unwrap_or(1).max(1);
+        let cursor = self.cursor_pos;
+        match target {
+            Target::Line => {
+                let last = (1..n).fold(cursor, |pos, _| motions::cursor_down(&self.query, pos));
+                let (start, end) = operations::line_range(&self.query, cursor, last);
+                Some((start, end, true))
+            }
+            Target::TextObject { around, object } => {
+                let obj = self.text_object(object, around)?;
+                Some((obj.start, obj.end + 1, false))
+            }
+            // `cw` on a word changes to its end, like `ce`
+            Target::Motion(Motion::WordForward)
+                if op == Operator::Change
+                    && self.query.chars().nth(cursor).is_some_and(|c| !c.is_whitespace()) =>
+            {
+                let first = motions::current_word_end(&self.query, cursor);
+                let end = (1..n).fold(first, |pos, _| motions::word_end(&self.query, pos));
+                Some((cursor, end + 1, false))
+            }
+            // `d%` takes the whole keyword at both ends (`BEGIN ... END`)
+            Target::Motion(Motion::MatchPair) if count.is_none() => {
+                let (here, partner) = self.pair_at_cursor()?;
+                Some((cursor.min(partner.start), here.end.max(partner.end), false))
+            }
+            Target::Motion(motion) => {
+                let (pos, motion) = self.resolve_motion(motion, count)?;
+                let (start, end) = (cursor.min(pos), cursor.max(pos));
+                match motion.kind() {
+                    MotionKind::Linewise => {
+                        let (start, end) = operations::line_range(&self.query, start, end);
+                        Some((start, end, true))
+                    }
+                    MotionKind::Inclusive => Some((start, (end + 1).min(self.query.chars().count()), false)),
+                    MotionKind::Exclusive if motion == Motion::WordForward => {
+                        // `dw` on the last word of a line stops at the newline
+                        let chars: Vec<char> = self.query.chars().collect();
+                        let end = match (start..end).rev().find(|&i| chars[i] == '\n') {
+                            Some(nl) if nl > start && chars[nl..end].iter().all(|c| c.is_whitespace()) => nl,
+                            _ => end,
+                        };
+                        Some((start, end, false))
+                    }
+                    MotionKind::Exclusive => Some((start, end, false)),
+                }
+            }
+        }
+    }
+
+    /// The text object `i{object}` / `a{object}` at the cursor
+    pub(super) fn text_object(&self, object: char, around: bool) -> Option<TextObject> {
+        let cursor = self.cursor_pos;
+        match (object, around) {
+            ('w', false) => text_objects::inner_word(&self.query, cursor),
+            ('w', true) => text_objects::a_word(&self.query, cursor),
+            ('"' | '\'' | '`', false) => text_objects::inner_quoted(&self.query, cursor, object),
+            ('"' | '\'' | '`', true) => text_objects::a_quoted(&self.query, cursor, object),
+            ('s', _) => sql_objects::statement(&self.query, cursor, around, self.sql_dialect()),
+            ('c', _) => sql_objects::clause(&self.query, cursor, around, self.sql_dialect()),
+            ('i', _) => sql_objects::identifier(&self.query, cursor, around, self.sql_dialect()),
+            ('q', _) => sql_objects::subquery(&self.query, cursor, around, self.sql_dialect()),
+            _ => {
+                let (open, close) = match object {
+                    '(' | ')' | 'b' => ('(', ')'),
+                    '[' | ']' => ('[', ']'),
+                    '{' | '}' | 'B' => ('{', '}'),
+                    _ => ('<', '>'),
+                };
+                if around {
+                    text_objects::a_bracket(&self.query, cursor, open, close)
+                } else {
+                    text_objects::inner_bracket(&self.query, cursor, open, close)
+                }
+            }
+        }
+    }
+
+    /// Apply an operator; returns whether the text changed
+    fn operate(&mut self, op: Operator, target: Target, count: Option<usize>, register: Option<char>) -> bool {
+        let Some((start, end, linewise)) = self.operator_range(op, target, count) else {
+            return false;
+        };
+        self.apply_operator(op, start, end, linewise, register)
+    }
+
+    /// Apply an operator to the char range `start..end` (also used by Visual
+    /// mode); returns whether the text changed
+    pub(super) fn apply_operator(&mut self, op: Operator, mut start: usize, end: usize, linewise: bool, register: Option<char>) -> bool {
+        if start == end && !linewise && op != Operator::Change {
+            return false;
+        }
+        let mut text = operations::yank_range(&self.query, start, end.saturating_sub(1));
+        if start == end {
+            text.clear();
+        }
+        if linewise && !text.ends_with('\n') {
+            text.push('\n');
+        }
+
+        match op {
+            Operator::Yank => {
+                self.write_register(register, &text, RegisterWrite::Yank);
+                self.message = Some(t!("yanked_chars", count = text.chars().count()).to_string());
+                if !linewise || start < motions::line_start(&self.query, self.cursor_pos) {
+                    self.cursor_pos = if linewise { motions::first_non_whitespace(&self.query, start) } else { start };
+                }
+                false
+            }
+            Operator::Delete => {
+                self.write_register(register, &text, RegisterWrite::Delete);
+                // Deleting the last lines takes the newline before them instead
+                if linewise && end == self.query.chars().count() && start > 0 && !self.query.ends_with('\n') {
+                    start -= 1;
+                }
+                if end > start {
+                    operations::delete_range(&mut self.query, start, end - 1);
+                }
+                let last = self.query.chars().count().saturating_sub(1);
+                self.cursor_pos = if linewise {
+                    motions::first_non_whitespace(&self.query, start.min(self.query.chars().count()))
+                } else {
+                    start.min(last)
+                };
+                true
+            }
+            Operator::Change => {
+                self.write_register(register, &text, RegisterWrite::Delete);
+                if linewise {
+                    // Keep one line with the first line's indentation
+                    let indent: String = self.query.chars().skip(start).take_while(|c| *c == ' ' || *c == '\t').collect();
+                    let content_end = if self.query.chars().nth(end.saturating_sub(1)) == Some('\n') { end - 1 } else { end };
+                    if content_end > start {
+                        operations::delete_range(&mut self.query, start, content_end - 1);
+                    }
+                    self.cursor_pos = operations::insert_text(&mut self.query, start, &indent);
+                } else {
+                    if end > start {
+                        operations::delete_range(&mut self.query, start, end - 1);
+                    }
+                    self.cursor_pos = start;
+                }
+                self.input_mode = InputMode::Insert;
+                true
+            }
+            Operator::Indent | Operator::Outdent => {
+                let width = self.editor_options.tab_width;
+                if op == Operator::Indent {
+                    operations::indent_lines(&mut self.query, start, end, width);
+                } else {
+                    operations::outdent_lines(&mut self.query, start, end, width);
+                }
+                self.cursor_pos = motions::first_non_whitespace(&self.query, start);
+                true
+            }
+            Operator::Format => self.format_range(start, end),
+        }
+    }
+
+    /// `r`: replace `n` characters under and after the cursor
+    fn replace_chars(&mut self, ch: char, n: usize) -> bool {
+        if self.cursor_pos + n > motions::line_end(&self.query, self.cursor_pos) {
+            return false;
+        }
+        for i in 0..n {
+            operations::replace_char(&mut self.query, self.cursor_pos + i, ch);
+        }
+        self.cursor_pos += n - 1;
+        true
+    }
+
+    /// `p` / `P`: paste a register `n` times. Text ending in a newline is
+    /// pasted as whole lines below / above the current one.
+    fn paste(&mut self, before: bool, n: usize, register: Option<char>) -> bool {
+        let Some(text) = self.read_register(register) else {
+            return false;
+        };
+        if text.is_empty() {
+            return false;
+        }
+        let text = text.repeat(n);
+
+        if text.ends_with('\n') {
+            let pos = if before {
+                motions::line_start(&self.query, self.cursor_pos)
+            } else {
+                motions::line_end(&self.query, self.cursor_pos) + 1
+            };
+            if pos > self.query.chars().count() {
+                // Below the last line, which has no trailing newline
+                let end = self.query.chars().count();
+                operations::insert_text(&mut self.query, end, &format!("\n{}", text.trim_end_matches('\n')));
+                self.cursor_pos = motions::first_non_whitespace(&self.query, end + 1);
+            } else {
+                operations::insert_text(&mut self.query, pos, &text);
+                self.cursor_pos = motions::first_non_whitespace(&self.query, pos);
+            }
+        } else {
+            let pos = if before || self.cursor_pos >= motions::line_end(&self.query, self.cursor_pos) {
+                self.cursor_pos
+            } else {
+                self.cursor_pos + 1
+            };
+            let end = operations::insert_text(&mut self.query, pos, &text);
+            self.cursor_pos = end - 1;
+        }
+        true
+    }
+
+    /// `J`: join `n` lines (at least two)
+    fn join_lines(&mut self, n: usize) -> bool {
+        let joins = n.saturating_sub(1).max(1);
+        if motions::line_end(&self.query, self.cursor_pos) >= self.query.chars().count() {
+            return false;
+        }
+        for _ in 0..joins {
+            let join_at = motions::line_end(&self.query, self.cursor_pos);
+            if join_at >= self.query.chars().count() {
+                break;
+            }
+            operations::join_lines(&mut self.query, self.cursor_pos);
+            self.cursor_pos = join_at;
+        }
+        true
+    }
+
+    /// Enter Insert mode at the place `kind` asks for
+    fn start_insert(&mut self, kind: InsertKind) {
+        match kind {
+            InsertKind::Before => {}
+            InsertKind::After => {
+                if self.cursor_pos < motions::line_end(&self.query, self.cursor_pos) {
+                    self.cursor_pos += 1;
+                }
+            }
+            InsertKind::LineStart => {
+                self.cursor_pos = motions::first_non_whitespace(&self.query, self.cursor_pos);
+            }
+            InsertKind::LineEnd => {
+                self.cursor_pos = motions::line_end(&self.query, self.cursor_pos);
+            }
+            InsertKind::LineBelow | InsertKind::LineAbove => {
+                let indent = self.get_current_line_indent();
+                let pos = if kind == InsertKind::LineBelow {
+                    motions::line_end(&self.query, self.cursor_pos)
+                } else {
+                    motions::line_start(&self.query, self.cursor_pos)
+                };
+                let line = if kind == InsertKind::LineBelow {
+                    format!("\n{}", indent)
+                } else {
+                    format!("{}\n", indent)
+                };
+                operations::insert_text(&mut self.query, pos, &line);
+                self.cursor_pos = if kind == InsertKind::LineBelow {
+                    pos + line.chars().count()
+                } else {
+                    pos + indent.chars().count()
+                };
+            }
+        }
+        self.input_mode = InputMode::Insert;
+    }
+}
@@ -1,10 +1,11 @@
 //! Query editor keyboard handlers
 
-use crate::app::{App, InputMode};
-use crate::completion::{extract_context, get_candidates, get_candidates_with_columns};
+use crate::app::{App, InputMode, KeyAction};
+use crate::completion::{
+    extract_context, get_candidates, get_candidates_with_columns, rank_candidates, snippet_candidates, CompletionItem,
+};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use rust_i18n::t;
+use crossterm::event::{KeyCode, KeyEvent};
 
 impl App {
     /// Get the char index of the start of the current line
@@ -19,7 +20,7 @@ impl App {
     }
 
     /// Get the indentation (leading whitespace) of the current line
-    fn get_current_line_indent(&self) -> String {
+    pub(super) fn get_current_line_indent(&self) -> String {
         let line_start = self.current_line_start_char();
 
         // Extract leading whitespace from current line
@@ -47,9 +48,8 @@ impl App {
         let trimmed_line = current_line.trim_end();
 
         // Check if line ends with BEGIN (case-insensitive)
-        let ends_with_begin = trimmed_line
-            .to_uppercase()
-            .ends_with("BEGIN");
+        let ends_with_begin = self.editor_options.auto_close
+            && trimmed_line.to_uppercase().ends_with("BEGIN");
 
         if ends_with_begin {
             // Insert: \n<indent>    <cursor>\n<indent>END
@@ -97,20 +97,14 @@ impl App {
 
     /// Query Editor handler
     pub(crate) fn handle_query_editor(&mut self, key: KeyEvent) -> Result<()> {
-        // Comandos que funcionam em ambos os modos
-        match key.code {
-            // Ctrl+E = executar query (Run)
-            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.start_query();
-                return Ok(());
-            }
-            // Ctrl+D = Smooth scroll down
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+        // Keys that work in every mode (Ctrl+E runs through the keymap)
+        let bound = if self.key_is_text(key) { None } else { self.bound_key_action(key) };
+        match bound {
+            Some(KeyAction::ScrollDown) => {
                 self.pending_scroll += 10;
                 return Ok(());
             }
-            // Ctrl+U = Smooth scroll up
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(KeyAction::ScrollUp) => {
                 self.pending_scroll -= 10;
                 return Ok(());
             }
@@ -121,78 +115,55 @@ impl App {
             InputMode::Insert => self.handle_insert_mode(key),
             InputMode::Normal => self.handle_normal_mode(key),
             InputMode::Visual => self.handle_visual_mode(key),
-            InputMode::Command => Ok(()), // Not implemented yet
+            InputMode::Command => Ok(()), // Handled globally in handle_key
         }
     }
 
     /// Handle Insert mode - normal typing
+    #[allow(clippy::collapsible_match)]
     fn handle_insert_mode(&mut self, key: KeyEvent) -> Result<()> {
-        // Handle completion navigation first if visible
-        if self.completion.visible {
-            match key.code {
-                // Navigate completion up
-                KeyCode::Up => {
-                    self.completion.select_prev();
-                    return Ok(());
-                }
-                KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.completion.select_prev();
-                    return Ok(());
-                }
-                // Navigate completion down
-                KeyCode::Down => {
-                    self.completion.select_next();
-                    return Ok(());
-                }
-                KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    self.completion.select_next();
-                    return Ok(());
-                }
-                // Accept completion with Enter or Tab
-                KeyCode::Enter | KeyCode::Tab => {
-                    self.accept_completion();
-                    return Ok(());
-                }
-                // Close completion with Escape
-                KeyCode::Esc => {
-                    self.completion.hide();
-                    return Ok(());
-                }
-                // Continue typing - will update completion
-                _ => {}
+        // Completion navigation while the popup is open, then Insert keys
+        match self.bound_key_action(key) {
+            Some(KeyAction::NextItem) => {
+                self.completion.select_next();
+                return Ok(());
             }
-        }
-
-        match key.code {
-            // Enter or Ctrl+J (Shift+Enter in iTerm2 sends Ctrl+J)
-            KeyCode::Enter => {
+            Some(KeyAction::PrevItem) => {
+                self.completion.select_prev();
+                return Ok(());
+            }
+            Some(KeyAction::Accept) => {
+                self.accept_completion();
+                return Ok(());
+            }
+            Some(KeyAction::Dismiss) => {
                 self.completion.hide();
-                self.save_undo_state();
-                self.insert_newline_with_autoclose();
+                return Ok(());
             }
-            // Ctrl+J = Line Feed (Shift+Enter in some terminals like iTerm2)
-            KeyCode::Char('j') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(KeyAction::Complete) => {
+                self.trigger_completion();
+                return Ok(());
+            }
+            Some(KeyAction::NewLine) => {
                 self.completion.hide();
-                self.save_undo_state();
                 self.insert_newline_with_autoclose();
+                return Ok(());
             }
-            // Tab = accept completion OR insert 4 spaces
+            _ => {}
+        }
+
+        match key.code {
+            // Tab = indent (:set tabstop); accepting a completion is bound above
             KeyCode::Tab => {
-                if self.completion.visible {
-                    self.accept_completion();
-                } else {
-                    // Insert 4 spaces for indentation
-                    self.save_undo_state();
-                    for _ in 0..4 {
-                        self.query.insert(self.query_byte_pos(), ' ');
-                        self.cursor_pos += 1;
-                    }
+                for _ in 0..self.editor_options.tab_width {
+                    self.query.insert(self.query_byte_pos(), ' ');
+                    self.cursor_pos += 1;
                 }
             }
-            // BackTab = remove 4 spaces
+            // BackTab = remove one indentation level
             KeyCode::BackTab => {
                 if !self.completion.visible {
-                    for _ in 0..4 {
+                    for _ in 0..self.editor_options.tab_width {
                         if self.cursor_pos > 0 {
                             self.cursor_pos -= 1;
                             self.query.remove(self.query_byte_pos());
@@ -200,38 +171,31 @@ impl App {
                     }
                 }
             }
-            // Ctrl+Space = force trigger completion
-            KeyCode::Char(' ') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.trigger_completion();
-            }
             // Escape = close completion or go to normal mode
             KeyCode::Esc => {
                 if self.completion.visible {
                     self.completion.hide();
                 } else {
-                    self.input_mode = InputMode::Normal;
+                    self.leave_insert_mode();
                 }
             }
             // Typing "." triggers completion automatically
             KeyCode::Char('.') => {
-                self.save_undo_state();
                 self.query.insert(self.query_byte_pos(), '.');
                 self.cursor_pos += 1;
                 self.trigger_completion();
             }
             // Typing "@" triggers variable completion
             KeyCode::Char('@') => {
-                self.save_undo_state();
                 self.query.insert(self.query_byte_pos(), '@');
                 self.cursor_pos += 1;
                 self.trigger_completion();
             }
             // Regular typing
             KeyCode::Char(c) => {
-                self.save_undo_state();
 
                 // Autoclose: single quotes
-                if c == '\'' {
+                if c == '\'' && self.editor_options.auto_close {
                     // If next char is already a closing quote, just skip over it
                     let next_char = self.query.chars().nth(self.cursor_pos);
                     if next_char == Some('\'') {
@@ -258,7 +222,6 @@ impl App {
             // Backspace
             KeyCode::Backspace => {
                 if self.cursor_pos > 0 {
-                    self.save_undo_state();
                     self.cursor_pos -= 1;
                     self.query.remove(self.query_byte_pos());
                     // Update or hide completion
@@ -270,7 +233,6 @@ impl App {
             // Delete
             KeyCode::Delete => {
                 if self.cursor_pos < self.query.chars().count() {
-                    self.save_undo_state();
                     self.query.remove(self.query_byte_pos());
                 }
             }
@@ -310,20 +272,26 @@ impl App {
         Ok(())
     }
 
-    /// Trigger autocomplete at current cursor position
-    fn trigger_completion(&mut self) {
-        let context = extract_context(&self.query, self.cursor_pos);
-        
-        // Get prefix for filtering (text after last separator)
-        let prefix = self.get_completion_prefix();
+    /// Candidates at the cursor that match `prefix`, best first
+    fn completion_candidates(&self, prefix: &str) -> Vec<CompletionItem> {
+        let context = extract_context(&self.query, self.cursor_pos, self.sql_dialect());
         
         // Try to get column cache (non-blocking)
-        let candidates = if let Ok(cache) = self.column_cache.try_read() {
-            get_candidates_with_columns(&context, &self.schema_tree, &prefix, &cache)
+        let mut candidates = if let Ok(cache) = self.column_cache.try_read() {
+            get_candidates_with_columns(&context, &self.schema_tree, &cache)
         } else {
             // Cache is locked, use version without columns
-            get_candidates(&context, &self.schema_tree, &prefix)
+            get_candidates(&context, &self.schema_tree)
         };
+        candidates.extend(snippet_candidates(&self.app_config.snippets, &context, prefix));
+        rank_candidates(candidates, prefix, self.history.usage())
+    }
+
+    /// Trigger autocomplete at current cursor position
+    fn trigger_completion(&mut self) {
+        // Get prefix for filtering (text after last separator)
+        let prefix = self.get_completion_prefix();
+        let candidates = self.completion_candidates(&prefix);
         
         if candidates.is_empty() {
             self.completion.hide();
@@ -357,14 +325,7 @@ impl App {
         
         // Always re-calculate context and get fresh candidates
         // This ensures we keep the right context (e.g., AfterWhere with tables)
-        let context = extract_context(&self.query, self.cursor_pos);
-        
-        // Try to get column cache (non-blocking)
-        let candidates = if let Ok(cache) = self.column_cache.try_read() {
-            get_candidates_with_columns(&context, &self.schema_tree, &prefix, &cache)
-        } else {
-            get_candidates(&context, &self.schema_tree, &prefix)
-        };
+        let candidates = self.completion_candidates(&prefix);
         
         if candidates.is_empty() {
             self.completion.hide();
@@ -423,7 +384,6 @@ impl App {
     /// Accept the currently selected completion
     fn accept_completion(&mut self) {
         if let Some(item) = self.completion.get_selected().cloned() {
-            self.save_undo_state();
             
             // Remove the prefix that was already typed (prefix.len() is char count since it was built from chars)
             let prefix_char_len = self.completion.prefix.chars().count();
@@ -445,7 +405,8 @@ impl App {
     }
 
     /// Handle g prefix motions (gg, g_, ge, etc.)
-    fn handle_g_motion(&mut self, ch: char) {
+    #[allow(clippy::collapsible_match)]
+    pub(super) fn handle_g_motion(&mut self, ch: char) {
         match ch {
             // gg = go to start of document
             'g' => {
@@ -483,404 +444,16 @@ impl App {
                     self.cursor_pos = pos;
                 }
             }
+            // gt / gT = next / previous buffer tab
+            't' => self.next_buffer(),
+            'T' => self.prev_buffer(),
             _ => {}
         }
     }
 
     /// Handle Normal mode - vim commands
     fn handle_normal_mode(&mut self, key: KeyEvent) -> Result<()> {
-        // Handle pending character search (f/F/t/T waiting for char)
-        if let Some(pending) = self.pending_char_search {
-            self.pending_char_search = None;
-            if let KeyCode::Char(ch) = key.code {
-                match pending {
-                    'f' => { self.find_char_forward(ch, false); }
-                    'F' => { self.find_char_backward(ch, false); }
-                    't' => { self.find_char_forward(ch, true); }
-                    'T' => { self.find_char_backward(ch, true); }
-                    _ => {}
-                }
-            }
-            return Ok(());
-        }
-
-        // Handle pending g prefix (gg, g_, ge, etc.)
-        if self.pending_g {
-            self.pending_g = false;
-            if let KeyCode::Char(ch) = key.code {
-                self.handle_g_motion(ch);
-            }
-            return Ok(());
-        }
-
-        match key.code {
-            // Movement
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.cursor_pos = self.cursor_pos.saturating_sub(1);
-            }
-            KeyCode::Char('l') | KeyCode::Right => {
-                self.cursor_pos = (self.cursor_pos + 1).min(self.query.chars().count().saturating_sub(1));
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.move_cursor_up();
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.move_cursor_down();
-            }
-            KeyCode::Char('p') => {
-                // Paste from system clipboard
-                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                    if let Ok(text) = clipboard.get_text() {
-                        self.save_undo_state();
-                        for c in text.chars() {
-                            self.query.insert(self.query_byte_pos(), c);
-                            self.cursor_pos += 1;
-                        }
-                    }
-                }
-            }
-            // Line start/end
-            KeyCode::Char('0') | KeyCode::Home => {
-                self.cursor_pos = self.current_line_start_char();
-            }
-            KeyCode::Char('$') | KeyCode::End => {
-                let chars: Vec<char> = self.query.chars().collect();
-                let mut end = self.cursor_pos;
-                while end < chars.len() && chars[end] != '\n' {
-                    end += 1;
-                }
-                self.cursor_pos = end;
-            }
-            // First non-whitespace character
-            KeyCode::Char('^') => {
-                let line_start = self.current_line_start_char();
-                let chars: Vec<char> = self.query.chars().collect();
-                let mut pos = line_start;
-                while pos < chars.len() && chars[pos] != '\n' && (chars[pos] == ' ' || chars[pos] == '\t') {
-                    pos += 1;
-                }
-                self.cursor_pos = pos;
-            }
-            // Word forward (w)
-            KeyCode::Char('w') => {
-                use crate::app::editor::motions::word_forward;
-                self.cursor_pos = word_forward(&self.query, self.cursor_pos);
-            }
-            // Word backward (b)
-            KeyCode::Char('b') => {
-                use crate::app::editor::motions::word_backward;
-                self.cursor_pos = word_backward(&self.query, self.cursor_pos);
-            }
-            // Word end forward (e)
-            KeyCode::Char('e') => {
-                use crate::app::editor::motions::word_end;
-                self.cursor_pos = word_end(&self.query, self.cursor_pos);
-            }
-            // Find character forward (f)
-            KeyCode::Char('f') => {
-                self.pending_char_search = Some('f');
-            }
-            // Find character backward (F)
-            KeyCode::Char('F') => {
-                self.pending_char_search = Some('F');
-            }
-            // Till character forward (t)
-            KeyCode::Char('t') => {
-                self.pending_char_search = Some('t');
-            }
-            // Till character backward (T)
-            KeyCode::Char('T') => {
-                self.pending_char_search = Some('T');
-            }
-            // Repeat last f/F/t/T search (;)
-            KeyCode::Char(';') => {
-                self.repeat_char_search();
-            }
-            // Repeat last f/F/t/T search in opposite direction (,)
-            KeyCode::Char(',') => {
-                self.repeat_char_search_opposite();
-            }
-            // g prefix (gg, g_, ge, etc.)
-            KeyCode::Char('g') => {
-                self.pending_g = true;
-            }
-            // G = go to end of document
-            KeyCode::Char('G') => {
-                self.cursor_pos = self.query.chars().count().saturating_sub(1);
-            }
-            // Undo
-            KeyCode::Char('u') => {
-                self.undo();
-            }
-            // Redo (Ctrl+R)
-            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.redo();
-            }
-            // Delete character
-            KeyCode::Char('x') => {
-                let char_count = self.query.chars().count();
-                if self.cursor_pos < char_count {
-                    self.save_undo_state();
-                    self.query.remove(self.query_byte_pos());
-                    let new_char_count = self.query.chars().count();
-                    if self.cursor_pos >= new_char_count && self.cursor_pos > 0 {
-                        self.cursor_pos -= 1;
-                    }
-                }
-            }
-            // Delete line
-            KeyCode::Char('d') => {
-                self.save_undo_state();
-                let text_before: String = self.query.chars().take(self.cursor_pos).collect();
-                let line_start_char = if let Some(last_newline) = text_before.rfind('\n') {
-                    // last_newline is a byte index in text_before; convert to char index
-                    text_before[..last_newline].chars().count() + 1
-                } else {
-                    0
-                };
-                let text_after: String = self.query.chars().skip(self.cursor_pos).collect();
-                let line_end_char = if let Some(next_newline) = text_after.find('\n') {
-                    // next_newline is byte index in text_after; convert to char count
-                    self.cursor_pos + text_after[..next_newline].chars().count() + 1
-                } else {
-                    self.query.chars().count()
-                };
-                let byte_start = Self::char_to_byte_index(&self.query, line_start_char);
-                let byte_end = Self::char_to_byte_index(&self.query, line_end_char);
-                self.query.drain(byte_start..byte_end);
-                self.cursor_pos = line_start_char.min(self.query.chars().count().saturating_sub(1));
-            }
-            // Append (insert after cursor)
-            KeyCode::Char('a') => {
-                if self.cursor_pos < self.query.chars().count() {
-                    self.cursor_pos += 1;
-                }
-                self.input_mode = InputMode::Insert;
-            }
-            // Append at end of line
-            KeyCode::Char('A') => {
-                let chars: Vec<char> = self.query.chars().collect();
-                let mut end = self.cursor_pos;
-                while end < chars.len() && chars[end] != '\n' {
-                    end += 1;
-                }
-                self.cursor_pos = end;
-                self.input_mode = InputMode::Insert;
-            }
-            // Insert at start of line
-            KeyCode::Char('I') => {
-                self.cursor_pos = self.current_line_start_char();
-                self.input_mode = InputMode::Insert;
-            }
-            // New line below
-            KeyCode::Char('o') => {
-                self.save_undo_state();
-                let indent = self.get_current_line_indent();
-                let text_after: String = self.query.chars().skip(self.cursor_pos).collect();
-                let line_end_char = if let Some(next_newline) = text_after.find('\n') {
-                    self.cursor_pos + text_after[..next_newline].chars().count()
-                } else {
-                    self.query.chars().count()
-                };
-                let byte_pos = Self::char_to_byte_index(&self.query, line_end_char);
-                self.query.insert(byte_pos, '\n');
-                self.cursor_pos = line_end_char + 1;
-                // Insert the same indentation on the new line
-                for c in indent.chars() {
-                    self.query.insert(self.query_byte_pos(), c);
-                    self.cursor_pos += 1;
-                }
-                self.input_mode = InputMode::Insert;
-            }
-            // New line above
-            KeyCode::Char('O') => {
-                self.save_undo_state();
-                let indent = self.get_current_line_indent();
-                let text_before: String = self.query.chars().take(self.cursor_pos).collect();
-                let line_start_char = if let Some(last_newline) = text_before.rfind('\n') {
-                    text_before[..last_newline].chars().count() + 1
-                } else {
-                    0
-                };
-                // Build the string to insert: indent + newline
-                let mut insert_str = indent.clone();
-                insert_str.push('\n');
-                let byte_start = Self::char_to_byte_index(&self.query, line_start_char);
-                self.query.insert_str(byte_start, &insert_str);
-                self.cursor_pos = line_start_char + indent.chars().count();
-                self.input_mode = InputMode::Insert;
-            }
-            // Change character
-            KeyCode::Char('c') => {
-                let char_count = self.query.chars().count();
-                if self.cursor_pos < char_count {
-                    self.save_undo_state();
-                    self.query.remove(self.query_byte_pos());
-                    let new_char_count = self.query.chars().count();
-                    if self.cursor_pos >= new_char_count && self.cursor_pos > 0 {
-                        self.cursor_pos -= 1;
-                    }
-                    self.input_mode = InputMode::Insert;
-                }
-            }
-            // Insert mode
-            KeyCode::Char('i') => {
-                self.input_mode = InputMode::Insert;
-            }
-            // Visual mode
-            KeyCode::Char('v') => {
-                self.visual_anchor = self.cursor_pos;
-                self.input_mode = InputMode::Visual;
-            }
-            _ => {}
-        }
-        Ok(())
-    }
-
-    /// Handle Visual mode - text selection
-    fn handle_visual_mode(&mut self, key: KeyEvent) -> Result<()> {
-        // Handle pending character search (f/F/t/T waiting for char)
-        if let Some(pending) = self.pending_char_search {
-            self.pending_char_search = None;
-            if let KeyCode::Char(ch) = key.code {
-                match pending {
-                    'f' => { self.find_char_forward(ch, false); }
-                    'F' => { self.find_char_backward(ch, false); }
-                    't' => { self.find_char_forward(ch, true); }
-                    'T' => { self.find_char_backward(ch, true); }
-                    _ => {}
-                }
-            }
-            return Ok(());
-        }
-
-        // Handle pending g prefix
-        if self.pending_g {
-            self.pending_g = false;
-            if let KeyCode::Char(ch) = key.code {
-                self.handle_g_motion(ch);
-            }
-            return Ok(());
-        }
-
-        match key.code {
-            // Exit visual mode
-            KeyCode::Esc | KeyCode::Char('v') => {
-                self.input_mode = InputMode::Normal;
-            }
-            // Movement - expands/contracts selection
-            KeyCode::Char('h') | KeyCode::Left => {
-                self.cursor_pos = self.cursor_pos.saturating_sub(1);
-            }
-            KeyCode::Char('l') | KeyCode::Right => {
-                self.cursor_pos = (self.cursor_pos + 1).min(self.query.chars().count().saturating_sub(1));
-            }
-            KeyCode::Char('k') | KeyCode::Up => {
-                self.move_cursor_up();
-            }
-            KeyCode::Char('j') | KeyCode::Down => {
-                self.move_cursor_down();
-            }
-            // Line start/end
-            KeyCode::Char('0') | KeyCode::Home => {
-                let text_before: String = self.query.chars().take(self.cursor_pos).collect();
-                if let Some(last_newline) = text_before.rfind('\n') {
-                    self.cursor_pos = text_before[..last_newline].chars().count() + 1;
-                } else {
-                    self.cursor_pos = 0;
-                }
-            }
-            KeyCode::Char('$') | KeyCode::End => {
-                let text_after: String = self.query.chars().skip(self.cursor_pos).collect();
-                if let Some(next_newline) = text_after.find('\n') {
-                    self.cursor_pos += text_after[..next_newline].chars().count();
-                } else {
-                    self.cursor_pos = self.query.chars().count().saturating_sub(1);
-                }
-            }
-            // Word forward (w)
-            KeyCode::Char('w') => {
-                use crate::app::editor::motions::word_forward;
-                self.cursor_pos = word_forward(&self.query, self.cursor_pos);
-            }
-            // Word backward (b)
-            KeyCode::Char('b') => {
-                use crate::app::editor::motions::word_backward;
-                self.cursor_pos = word_backward(&self.query, self.cursor_pos);
-            }
-            // Word end forward (e)
-            KeyCode::Char('e') => {
-                use crate::app::editor::motions::word_end;
-                self.cursor_pos = word_end(&self.query, self.cursor_pos);
-            }
-            // Find character forward (f)
-            KeyCode::Char('f') => {
-                self.pending_char_search = Some('f');
-            }
-            // Find character backward (F)
-            KeyCode::Char('F') => {
-                self.pending_char_search = Some('F');
-            }
-            // Till character forward (t)
-            KeyCode::Char('t') => {
-                self.pending_char_search = Some('t');
-            }
-            // Till character backward (T)
-            KeyCode::Char('T') => {
-                self.pending_char_search = Some('T');
-            }
-            // Repeat last f/F/t/T search (;)
-            KeyCode::Char(';') => {
-                self.repeat_char_search();
-            }
-            // Repeat last f/F/t/T search in opposite direction (,)
-            KeyCode::Char(',') => {
-                self.repeat_char_search_opposite();
-            }
-            // g prefix (gg, g_, ge, etc.)
-            KeyCode::Char('g') => {
-                self.pending_g = true;
-            }
-            // G = go to end of document
-            KeyCode::Char('G') => {
-                self.cursor_pos = self.query.chars().count().saturating_sub(1);
-            }
-            // Yank (copy) selection
-            KeyCode::Char('y') => {
-                if let Some(text) = self.yank_selection() {
-                    if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                        let _ = clipboard.set_text(&text);
-                        self.message = Some(t!("yanked_chars", count = text.len()).to_string());
-                    }
-                }
-            }
-            // Delete selection
-            KeyCode::Char('d') | KeyCode::Char('x') => {
-                self.save_undo_state();
-                let text = self.get_selected_text();
-                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                    let _ = clipboard.set_text(&text);
-                }
-                self.delete_selection();
-                self.message = Some(t!("deleted_chars", count = text.len()).to_string());
-            }
-            // Change (delete and enter insert mode)
-            KeyCode::Char('c') => {
-                self.save_undo_state();
-                let text = self.get_selected_text();
-                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                    let _ = clipboard.set_text(&text);
-                }
-                self.delete_selection();
-                self.input_mode = InputMode::Insert;
-            }
-            // Select all (simulated ggVG)
-            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.visual_anchor = 0;
-                self.cursor_pos = self.query.chars().count().saturating_sub(1);
-            }
-            _ => {}
-        }
+        self.handle_normal_key(key);
         Ok(())
     }
 }
@@ -1,114 +1,91 @@
 //! Results panel keyboard handlers
 
-use crate::app::{App, ActivePanel, ResultsTab};
+use crate::app::{App, KeyAction, ResultsTab};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::KeyEvent;
 use rust_i18n::t;
 
 impl App {
     /// Results panel navigation
+    #[allow(clippy::collapsible_match)]
     pub(crate) fn handle_results(&mut self, key: KeyEvent) -> Result<()> {
-        match key.code {
-            // Tab switching with number keys 1, 2, 3
-            KeyCode::Char('1') => {
-                self.results_tab = ResultsTab::Data;
-                self.results_scroll = 0;
-                self.results_selected = 0;
-            }
-            KeyCode::Char('2') => {
-                self.results_tab = ResultsTab::Columns;
-                self.results_scroll = 0;
-                self.results_selected = 0;
-            }
-            KeyCode::Char('3') => {
-                self.results_tab = ResultsTab::Stats;
-                self.results_scroll = 0;
-                self.results_selected = 0;
-            }
-            // Tab switching with Tab key
-            KeyCode::Tab => {
-                self.results_tab = match self.results_tab {
-                    ResultsTab::Data => ResultsTab::Columns,
-                    ResultsTab::Columns => ResultsTab::Stats,
-                    ResultsTab::Stats => ResultsTab::Data,
-                };
-                self.results_scroll = 0;
-                self.results_selected = 0;
-            }
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.pending_scroll += 10;
-                return Ok(());
-            }
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.pending_scroll -= 10;
-                return Ok(());
-            }
-            KeyCode::Up | KeyCode::Char('k') => {
+        let Some(action) = self.bound_key_action(key) else {
+            return Ok(());
+        };
+        match action {
+            KeyAction::DataTab => self.show_results_tab(ResultsTab::Data),
+            KeyAction::ColumnsTab => self.show_results_tab(ResultsTab::Columns),
+            KeyAction::StatsTab => self.show_results_tab(ResultsTab::Stats),
+            KeyAction::ProblemsTab => self.show_results_tab(ResultsTab::Problems),
+            KeyAction::ScrollDown => self.pending_scroll += 10,
+            KeyAction::ScrollUp => self.pending_scroll -= 10,
+            KeyAction::Up => {
                 self.results_selected = self.results_selected.saturating_sub(1);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            KeyAction::Down => {
                 let max_rows = match self.results_tab {
                     ResultsTab::Data => self.result.rows.len(),
                     ResultsTab::Columns => self.result.columns.len(),
                     ResultsTab::Stats => 10, // Fixed stats count
+                    ResultsTab::Problems => self.diagnostics.len(),
                 };
                 if self.results_selected < max_rows.saturating_sub(1) {
                     self.results_selected += 1;
                 }
             }
-            KeyCode::Left | KeyCode::Char('h') => {
+            KeyAction::Left => {
                 if self.results_col_selected > 0 {
                     self.results_col_selected -= 1;
                 }
             }
-            KeyCode::Right | KeyCode::Char('l') => {
+            KeyAction::Right => {
                 let max_col = self.result.columns.len().saturating_sub(1);
                 if self.results_col_selected < max_col {
                     self.results_col_selected += 1;
                 }
             }
-            KeyCode::Home => {
+            KeyAction::First => {
                 self.results_selected = 0;
                 self.results_col_selected = 0;
                 self.results_col_scroll = 0;
             }
-            KeyCode::End => {
+            KeyAction::Last => {
                 let max_rows = match self.results_tab {
                     ResultsTab::Data => self.result.rows.len(),
                     ResultsTab::Columns => self.result.columns.len(),
                     ResultsTab::Stats => 10,
+                    ResultsTab::Problems => self.diagnostics.len(),
                 };
                 self.results_selected = max_rows.saturating_sub(1);
             }
-            // Copy cell
-            KeyCode::Char('y') => {
-                self.copy_current_cell();
-            }
-            // Export CSV (Ctrl+E)
-            KeyCode::Char('e') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.export_results_csv();
-            }
-            KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.export_results_json();
+            // Jump to the selected diagnostic
+            KeyAction::Open if self.results_tab == ResultsTab::Problems => {
+                self.show_diagnostic(self.results_selected);
             }
+            KeyAction::CopyCell => self.copy_current_cell(),
             // Copy row as INSERT statement
-            KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.copy_row_as_insert();
-            }
+            KeyAction::CopyInsert => self.copy_row_as_insert(),
             _ => {}
         }
         Ok(())
     }
 
+    /// Switch the results tab, back at the top
+    fn show_results_tab(&mut self, tab: ResultsTab) {
+        self.results_tab = tab;
+        self.results_scroll = 0;
+        self.results_selected = 0;
+    }
+
     /// Copy current cell to clipboard
     pub(crate) fn copy_current_cell(&mut self) {
-        if let Some(row) = self.result.rows.get(self.results_selected) {
-            if let Some(cell) = row.get(self.results_col_selected) {
-                let text = cell.to_string();
-                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                    let _ = clipboard.set_text(&text);
-                    self.message = Some(t!("copied", text = text).to_string());
-                }
+        if let Some(row) = self.result.rows.get(self.results_selected)
+            && let Some(cell) = row.get(self.results_col_selected)
+        {
+            let text = cell.to_string();
+            if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                let _ = clipboard.set_text(&text);
+                self.message = Some(t!("copied", text = text).to_string());
             }
         }
     }
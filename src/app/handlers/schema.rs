@@ -1,11 +1,12 @@
 //! Schema explorer keyboard handlers
 
-use crate::app::{App, ActivePanel, SchemaNodeType};
+use crate::app::{App, KeyAction, SchemaNodeType};
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{KeyCode, KeyEvent};
 
 impl App {
     /// Schema explorer handler
+    #[allow(clippy::collapsible_match)]
     pub(crate) async fn handle_schema(&mut self, key: KeyEvent) -> Result<()> {
         // If search mode is active, process search input
         if self.show_search_schema {
@@ -44,34 +45,26 @@ impl App {
             return Ok(());
         }
 
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
+        match self.bound_key_action(key) {
+            Some(KeyAction::Up) => {
                 self.schema_selected = self.schema_selected.saturating_sub(1);
             }
-            KeyCode::Down | KeyCode::Char('j') => {
+            Some(KeyAction::Down) => {
                 let max = self.get_visible_schema_nodes().len().saturating_sub(1);
                 if self.schema_selected < max {
                     self.schema_selected += 1;
                 }
             }
-            // Ctrl+D = Smooth scroll down
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.pending_scroll += 10;
-                return Ok(());
-            }
-            // Ctrl+U = Smooth scroll up
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.pending_scroll -= 10;
-                return Ok(());
-            }
-            // Fetch source
-            KeyCode::Char('s') => {
+            // Smooth scroll
+            Some(KeyAction::ScrollDown) => self.pending_scroll += 10,
+            Some(KeyAction::ScrollUp) => self.pending_scroll -= 10,
+            Some(KeyAction::Source) => {
                 let visible = self.get_visible_schema_nodes();
                 if let Some((_, node)) = visible.get(self.schema_selected) {
                     self.fetch_source(node.name.clone());
                 }
             }
-            KeyCode::Enter | KeyCode::Char(' ') => {
+            Some(KeyAction::Open) => {
                 let visible = self.get_visible_schema_nodes();
                 if let Some((_, node)) = visible.get(self.schema_selected) {
                     if !node.children.is_empty() || node.node_type == SchemaNodeType::Folder {
@@ -81,12 +74,12 @@ impl App {
                     }
                 }
             }
-            KeyCode::Char('/') => {
+            Some(KeyAction::Search) => {
                 self.show_search_schema = true;
                 self.schema_search_query.clear();
             }
-            KeyCode::Esc => {
-                // If search is active, clear it first
+            // Clear an active filter
+            Some(KeyAction::ClearSearch) => {
                 if !self.schema_search_query.is_empty() {
                     self.schema_search_query.clear();
                     self.schema_selected = 0;
@@ -0,0 +1,188 @@
+//! `/` and `?` search prompt, `n` / `N` and `*` / `#`
+
+use crate::app::editor::search::{next_match, Search, SearchPrompt};
+use crate::app::editor::text_objects;
+use crate::app::{App, InputMode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rust_i18n::t;
+
+impl App {
+    /// Open the `/` (forward) or `?` (backward) prompt
+    pub(crate) fn start_search(&mut self, forward: bool, count: Option<usize>) {
+        self.enter_command_mode();
+        self.search_prompt = Some(SearchPrompt {
+            forward,
+            count,
+            origin: self.cursor_pos,
+            origin_scroll: (self.query_scroll_x, self.query_scroll_y),
+        });
+    }
+
+    /// Keys typed in the search prompt
+    pub(crate) fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.cancel_search(),
+            KeyCode::Backspace if self.command_buffer.is_empty() => self.cancel_search(),
+            KeyCode::Enter => self.commit_search(),
+            KeyCode::Backspace => {
+                self.command_buffer.pop();
+                self.search_history.reset();
+                self.update_search_preview();
+            }
+            KeyCode::Up => {
+                if let Some(entry) = self.search_history.prev(&self.command_buffer) {
+                    self.command_buffer = entry;
+                    self.update_search_preview();
+                }
+            }
+            KeyCode::Down => {
+                if let Some(entry) = self.search_history.next() {
+                    self.command_buffer = entry;
+                    self.update_search_preview();
+                }
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.command_buffer.clear();
+                self.update_search_preview();
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.command_buffer.push(c);
+                self.search_history.reset();
+                self.update_search_preview();
+            }
+            _ => {}
+        }
+    }
+
+    /// Close the prompt, putting the cursor back where it was
+    fn cancel_search(&mut self) {
+        if let Some(prompt) = self.close_search_prompt() {
+            self.cursor_pos = prompt.origin;
+            (self.query_scroll_x, self.query_scroll_y) = prompt.origin_scroll;
+        }
+    }
+
+    fn close_search_prompt(&mut self) -> Option<SearchPrompt> {
+        self.search_preview = None;
+        self.command_buffer.clear();
+        self.search_history.reset();
+        self.input_mode = InputMode::Normal;
+        self.search_prompt.take()
+    }
+
+    /// Move to the first match of what has been typed so far (`incsearch`)
+    fn update_search_preview(&mut self) {
+        let Some(prompt) = self.search_prompt else {
+            return;
+        };
+        self.cursor_pos = prompt.origin;
+        self.search_preview = None;
+        if !self.editor_options.inc_search || self.command_buffer.is_empty() {
+            return;
+        }
+        // An incomplete regex is normal while typing; errors wait for Enter
+        let Ok(search) = Search::new(&self.command_buffer, prompt.forward, self.editor_options.search_flags()) else {
+            return;
+        };
+        let matches = search.matches(&self.query);
+        if let Some((index, _)) = next_match(&matches, prompt.origin, prompt.forward, prompt.count.unwrap_or(1)) {
+            self.cursor_pos = matches[index].0;
+        }
+        self.search_preview = Some(search);
+    }
+
+    /// Enter in the prompt: search for the pattern (an empty one repeats the last)
+    fn commit_search(&mut self) {
+        let pattern = self.command_buffer.clone();
+        let Some(prompt) = self.close_search_prompt() else {
+            return;
+        };
+        self.cursor_pos = prompt.origin;
+
+        let pattern = if pattern.is_empty() {
+            match &self.search {
+                Some(last) => last.pattern.clone(),
+                None => {
+                    self.error = Some(t!("search_no_previous").to_string());
+                    return;
+                }
+            }
+        } else {
+            self.search_history.push(&pattern);
+            pattern
+        };
+        match Search::new(&pattern, prompt.forward, self.editor_options.search_flags()) {
+            Ok(search) => {
+                self.search = Some(search);
+                self.search_highlight = true;
+                if let Some(target) = self.search_target(false, prompt.count) {
+                    self.set_jump_mark();
+                    self.cursor_pos = target;
+                }
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Where the `count`-th match of the last search is, in its direction
+    /// (`reverse` for `N`). Reports wrapping and missing matches.
+    pub(crate) fn search_target(&mut self, reverse: bool, count: Option<usize>) -> Option<usize> {
+        let Some(search) = &self.search else {
+            self.error = Some(t!("search_no_previous").to_string());
+            return None;
+        };
+        let forward = search.forward != reverse;
+        let matches = search.matches(&self.query);
+        let Some((index, wrapped)) = next_match(&matches, self.cursor_pos, forward, count.unwrap_or(1)) else {
+            self.error = Some(t!("search_not_found", pattern = search.pattern).to_string());
+            return None;
+        };
+        self.search_highlight = true;
+        let prefix = if search.forward { '/' } else { '?' };
+        let position = format!("{}{} [{}/{}]", prefix, search.pattern, index + 1, matches.len());
+        self.message = Some(match (wrapped, forward) {
+            (true, true) => t!("search_wrapped_top", position = position).to_string(),
+            (true, false) => t!("search_wrapped_bottom", position = position).to_string(),
+            _ => position,
+        });
+        Some(matches[index].0)
+    }
+
+    /// `*` / `#`: make the word under the cursor the search and find it
+    pub(crate) fn search_word_target(&mut self, forward: bool, count: Option<usize>) -> Option<usize> {
+        let word = text_objects::inner_word(&self.query, self.cursor_pos)
+            .filter(|obj| obj.extract(&self.query).chars().any(|c| c.is_alphanumeric() || c == '_'));
+        let Some(word) = word else {
+            self.error = Some(t!("search_no_word").to_string());
+            return None;
+        };
+        let search = match Search::word(&word.extract(&self.query), forward, self.editor_options.search_flags()) {
+            Ok(search) => search,
+            Err(e) => {
+                self.error = Some(e);
+                return None;
+            }
+        };
+        self.search = Some(search);
+        // `#` starts from the word's beginning so it skips the word itself
+        let cursor = self.cursor_pos;
+        if !forward {
+            self.cursor_pos = word.start;
+        }
+        let target = self.search_target(false, count);
+        self.cursor_pos = cursor;
+        target
+    }
+
+    /// Matches to highlight in the editor
+    pub fn search_highlights(&self) -> Vec<(usize, usize)> {
+        let search = if self.search_prompt.is_some() {
+            self.search_preview.as_ref()
+        } else if self.search_highlight && self.editor_options.hl_search {
+            self.search.as_ref()
+        } else {
+            None
+        };
+        search.map(|s| s.matches(&self.query)).unwrap_or_default()
+    }
+}
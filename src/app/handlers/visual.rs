@@ -0,0 +1,278 @@
+//! Visual mode: characterwise (`v`), linewise (`V`) and block (`Ctrl+V`)
+//! selections
+//!
+//! Yank, delete, change and indent on a `v` / `V` selection go through the
+//! same code as the Normal mode operators. Blocks are cut column by column,
+//! and `I` / `A` type on the first line and copy the text to the others when
+//! Insert mode ends.
+
+use crate::app::editor::normal::{inserted_text, is_text_object, Operator};
+use crate::app::editor::operations;
+use crate::app::editor::registers::{position_of, RegisterWrite};
+use crate::app::editor::visual::{insert_at_column, BlockInsert, VisualKind};
+use crate::app::{App, InputMode};
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rust_i18n::t;
+
+impl App {
+    /// Handle Visual mode - text selection
+    pub(super) fn handle_visual_mode(&mut self, key: KeyEvent) -> Result<()> {
+        // A block extended with `$` stays at the line ends while moving up and down
+        if !matches!(
+            key.code,
+            KeyCode::Char('j' | 'k' | '$' | 'y' | 'd' | 'x' | 'c' | '<' | '>' | 'I' | 'A') | KeyCode::Up | KeyCode::Down | KeyCode::End
+        ) {
+            self.visual_to_line_end = false;
+        }
+
+        // Handle pending character search (f/F/t/T waiting for char)
+        if let Some(pending) = self.pending_char_search {
+            self.pending_char_search = None;
+            if let KeyCode::Char(ch) = key.code {
+                match pending {
+                    'f' => { self.find_char_forward(ch, false); }
+                    'F' => { self.find_char_backward(ch, false); }
+                    't' => { self.find_char_forward(ch, true); }
+                    'T' => { self.find_char_backward(ch, true); }
+                    _ => {}
+                }
+            }
+            return Ok(());
+        }
+
+        // Select a text object (`vis`, `va(`)
+        if let Some(around) = self.pending_text_object.take() {
+            if let KeyCode::Char(c) = key.code
+                && is_text_object(c)
+                && let Some(obj) = self.text_object(c, around)
+            {
+                self.visual_kind = VisualKind::Char;
+                self.visual_anchor = obj.start;
+                self.cursor_pos = obj.end;
+            }
+            return Ok(());
+        }
+
+        // Handle pending g prefix
+        if self.pending_g {
+            self.pending_g = false;
+            if let KeyCode::Char(ch) = key.code {
+                self.handle_g_motion(ch);
+            }
+            return Ok(());
+        }
+
+        match key.code {
+            // Exit visual mode
+            KeyCode::Esc => {
+                self.input_mode = InputMode::Normal;
+            }
+            // v / V / Ctrl+V switch the kind of selection, or leave it
+            KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => self.switch_visual(VisualKind::Block),
+            KeyCode::Char('v') => self.switch_visual(VisualKind::Char),
+            KeyCode::Char('V') => self.switch_visual(VisualKind::Line),
+            KeyCode::Char(c @ ('i' | 'a')) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.pending_text_object = Some(c == 'a');
+            }
+            // Go to the other end of the selection
+            KeyCode::Char('o') => {
+                std::mem::swap(&mut self.visual_anchor, &mut self.cursor_pos);
+            }
+            // Movement - expands/contracts selection
+            KeyCode::Char('h') | KeyCode::Left => {
+                self.cursor_pos = self.cursor_pos.saturating_sub(1);
+            }
+            KeyCode::Char('l') | KeyCode::Right => {
+                self.cursor_pos = (self.cursor_pos + 1).min(self.query.chars().count().saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.move_cursor_up();
+            }
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.move_cursor_down();
+            }
+            // Line start/end
+            KeyCode::Char('0') | KeyCode::Home => {
+                let text_before: String = self.query.chars().take(self.cursor_pos).collect();
+                if let Some(last_newline) = text_before.rfind('\n') {
+                    self.cursor_pos = text_before[..last_newline].chars().count() + 1;
+                } else {
+                    self.cursor_pos = 0;
+                }
+            }
+            KeyCode::Char('$') | KeyCode::End => {
+                let text_after: String = self.query.chars().skip(self.cursor_pos).collect();
+                if let Some(next_newline) = text_after.find('\n') {
+                    self.cursor_pos += text_after[..next_newline].chars().count();
+                } else {
+                    self.cursor_pos = self.query.chars().count().saturating_sub(1);
+                }
+                self.visual_to_line_end = self.visual_kind == VisualKind::Block;
+            }
+            // Word forward (w)
+            KeyCode::Char('w') => {
+                use crate::app::editor::motions::word_forward;
+                self.cursor_pos = word_forward(&self.query, self.cursor_pos);
+            }
+            // Word backward (b)
+            KeyCode::Char('b') => {
+                use crate::app::editor::motions::word_backward;
+                self.cursor_pos = word_backward(&self.query, self.cursor_pos);
+            }
+            // Word end forward (e)
+            KeyCode::Char('e') => {
+                use crate::app::editor::motions::word_end;
+                self.cursor_pos = word_end(&self.query, self.cursor_pos);
+            }
+            // Find character forward (f)
+            KeyCode::Char('f') => {
+                self.pending_char_search = Some('f');
+            }
+            // Find character backward (F)
+            KeyCode::Char('F') => {
+                self.pending_char_search = Some('F');
+            }
+            // Till character forward (t)
+            KeyCode::Char('t') => {
+                self.pending_char_search = Some('t');
+            }
+            // Till character backward (T)
+            KeyCode::Char('T') => {
+                self.pending_char_search = Some('T');
+            }
+            // Repeat last f/F/t/T search (;)
+            KeyCode::Char(';') => {
+                self.repeat_char_search();
+            }
+            // Repeat last f/F/t/T search in opposite direction (,)
+            KeyCode::Char(',') => {
+                self.repeat_char_search_opposite();
+            }
+            // g prefix (gg, g_, ge, etc.)
+            KeyCode::Char('g') => {
+                self.pending_g = true;
+            }
+            // G = go to end of document
+            KeyCode::Char('G') => {
+                self.cursor_pos = self.query.chars().count().saturating_sub(1);
+            }
+            // % = partner of the bracket, BEGIN, CASE or END
+            KeyCode::Char('%') => {
+                if let Some((_, partner)) = self.pair_at_cursor() {
+                    self.cursor_pos = partner.start;
+                }
+            }
+            KeyCode::Char('y') => self.visual_operate(Operator::Yank),
+            KeyCode::Char('d') | KeyCode::Char('x') => self.visual_operate(Operator::Delete),
+            KeyCode::Char('c') => self.visual_operate(Operator::Change),
+            KeyCode::Char('>') => self.visual_operate(Operator::Indent),
+            KeyCode::Char('<') => self.visual_operate(Operator::Outdent),
+            KeyCode::Char('=') => self.visual_operate(Operator::Format),
+            // Block insert before / append after the block on every line
+            KeyCode::Char('I') if self.visual_kind == VisualKind::Block => {
+                let block = self.visual_block();
+                self.start_block_insert(block.first_line, block.last_line, Some(block.left), false);
+            }
+            KeyCode::Char('A') if self.visual_kind == VisualKind::Block => {
+                let block = self.visual_block();
+                let col = (!self.visual_to_line_end).then(|| block.right + 1);
+                self.start_block_insert(block.first_line, block.last_line, col, true);
+            }
+            // Select all (simulated ggVG)
+            KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.visual_anchor = 0;
+                self.cursor_pos = self.query.chars().count().saturating_sub(1);
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// `v` / `V` / `Ctrl+V` inside Visual mode: the same kind leaves it
+    fn switch_visual(&mut self, kind: VisualKind) {
+        if self.visual_kind == kind {
+            self.input_mode = InputMode::Normal;
+        } else {
+            self.visual_kind = kind;
+        }
+    }
+
+    /// Run an operator on the selection and leave Visual mode
+    fn visual_operate(&mut self, op: Operator) {
+        let kind = self.visual_kind;
+        self.input_mode = InputMode::Normal;
+        if kind == VisualKind::Block && matches!(op, Operator::Yank | Operator::Delete | Operator::Change) {
+            self.block_operate(op);
+            return;
+        }
+        let (start, end) = self.get_visual_selection();
+        let end = (end + 1).min(self.query.chars().count());
+        let deleted = self.get_selected_text().chars().count();
+        let (start, end, linewise) = match kind {
+            VisualKind::Char => (start, end, false),
+            _ => {
+                let (start, end) = operations::line_range(&self.query, start, end.saturating_sub(1));
+                (start, end, true)
+            }
+        };
+        self.apply_operator(op, start, end, linewise, None);
+        if op == Operator::Delete {
+            self.message = Some(t!("deleted_chars", count = deleted).to_string());
+        }
+    }
+
+    /// Yank, delete or change a block
+    fn block_operate(&mut self, op: Operator) {
+        let block = self.visual_block();
+        let text = block.text(&self.query);
+        self.cursor_pos = block.top_left(&self.query);
+        if op == Operator::Yank {
+            self.write_register(None, &text, RegisterWrite::Yank);
+            self.message = Some(t!("yanked_chars", count = text.chars().count()).to_string());
+            return;
+        }
+        self.write_register(None, &text, RegisterWrite::Delete);
+        block.delete(&mut self.query);
+        self.cursor_pos = block.top_left(&self.query);
+        if op == Operator::Change {
+            self.start_block_insert(block.first_line, block.last_line, Some(block.left), false);
+        } else {
+            self.cursor_pos = self.cursor_pos.min(self.query.chars().count().saturating_sub(1));
+            self.message = Some(t!("deleted_chars", count = text.chars().count()).to_string());
+        }
+    }
+
+    /// Enter Insert mode at column `col` of the block's first line (None = its end)
+    fn start_block_insert(&mut self, first_line: usize, last_line: usize, col: Option<usize>, pad: bool) {
+        if pad {
+            insert_at_column(&mut self.query, first_line, first_line, col, "", true);
+        }
+        self.cursor_pos = position_of(&self.query, first_line, col.unwrap_or(usize::MAX));
+        self.block_insert = Some(BlockInsert {
+            before: self.query.clone(),
+            start: self.cursor_pos,
+            first_line,
+            last_line,
+            col,
+            pad,
+        });
+        self.input_mode = InputMode::Insert;
+    }
+
+    /// Copy what was typed on the first line of a block insert to the other
+    /// lines. Nothing is copied if the text spans lines or other text changed.
+    pub(super) fn finish_block_insert(&mut self) {
+        let Some(block) = self.block_insert.take() else {
+            return;
+        };
+        let Some(text) = inserted_text(&block.before, block.start, &self.query) else {
+            return;
+        };
+        if text.is_empty() || text.contains('\n') || block.last_line == block.first_line {
+            return;
+        }
+        insert_at_column(&mut self.query, block.first_line + 1, block.last_line, block.col, &text, block.pad);
+        self.cursor_pos = block.start;
+    }
+}
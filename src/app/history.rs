@@ -1,8 +1,11 @@
 //! Query history management
 
+use crate::db::DatabaseBackend;
+use crate::sql::lexer::{is_keyword, tokenize};
 use anyhow::Result;
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::PathBuf;
 
@@ -22,6 +25,35 @@ pub struct QueryHistory {
     entries: Vec<HistoryEntry>,
     max_entries: usize,
     current_index: Option<usize>,
+    /// How many entries use each table, column or other name (lowercased),
+    /// to rank completions
+    usage: HashMap<String, usize>,
+}
+
+/// The distinct names a query uses, lowercased and unquoted. History doesn't
+/// record the dialect; the T-SQL lexer reads both well enough for names.
+fn names(query: &str) -> HashSet<String> {
+    let dialect = DatabaseBackend::SqlServer;
+    tokenize(query, dialect)
+        .iter()
+        .filter_map(|token| token.name())
+        .filter(|name| !is_keyword(name, dialect))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Count the names of a query added to (or removed from) the history
+fn count_usage(usage: &mut HashMap<String, usize>, query: &str, added: bool) {
+    for name in names(query) {
+        if added {
+            *usage.entry(name).or_default() += 1;
+        } else if let Some(count) = usage.get_mut(&name) {
+            *count -= 1;
+            if *count == 0 {
+                usage.remove(&name);
+            }
+        }
+    }
 }
 
 impl QueryHistory {
@@ -30,6 +62,7 @@ impl QueryHistory {
             entries: Vec::new(),
             max_entries,
             current_index: None,
+            usage: HashMap::new(),
         };
         let _ = history.load();
         history
@@ -38,10 +71,10 @@ impl QueryHistory {
     /// Add a new entry to history
     pub fn add(&mut self, query: String, execution_time_ms: u64, row_count: Option<usize>, database: String) {
         // Don't add duplicates of the last entry
-        if let Some(last) = self.entries.last() {
-            if last.query.trim() == query.trim() {
-                return;
-            }
+        if let Some(last) = self.entries.last()
+            && last.query.trim() == query.trim()
+        {
+            return;
         }
 
         let entry = HistoryEntry {
@@ -52,11 +85,13 @@ impl QueryHistory {
             database,
         };
 
+        count_usage(&mut self.usage, &entry.query, true);
         self.entries.push(entry);
 
         // Limit history size
         while self.entries.len() > self.max_entries {
-            self.entries.remove(0);
+            let removed = self.entries.remove(0);
+            count_usage(&mut self.usage, &removed.query, false);
         }
 
         self.current_index = None;
@@ -80,6 +115,7 @@ impl QueryHistory {
     }
 
     /// Get next entry (for down arrow)
+    #[allow(clippy::should_implement_trait)]
     pub fn next(&mut self) -> Option<&HistoryEntry> {
         if self.entries.is_empty() {
             return None;
@@ -104,6 +140,11 @@ impl QueryHistory {
         &self.entries
     }
 
+    /// Number of entries that use each name, by lowercased name
+    pub fn usage(&self) -> &HashMap<String, usize> {
+        &self.usage
+    }
+
     /// Search history
     pub fn search(&self, term: &str) -> Vec<&HistoryEntry> {
         let term_lower = term.to_lowercase();
@@ -127,6 +168,10 @@ impl QueryHistory {
         if path.exists() {
             let content = fs::read_to_string(&path)?;
             self.entries = serde_json::from_str(&content)?;
+            self.usage.clear();
+            for entry in &self.entries {
+                count_usage(&mut self.usage, &entry.query, true);
+            }
         }
         Ok(())
     }
@@ -145,6 +190,7 @@ impl QueryHistory {
     /// Clear history
     pub fn clear(&mut self) {
         self.entries.clear();
+        self.usage.clear();
         self.current_index = None;
         let _ = self.save();
     }
@@ -0,0 +1,738 @@
+//! Key bindings
+//!
+//! Keys map to named actions per context: either a registered action (the
+//! same ones `:name` runs) or a key action that only means something in one
+//! panel or mode. The defaults below are overridden from the `keymap`
+//! section of the config, and the F1 help is generated from the result.
+//! The vim grammar of the editor (motions, operators, text objects) is
+//! parsed separately and is not rebindable.
+
+use crate::app::registry::Action;
+use crate::config::KeymapConfig;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rust_i18n::t;
+use std::fmt;
+
+/// A key with its modifiers, as written in the config ("Ctrl+E", "Shift+Tab", "q")
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let mut modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            // The case of a letter already says whether Shift was held
+            KeyCode::Char(c) => {
+                let shift = modifiers.contains(KeyModifiers::SHIFT);
+                modifiers.remove(KeyModifiers::SHIFT);
+                if modifiers.contains(KeyModifiers::CONTROL) {
+                    KeyCode::Char(c.to_ascii_lowercase())
+                } else if shift {
+                    KeyCode::Char(c.to_ascii_uppercase())
+                } else {
+                    code
+                }
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                KeyCode::BackTab
+            }
+            KeyCode::BackTab => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                code
+            }
+            _ => code,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parse a chord like "Ctrl+E", "alt+enter", "F5" or "+"
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        let (mods, key) = if s == "+" {
+            ("", "+")
+        } else if let Some(mods) = s.strip_suffix("++") {
+            (mods, "+")
+        } else {
+            match s.rsplit_once('+') {
+                Some((mods, key)) => (mods, key),
+                None => ("", s),
+            }
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for m in mods.split('+').filter(|m| !m.is_empty()) {
+            modifiers |= match m.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return None,
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_ascii_lowercase().as_str() {
+                "enter" | "return" | "cr" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "backspace" | "bs" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return None,
+                },
+            },
+        };
+        Some(Self::new(code, modifiers))
+    }
+
+    /// A printable character without Ctrl/Alt, which text input would type
+    pub fn is_plain_char(&self) -> bool {
+        matches!(self.code, KeyCode::Char(_)) && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) || self.code == KeyCode::BackTab {
+            write!(f, "Shift+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) if self.modifiers.contains(KeyModifiers::CONTROL) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Enter => write!(f, "Enter"),
+            KeyCode::Esc => write!(f, "Esc"),
+            KeyCode::Tab | KeyCode::BackTab => write!(f, "Tab"),
+            KeyCode::Backspace => write!(f, "Backspace"),
+            KeyCode::Delete => write!(f, "Del"),
+            KeyCode::Insert => write!(f, "Ins"),
+            KeyCode::Up => write!(f, "Up"),
+            KeyCode::Down => write!(f, "Down"),
+            KeyCode::Left => write!(f, "Left"),
+            KeyCode::Right => write!(f, "Right"),
+            KeyCode::Home => write!(f, "Home"),
+            KeyCode::End => write!(f, "End"),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            other => write!(f, "{:?}", other),
+        }
+    }
+}
+
+/// Where a binding applies
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyContext {
+    /// Every panel, before the panel's own keys
+    Global,
+    /// The key after the leader (Space)
+    Leader,
+    /// Query editor, Normal mode
+    Normal,
+    /// Query editor, Insert mode
+    Insert,
+    /// Query editor, Insert mode with the completion popup open
+    Completion,
+    /// Query editor, Visual mode
+    Visual,
+    Results,
+    Schema,
+    History,
+    /// Connection modal
+    Connections,
+}
+
+impl KeyContext {
+    /// All contexts, in the order the help lists them
+    pub const ALL: [KeyContext; 10] = [
+        KeyContext::Global,
+        KeyContext::Leader,
+        KeyContext::Normal,
+        KeyContext::Insert,
+        KeyContext::Completion,
+        KeyContext::Visual,
+        KeyContext::Results,
+        KeyContext::Schema,
+        KeyContext::History,
+        KeyContext::Connections,
+    ];
+
+    /// Name used in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyContext::Global => "global",
+            KeyContext::Leader => "leader",
+            KeyContext::Normal => "normal",
+            KeyContext::Insert => "insert",
+            KeyContext::Completion => "completion",
+            KeyContext::Visual => "visual",
+            KeyContext::Results => "results",
+            KeyContext::Schema => "schema",
+            KeyContext::History => "history",
+            KeyContext::Connections => "connections",
+        }
+    }
+
+    /// The context named `name`; `modal` is another name for `connections`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "modal" => Some(KeyContext::Connections),
+            _ => Self::ALL.iter().copied().find(|c| c.name() == name),
+        }
+    }
+
+    /// Where text is typed, so plain characters should stay unbound
+    fn is_typing(self) -> bool {
+        matches!(self, KeyContext::Insert | KeyContext::Completion)
+    }
+
+    /// Key actions that mean something here
+    fn key_actions(self) -> &'static [KeyAction] {
+        use KeyAction::*;
+        match self {
+            KeyContext::Global => &[Quit, NextPanel, Leader, CommandLine],
+            KeyContext::Leader => &[GrowMain, ShrinkMain, GrowPanel, ShrinkPanel],
+            KeyContext::Normal | KeyContext::Visual => &[ScrollDown, ScrollUp],
+            KeyContext::Insert => &[Complete, NewLine, ScrollDown, ScrollUp],
+            KeyContext::Completion => &[NextItem, PrevItem, Accept, Dismiss],
+            KeyContext::Results => &[
+                DataTab, ColumnsTab, StatsTab, ProblemsTab, Up, Down, Left, Right, First, Last, ScrollDown, ScrollUp,
+                Open, CopyCell, CopyInsert,
+            ],
+            KeyContext::Schema => &[Up, Down, ScrollDown, ScrollUp, Open, Search, ClearSearch, Source],
+            KeyContext::History => &[Up, Down, ScrollDown, ScrollUp, Open, Back],
+            KeyContext::Connections => &[Close, Open, Up, Down, NextField, PrevField, ToggleBackend, CycleOption],
+        }
+    }
+
+    /// Can registered actions be bound here? The completion popup falls
+    /// back to Insert, and the modal only has its own keys.
+    fn runs_actions(self) -> bool {
+        !matches!(self, KeyContext::Completion | KeyContext::Connections)
+    }
+}
+
+/// Keys that only mean something in their own context
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyAction {
+    Quit,
+    NextPanel,
+    Leader,
+    CommandLine,
+    GrowMain,
+    ShrinkMain,
+    GrowPanel,
+    ShrinkPanel,
+    ScrollDown,
+    ScrollUp,
+    Complete,
+    NewLine,
+    NextItem,
+    PrevItem,
+    Accept,
+    Dismiss,
+    DataTab,
+    ColumnsTab,
+    StatsTab,
+    ProblemsTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    First,
+    Last,
+    Open,
+    CopyCell,
+    CopyInsert,
+    Search,
+    ClearSearch,
+    Source,
+    Back,
+    Close,
+    NextField,
+    PrevField,
+    ToggleBackend,
+    CycleOption,
+}
+
+impl KeyAction {
+    pub const ALL: &'static [KeyAction] = &[
+        KeyAction::Quit,
+        KeyAction::NextPanel,
+        KeyAction::Leader,
+        KeyAction::CommandLine,
+        KeyAction::GrowMain,
+        KeyAction::ShrinkMain,
+        KeyAction::GrowPanel,
+        KeyAction::ShrinkPanel,
+        KeyAction::ScrollDown,
+        KeyAction::ScrollUp,
+        KeyAction::Complete,
+        KeyAction::NewLine,
+        KeyAction::NextItem,
+        KeyAction::PrevItem,
+        KeyAction::Accept,
+        KeyAction::Dismiss,
+        KeyAction::DataTab,
+        KeyAction::ColumnsTab,
+        KeyAction::StatsTab,
+        KeyAction::ProblemsTab,
+        KeyAction::Up,
+        KeyAction::Down,
+        KeyAction::Left,
+        KeyAction::Right,
+        KeyAction::First,
+        KeyAction::Last,
+        KeyAction::Open,
+        KeyAction::CopyCell,
+        KeyAction::CopyInsert,
+        KeyAction::Search,
+        KeyAction::ClearSearch,
+        KeyAction::Source,
+        KeyAction::Back,
+        KeyAction::Close,
+        KeyAction::NextField,
+        KeyAction::PrevField,
+        KeyAction::ToggleBackend,
+        KeyAction::CycleOption,
+    ];
+
+    /// Name used in the config
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyAction::Quit => "quit",
+            KeyAction::NextPanel => "next-panel",
+            KeyAction::Leader => "leader",
+            KeyAction::CommandLine => "command-line",
+            KeyAction::GrowMain => "grow-main",
+            KeyAction::ShrinkMain => "shrink-main",
+            KeyAction::GrowPanel => "grow-panel",
+            KeyAction::ShrinkPanel => "shrink-panel",
+            KeyAction::ScrollDown => "scroll-down",
+            KeyAction::ScrollUp => "scroll-up",
+            KeyAction::Complete => "complete",
+            KeyAction::NewLine => "new-line",
+            KeyAction::NextItem => "next-item",
+            KeyAction::PrevItem => "prev-item",
+            KeyAction::Accept => "accept",
+            KeyAction::Dismiss => "dismiss",
+            KeyAction::DataTab => "data-tab",
+            KeyAction::ColumnsTab => "columns-tab",
+            KeyAction::StatsTab => "stats-tab",
+            KeyAction::ProblemsTab => "problems-tab",
+            KeyAction::Up => "up",
+            KeyAction::Down => "down",
+            KeyAction::Left => "left",
+            KeyAction::Right => "right",
+            KeyAction::First => "first",
+            KeyAction::Last => "last",
+            KeyAction::Open => "open",
+            KeyAction::CopyCell => "copy-cell",
+            KeyAction::CopyInsert => "copy-insert",
+            KeyAction::Search => "search",
+            KeyAction::ClearSearch => "clear-search",
+            KeyAction::Source => "source",
+            KeyAction::Back => "back",
+            KeyAction::Close => "close",
+            KeyAction::NextField => "next-field",
+            KeyAction::PrevField => "prev-field",
+            KeyAction::ToggleBackend => "toggle-backend",
+            KeyAction::CycleOption => "cycle-option",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.name() == name)
+    }
+}
+
+/// What a key does
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Binding {
+    /// A registered action, run by `App::run_action`
+    Run(Action),
+    /// A key handled by the panel or mode itself
+    Key(KeyAction),
+}
+
+impl Binding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Binding::Run(action) => action.name(),
+            Binding::Key(action) => action.name(),
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        KeyAction::from_name(name).map(Binding::Key).or_else(|| Action::from_name(name).map(Binding::Run))
+    }
+
+    /// Can this be bound in `context`?
+    fn allowed_in(self, context: KeyContext) -> bool {
+        match self {
+            Binding::Run(_) => context.runs_actions(),
+            Binding::Key(action) => context.key_actions().contains(&action),
+        }
+    }
+
+    /// One-line description for the help
+    pub fn description(self) -> String {
+        t!(format!("key_{}", self.name().replace('-', "_"))).to_string()
+    }
+}
+
+/// Bindings as shipped
+fn defaults() -> Vec<(KeyContext, &'static str, Binding)> {
+    use Binding::{Key, Run};
+    use KeyAction::*;
+    use KeyContext as C;
+
+    let mut defaults = vec![
+        (C::Global, "Ctrl+Q", Key(Quit)),
+        (C::Global, "Ctrl+C", Key(Quit)),
+        (C::Global, "F1", Run(Action::Help)),
+        (C::Global, "Tab", Key(NextPanel)),
+        (C::Global, "Space", Key(Leader)),
+        (C::Global, ":", Key(CommandLine)),
+        (C::Leader, "q", Run(Action::FocusEditor)),
+        (C::Leader, "r", Run(Action::FocusResults)),
+        (C::Leader, "s", Run(Action::FocusSchema)),
+        (C::Leader, "h", Run(Action::FocusHistory)),
+        (C::Leader, "c", Run(Action::Connections)),
+        (C::Leader, ">", Key(GrowMain)),
+        (C::Leader, "<", Key(ShrinkMain)),
+        (C::Leader, "+", Key(GrowPanel)),
+        (C::Leader, "-", Key(ShrinkPanel)),
+        (C::Leader, "z", Run(Action::Zoom)),
+        (C::Leader, "=", Run(Action::ResetLayout)),
+        (C::Leader, "S", Run(Action::ToggleSchema)),
+        (C::Leader, "H", Run(Action::ToggleHistory)),
+        (C::Leader, "L", Run(Action::ToggleLayout)),
+    ];
+    for context in [C::Normal, C::Insert, C::Visual] {
+        defaults.extend([
+            (context, "Ctrl+E", Run(Action::ExecuteQuery)),
+            (context, "Ctrl+D", Key(ScrollDown)),
+            (context, "Ctrl+U", Key(ScrollUp)),
+        ]);
+    }
+    defaults.extend([
+        (C::Insert, "Ctrl+Space", Key(Complete)),
+        (C::Insert, "Enter", Key(NewLine)),
+        // Shift+Enter in some terminals (iTerm2)
+        (C::Insert, "Ctrl+J", Key(NewLine)),
+        (C::Completion, "Down", Key(NextItem)),
+        (C::Completion, "Ctrl+N", Key(NextItem)),
+        (C::Completion, "Up", Key(PrevItem)),
+        (C::Completion, "Ctrl+P", Key(PrevItem)),
+        (C::Completion, "Enter", Key(Accept)),
+        (C::Completion, "Tab", Key(Accept)),
+        (C::Completion, "Esc", Key(Dismiss)),
+        (C::Results, "1", Key(DataTab)),
+        (C::Results, "2", Key(ColumnsTab)),
+        (C::Results, "3", Key(StatsTab)),
+        (C::Results, "4", Key(ProblemsTab)),
+        (C::Results, "Up", Key(Up)),
+        (C::Results, "k", Key(Up)),
+        (C::Results, "Down", Key(Down)),
+        (C::Results, "j", Key(Down)),
+        (C::Results, "Left", Key(Left)),
+        (C::Results, "h", Key(Left)),
+        (C::Results, "Right", Key(Right)),
+        (C::Results, "l", Key(Right)),
+        (C::Results, "Home", Key(First)),
+        (C::Results, "End", Key(Last)),
+        (C::Results, "Ctrl+D", Key(ScrollDown)),
+        (C::Results, "Ctrl+U", Key(ScrollUp)),
+        (C::Results, "Enter", Key(Open)),
+        (C::Results, "y", Key(CopyCell)),
+        (C::Results, "Ctrl+I", Key(CopyInsert)),
+        (C::Results, "Ctrl+E", Run(Action::ExportCsv)),
+        (C::Results, "Ctrl+S", Run(Action::ExportJson)),
+        (C::Schema, "Up", Key(Up)),
+        (C::Schema, "k", Key(Up)),
+        (C::Schema, "Down", Key(Down)),
+        (C::Schema, "j", Key(Down)),
+        (C::Schema, "Ctrl+D", Key(ScrollDown)),
+        (C::Schema, "Ctrl+U", Key(ScrollUp)),
+        (C::Schema, "Enter", Key(Open)),
+        (C::Schema, "/", Key(Search)),
+        (C::Schema, "Esc", Key(ClearSearch)),
+        (C::Schema, "s", Key(Source)),
+        (C::History, "Up", Key(Up)),
+        (C::History, "k", Key(Up)),
+        (C::History, "Down", Key(Down)),
+        (C::History, "j", Key(Down)),
+        (C::History, "Ctrl+D", Key(ScrollDown)),
+        (C::History, "Ctrl+U", Key(ScrollUp)),
+        (C::History, "Enter", Key(Open)),
+        (C::History, "Esc", Key(Back)),
+        (C::History, "Ctrl+L", Run(Action::ClearHistory)),
+        (C::Connections, "Esc", Key(Close)),
+        (C::Connections, "Enter", Key(Open)),
+        (C::Connections, "Up", Key(Up)),
+        (C::Connections, "k", Key(Up)),
+        (C::Connections, "Down", Key(Down)),
+        (C::Connections, "j", Key(Down)),
+        (C::Connections, "Tab", Key(NextField)),
+        (C::Connections, "Shift+Tab", Key(PrevField)),
+        (C::Connections, "Ctrl+T", Key(ToggleBackend)),
+        (C::Connections, "Left", Key(CycleOption)),
+        (C::Connections, "Right", Key(CycleOption)),
+    ]);
+    defaults
+}
+
+/// Does a Global binding take `chord` before the keys of `context`?
+/// Typing contexts keep their plain characters, and the editor keeps
+/// Tab and ':' outside Normal mode for itself.
+pub fn global_applies(binding: Binding, chord: KeyChord, context: KeyContext) -> bool {
+    if context == KeyContext::Connections {
+        return binding == Binding::Key(KeyAction::Quit);
+    }
+    if context == KeyContext::Leader || (context.is_typing() && chord.is_plain_char()) {
+        return false;
+    }
+    match binding {
+        Binding::Key(KeyAction::NextPanel) => !matches!(context, KeyContext::Insert | KeyContext::Completion | KeyContext::Visual),
+        Binding::Key(KeyAction::CommandLine) => {
+            !matches!(context, KeyContext::Normal | KeyContext::Insert | KeyContext::Completion | KeyContext::Visual)
+        }
+        _ => true,
+    }
+}
+
+/// The bindings in effect
+#[derive(Clone, Debug)]
+pub struct Keymap {
+    /// In help order: defaults first, then bindings added by the config
+    bindings: Vec<(KeyContext, KeyChord, Binding)>,
+    /// Problems in the configured bindings, shown in the help
+    pub problems: Vec<String>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&KeymapConfig::new())
+    }
+}
+
+impl Keymap {
+    /// The defaults with `overrides` (context → key → action name, "none"
+    /// unbinds) applied
+    pub fn new(overrides: &KeymapConfig) -> Self {
+        let bindings = defaults()
+            .into_iter()
+            .map(|(context, key, binding)| {
+                let chord = KeyChord::parse(key).unwrap_or_else(|| panic!("bad default key {}", key));
+                (context, chord, binding)
+            })
+            .collect();
+        let mut keymap = Self { bindings, problems: Vec::new() };
+
+        for (context_name, keys) in overrides {
+            let Some(context) = KeyContext::from_name(context_name) else {
+                keymap.problems.push(t!("keymap_unknown_context", context = context_name).to_string());
+                continue;
+            };
+            for (key, name) in keys {
+                let Some(chord) = KeyChord::parse(key) else {
+                    keymap.problems.push(t!("keymap_unknown_key", key = key, context = context_name).to_string());
+                    continue;
+                };
+                if name == "none" {
+                    keymap.bindings.retain(|(c, k, _)| !(*c == context && *k == chord));
+                    continue;
+                }
+                match Binding::from_name(name) {
+                    Some(binding) if binding.allowed_in(context) => keymap.bind(context, chord, binding),
+                    _ => keymap
+                        .problems
+                        .push(t!("keymap_unknown_action", action = name, context = context_name).to_string()),
+                }
+            }
+        }
+
+        keymap.problems.extend(keymap.conflicts());
+        keymap
+    }
+
+    fn bind(&mut self, context: KeyContext, chord: KeyChord, binding: Binding) {
+        match self.bindings.iter_mut().find(|(c, k, _)| *c == context && *k == chord) {
+            Some(entry) => entry.2 = binding,
+            None => self.bindings.push((context, chord, binding)),
+        }
+    }
+
+    /// Bindings that can never fire, or that take keys away from typing
+    fn conflicts(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for &(context, chord, binding) in &self.bindings {
+            if context == KeyContext::Global {
+                continue;
+            }
+            if let Some(global) = self.get(KeyContext::Global, chord)
+                && global != binding
+                && global_applies(global, chord, context)
+            {
+                problems.push(
+                    t!(
+                        "keymap_shadowed",
+                        key = chord.to_string(),
+                        context = context.name(),
+                        action = binding.name(),
+                        global = global.name()
+                    )
+                    .to_string(),
+                );
+            }
+            if context.is_typing() && chord.is_plain_char() {
+                problems.push(t!("keymap_blocks_typing", key = chord.to_string(), context = context.name()).to_string());
+            }
+        }
+        problems
+    }
+
+    /// What `chord` does in `context`
+    pub fn get(&self, context: KeyContext, chord: KeyChord) -> Option<Binding> {
+        self.bindings.iter().find(|(c, k, _)| *c == context && *k == chord).map(|(_, _, b)| *b)
+    }
+
+    /// What a key press does in `context`
+    pub fn lookup(&self, context: KeyContext, key: KeyEvent) -> Option<Binding> {
+        self.get(context, key.into())
+    }
+
+    /// The key action of a key press in `context`, if it is bound to one
+    pub fn key_action(&self, context: KeyContext, key: KeyEvent) -> Option<KeyAction> {
+        match self.lookup(context, key) {
+            Some(Binding::Key(action)) => Some(action),
+            _ => None,
+        }
+    }
+
+    /// Bindings of a context for the help, keys grouped per action
+    pub fn help(&self, context: KeyContext) -> Vec<(Vec<KeyChord>, Binding)> {
+        let mut entries: Vec<(Vec<KeyChord>, Binding)> = Vec::new();
+        for &(c, chord, binding) in &self.bindings {
+            if c != context {
+                continue;
+            }
+            match entries.iter_mut().find(|(_, b)| *b == binding) {
+                Some((chords, _)) => chords.push(chord),
+                None => entries.push((vec![chord], binding)),
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn chord(s: &str) -> KeyChord {
+        KeyChord::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_chord_parse_and_display() {
+        for s in ["Ctrl+E", "Shift+Tab", "F1", "Space", "Ctrl+Space", "q", "S", ":", "+", "Ctrl++", "Alt+Enter", "PgDn"] {
+            assert_eq!(chord(s).to_string(), s);
+        }
+        assert_eq!(chord("ctrl+e"), chord("Ctrl+E"));
+        assert_eq!(chord("BackTab"), chord("Shift+Tab"));
+        assert_eq!(chord("Shift+s"), chord("S"));
+        assert_eq!(KeyChord::from(KeyEvent::new(KeyCode::Char('S'), KeyModifiers::SHIFT)), chord("S"));
+        assert_eq!(KeyChord::from(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)), chord("Shift+Tab"));
+        assert!(KeyChord::parse("Hyper+x").is_none());
+        assert!(KeyChord::parse("F13").is_none());
+        assert!(KeyChord::parse("nope").is_none());
+    }
+
+    #[test]
+    fn test_names_are_unique() {
+        for action in KeyAction::ALL {
+            assert_eq!(KeyAction::from_name(action.name()), Some(*action));
+            assert!(Action::from_name(action.name()).is_none(), "{} is also a registered action", action.name());
+        }
+        for context in KeyContext::ALL {
+            assert_eq!(KeyContext::from_name(context.name()), Some(context));
+        }
+    }
+
+    #[test]
+    fn test_defaults_have_no_conflicts() {
+        let keymap = Keymap::default();
+        assert!(keymap.problems.is_empty(), "{:?}", keymap.problems);
+        for &(context, _, binding) in &keymap.bindings {
+            assert!(binding.allowed_in(context), "{} in {}", binding.name(), context.name());
+        }
+        assert_eq!(keymap.get(KeyContext::Insert, chord("Ctrl+E")), Some(Binding::Run(Action::ExecuteQuery)));
+        assert_eq!(keymap.get(KeyContext::Results, chord("Ctrl+E")), Some(Binding::Run(Action::ExportCsv)));
+        let global = keymap.help(KeyContext::Global);
+        assert_eq!(global[0], (vec![chord("Ctrl+Q"), chord("Ctrl+C")], Binding::Key(KeyAction::Quit)));
+    }
+
+    #[test]
+    fn test_overrides_and_conflicts() {
+        let mut overrides: KeymapConfig = BTreeMap::new();
+        overrides.insert(
+            "normal".into(),
+            BTreeMap::from([("F5".into(), "execute".into()), ("Ctrl+E".into(), "none".into())]),
+        );
+        overrides.insert(
+            "results".into(),
+            BTreeMap::from([("Tab".into(), "data-tab".into()), ("x".into(), "complete".into())]),
+        );
+        overrides.insert("insert".into(), BTreeMap::from([("x".into(), "format".into())]));
+        overrides.insert("popup".into(), BTreeMap::new());
+        overrides.insert("modal".into(), BTreeMap::from([("Ctrl+J".into(), "down".into())]));
+        let keymap = Keymap::new(&overrides);
+
+        assert_eq!(keymap.get(KeyContext::Normal, chord("F5")), Some(Binding::Run(Action::ExecuteQuery)));
+        assert_eq!(keymap.get(KeyContext::Normal, chord("Ctrl+E")), None);
+        assert_eq!(keymap.get(KeyContext::Results, chord("x")), None);
+        assert_eq!(keymap.problems.len(), 4, "{:?}", keymap.problems);
+        // Tab switches panels before results see it; Tab in insert mode is fine
+        assert!(keymap.problems.iter().any(|p| p.contains("Tab") && p.contains("next-panel")));
+        assert!(keymap.problems.iter().any(|p| p.contains("complete")));
+        assert!(keymap.problems.iter().any(|p| p.contains("popup")));
+        // `modal` names the connections context
+        assert!(!keymap.problems.iter().any(|p| p.contains("modal")));
+        assert_eq!(keymap.get(KeyContext::Connections, chord("Ctrl+J")), Some(Binding::Key(KeyAction::Down)));
+        assert!(!global_applies(Binding::Key(KeyAction::NextPanel), chord("Tab"), KeyContext::Insert));
+        assert!(!global_applies(Binding::Key(KeyAction::Leader), chord("Space"), KeyContext::Insert));
+        assert!(global_applies(Binding::Key(KeyAction::Leader), chord("Space"), KeyContext::Normal));
+    }
+}
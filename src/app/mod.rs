@@ -5,9 +5,26 @@ mod actions;
 mod handlers;
 mod history;
 mod export;
+mod files;
+mod buffers;
+mod command;
+mod options;
+mod registry;
+mod keymap;
+mod panels;
+mod editor_state;
 mod undo;
+mod diagnostics;
 pub mod editor;
 
 pub use state::*;
 pub use history::*;
 pub use undo::*;
+pub use export::{ExportFormat, write_results};
+pub use buffers::{BufferTab, EditorBuffer};
+pub use command::CommandHistory;
+pub use options::EditorOptions;
+pub use registry::Action;
+pub use keymap::{global_applies, Binding, KeyAction, KeyChord, KeyContext, Keymap};
+pub use panels::{Divider, PanelRects, RESIZE_STEP};
+pub use editor_state::EditorState;
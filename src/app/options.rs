@@ -0,0 +1,205 @@
+//! Editor options changed with `:set`
+
+use crate::app::editor::search::SearchFlags;
+use rust_i18n::t;
+
+/// Names accepted by `:set` (long form first, then its abbreviation)
+pub const OPTION_NAMES: &[(&str, &str)] = &[
+    ("tabstop", "ts"),
+    ("autoclose", "ac"),
+    ("number", "nu"),
+    ("locale", "lang"),
+    ("ignorecase", "ic"),
+    ("smartcase", "scs"),
+    ("regex", "re"),
+    ("hlsearch", "hls"),
+    ("incsearch", "is"),
+];
+
+/// Editor behaviour toggled at runtime
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EditorOptions {
+    /// Spaces inserted by Tab (and removed by Shift+Tab)
+    pub tab_width: usize,
+    /// Auto-close quotes and BEGIN/END blocks
+    pub auto_close: bool,
+    /// Show the line number gutter
+    pub line_numbers: bool,
+    /// Search ignores case...
+    pub ignore_case: bool,
+    /// ...unless the pattern has an uppercase letter
+    pub smart_case: bool,
+    /// Search patterns are regular expressions instead of plain text
+    pub regex: bool,
+    /// Highlight every match of the last search
+    pub hl_search: bool,
+    /// Move to and highlight matches while the pattern is typed
+    pub inc_search: bool,
+}
+
+impl Default for EditorOptions {
+    fn default() -> Self {
+        Self {
+            tab_width: 4,
+            auto_close: true,
+            line_numbers: true,
+            ignore_case: true,
+            smart_case: true,
+            regex: false,
+            hl_search: true,
+            inc_search: true,
+        }
+    }
+}
+
+/// Resolve an abbreviation to the option's long name
+pub fn option_name(name: &str) -> Option<&'static str> {
+    OPTION_NAMES
+        .iter()
+        .find(|(long, short)| *long == name || *short == name)
+        .map(|(long, _)| *long)
+}
+
+/// One `:set` argument: `name`, `noname`, `name!`, `name?` or `name=value`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SetArg {
+    Enable(String),
+    Disable(String),
+    Toggle(String),
+    Query(String),
+    Assign(String, String),
+}
+
+impl SetArg {
+    pub fn parse(arg: &str) -> Self {
+        if let Some((name, value)) = arg.split_once(['=', ':']) {
+            SetArg::Assign(name.to_string(), value.to_string())
+        } else if let Some(name) = arg.strip_suffix('!') {
+            SetArg::Toggle(name.to_string())
+        } else if let Some(name) = arg.strip_suffix('?') {
+            SetArg::Query(name.to_string())
+        } else if let Some(name) = arg.strip_prefix("no").filter(|n| option_name(n).is_some()) {
+            SetArg::Disable(name.to_string())
+        } else {
+            SetArg::Enable(arg.to_string())
+        }
+    }
+}
+
+impl EditorOptions {
+    /// Apply a boolean or numeric `:set` argument. Returns the text to show
+    /// for queries (`:set ts?`). `locale` is handled by the App.
+    pub fn apply(&mut self, arg: &SetArg) -> Result<Option<String>, String> {
+        let (name, value) = match arg {
+            SetArg::Enable(n) | SetArg::Disable(n) | SetArg::Toggle(n) | SetArg::Query(n) => (n, None),
+            SetArg::Assign(n, v) => (n, Some(v.as_str())),
+        };
+        let unknown = || t!("set_unknown_option", name = name).to_string();
+        let long = option_name(name).ok_or_else(unknown)?;
+
+        match (long, arg) {
+            (_, SetArg::Query(_)) => Ok(self.get(long).map(|v| format!("{}={}", long, v))),
+            ("tabstop", SetArg::Assign(_, _)) => {
+                let width = value
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .filter(|w| (1..=16).contains(w))
+                    .ok_or_else(|| t!("set_invalid_value", name = long, value = value.unwrap_or("")).to_string())?;
+                self.tab_width = width;
+                Ok(None)
+            }
+            ("autoclose" | "number" | "ignorecase" | "smartcase" | "regex" | "hlsearch" | "incsearch", _) => {
+                let flag = match long {
+                    "autoclose" => &mut self.auto_close,
+                    "number" => &mut self.line_numbers,
+                    "ignorecase" => &mut self.ignore_case,
+                    "smartcase" => &mut self.smart_case,
+                    "regex" => &mut self.regex,
+                    "hlsearch" => &mut self.hl_search,
+                    _ => &mut self.inc_search,
+                };
+                *flag = match arg {
+                    SetArg::Enable(_) => true,
+                    SetArg::Disable(_) => false,
+                    SetArg::Toggle(_) => !*flag,
+                    _ => return Err(t!("set_invalid_value", name = long, value = value.unwrap_or("")).to_string()),
+                };
+                Ok(None)
+            }
+            // Enabling a numeric option is the same as querying it, like vim
+            ("tabstop", _) => Ok(self.get(long).map(|v| format!("{}={}", long, v))),
+            _ => Err(unknown()),
+        }
+    }
+
+    /// Current value of an option as text
+    pub fn get(&self, name: &str) -> Option<String> {
+        match option_name(name)? {
+            "tabstop" => Some(self.tab_width.to_string()),
+            "autoclose" => Some(self.auto_close.to_string()),
+            "number" => Some(self.line_numbers.to_string()),
+            "ignorecase" => Some(self.ignore_case.to_string()),
+            "smartcase" => Some(self.smart_case.to_string()),
+            "regex" => Some(self.regex.to_string()),
+            "hlsearch" => Some(self.hl_search.to_string()),
+            "incsearch" => Some(self.inc_search.to_string()),
+            _ => None,
+        }
+    }
+
+    /// How search patterns are compiled
+    pub fn search_flags(&self) -> SearchFlags {
+        SearchFlags {
+            ignore_case: self.ignore_case,
+            smart_case: self.smart_case,
+            regex: self.regex,
+        }
+    }
+
+    /// Summary shown by a bare `:set`
+    pub fn describe(&self) -> String {
+        let flag = |on: bool, name: &str| format!("{}{}", if on { "" } else { "no" }, name);
+        format!(
+            "tabstop={} {} {} {} {} {} {} {}",
+            self.tab_width,
+            flag(self.auto_close, "autoclose"),
+            flag(self.line_numbers, "number"),
+            flag(self.ignore_case, "ignorecase"),
+            flag(self.smart_case, "smartcase"),
+            flag(self.regex, "regex"),
+            flag(self.hl_search, "hlsearch"),
+            flag(self.inc_search, "incsearch"),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_set_args() {
+        assert_eq!(SetArg::parse("ts=2"), SetArg::Assign("ts".into(), "2".into()));
+        assert_eq!(SetArg::parse("nonumber"), SetArg::Disable("number".into()));
+        assert_eq!(SetArg::parse("number!"), SetArg::Toggle("number".into()));
+        assert_eq!(SetArg::parse("ts?"), SetArg::Query("ts".into()));
+        assert_eq!(SetArg::parse("autoclose"), SetArg::Enable("autoclose".into()));
+    }
+
+    #[test]
+    fn test_apply_options() {
+        let mut opts = EditorOptions::default();
+        opts.apply(&SetArg::parse("ts=2")).unwrap();
+        assert_eq!(opts.tab_width, 2);
+        opts.apply(&SetArg::parse("noac")).unwrap();
+        assert!(!opts.auto_close);
+        opts.apply(&SetArg::parse("nu!")).unwrap();
+        assert!(!opts.line_numbers);
+        opts.apply(&SetArg::parse("noic")).unwrap();
+        assert!(!opts.ignore_case);
+        opts.apply(&SetArg::parse("regex")).unwrap();
+        assert!(opts.regex);
+        assert_eq!(opts.apply(&SetArg::parse("tabstop?")).unwrap(), Some("tabstop=2".into()));
+        assert!(opts.apply(&SetArg::parse("ts=0")).is_err());
+        assert!(opts.apply(&SetArg::parse("bogus")).is_err());
+    }
+}
@@ -0,0 +1,400 @@
+//! Panel layout: sizes, visibility, zoom and the stacked arrangement
+//!
+//! The geometry lives here rather than in the UI so the mouse handler can
+//! hit-test exactly the rectangles that were drawn.
+
+use crate::app::{ActivePanel, App};
+use crate::config::{LayoutConfig, LayoutMode};
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use rust_i18n::t;
+
+/// Terminals narrower than this get the stacked layout in `LayoutMode::Auto`
+const STACKED_BELOW_WIDTH: u16 = 100;
+/// Splits stay within these bounds so no panel collapses to nothing
+const MIN_SPLIT: u16 = 15;
+const MAX_SPLIT: u16 = 85;
+/// Percentage step for keyboard resizing
+pub const RESIZE_STEP: i16 = 5;
+
+/// Where each panel is drawn; hidden panels have no rectangle
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PanelRects {
+    pub editor: Option<Rect>,
+    pub results: Option<Rect>,
+    pub schema: Option<Rect>,
+    pub history: Option<Rect>,
+}
+
+impl PanelRects {
+    pub fn get(&self, panel: ActivePanel) -> Option<Rect> {
+        match panel {
+            ActivePanel::QueryEditor => self.editor,
+            ActivePanel::Results => self.results,
+            ActivePanel::SchemaExplorer => self.schema,
+            ActivePanel::History => self.history,
+        }
+    }
+
+    /// The panel drawn at a screen position
+    pub fn panel_at(&self, x: u16, y: u16) -> Option<ActivePanel> {
+        [
+            ActivePanel::QueryEditor,
+            ActivePanel::Results,
+            ActivePanel::SchemaExplorer,
+            ActivePanel::History,
+        ]
+        .into_iter()
+        .find(|&panel| self.get(panel).is_some_and(|r| contains(r, x, y)))
+    }
+}
+
+/// A boundary between panels that can be dragged with the mouse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Divider {
+    /// Between the main column (editor + results) and the side panels
+    Main,
+    /// Between the editor and the results
+    Editor,
+    /// Between the schema explorer and the history
+    Side,
+}
+
+/// Intermediate geometry shared by drawing, hit-testing and dragging
+struct Geometry {
+    stacked: bool,
+    main: Rect,
+    side: Option<Rect>,
+    rects: PanelRects,
+}
+
+/// Whether the panels are arranged in a single column
+pub fn is_stacked(layout: &LayoutConfig, width: u16) -> bool {
+    match layout.mode {
+        LayoutMode::Auto => width < STACKED_BELOW_WIDTH,
+        LayoutMode::Wide => false,
+        LayoutMode::Stacked => true,
+    }
+}
+
+/// Compute panel rectangles. With `zoomed` set only that panel is shown.
+pub fn panel_rects(area: Rect, layout: &LayoutConfig, zoomed: Option<ActivePanel>) -> PanelRects {
+    match zoomed {
+        Some(panel) => {
+            let mut rects = PanelRects::default();
+            match panel {
+                ActivePanel::QueryEditor => rects.editor = Some(area),
+                ActivePanel::Results => rects.results = Some(area),
+                ActivePanel::SchemaExplorer => rects.schema = Some(area),
+                ActivePanel::History => rects.history = Some(area),
+            }
+            rects
+        }
+        None => geometry(area, layout).rects,
+    }
+}
+
+fn geometry(area: Rect, layout: &LayoutConfig) -> Geometry {
+    let stacked = is_stacked(layout, area.width);
+    let (main, side) = if layout.show_schema || layout.show_history {
+        let (main, side) = split(area, layout.main_split, !stacked);
+        (main, Some(side))
+    } else {
+        (area, None)
+    };
+
+    let (editor, results) = split(main, layout.editor_split, false);
+    let (schema, history) = match side {
+        Some(side) if layout.show_schema && layout.show_history => {
+            let (schema, history) = split(side, layout.side_split, stacked);
+            (Some(schema), Some(history))
+        }
+        Some(side) if layout.show_schema => (Some(side), None),
+        Some(side) => (None, Some(side)),
+        None => (None, None),
+    };
+
+    Geometry {
+        stacked,
+        main,
+        side,
+        rects: PanelRects {
+            editor: Some(editor),
+            results: Some(results),
+            schema,
+            history,
+        },
+    }
+}
+
+/// Split `area` giving `percent` to the first part, side by side when
+/// `horizontal`, otherwise one above the other
+fn split(area: Rect, percent: u16, horizontal: bool) -> (Rect, Rect) {
+    // Hand-edited configs may hold anything
+    let percent = percent.clamp(MIN_SPLIT, MAX_SPLIT);
+    if horizontal {
+        let first = (area.width as u32 * percent as u32 / 100) as u16;
+        (
+            Rect { width: first, ..area },
+            Rect { x: area.x + first, width: area.width - first, ..area },
+        )
+    } else {
+        let first = (area.height as u32 * percent as u32 / 100) as u16;
+        (
+            Rect { height: first, ..area },
+            Rect { y: area.y + first, height: area.height - first, ..area },
+        )
+    }
+}
+
+fn contains(r: Rect, x: u16, y: u16) -> bool {
+    x >= r.x && x < r.x + r.width && y >= r.y && y < r.y + r.height
+}
+
+/// Whether (x, y) is on the border pair where `second` starts
+fn on_boundary(second: Rect, horizontal: bool, x: u16, y: u16) -> bool {
+    if horizontal {
+        (x == second.x || x + 1 == second.x) && y >= second.y && y < second.y + second.height
+    } else {
+        (y == second.y || y + 1 == second.y) && x >= second.x && x < second.x + second.width
+    }
+}
+
+/// The divider under a screen position, if any
+pub fn divider_at(area: Rect, layout: &LayoutConfig, x: u16, y: u16) -> Option<Divider> {
+    let geo = geometry(area, layout);
+    if geo.rects.schema.is_some()
+        && let Some(history) = geo.rects.history
+        && on_boundary(history, geo.stacked, x, y)
+    {
+        return Some(Divider::Side);
+    }
+    if let Some(results) = geo.rects.results
+        && on_boundary(results, false, x, y)
+    {
+        return Some(Divider::Editor);
+    }
+    if let Some(side) = geo.side
+        && on_boundary(side, !geo.stacked, x, y)
+    {
+        return Some(Divider::Main);
+    }
+    None
+}
+
+/// New split percentage for `divider` when dragged to (x, y)
+pub fn drag_split(area: Rect, layout: &LayoutConfig, divider: Divider, x: u16, y: u16) -> u16 {
+    let geo = geometry(area, layout);
+    let (region, horizontal) = match divider {
+        Divider::Main => (area, !geo.stacked),
+        Divider::Editor => (geo.main, false),
+        Divider::Side => (geo.side.unwrap_or(area), geo.stacked),
+    };
+    let (pos, start, len) = if horizontal {
+        (x, region.x, region.width)
+    } else {
+        (y, region.y, region.height)
+    };
+    let percent = (pos.saturating_sub(start) as u32 * 100 / len.max(1) as u32) as u16;
+    percent.clamp(MIN_SPLIT, MAX_SPLIT)
+}
+
+fn adjust(split: u16, delta: i16) -> u16 {
+    (split as i16 + delta).clamp(MIN_SPLIT as i16, MAX_SPLIT as i16) as u16
+}
+
+impl App {
+    /// Panel rectangles for the last drawn frame
+    pub fn panel_rects(&self) -> PanelRects {
+        panel_rects(
+            self.content_area,
+            &self.app_config.layout,
+            self.zoomed.then_some(self.active_panel),
+        )
+    }
+
+    /// Whether a panel is currently shown (zoom aside)
+    pub fn is_panel_visible(&self, panel: ActivePanel) -> bool {
+        match panel {
+            ActivePanel::SchemaExplorer => self.app_config.layout.show_schema,
+            ActivePanel::History => self.app_config.layout.show_history,
+            _ => true,
+        }
+    }
+
+    /// Focus a panel, showing it first if it was hidden
+    pub fn focus_panel(&mut self, panel: ActivePanel) {
+        if !self.is_panel_visible(panel) {
+            self.toggle_panel(panel);
+        }
+        self.active_panel = panel;
+    }
+
+    /// Focus the next visible panel
+    pub fn focus_next_panel(&mut self) {
+        loop {
+            self.active_panel = match self.active_panel {
+                ActivePanel::QueryEditor => ActivePanel::Results,
+                ActivePanel::Results => ActivePanel::SchemaExplorer,
+                ActivePanel::SchemaExplorer => ActivePanel::History,
+                ActivePanel::History => ActivePanel::QueryEditor,
+            };
+            if self.is_panel_visible(self.active_panel) {
+                break;
+            }
+        }
+    }
+
+    /// Grow (positive) or shrink the main column
+    pub fn resize_main(&mut self, delta: i16) {
+        let layout = &mut self.app_config.layout;
+        layout.main_split = adjust(layout.main_split, delta);
+        self.save_layout();
+    }
+
+    /// Grow (positive) or shrink the active panel within its column
+    pub fn resize_active(&mut self, delta: i16) {
+        let layout = &mut self.app_config.layout;
+        match self.active_panel {
+            ActivePanel::QueryEditor => layout.editor_split = adjust(layout.editor_split, delta),
+            ActivePanel::Results => layout.editor_split = adjust(layout.editor_split, -delta),
+            ActivePanel::SchemaExplorer => layout.side_split = adjust(layout.side_split, delta),
+            ActivePanel::History => layout.side_split = adjust(layout.side_split, -delta),
+        }
+        self.save_layout();
+    }
+
+    /// Show only the active panel, or go back to all panels
+    pub fn toggle_zoom(&mut self) {
+        self.zoomed = !self.zoomed;
+    }
+
+    /// Hide or show the schema explorer or history panel
+    pub fn toggle_panel(&mut self, panel: ActivePanel) {
+        let layout = &mut self.app_config.layout;
+        let shown = match panel {
+            ActivePanel::SchemaExplorer => &mut layout.show_schema,
+            ActivePanel::History => &mut layout.show_history,
+            _ => return,
+        };
+        *shown = !*shown;
+        if !*shown && self.active_panel == panel {
+            self.active_panel = ActivePanel::QueryEditor;
+        }
+        self.save_layout();
+    }
+
+    /// Switch between the side-by-side and stacked arrangements
+    pub fn toggle_stacked(&mut self) {
+        let stacked = is_stacked(&self.app_config.layout, self.content_area.width);
+        self.set_layout_mode(if stacked { LayoutMode::Wide } else { LayoutMode::Stacked });
+    }
+
+    pub fn set_layout_mode(&mut self, mode: LayoutMode) {
+        self.app_config.layout.mode = mode;
+        self.save_layout();
+    }
+
+    /// Restore the default sizes and show every panel
+    pub fn reset_layout(&mut self) {
+        self.app_config.layout = LayoutConfig::default();
+        self.zoomed = false;
+        self.save_layout();
+    }
+
+    fn save_layout(&mut self) {
+        if self.app_config.save().is_err() {
+            self.error = Some(t!("layout_save_failed").to_string());
+        }
+    }
+
+    /// Mouse clicks focus panels and drags on borders resize them.
+    /// Returns true when the event was consumed.
+    pub(crate) fn handle_layout_mouse(&mut self, mouse: MouseEvent) -> bool {
+        let (x, y) = (mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if !self.zoomed
+                    && let Some(divider) = divider_at(self.content_area, &self.app_config.layout, x, y)
+                {
+                    self.layout_drag = Some(divider);
+                } else if let Some(panel) = self.panel_rects().panel_at(x, y) {
+                    self.active_panel = panel;
+                }
+                true
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                let Some(divider) = self.layout_drag else {
+                    return false;
+                };
+                let percent = drag_split(self.content_area, &self.app_config.layout, divider, x, y);
+                let layout = &mut self.app_config.layout;
+                match divider {
+                    Divider::Main => layout.main_split = percent,
+                    Divider::Editor => layout.editor_split = percent,
+                    Divider::Side => layout.side_split = percent,
+                }
+                true
+            }
+            MouseEventKind::Up(MouseButton::Left) => {
+                if self.layout_drag.take().is_some() {
+                    self.save_layout();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AREA: Rect = Rect { x: 0, y: 3, width: 200, height: 50 };
+
+    #[test]
+    fn test_wide_layout() {
+        let rects = panel_rects(AREA, &LayoutConfig::default(), None);
+        assert_eq!(rects.editor, Some(Rect { x: 0, y: 3, width: 140, height: 30 }));
+        assert_eq!(rects.results, Some(Rect { x: 0, y: 33, width: 140, height: 20 }));
+        assert_eq!(rects.schema, Some(Rect { x: 140, y: 3, width: 60, height: 30 }));
+        assert_eq!(rects.history, Some(Rect { x: 140, y: 33, width: 60, height: 20 }));
+        assert_eq!(rects.panel_at(150, 40), Some(ActivePanel::History));
+    }
+
+    #[test]
+    fn test_stacked_and_hidden_panels() {
+        // Narrow terminals stack automatically
+        let narrow = Rect { width: 80, ..AREA };
+        let rects = panel_rects(narrow, &LayoutConfig::default(), None);
+        assert_eq!(rects.editor.unwrap().width, 80);
+        assert_eq!(rects.schema.unwrap().y, rects.history.unwrap().y);
+
+        let layout = LayoutConfig { show_schema: false, show_history: false, ..LayoutConfig::default() };
+        let rects = panel_rects(AREA, &layout, None);
+        assert_eq!(rects.editor.unwrap().width, 200);
+        assert_eq!(rects.schema, None);
+
+        let layout = LayoutConfig { show_schema: false, ..LayoutConfig::default() };
+        let rects = panel_rects(AREA, &layout, None);
+        assert_eq!(rects.history, Some(Rect { x: 140, y: 3, width: 60, height: 50 }));
+
+        let rects = panel_rects(AREA, &LayoutConfig::default(), Some(ActivePanel::Results));
+        assert_eq!(rects.results, Some(AREA));
+        assert_eq!(rects.editor, None);
+    }
+
+    #[test]
+    fn test_dividers_and_dragging() {
+        let layout = LayoutConfig::default();
+        assert_eq!(divider_at(AREA, &layout, 139, 10), Some(Divider::Main));
+        assert_eq!(divider_at(AREA, &layout, 50, 33), Some(Divider::Editor));
+        assert_eq!(divider_at(AREA, &layout, 160, 32), Some(Divider::Side));
+        assert_eq!(divider_at(AREA, &layout, 50, 10), None);
+
+        assert_eq!(drag_split(AREA, &layout, Divider::Main, 100, 10), 50);
+        assert_eq!(drag_split(AREA, &layout, Divider::Editor, 10, 43), 80);
+        assert_eq!(drag_split(AREA, &layout, Divider::Editor, 10, 3), MIN_SPLIT);
+    }
+}
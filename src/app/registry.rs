@@ -0,0 +1,138 @@
+//! Named application actions
+//!
+//! Every action here can be run from the command line by name (`:format`),
+//! which gives each feature a keyboard-driven entry point, and can be bound
+//! to a key in any panel (see `keymap`).
+
+use crate::app::{ActivePanel, App, ExportFormat};
+
+/// An application action that can be invoked by name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    ExecuteQuery,
+    FormatQuery,
+    Undo,
+    Redo,
+    ExportCsv,
+    ExportJson,
+    FocusEditor,
+    FocusResults,
+    FocusSchema,
+    FocusHistory,
+    Connections,
+    Help,
+    ReloadSchema,
+    ClearHistory,
+    Zoom,
+    ToggleSchema,
+    ToggleHistory,
+    ToggleLayout,
+    ResetLayout,
+}
+
+impl Action {
+    /// All registered actions, in the order they are listed
+    pub const ALL: &'static [Action] = &[
+        Action::ExecuteQuery,
+        Action::FormatQuery,
+        Action::Undo,
+        Action::Redo,
+        Action::ExportCsv,
+        Action::ExportJson,
+        Action::FocusEditor,
+        Action::FocusResults,
+        Action::FocusSchema,
+        Action::FocusHistory,
+        Action::Connections,
+        Action::Help,
+        Action::ReloadSchema,
+        Action::ClearHistory,
+        Action::Zoom,
+        Action::ToggleSchema,
+        Action::ToggleHistory,
+        Action::ToggleLayout,
+        Action::ResetLayout,
+    ];
+
+    /// Name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ExecuteQuery => "execute",
+            Action::FormatQuery => "format",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::ExportCsv => "export-csv",
+            Action::ExportJson => "export-json",
+            Action::FocusEditor => "editor",
+            Action::FocusResults => "results",
+            Action::FocusSchema => "schema",
+            Action::FocusHistory => "history",
+            Action::Connections => "connections",
+            Action::Help => "help",
+            Action::ReloadSchema => "reload-schema",
+            Action::ClearHistory => "clear-history",
+            Action::Zoom => "zoom",
+            Action::ToggleSchema => "toggle-schema",
+            Action::ToggleHistory => "toggle-history",
+            Action::ToggleLayout => "toggle-layout",
+            Action::ResetLayout => "reset-layout",
+        }
+    }
+
+    /// Look up an action by its command-line name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|a| a.name() == name)
+    }
+}
+
+impl App {
+    /// Run a registered action
+    pub async fn run_action(&mut self, action: Action) {
+        match action {
+            Action::ExecuteQuery => self.start_query(),
+            Action::FormatQuery => self.format_sql(),
+            Action::Undo => { self.undo(); }
+            Action::Redo => { self.redo(); }
+            Action::ExportCsv => self.export_results(ExportFormat::Csv, None),
+            Action::ExportJson => self.export_results(ExportFormat::Json, None),
+            Action::FocusEditor => self.active_panel = ActivePanel::QueryEditor,
+            Action::FocusResults => self.active_panel = ActivePanel::Results,
+            Action::FocusSchema => self.focus_panel(ActivePanel::SchemaExplorer),
+            Action::FocusHistory => self.focus_panel(ActivePanel::History),
+            Action::Connections => {
+                self.show_connection_modal = true;
+                self.update_form_from_selection();
+            }
+            Action::Help => {
+                self.show_help = true;
+                self.help_scroll = 0;
+            }
+            Action::ReloadSchema => {
+                if let Err(e) = self.load_schema().await {
+                    self.error = Some(e.to_string());
+                } else {
+                    self.start_column_loading();
+                }
+            }
+            Action::ClearHistory => self.clear_history(),
+            Action::Zoom => self.toggle_zoom(),
+            Action::ToggleSchema => self.toggle_panel(ActivePanel::SchemaExplorer),
+            Action::ToggleHistory => self.toggle_panel(ActivePanel::History),
+            Action::ToggleLayout => self.toggle_stacked(),
+            Action::ResetLayout => self.reset_layout(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_names_round_trip() {
+        for action in Action::ALL {
+            assert_eq!(Action::from_name(action.name()), Some(*action));
+        }
+        assert_eq!(Action::from_name("nope"), None);
+    }
+}
@@ -3,13 +3,28 @@
 //! This module contains the main App struct and related types.
 //! Business logic and async operations are in the actions module.
 
+use crate::cli::TuiArgs;
 use crate::completion::CompletionState;
-use crate::config::{AppConfig, ConnectionConfig, ConnectionForm};
+use crate::config::{AppConfig, ConnectionConfig, ConnectionForm, Encryption, TagColor};
 use crate::db::{ColumnDef, DatabaseBackend, DatabaseDriver, QueryResult};
-use crate::app::{QueryHistory, UndoManager};
+use crate::sql::lint::Diagnostic;
+use crate::sql::pairs::Pairs;
+use crate::ui::{ActiveTheme, Theme};
+use crate::app::buffers::UNDO_HISTORY_LIMIT;
+use crate::app::editor::normal::{InsertSession, LastChange, PendingCommand};
+use crate::app::editor::registers::Marks;
+use crate::app::editor::search::{Search, SearchPrompt};
+use crate::app::editor::visual::{Block, BlockInsert, Selection, VisualKind};
+use crate::app::editor::operations;
+use crate::app::undo::EditorSnapshot;
+use crate::app::{CommandHistory, Divider, EditorBuffer, EditorOptions, EditorState, Keymap, QueryHistory, UndoManager};
 use anyhow::Result;
-use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::Rect;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Instant;
 use tokio::sync::{oneshot, RwLock};
 use rust_i18n::t;
 
@@ -34,6 +49,7 @@ pub enum ResultsTab {
     Data,       // Table data
     Columns,    // Column names and types
     Stats,      // Query statistics
+    Problems,   // Lint diagnostics of the editor buffer
 }
 
 /// Input mode for the query editor
@@ -102,11 +118,24 @@ pub enum ConnectionModalFocus {
     Form,
 }
 
+/// Typed confirmation asked before a destructive batch runs
+#[derive(Clone, Debug)]
+pub struct ConfirmPrompt {
+    /// The batch waiting to run
+    pub query: String,
+    /// Destructive verbs found in it, for the warning
+    pub verbs: Vec<String>,
+    /// What was typed so far; must equal the connection name
+    pub typed: String,
+}
+
 /// Main application state
 pub struct App {
     // === Database ===
     /// Database driver (None when not connected yet)
     pub db: Option<Box<dyn DatabaseDriver>>,
+    /// Connection the driver was created from (for `:use` and reconnects)
+    pub current_connection: Option<ConnectionConfig>,
 
     // === Connection Management ===
     /// Application configuration (saved connections)
@@ -123,6 +152,11 @@ pub struct App {
     pub connection_modal_focus: ConnectionModalFocus,
 
     // === Query Editor ===
+    /// All editor buffers (tabs). The entry at `active_buffer` is a placeholder:
+    /// the active buffer's state lives in the fields below.
+    pub buffers: Vec<EditorBuffer>,
+    /// Index of the active buffer
+    pub active_buffer: usize,
     /// Current query text
     pub query: String,
     /// Cursor position in query
@@ -135,15 +169,68 @@ pub struct App {
     pub input_mode: InputMode,
     /// Visual mode selection anchor (start position)
     pub visual_anchor: usize,
+    /// Characterwise, linewise or block selection
+    pub visual_kind: VisualKind,
+    /// `$` in a block: the block reaches the end of every line
+    pub visual_to_line_end: bool,
+    /// Block `I` / `A` being typed, copied to the block's other lines on Esc
+    pub block_insert: Option<BlockInsert>,
     /// Last character search for f/F/t/T with ; and , repeat
     /// (character, is_forward, is_till)
     pub last_char_search: Option<(char, bool, bool)>,
     /// Pending operator waiting for character input (f, F, t, T)
     pub pending_char_search: Option<char>,
+    /// Visual mode `i` / `a` waiting for a text object (true = `a`)
+    pub pending_text_object: Option<bool>,
     /// Pending g prefix (waiting for second key: g, _, e, etc.)
     pub pending_g: bool,
+    /// Normal mode keys typed so far (count, operator, ...)
+    pub normal_pending: PendingCommand,
+    /// Last change, repeated with `.`
+    pub last_change: Option<LastChange>,
+    /// Insert mode entered by the last change, to record the typed text
+    pub insert_session: Option<InsertSession>,
+    /// Registers and saved file marks (persisted)
+    pub editor_state: EditorState,
+    /// Marks of the active buffer
+    pub marks: Marks,
+    /// Last text we put on the system clipboard
+    pub clipboard_sent: Option<String>,
+    /// Macro being recorded: register and keys so far
+    pub recording: Option<(char, Vec<KeyEvent>)>,
+    /// Last macro played, for `@@`
+    pub last_macro: Option<char>,
+    /// Macro keys waiting to be handled
+    pub replay_keys: VecDeque<KeyEvent>,
+    /// Last search, repeated by `n` / `N`
+    pub search: Option<Search>,
+    /// Whether matches of the last search are highlighted (`:noh` clears it)
+    pub search_highlight: bool,
+    /// Open `/` or `?` prompt (the pattern is typed into `command_buffer`)
+    pub search_prompt: Option<SearchPrompt>,
+    /// Pattern typed so far, while `incsearch` previews it
+    pub search_preview: Option<Search>,
+    pub search_history: CommandHistory,
     /// Autocomplete state
     pub completion: CompletionState,
+    /// Options changed with `:set`
+    pub editor_options: EditorOptions,
+    /// File backing the editor buffer (None = unnamed buffer)
+    pub query_path: Option<PathBuf>,
+    /// Buffer contents at the last open/save, used for the dirty indicator
+    pub saved_query: String,
+    /// Lint diagnostics of the buffer, sorted by position
+    pub diagnostics: Vec<Diagnostic>,
+    /// Buffer text and schema size the diagnostics were computed for
+    pub linted: (String, usize),
+    /// Lint running in the background, with the text it checks
+    pub pending_lint: Option<(String, oneshot::Receiver<Vec<Diagnostic>>)>,
+    /// Buffer text last seen by the run loop and when it changed, so
+    /// linting waits for a pause in typing
+    pub lint_watch: (String, Instant),
+    /// Bracket and BEGIN / CASE / END pairs of the buffer, with the text
+    /// and dialect they were found for (see `App::pairs`)
+    pub pairs: (String, DatabaseBackend, Pairs),
 
     // === Query Execution ===
     /// Current query result
@@ -154,6 +241,8 @@ pub struct App {
     pub pending_query: Option<oneshot::Receiver<Result<QueryResult, String>>>,
     /// Query being executed (for history)
     pub pending_query_text: Option<String>,
+    /// Destructive batch waiting for the connection name to be typed
+    pub confirm_prompt: Option<ConfirmPrompt>,
     /// Spinner frame for loading animation
     pub spinner_frame: usize,
 
@@ -204,8 +293,14 @@ pub struct App {
     pub active_panel: ActivePanel,
     /// Should quit?
     pub should_quit: bool,
+    /// Quit was requested with unsaved changes; the next quit key confirms it
+    pub quit_pending: bool,
     /// Show help popup
     pub show_help: bool,
+    /// First line of the help shown
+    pub help_scroll: u16,
+    /// Key bindings in effect
+    pub keymap: Keymap,
     /// Error message
     pub error: Option<String>,
     /// Success message
@@ -216,35 +311,73 @@ pub struct App {
     pub server_version: String,
     /// Command buffer (for : commands)
     pub command_buffer: String,
+    /// Previously executed : commands
+    pub command_history: CommandHistory,
+    /// Tab completion candidates for the command line and the selected one
+    pub command_completion: Option<(Vec<String>, usize)>,
     /// Pending smooth scroll amount (positive = down, negative = up)
     pub pending_scroll: i32,
     /// Command mode
     pub command_mode: bool,
+
+    // === Layout ===
+    /// Show only the active panel
+    pub zoomed: bool,
+    /// Area the panels were last drawn in (for mouse hit-testing)
+    pub content_area: Rect,
+    /// Divider being dragged with the mouse
+    pub layout_drag: Option<Divider>,
 }
 
 impl App {
-    /// Create new app, attempting to auto-connect to last used connection
-    pub async fn new() -> Result<Self> {
+    /// Create new app, connecting to the connection chosen on the command line
+    /// or, by default, to the last used connection
+    pub async fn new(args: TuiArgs) -> Result<Self> {
         let app_config = AppConfig::load();
         
         // Initialize i18n locale from config or system
         crate::init_locale(app_config.locale.as_deref());
         
-        // Try to connect to the last used connection
+        let explicit = !args.connection.is_empty();
+        let mut startup_error = app_config
+            .project
+            .as_ref()
+            .and_then(|project| project.error.as_ref())
+            .map(|error| t!("project_config_error", error = error).to_string());
+        ActiveTheme::set_no_color(ActiveTheme::no_color_requested());
+        if let Some(name) = &app_config.theme {
+            match Theme::load(name) {
+                Ok(theme) => ActiveTheme::set(theme),
+                Err(e) => startup_error = startup_error.or(Some(format!("{:#}", e))),
+            }
+        }
+
+        let keymap = Keymap::new(&app_config.keymap);
+        if startup_error.is_none() && !keymap.problems.is_empty() {
+            startup_error = Some(t!("keymap_problems", count = keymap.problems.len()).to_string());
+        }
+        let conn_config = args.connection.resolve(&app_config).unwrap_or_else(|e| {
+            startup_error = Some(e.to_string());
+            None
+        });
+
+        // Try to connect
         let (db, show_modal, server_version): (Option<Box<dyn DatabaseDriver>>, bool, String) =
-            if let Some(ref last_name) = app_config.last_connection {
-                if let Some(conn_config) = app_config.get_connection(last_name) {
-                    match Self::create_driver(conn_config).await {
-                        Ok(driver) => {
-                            let version = driver.get_server_version().await
-                                .unwrap_or_else(|_| "Unknown".to_string());
-                            let short = version.lines().next().unwrap_or("Database").to_string();
-                            (Some(driver), false, short)
+            if let Some(ref conn_config) = conn_config {
+                match Self::create_driver(conn_config).await {
+                    Ok(driver) => {
+                        let version = driver.get_server_version().await
+                            .unwrap_or_else(|_| "Unknown".to_string());
+                        let short = version.lines().next().unwrap_or("Database").to_string();
+                        (Some(driver), false, short)
+                    }
+                    Err(e) => {
+                        // Only report failures for connections the user asked for
+                        if explicit {
+                            startup_error = Some(t!("connection_error", error = format!("{:#}", e)).to_string());
                         }
-                        Err(_) => (None, true, String::new()),
+                        (None, true, String::new())
                     }
-                } else {
-                    (None, true, String::new())
                 }
             } else {
                 (None, true, String::new())
@@ -254,6 +387,7 @@ impl App {
         let cursor_pos = 0;
 
         let mut app = Self {
+            current_connection: if db.is_some() { conn_config } else { None },
             db,
             app_config,
             show_connection_modal: show_modal,
@@ -261,20 +395,49 @@ impl App {
             connection_form: ConnectionForm::new_empty(),
             connection_form_focus: 0,
             connection_modal_focus: ConnectionModalFocus::List,
+            buffers: vec![EditorBuffer::default()],
+            active_buffer: 0,
             query: String::new(), 
             cursor_pos,
             query_scroll_x: 0,
             query_scroll_y: 0,
             input_mode: InputMode::Insert,
             visual_anchor: 0,
+            visual_kind: VisualKind::Char,
+            visual_to_line_end: false,
+            block_insert: None,
             last_char_search: None,
             pending_char_search: None,
+            pending_text_object: None,
             pending_g: false,
+            normal_pending: PendingCommand::default(),
+            last_change: None,
+            insert_session: None,
+            editor_state: EditorState::load(),
+            marks: Marks::new(),
+            clipboard_sent: None,
+            recording: None,
+            last_macro: None,
+            replay_keys: VecDeque::new(),
+            search: None,
+            search_highlight: false,
+            search_prompt: None,
+            search_preview: None,
+            search_history: CommandHistory::default(),
             completion: CompletionState::new(),
+            editor_options: EditorOptions::default(),
+            query_path: None,
+            saved_query: String::new(),
+            diagnostics: Vec::new(),
+            linted: (String::new(), 0),
+            pending_lint: None,
+            lint_watch: (String::new(), Instant::now()),
+            pairs: (String::new(), DatabaseBackend::default(), Pairs::default()),
             result: QueryResult::empty(),
             is_loading: false,
             pending_query: None,
             pending_query_text: None,
+            confirm_prompt: None,
             spinner_frame: 0,
             results_scroll: 0,
             results_selected: 0,
@@ -292,19 +455,32 @@ impl App {
             history: QueryHistory::new(1000),
             history_selected: 0,
             history_scroll_offset: 0,
-            undo_manager: UndoManager::new(1000),
+            undo_manager: UndoManager::new(UNDO_HISTORY_LIMIT),
             command_mode: false,
+            zoomed: false,
+            content_area: Rect::default(),
+            layout_drag: None,
             active_panel: ActivePanel::QueryEditor,
             should_quit: false,
+            quit_pending: false,
             show_help: false,
-            error: None,
+            help_scroll: 0,
+            keymap,
+            error: startup_error,
             message: if is_connected { Some(t!("connected").to_string()) } else { None },
             status: if is_connected { format!("{} | {}", t!("connected"), server_version) } else { t!("disconnected").to_string() },
             server_version,
             command_buffer: String::new(),
+            command_history: CommandHistory::default(),
+            command_completion: None,
             pending_scroll: 0,
         };
 
+        // Load the SQL file given on the command line into the editor
+        if let Some(path) = args.file {
+            app.open_file(path);
+        }
+
         // If connected, load schema
         if is_connected {
             let _ = app.load_schema().await;
@@ -320,6 +496,16 @@ impl App {
         self.db.is_some()
     }
 
+    /// Tag colour of the current connection (borders, header)
+    pub fn connection_tag(&self) -> Option<TagColor> {
+        self.current_connection.as_ref().map(ConnectionConfig::tag_color)
+    }
+
+    /// Dialect of the current connection (T-SQL when not connected)
+    pub fn sql_dialect(&self) -> DatabaseBackend {
+        self.current_connection.as_ref().map(|c| c.backend).unwrap_or_default()
+    }
+
     /// Get database driver reference (panics if not connected)
     pub fn db(&self) -> &dyn DatabaseDriver {
         self.db.as_ref().expect("Not connected to database").as_ref()
@@ -332,24 +518,43 @@ impl App {
 
     /// Create a database driver from a ConnectionConfig
     pub async fn create_driver(config: &ConnectionConfig) -> Result<Box<dyn DatabaseDriver>> {
+        // Secrets are resolved here only, so they never reach the saved config;
+        // password_cmd blocks, so off the runtime
+        let unresolved = config.clone();
+        let config = &tokio::task::spawn_blocking(move || unresolved.resolved()).await??;
         match config.backend {
             DatabaseBackend::SqlServer => {
                 use crate::db::sqlserver::{SqlServerConfig, SqlServerDriver};
+                use tiberius::EncryptionLevel;
+                let (host, instance) = config.host_and_instance();
                 let cfg = SqlServerConfig {
-                    host: config.host.clone(),
+                    host,
                     port: config.port,
                     user: config.user.clone(),
                     password: config.password.clone(),
                     database: config.database.clone(),
-                    encrypt: false,
-                    trust_cert: true,
+                    instance,
+                    encryption: match config.encryption {
+                        Encryption::Off => EncryptionLevel::NotSupported,
+                        Encryption::On => EncryptionLevel::On,
+                        Encryption::Required => EncryptionLevel::Required,
+                    },
+                    trust_cert: config.trust_server_cert,
+                    ca_file: Some(config.ca_file.clone()).filter(|path| !path.is_empty()),
+                    application_name: match config.application_name.as_str() {
+                        "" => "sql-tui".to_string(),
+                        name => name.to_string(),
+                    },
+                    connect_timeout: std::time::Duration::from_secs(config.connect_timeout.max(1)),
+                    read_only: config.read_only,
+                    ssh: config.ssh_jump(),
                 };
                 let driver = SqlServerDriver::new(cfg).await?;
                 Ok(Box::new(driver))
             }
             DatabaseBackend::Sqlite => {
                 use crate::db::sqlite::SqliteDriver;
-                let driver = SqliteDriver::new(config.sqlite_path.clone().into()).await?;
+                let driver = SqliteDriver::open(config.sqlite_path.clone().into(), config.read_only).await?;
                 Ok(Box::new(driver))
             }
         }
@@ -364,14 +569,17 @@ impl App {
         let short_version = version.lines().next().unwrap_or("Database").to_string();
 
         self.db = Some(driver);
+        self.current_connection = Some(config.clone());
         self.server_version = short_version.clone();
-        self.status = format!("Conectado | {}", short_version);
+        self.status = format!("{} | {}", t!("connected"), short_version);
         self.message = Some(t!("connected_to", name = config.name).to_string());
         self.show_connection_modal = false;
 
-        // Update last connection and save config
-        self.app_config.set_last_connection(&config.name);
-        let _ = self.app_config.save();
+        // Update last connection and save config (ad-hoc URL connections are not remembered)
+        if self.app_config.get_connection(&config.name).is_some() {
+            self.app_config.set_last_connection(&config.name);
+            let _ = self.app_config.save();
+        }
 
         // Load schema and start loading columns in background
         let _ = self.load_schema().await;
@@ -453,19 +661,58 @@ impl App {
         }
     }
 
-    /// Get visual selection range (start, end) - always start <= end
+    /// Get visual selection range (start, end) - always start <= end.
+    /// Linewise selections cover whole lines; a block gives its first and
+    /// last line.
     pub fn get_visual_selection(&self) -> (usize, usize) {
-        if self.visual_anchor <= self.cursor_pos {
+        let (start, end) = if self.visual_anchor <= self.cursor_pos {
             (self.visual_anchor, self.cursor_pos)
         } else {
             (self.cursor_pos, self.visual_anchor)
+        };
+        match self.visual_kind {
+            VisualKind::Char => (start, end),
+            VisualKind::Line | VisualKind::Block => {
+                let (start, end) = operations::line_range(&self.query, start, end);
+                (start, end.saturating_sub(1).max(start))
+            }
+        }
+    }
+
+    /// The visual selection as drawn by the editor
+    pub fn visual_selection(&self) -> Selection {
+        match self.visual_kind {
+            VisualKind::Block => Selection::Block(self.visual_block()),
+            _ => {
+                let (start, end) = self.get_visual_selection();
+                Selection::Range(start, end)
+            }
+        }
+    }
+
+    /// The visual block with the anchor and cursor in opposite corners
+    pub fn visual_block(&self) -> Block {
+        let mut block = Block::new(&self.query, self.visual_anchor, self.cursor_pos);
+        if self.visual_to_line_end {
+            block.right = usize::MAX;
         }
+        block
     }
 
-    /// Get selected text in visual mode
+    /// Get selected text in visual mode (a block gives one line per row)
     pub fn get_selected_text(&self) -> String {
-        let (start, end) = self.get_visual_selection();
-        self.query.chars().skip(start).take(end - start + 1).collect()
+        match self.visual_kind {
+            VisualKind::Block => self.visual_block().text(&self.query),
+            VisualKind::Line => {
+                let (start, end) = self.get_visual_selection();
+                let text: String = self.query.chars().skip(start).take(end - start + 1).collect();
+                text.trim_end_matches('\n').to_string()
+            }
+            VisualKind::Char => {
+                let (start, end) = self.get_visual_selection();
+                self.query.chars().skip(start).take(end - start + 1).collect()
+            }
+        }
     }
 
     // === Schema State Helpers ===
@@ -527,34 +774,56 @@ impl App {
 
     // === Undo/Redo Helpers ===
 
-    /// Save current state before making changes
-    pub fn save_undo_state(&mut self) {
-        self.undo_manager.save_state(&self.query, self.cursor_pos);
+    /// Record what an event changed in the buffer as one undo step. Typing
+    /// in Insert mode is kept together until the mode is left or the cursor
+    /// is moved with the arrow keys (like vim).
+    pub fn record_undo_step(&mut self, key: Option<KeyEvent>) {
+        let moves_cursor = key.is_some_and(|k| {
+            matches!(
+                k.code,
+                KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right | KeyCode::Home | KeyCode::End | KeyCode::PageUp | KeyCode::PageDown
+            )
+        });
+        if self.input_mode == InputMode::Insert && key.is_some() && !moves_cursor {
+            return;
+        }
+        self.undo_manager.commit(&self.query);
     }
 
     /// Undo last change
     pub fn undo(&mut self) -> bool {
-        if let Some(state) = self.undo_manager.undo(&self.query, self.cursor_pos) {
-            self.query = state.text;
-            self.cursor_pos = state.cursor_pos.min(self.query.chars().count());
-            self.message = Some(t!("undo").to_string());
-            true
-        } else {
-            self.message = Some(t!("nothing_to_undo").to_string());
-            false
-        }
+        self.undo_manager.commit(&self.query);
+        let state = self.undo_manager.undo();
+        self.restore_snapshot(state, "undo", "nothing_to_undo")
     }
 
     /// Redo last undone change
     pub fn redo(&mut self) -> bool {
-        if let Some(state) = self.undo_manager.redo(&self.query, self.cursor_pos) {
-            self.query = state.text;
-            self.cursor_pos = state.cursor_pos.min(self.query.chars().count());
-            self.message = Some(t!("redo").to_string());
-            true
-        } else {
-            self.message = Some(t!("nothing_to_redo").to_string());
-            false
+        self.undo_manager.commit(&self.query);
+        let state = self.undo_manager.redo();
+        self.restore_snapshot(state, "redo", "nothing_to_redo")
+    }
+
+    /// `g-` / `g+`: go to the previous / next text state in time, across undo branches
+    pub fn undo_travel(&mut self, forward: bool) -> bool {
+        self.undo_manager.commit(&self.query);
+        let state = self.undo_manager.travel(forward);
+        let (done, nothing) = if forward { ("redo", "nothing_to_redo") } else { ("undo", "nothing_to_undo") };
+        self.restore_snapshot(state, done, nothing)
+    }
+
+    fn restore_snapshot(&mut self, state: Option<EditorSnapshot>, done: &str, nothing: &str) -> bool {
+        match state {
+            Some(state) => {
+                self.query = state.text;
+                self.cursor_pos = state.cursor_pos.min(self.query.chars().count().saturating_sub(1));
+                self.message = Some(t!(done).to_string());
+                true
+            }
+            None => {
+                self.message = Some(t!(nothing).to_string());
+                false
+            }
         }
     }
 
@@ -566,12 +835,12 @@ impl App {
         let chars: Vec<char> = self.query.chars().collect();
         let start = self.cursor_pos + 1;
         
-        for i in start..chars.len() {
+        for (i, &c) in chars.iter().enumerate().skip(start) {
             // Stop at newline - f/F only work within current line
-            if chars[i] == '\n' {
+            if c == '\n' {
                 return false;
             }
-            if chars[i] == ch {
+            if c == ch {
                 self.cursor_pos = if till { i.saturating_sub(1) } else { i };
                 self.last_char_search = Some((ch, true, till));
                 return true;
@@ -1,120 +1,298 @@
 //! Undo/Redo system for the query editor
 //!
-//! Implements a vim-like undo system with snapshots of text state.
+//! Implements a vim-like undo tree. Each change is stored as a delta (the
+//! text removed and inserted at one place) from the state before it, so
+//! history stays small on large scripts. Undoing and then making a new
+//! change starts a branch; `u` / `Ctrl+R` walk the current branch and
+//! `g-` / `g+` visit every state in the order it was made.
+//!
+//! Callers don't save states before editing: `commit` compares the text with
+//! the last recorded state and stores the difference as one step.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One change: `removed` was replaced by `inserted` at byte `pos`
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Edit {
+    pub pos: usize,
+    pub removed: String,
+    pub inserted: String,
+}
+
+impl Edit {
+    /// The smallest single replacement turning `old` into `new`
+    pub fn between(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let (a, b) = (old.as_bytes(), new.as_bytes());
+        let mut prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let max_suffix = a.len().min(b.len()) - prefix;
+        let mut suffix = a.iter().rev().zip(b.iter().rev()).take(max_suffix).take_while(|(x, y)| x == y).count();
+        while !old.is_char_boundary(a.len() - suffix) || !new.is_char_boundary(b.len() - suffix) {
+            suffix -= 1;
+        }
+        Some(Self {
+            pos: prefix,
+            removed: old[prefix..a.len() - suffix].to_string(),
+            inserted: new[prefix..b.len() - suffix].to_string(),
+        })
+    }
 
-/// A snapshot of the editor state for undo/redo
-#[derive(Clone, Debug)]
+    fn apply(&self, text: &mut String) {
+        text.replace_range(self.pos..self.pos + self.removed.len(), &self.inserted);
+    }
+
+    fn revert(&self, text: &mut String) {
+        text.replace_range(self.pos..self.pos + self.inserted.len(), &self.removed);
+    }
+}
+
+/// A state in the undo tree, reached from its parent by `edit`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Node {
+    parent: Option<usize>,
+    edit: Edit,
+    /// Child that `Ctrl+R` goes to (the last one visited)
+    redo_child: Option<usize>,
+}
+
+/// Text and cursor (char index) to show after moving through history
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct EditorSnapshot {
-    /// The text content
     pub text: String,
-    /// Cursor position
     pub cursor_pos: usize,
 }
 
-/// Undo manager with undo/redo stacks
+/// Undo tree for one buffer
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct UndoManager {
-    /// Stack of previous states (for undo)
-    undo_stack: Vec<EditorSnapshot>,
-    /// Stack of undone states (for redo)
-    redo_stack: Vec<EditorSnapshot>,
+    /// States by sequence number (the order they were made); the oldest is the root
+    nodes: BTreeMap<usize, Node>,
+    current: usize,
+    next_seq: usize,
+    /// Text at `current`
+    #[serde(skip)]
+    text: String,
     /// Maximum number of undo levels
+    #[serde(skip)]
     max_history: usize,
 }
 
+/// What is written next to a saved file's history
+#[derive(Serialize, Deserialize)]
+struct UndoFile {
+    /// Hash of the file contents the history ends at
+    text_hash: u64,
+    history: UndoManager,
+}
+
 impl UndoManager {
-    /// Create a new undo manager
+    /// Create a new undo manager for an empty buffer
     pub fn new(max_history: usize) -> Self {
-        Self {
-            undo_stack: Vec::new(),
-            redo_stack: Vec::new(),
+        let mut manager = Self {
+            nodes: BTreeMap::new(),
+            current: 0,
+            next_seq: 0,
+            text: String::new(),
             max_history,
-        }
+        };
+        manager.reset("");
+        manager
     }
 
-    /// Save current state before making changes
-    /// 
-    /// Call this BEFORE modifying the text (not after)
-    pub fn save_state(&mut self, text: &str, cursor_pos: usize) {
-        // Don't save if identical to last state
-        if let Some(last) = self.undo_stack.last() {
-            if last.text == text && last.cursor_pos == cursor_pos {
-                return;
-            }
+    /// Forget all history; `text` becomes the original state
+    pub fn reset(&mut self, text: &str) {
+        self.nodes.clear();
+        self.nodes.insert(0, Node { parent: None, edit: Edit::default(), redo_child: None });
+        self.current = 0;
+        self.next_seq = 1;
+        self.text = text.to_string();
+    }
+
+    /// Record the change from the last state to `text` as one undo step.
+    /// Returns whether anything changed.
+    pub fn commit(&mut self, text: &str) -> bool {
+        let Some(edit) = Edit::between(&self.text, text) else {
+            return false;
+        };
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.nodes.insert(seq, Node { parent: Some(self.current), edit, redo_child: None });
+        if let Some(node) = self.nodes.get_mut(&self.current) {
+            node.redo_child = Some(seq);
         }
+        self.current = seq;
+        self.text = text.to_string();
+        self.prune();
+        true
+    }
 
-        self.undo_stack.push(EditorSnapshot {
-            text: text.to_string(),
-            cursor_pos,
-        });
+    /// Undo: go back to the parent state
+    pub fn undo(&mut self) -> Option<EditorSnapshot> {
+        let parent = self.nodes.get(&self.current)?.parent?;
+        let pos = self.step_up();
+        let child = self.current;
+        self.nodes.get_mut(&parent)?.redo_child = Some(child);
+        self.current = parent;
+        Some(self.snapshot(pos))
+    }
 
-        // Clear redo stack when new changes are made
-        self.redo_stack.clear();
+    /// Redo: follow the last visited child
+    pub fn redo(&mut self) -> Option<EditorSnapshot> {
+        let child = self.nodes.get(&self.current)?.redo_child?;
+        let pos = self.step_down(child);
+        Some(self.snapshot(pos))
+    }
 
-        // Limit history size
-        if self.undo_stack.len() > self.max_history {
-            self.undo_stack.remove(0);
+    /// `g-` / `g+`: go to the state made just before / after the current
+    /// one, across branches
+    pub fn travel(&mut self, forward: bool) -> Option<EditorSnapshot> {
+        let target = if forward {
+            *self.nodes.range(self.current + 1..).next()?.0
+        } else {
+            *self.nodes.range(..self.current).next_back()?.0
+        };
+        let target_path = self.path_from_root(target);
+        let mut pos = 0;
+        while !target_path.contains(&self.current) {
+            pos = self.step_up();
+            self.current = self.nodes[&self.current].parent?;
+        }
+        let below = target_path.iter().position(|&seq| seq == self.current)? + 1;
+        for &seq in &target_path[below..] {
+            if let Some(node) = self.nodes.get_mut(&self.current) {
+                node.redo_child = Some(seq);
+            }
+            pos = self.step_down(seq);
         }
+        Some(self.snapshot(pos))
     }
 
-    /// Undo: restore previous state
-    /// 
-    /// Returns the state to restore, or None if nothing to undo
-    pub fn undo(&mut self, current_text: &str, current_cursor: usize) -> Option<EditorSnapshot> {
-        if let Some(state) = self.undo_stack.pop() {
-            // Save current state to redo stack
-            self.redo_stack.push(EditorSnapshot {
-                text: current_text.to_string(),
-                cursor_pos: current_cursor,
-            });
-            Some(state)
-        } else {
-            None
+    /// Revert the current node's edit; returns where it happened
+    fn step_up(&mut self) -> usize {
+        let edit = &self.nodes[&self.current].edit;
+        edit.revert(&mut self.text);
+        edit.pos
+    }
+
+    /// Apply a child's edit and make it current; returns where it happened
+    fn step_down(&mut self, child: usize) -> usize {
+        let edit = &self.nodes[&child].edit;
+        edit.apply(&mut self.text);
+        self.current = child;
+        edit.pos
+    }
+
+    /// The current state, or its ancestor, that is a child of `parent`
+    fn current_child_of(&self, parent: usize) -> Option<usize> {
+        let mut seq = self.current;
+        loop {
+            let up = self.nodes.get(&seq)?.parent?;
+            if up == parent {
+                return Some(seq);
+            }
+            seq = up;
         }
     }
 
-    /// Redo: restore previously undone state
-    /// 
-    /// Returns the state to restore, or None if nothing to redo
-    pub fn redo(&mut self, current_text: &str, current_cursor: usize) -> Option<EditorSnapshot> {
-        if let Some(state) = self.redo_stack.pop() {
-            // Save current state to undo stack
-            self.undo_stack.push(EditorSnapshot {
-                text: current_text.to_string(),
-                cursor_pos: current_cursor,
-            });
-            Some(state)
-        } else {
-            None
+    /// Sequence numbers from the root down to `seq`
+    fn path_from_root(&self, seq: usize) -> Vec<usize> {
+        let mut path = vec![seq];
+        while let Some(parent) = self.nodes.get(path.last().unwrap_or(&seq)).and_then(|n| n.parent) {
+            path.push(parent);
         }
+        path.reverse();
+        path
     }
 
-    /// Check if undo is available
-    pub fn can_undo(&self) -> bool {
-        !self.undo_stack.is_empty()
+    fn snapshot(&self, byte_pos: usize) -> EditorSnapshot {
+        let pos = byte_pos.min(self.text.len());
+        EditorSnapshot {
+            text: self.text.clone(),
+            cursor_pos: self.text[..pos].chars().count(),
+        }
     }
 
-    /// Check if redo is available
-    pub fn can_redo(&self) -> bool {
-        !self.redo_stack.is_empty()
+    /// Drop the oldest states beyond `max_history`. The root's child on the
+    /// way to the current state becomes the new root; other branches off
+    /// the dropped root go with it.
+    fn prune(&mut self) {
+        while self.nodes.len() > self.max_history.max(1) + 1 {
+            let root = *self.nodes.keys().next().unwrap_or(&0);
+            let Some(keep) = self.current_child_of(root) else {
+                return;
+            };
+            let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+            for (&seq, node) in &self.nodes {
+                if let Some(parent) = node.parent {
+                    children.entry(parent).or_default().push(seq);
+                }
+            }
+            let mut drop = vec![root];
+            while let Some(seq) = drop.pop() {
+                self.nodes.remove(&seq);
+                drop.extend(children.get(&seq).into_iter().flatten().filter(|&&c| c != keep));
+            }
+            if let Some(node) = self.nodes.get_mut(&keep) {
+                node.parent = None;
+                node.edit = Edit::default();
+            }
+        }
     }
 
-    /// Get number of undo levels available
+    /// Number of changes that can be undone on the current branch
     pub fn undo_count(&self) -> usize {
-        self.undo_stack.len()
+        self.path_from_root(self.current).len() - 1
     }
 
-    /// Get number of redo levels available
-    pub fn redo_count(&self) -> usize {
-        self.redo_stack.len()
+    /// Save the history of a file whose contents are `text`
+    pub fn save_for(&self, path: &Path, text: &str) -> Result<()> {
+        let file = undo_file(path);
+        if let Some(parent) = file.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let saved = UndoFile { text_hash: fnv_hash(text), history: self.clone() };
+        fs::write(file, serde_json::to_string(&saved)?)?;
+        Ok(())
     }
 
-    /// Clear all history
-    pub fn clear(&mut self) {
-        self.undo_stack.clear();
-        self.redo_stack.clear();
+    /// History saved for a file, if it ends at the contents `text`
+    pub fn load_for(path: &Path, text: &str, max_history: usize) -> Option<Self> {
+        let content = fs::read_to_string(undo_file(path)).ok()?;
+        let saved: UndoFile = serde_json::from_str(&content).ok()?;
+        if saved.text_hash != fnv_hash(text) || !saved.history.nodes.contains_key(&saved.history.current) {
+            return None;
+        }
+        let mut history = saved.history;
+        history.text = text.to_string();
+        history.max_history = max_history;
+        Some(history)
     }
 }
 
+/// Where a file's undo history is kept
+fn undo_file(path: &Path) -> PathBuf {
+    let full = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("alrajhi-sql-tui")
+        .join("undo")
+        .join(format!("{:016x}.json", fnv_hash(&full.to_string_lossy())))
+}
+
+/// FNV-1a, stable across runs and Rust versions
+fn fnv_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -122,22 +300,71 @@ mod tests {
     #[test]
     fn test_undo_redo() {
         let mut undo = UndoManager::new(100);
-        
-        // Initial state
-        undo.save_state("hello", 5);
-        
+        undo.reset("hello");
+
         // Make changes
-        undo.save_state("hello world", 11);
-        
+        undo.commit("hello world");
+        undo.commit("hello world!!!");
+
         // Undo
-        let state = undo.undo("hello world!!!", 14).unwrap();
+        let state = undo.undo().unwrap();
         assert_eq!(state.text, "hello world");
-        
-        let state = undo.undo("hello world", 11).unwrap();
+        assert_eq!(state.cursor_pos, 11);
+
+        let state = undo.undo().unwrap();
         assert_eq!(state.text, "hello");
-        
+        assert!(undo.undo().is_none());
+
         // Redo
-        let state = undo.redo("hello", 5).unwrap();
+        let state = undo.redo().unwrap();
         assert_eq!(state.text, "hello world");
     }
+
+    #[test]
+    fn test_edit_between() {
+        let edit = Edit::between("select a from t", "select a, b from t").unwrap();
+        assert_eq!(edit, Edit { pos: 8, removed: String::new(), inserted: ", b".into() });
+        // Multi-byte chars are never split
+        let edit = Edit::between("é", "è").unwrap();
+        assert_eq!((edit.removed.as_str(), edit.inserted.as_str()), ("é", "è"));
+        assert_eq!(Edit::between("aa", "aaa").unwrap().pos, 2);
+        assert!(Edit::between("x", "x").is_none());
+    }
+
+    #[test]
+    fn test_undo_tree_travel() {
+        let mut undo = UndoManager::new(100);
+        undo.commit("one");
+        undo.commit("one two");
+        undo.undo();
+        // A new change after undo starts a branch
+        undo.commit("one three");
+        assert_eq!(undo.undo().unwrap().text, "one");
+        assert_eq!(undo.redo().unwrap().text, "one three");
+
+        // g- walks back in time through the other branch
+        assert_eq!(undo.travel(false).unwrap().text, "one two");
+        assert_eq!(undo.travel(false).unwrap().text, "one");
+        assert_eq!(undo.travel(false).unwrap().text, "");
+        assert!(undo.travel(false).is_none());
+        assert_eq!(undo.travel(true).unwrap().text, "one");
+        assert_eq!(undo.travel(true).unwrap().text, "one two");
+        assert_eq!(undo.travel(true).unwrap().text, "one three");
+        // Ctrl+R now follows the branch that was visited last
+        undo.undo();
+        assert_eq!(undo.redo().unwrap().text, "one three");
+    }
+
+    #[test]
+    fn test_prune_keeps_recent_history() {
+        let mut undo = UndoManager::new(3);
+        for text in ["a", "ab", "abc", "abcd", "abcde"] {
+            undo.commit(text);
+        }
+        assert_eq!(undo.undo_count(), 3);
+        for expected in ["abcd", "abc", "ab"] {
+            assert_eq!(undo.undo().unwrap().text, expected);
+        }
+        assert!(undo.undo().is_none());
+    }
 }
@@ -0,0 +1,372 @@
+//! Command-line interface
+//!
+//! Parses the process arguments and implements the headless `exec` mode,
+//! which runs a query against a saved connection and prints the results
+//! without starting the TUI.
+
+use crate::app::{App, ExportFormat, write_results};
+use crate::config::{AppConfig, ConnectionConfig};
+use crate::db::DatabaseBackend;
+use crate::secrets;
+use crate::sql::statements::{self, WriteKind};
+use anyhow::{anyhow, bail, Context, Result};
+use rust_i18n::t;
+use std::io::{IsTerminal, Read, Write};
+use std::path::PathBuf;
+
+/// Exit code for runtime failures (connection or SQL errors)
+pub const EXIT_FAILURE: i32 = 1;
+/// Exit code for invalid command-line usage
+pub const EXIT_USAGE: i32 = 2;
+
+/// Usage text printed by `--help`
+pub const USAGE: &str = "\
+Usage:
+  sql-tui [OPTIONS] [URL] [FILE.sql]   Start the terminal UI
+  sql-tui exec [OPTIONS] [URL]         Run a query without the UI and print the results
+
+Connection options:
+  -c, --connection <NAME>      Saved connection to use (default: last used)
+  -d, --database <NAME>        Database to use instead of the connection's default
+                               (SQL Server only)
+  URL                          Ad-hoc connection, e.g. mssql://user@host:1433/db
+                               or sqlite:///path/to/file.db
+
+Exec options:
+  -f, --file <PATH>            Read the query from a file ('-' for stdin)
+      --format <FORMAT>        Output format: table, csv or json (default: table)
+      --confirm <NAME>         Run destructive statements on a prod (or confirming)
+                               connection; must repeat the connection name
+
+  -h, --help                   Show this help
+
+In exec mode, the query is read from stdin when no file is given. Read-only
+connections refuse statements that write.";
+
+/// What the process was asked to do
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Start the interactive terminal UI
+    Tui(TuiArgs),
+    /// Run a query headless and print the results
+    Exec(ExecArgs),
+    /// Print usage and exit
+    Help,
+}
+
+/// How to pick the connection at startup
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ConnectionArgs {
+    /// Saved connection name (overrides `last_connection`)
+    pub connection: Option<String>,
+    /// Database override (SQL Server only)
+    pub database: Option<String>,
+    /// Ad-hoc connection URL (takes precedence over `connection`)
+    pub url: Option<String>,
+}
+
+impl ConnectionArgs {
+    /// Whether any connection option was given explicitly
+    pub fn is_empty(&self) -> bool {
+        self.connection.is_none() && self.database.is_none() && self.url.is_none()
+    }
+
+    /// Resolve to a connection config: URL first, then the named connection,
+    /// then the last used one. Returns `Ok(None)` when nothing applies.
+    pub fn resolve(&self, config: &AppConfig) -> Result<Option<ConnectionConfig>> {
+        let conn = if let Some(ref url) = self.url {
+            Some(ConnectionConfig::from_url(url)?)
+        } else if let Some(ref name) = self.connection {
+            let conn = config.get_connection(name)
+                .ok_or_else(|| anyhow!(t!("cli_connection_not_found", name = name).to_string()))?;
+            Some(conn.clone())
+        } else {
+            config.last_connection
+                .as_deref()
+                .and_then(|name| config.get_connection(name))
+                .cloned()
+        };
+
+        let Some(mut conn) = conn else {
+            return Ok(None);
+        };
+        if let Some(ref database) = self.database {
+            if conn.backend == DatabaseBackend::Sqlite {
+                bail!(t!("cli_database_sqlite", name = conn.name).to_string());
+            }
+            conn.database = database.clone();
+        }
+        Ok(Some(conn))
+    }
+}
+
+/// Arguments for the interactive UI
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TuiArgs {
+    pub connection: ConnectionArgs,
+    /// SQL file to load into the editor
+    pub file: Option<PathBuf>,
+}
+
+/// Arguments for `sql-tui exec`
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExecArgs {
+    pub connection: ConnectionArgs,
+    /// Query file (None or "-" = stdin)
+    pub file: Option<PathBuf>,
+    /// Output format
+    pub format: ExportFormat,
+    /// Connection name repeated to allow destructive statements
+    pub confirm: Option<String>,
+}
+
+impl Default for ExecArgs {
+    fn default() -> Self {
+        Self {
+            connection: ConnectionArgs::default(),
+            file: None,
+            format: ExportFormat::Table,
+            confirm: None,
+        }
+    }
+}
+
+/// Parse command-line arguments (without the program name)
+pub fn parse_args<I, S>(args: I) -> Result<Command>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut args = args.into_iter().map(Into::into).peekable();
+
+    if args.peek().map(String::as_str) == Some("exec") {
+        args.next();
+        let mut exec = ExecArgs::default();
+        let help = parse_options(args, &mut exec.connection, &mut exec.file, Some(&mut exec.format), Some(&mut exec.confirm))?;
+        Ok(if help { Command::Help } else { Command::Exec(exec) })
+    } else {
+        let mut tui = TuiArgs::default();
+        let help = parse_options(args, &mut tui.connection, &mut tui.file, None, None)?;
+        Ok(if help { Command::Help } else { Command::Tui(tui) })
+    }
+}
+
+/// Parse options shared by both modes. `format` and `confirm` are only
+/// accepted in exec mode. Returns true when help was requested.
+fn parse_options(
+    mut args: impl Iterator<Item = String>,
+    conn: &mut ConnectionArgs,
+    file: &mut Option<PathBuf>,
+    mut format: Option<&mut ExportFormat>,
+    mut confirm: Option<&mut Option<String>>,
+) -> Result<bool> {
+    while let Some(arg) = args.next() {
+        // Support both "--opt value" and "--opt=value"
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((f, v)) if f.starts_with("--") => (f.to_string(), Some(v.to_string())),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| anyhow!("missing value for {}", name))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(true),
+            "-c" | "--connection" => conn.connection = Some(value("--connection")?),
+            "-d" | "--database" => conn.database = Some(value("--database")?),
+            "-f" | "--file" if format.is_some() => *file = Some(PathBuf::from(value("--file")?)),
+            "--format" if format.is_some() => {
+                let name = value("--format")?;
+                let parsed = ExportFormat::parse(&name)
+                    .ok_or_else(|| anyhow!("unknown format '{}' (expected table, csv or json)", name))?;
+                if let Some(f) = format.as_deref_mut() {
+                    *f = parsed;
+                }
+            }
+            "--confirm" if confirm.is_some() => {
+                let name = value("--confirm")?;
+                if let Some(c) = confirm.as_deref_mut() {
+                    *c = Some(name);
+                }
+            }
+            positional if !positional.starts_with('-') || positional == "-" => {
+                if positional.contains("://") {
+                    if conn.url.is_some() {
+                        bail!("only one connection URL can be given");
+                    }
+                    conn.url = Some(arg);
+                } else if file.is_none() {
+                    *file = Some(PathBuf::from(arg));
+                } else {
+                    bail!("unexpected argument '{}'", arg);
+                }
+            }
+            _ => bail!("unknown argument '{}'", arg),
+        }
+    }
+
+    Ok(false)
+}
+
+/// Run `sql-tui exec` and return the process exit code
+pub async fn run_exec(args: ExecArgs) -> i32 {
+    match exec(args).await {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            EXIT_FAILURE
+        }
+    }
+}
+
+async fn exec(args: ExecArgs) -> Result<()> {
+    let config = AppConfig::load();
+    crate::init_locale(config.locale.as_deref());
+    if let Some(error) = config.project.as_ref().and_then(|project| project.error.as_ref()) {
+        eprintln!("{}", t!("project_config_error", error = error));
+    }
+
+    let conn = args.connection
+        .resolve(&config)?
+        .ok_or_else(|| anyhow!(t!("cli_no_connection").to_string()))?;
+
+    let sql = read_query(args.file.as_ref())?;
+    if sql.trim().is_empty() {
+        bail!(t!("cli_no_query").to_string());
+    }
+
+    let writes = statements::write_statements(&sql, conn.backend);
+    if conn.read_only && !writes.is_empty() {
+        let key = if writes.iter().any(|w| w.kind == WriteKind::Exec) { "read_only_exec_blocked" } else { "read_only_blocked" };
+        bail!(t!(key, name = conn.name, verbs = statements::verbs(&writes).join(", ")).to_string());
+    }
+    let destructive = statements::verbs(writes.iter().filter(|w| w.is_destructive()));
+    if conn.confirms_destructive()
+        && !destructive.is_empty()
+        && (conn.name.is_empty() || args.confirm.as_deref() != Some(conn.name.as_str()))
+    {
+        bail!(t!("cli_confirm_required", verbs = destructive.join(", "), name = conn.name).to_string());
+    }
+
+    if conn.uses_vault() {
+        secrets::ensure_unlocked()?;
+    }
+    let driver = App::create_driver(&conn)
+        .await
+        .map_err(|e| anyhow!(t!("connection_error", error = format!("{:#}", e)).to_string()))?;
+    let result = driver.execute_query(&sql).await?;
+
+    let stdout = std::io::stdout();
+    let mut out = std::io::BufWriter::new(stdout.lock());
+    write_results(&result, args.format, &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Read the query from a file, or from stdin when no file (or "-") is given
+fn read_query(file: Option<&PathBuf>) -> Result<String> {
+    match file {
+        Some(path) if path.as_os_str() != "-" => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display())),
+        _ => {
+            let mut stdin = std::io::stdin();
+            if stdin.is_terminal() {
+                bail!(t!("cli_no_query").to_string());
+            }
+            let mut sql = String::new();
+            stdin.read_to_string(&mut sql).context("Failed to read query from stdin")?;
+            Ok(sql)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_args_starts_tui() {
+        assert_eq!(parse_args(Vec::<String>::new()).unwrap(), Command::Tui(TuiArgs::default()));
+    }
+
+    #[test]
+    fn test_tui_args() {
+        let cmd = parse_args(["--connection", "prod", "-d", "Sales", "report.sql"]).unwrap();
+        assert_eq!(cmd, Command::Tui(TuiArgs {
+            connection: ConnectionArgs {
+                connection: Some("prod".into()),
+                database: Some("Sales".into()),
+                url: None,
+            },
+            file: Some(PathBuf::from("report.sql")),
+        }));
+
+        let cmd = parse_args(["sqlite:///tmp/a.db"]).unwrap();
+        let Command::Tui(tui) = cmd else { panic!("expected tui") };
+        assert_eq!(tui.connection.url.as_deref(), Some("sqlite:///tmp/a.db"));
+        assert_eq!(tui.file, None);
+    }
+
+    #[test]
+    fn test_exec_args() {
+        let cmd = parse_args(["exec", "--connection", "prod", "-f", "report.sql", "--format", "csv"]).unwrap();
+        assert_eq!(cmd, Command::Exec(ExecArgs {
+            connection: ConnectionArgs { connection: Some("prod".into()), ..ConnectionArgs::default() },
+            file: Some(PathBuf::from("report.sql")),
+            format: ExportFormat::Csv,
+            confirm: None,
+        }));
+    }
+
+    #[test]
+    fn test_exec_inline_values_and_defaults() {
+        let cmd = parse_args(["exec", "--format=json"]).unwrap();
+        assert_eq!(cmd, Command::Exec(ExecArgs { format: ExportFormat::Json, ..ExecArgs::default() }));
+
+        let cmd = parse_args(["exec", "--confirm=prod"]).unwrap();
+        assert_eq!(cmd, Command::Exec(ExecArgs { confirm: Some("prod".into()), ..ExecArgs::default() }));
+    }
+
+    #[test]
+    fn test_arg_errors() {
+        assert!(parse_args(["exec", "--format", "xml"]).is_err());
+        assert!(parse_args(["exec", "--connection"]).is_err());
+        assert!(parse_args(["exec", "--bogus"]).is_err());
+        assert!(parse_args(["--format", "csv"]).is_err());
+        assert!(parse_args(["--confirm", "prod"]).is_err());
+        assert!(parse_args(["a.sql", "b.sql"]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_connection() {
+        let mut config = AppConfig::default();
+        config.add_connection(ConnectionConfig {
+            name: "prod".into(),
+            host: "db".into(),
+            user: "sa".into(),
+            ..ConnectionConfig::default()
+        });
+        config.set_last_connection("prod");
+
+        let args = ConnectionArgs { database: Some("Sales".into()), ..ConnectionArgs::default() };
+        let conn = args.resolve(&config).unwrap().unwrap();
+        assert_eq!(conn.name, "prod");
+        assert_eq!(conn.database, "Sales");
+
+        let args = ConnectionArgs { connection: Some("missing".into()), ..ConnectionArgs::default() };
+        assert!(args.resolve(&config).is_err());
+
+        assert!(ConnectionArgs::default().resolve(&AppConfig::default()).unwrap().is_none());
+
+        // SQLite has no databases to switch to
+        let args = ConnectionArgs {
+            url: Some("sqlite:///tmp/app.db".into()),
+            database: Some("Sales".into()),
+            ..ConnectionArgs::default()
+        };
+        assert!(args.resolve(&config).is_err());
+    }
+}
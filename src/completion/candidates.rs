@@ -3,38 +3,43 @@
 //! Generates completion candidates based on SQL context and database schema.
 
 use super::{CompletionItem, CompletionKind, SqlContext, ObjectHint, TableRef};
+use super::fuzzy::fuzzy_match;
 use crate::app::{SchemaNode, SchemaNodeType};
 use crate::db::ColumnDef;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+/// Score added per history query that uses a table or column
+const USAGE_BONUS: i32 = 6;
+
+/// Uses beyond this don't add more, so a popular name can't outrank a much
+/// better match
+const MAX_USAGE_BOOSTS: usize = 10;
 
 /// Generate completion candidates based on context (sync version for non-column contexts)
 pub fn get_candidates(
     context: &SqlContext,
     schema_tree: &[SchemaNode],
-    prefix: &str,
 ) -> Vec<CompletionItem> {
     // For contexts that don't need columns, use the sync version
-    get_candidates_internal(context, schema_tree, prefix, &HashMap::new())
+    get_candidates_internal(context, schema_tree, &HashMap::new())
 }
 
 /// Generate completion candidates with column cache access
 pub fn get_candidates_with_columns(
     context: &SqlContext,
     schema_tree: &[SchemaNode],
-    prefix: &str,
     column_cache: &HashMap<(String, String), Vec<ColumnDef>>,
 ) -> Vec<CompletionItem> {
-    get_candidates_internal(context, schema_tree, prefix, column_cache)
+    get_candidates_internal(context, schema_tree, column_cache)
 }
 
 /// Internal implementation that handles all contexts
 fn get_candidates_internal(
     context: &SqlContext,
     schema_tree: &[SchemaNode],
-    prefix: &str,
     column_cache: &HashMap<(String, String), Vec<ColumnDef>>,
 ) -> Vec<CompletionItem> {
-    let mut items = match context {
+    match context {
         SqlContext::AfterSchemaDot { schema, object_hint } => {
             find_objects_in_schema(schema_tree, schema, *object_hint)
         }
@@ -98,41 +103,78 @@ fn get_candidates_internal(
                 CompletionItem::new(v.clone(), CompletionKind::Variable)
             }).collect()
         }
+        SqlContext::Literal => Vec::new(),
         SqlContext::General { prefix: _ } => {
             let mut items = sql_keywords();
             items.extend(find_all_objects(schema_tree));
             items
         }
-    };
-    
-    // Filter by prefix if provided
-    if !prefix.is_empty() {
-        let prefix_lower = prefix.to_lowercase();
-        items.retain(|item| {
-            item.label.to_lowercase().starts_with(&prefix_lower)
-        });
     }
-    
-    // Sort: ColumnList first (INSERT all columns), then columns, then keywords, then by label
-    items.sort_by(|a, b| {
-        match (&a.kind, &b.kind) {
-            // ColumnList has highest priority (for INSERT all columns suggestion)
-            (CompletionKind::ColumnList, CompletionKind::ColumnList) => a.label.cmp(&b.label),
-            (CompletionKind::ColumnList, _) => std::cmp::Ordering::Less,
-            (_, CompletionKind::ColumnList) => std::cmp::Ordering::Greater,
-            // Then columns
-            (CompletionKind::Column, CompletionKind::Column) => a.label.cmp(&b.label),
-            (CompletionKind::Column, _) => std::cmp::Ordering::Less,
-            (_, CompletionKind::Column) => std::cmp::Ordering::Greater,
-            // Then keywords
-            (CompletionKind::Keyword, CompletionKind::Keyword) => a.label.cmp(&b.label),
-            (CompletionKind::Keyword, _) => std::cmp::Ordering::Less,
-            (_, CompletionKind::Keyword) => std::cmp::Ordering::Greater,
-            _ => a.label.cmp(&b.label),
-        }
+}
+
+/// Order of kinds among items that score the same: the INSERT column list,
+/// then columns, then keywords, then the rest
+fn kind_rank(kind: CompletionKind) -> u8 {
+    match kind {
+        CompletionKind::ColumnList => 0,
+        CompletionKind::Column => 1,
+        CompletionKind::Keyword => 2,
+        _ => 3,
+    }
+}
+
+/// Keep the items the prefix fuzzy-matches and sort them, best first. Tables,
+/// views and columns get a boost for every history query that uses them
+/// (`usage`, by lowercased name); the INSERT column list always comes first.
+pub fn rank_candidates(
+    items: Vec<CompletionItem>,
+    prefix: &str,
+    usage: &HashMap<String, usize>,
+) -> Vec<CompletionItem> {
+    let mut scored: Vec<(i32, CompletionItem)> = items
+        .into_iter()
+        .filter_map(|item| {
+            let score = fuzzy_match(prefix, &item.label)?.score;
+            let uses = match item.kind {
+                CompletionKind::Table | CompletionKind::View | CompletionKind::Column => {
+                    usage.get(&item.label.to_lowercase()).copied().unwrap_or(0)
+                }
+                _ => 0,
+            };
+            Some((score + (uses.min(MAX_USAGE_BOOSTS) as i32) * USAGE_BONUS, item))
+        })
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| {
+        (a.kind != CompletionKind::ColumnList)
+            .cmp(&(b.kind != CompletionKind::ColumnList))
+            .then(b_score.cmp(a_score))
+            .then(kind_rank(a.kind).cmp(&kind_rank(b.kind)))
+            .then_with(|| a.label.cmp(&b.label))
     });
-    
-    items
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Snippets, once something is typed (none after `schema.` or `alias.`,
+/// where only object names fit, or inside strings and comments)
+pub fn snippet_candidates(
+    snippets: &BTreeMap<String, String>,
+    context: &SqlContext,
+    prefix: &str,
+) -> Vec<CompletionItem> {
+    if prefix.is_empty()
+        || matches!(context, SqlContext::AfterSchemaDot { .. } | SqlContext::AfterTableAliasDot { .. } | SqlContext::Literal)
+    {
+        return Vec::new();
+    }
+    snippets
+        .iter()
+        .map(|(name, body)| CompletionItem {
+            label: name.clone(),
+            kind: CompletionKind::Snippet,
+            insert_text: body.clone(),
+            detail: body.lines().next().map(|line| line.trim().to_string()),
+        })
+        .collect()
 }
 
 /// Find columns for a specific table reference using the cache
@@ -429,3 +471,34 @@ fn sql_clause_keywords() -> Vec<CompletionItem> {
         .map(|kw| CompletionItem::new(*kw, CompletionKind::Keyword))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rank_candidates() {
+        let items = || {
+            vec![
+                CompletionItem::new("Cidade", CompletionKind::Table),
+                CompletionItem::new("ClienteEndereco", CompletionKind::Table),
+                CompletionItem::new("Clientes", CompletionKind::Table),
+                CompletionItem::new("CASE", CompletionKind::Keyword),
+                CompletionItem::new("Id, Nome", CompletionKind::ColumnList),
+            ]
+        };
+        let usage = HashMap::new();
+        assert_eq!(labels(&rank_candidates(items(), "cliend", &usage)), vec!["ClienteEndereco"]);
+        // Word starts count; the column list stays on top, then keywords
+        assert_eq!(labels(&rank_candidates(items(), "ce", &usage))[0], "ClienteEndereco");
+        assert_eq!(labels(&rank_candidates(items(), "", &usage))[..2], ["Id, Nome", "CASE"]);
+        // Names used in the history rise
+        let usage = HashMap::from([("cidade".to_string(), 3)]);
+        assert_eq!(labels(&rank_candidates(items(), "", &usage))[..2], ["Id, Nome", "Cidade"]);
+        assert_eq!(labels(&rank_candidates(items(), "c", &usage))[..2], ["Cidade", "CASE"]);
+    }
+}
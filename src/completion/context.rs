@@ -1,7 +1,20 @@
 //! SQL context extraction for autocomplete
 //!
-//! Analyzes the SQL query text up to the cursor position to determine
-//! what kind of completions should be offered.
+//! Tokenizes the statement around the cursor with the SQL lexer and looks at
+//! the tokens before the cursor to determine what kind of completions should
+//! be offered. Keywords inside strings and comments never count.
+
+use crate::db::DatabaseBackend;
+use crate::sql::lexer::{is_keyword, tokenize, tokenize_from, LexState, Token, TokenKind};
+use crate::sql::statements::statement_spans;
+
+/// Keywords that start a statement when they start a line
+const STATEMENT_STARTERS: &[&str] = &[
+    "SELECT", "INSERT", "UPDATE", "DELETE", "EXEC", "EXECUTE", "WITH", "CREATE", "ALTER", "DROP", "DECLARE", "BEGIN",
+];
+
+/// Words after FROM / JOIN that can't be a table name
+const NOT_TABLES: &[&str] = &["WHERE", "ORDER", "GROUP", "HAVING", "ON", "SET", "VALUES"];
 
 /// Table reference found in query (for column suggestions)
 #[derive(Clone, Debug, PartialEq)]
@@ -66,6 +79,10 @@ pub enum SqlContext {
         variables: Vec<String>,
     },
 
+    /// Inside a string or comment - nothing to suggest
+    /// Example: WHERE Nome = 'Jo|
+    Literal,
+
     /// General context - suggest keywords and all objects
     General {
         prefix: String,
@@ -103,73 +120,56 @@ enum CurrentClause {
     Unknown,
 }
 
-/// Detect which clause we're currently in based on the last significant keyword
-fn detect_current_clause(upper_text: &str) -> CurrentClause {
-    // List of clause keywords with their positions (keyword, position, clause type)
-    let clause_keywords: &[(&str, CurrentClause)] = &[
-        ("SELECT ", CurrentClause::Select),
-        ("FROM ", CurrentClause::From),
-        ("INNER JOIN ", CurrentClause::Join),
-        ("LEFT JOIN ", CurrentClause::Join),
-        ("RIGHT JOIN ", CurrentClause::Join),
-        ("FULL JOIN ", CurrentClause::Join),
-        ("CROSS JOIN ", CurrentClause::Join),
-        ("JOIN ", CurrentClause::Join),
-        ("WHERE ", CurrentClause::Where),
-        (" AND ", CurrentClause::And),
-        (" OR ", CurrentClause::Or),
-        (" ON ", CurrentClause::On),
-        ("ORDER BY ", CurrentClause::OrderBy),
-        ("GROUP BY ", CurrentClause::GroupBy),
-        ("HAVING ", CurrentClause::Having),
-        ("SET ", CurrentClause::Set),
-        ("EXEC ", CurrentClause::Exec),
-        ("EXECUTE ", CurrentClause::Exec),
-        ("INSERT INTO ", CurrentClause::InsertInto),
-        ("UPDATE ", CurrentClause::Update),
-    ];
-    
-    // Find the last occurrence of each keyword
-    let mut last_pos: Option<usize> = None;
-    let mut last_clause = CurrentClause::Unknown;
-    
-    for (keyword, clause) in clause_keywords {
-        if let Some(pos) = upper_text.rfind(keyword) {
-            if last_pos.is_none() || pos > last_pos.unwrap() {
-                last_pos = Some(pos);
-                last_clause = *clause;
-            }
-        }
+/// The clause a keyword token starts, if it starts one
+fn clause_at(tokens: &[Token], i: usize) -> Option<CurrentClause> {
+    let token = &tokens[i];
+    if token.kind != TokenKind::Word {
+        return None;
     }
-    
-    last_clause
+    let next_is = |word: &str| tokens.get(i + 1).is_some_and(|t| t.is(word));
+    Some(match token.text.to_uppercase().as_str() {
+        "SELECT" => CurrentClause::Select,
+        "FROM" => CurrentClause::From,
+        "JOIN" => CurrentClause::Join,
+        "WHERE" => CurrentClause::Where,
+        "AND" => CurrentClause::And,
+        "OR" => CurrentClause::Or,
+        "ON" => CurrentClause::On,
+        "ORDER" if next_is("BY") => CurrentClause::OrderBy,
+        "GROUP" if next_is("BY") => CurrentClause::GroupBy,
+        "HAVING" => CurrentClause::Having,
+        "SET" => CurrentClause::Set,
+        "EXEC" | "EXECUTE" => CurrentClause::Exec,
+        "INSERT" if next_is("INTO") => CurrentClause::InsertInto,
+        "UPDATE" => CurrentClause::Update,
+        _ => return None,
+    })
+}
+
+/// Detect which clause we're currently in based on the last clause keyword
+fn detect_current_clause(tokens: &[Token]) -> CurrentClause {
+    (0..tokens.len()).rev().find_map(|i| clause_at(tokens, i)).unwrap_or(CurrentClause::Unknown)
 }
 
 /// Check for dot contexts (schema.| or alias.|)
-fn check_dot_context(before_cursor: &str, before_upper: &str, tables: &[TableRef]) -> Option<SqlContext> {
-    // Check if we're right after a dot
-    if before_cursor.ends_with('.') {
-        if let Some(word_before) = extract_word_before_dot(before_cursor) {
-            return Some(resolve_dot_context(&word_before, before_upper, tables));
-        }
+fn check_dot_context(tokens: &[Token], cursor: usize, tables: &[TableRef]) -> Option<SqlContext> {
+    let last = tokens.len().checked_sub(1)?;
+    if tokens[last].end() != cursor {
+        return None;
     }
-    
-    // Check if there's a dot with partial text after it (alias.col| or schema.Tab|)
-    if let Some(dot_pos) = before_cursor.rfind('.') {
-        let after_dot = &before_cursor[dot_pos + 1..];
-        // If there's text after the dot (no spaces), we're still in dot context
-        if !after_dot.is_empty() && !after_dot.contains(char::is_whitespace) {
-            if let Some(word_before) = extract_word_before_dot(&before_cursor[..=dot_pos]) {
-                return Some(resolve_dot_context(&word_before, before_upper, tables));
-            }
-        }
+    // Right after the dot, or with part of a name typed after it (alias.col|)
+    let dot = if tokens[last].is_punct(".") { last } else { last.checked_sub(1)? };
+    let adjacent = |i: usize| tokens[i].end() == tokens[i + 1].start;
+    if !tokens[dot].is_punct(".") || (dot < last && (tokens[last].name().is_none() || !adjacent(dot))) {
+        return None;
     }
-    
-    None
+    let word = dot.checked_sub(1).filter(|&w| adjacent(w))?;
+    let word_before = tokens[word].name()?;
+    Some(resolve_dot_context(word_before, tokens, tables))
 }
 
 /// Resolve what kind of dot context this is (schema or alias/table)
-fn resolve_dot_context(word_before: &str, before_upper: &str, tables: &[TableRef]) -> SqlContext {
+fn resolve_dot_context(word_before: &str, tokens: &[Token], tables: &[TableRef]) -> SqlContext {
     // Check if this is a table alias
     if let Some(table_ref) = find_table_by_alias(tables, word_before) {
         return SqlContext::AfterTableAliasDot {
@@ -190,7 +190,7 @@ fn resolve_dot_context(word_before: &str, before_upper: &str, tables: &[TableRef
     }
     
     // Otherwise, treat as schema
-    let object_hint = if contains_exec_context(before_upper) {
+    let object_hint = if contains_exec_context(tokens) {
         ObjectHint::Procedure
     } else {
         ObjectHint::TableOrView
@@ -202,74 +202,78 @@ fn resolve_dot_context(word_before: &str, before_upper: &str, tables: &[TableRef
     }
 }
 
-/// Find the start of the current SQL statement containing the cursor.
-/// Splits on statement-starting keywords (SELECT, INSERT, UPDATE, DELETE, EXEC, WITH, CREATE, ALTER, DROP)
-/// that appear at the beginning of a line (after optional whitespace).
-fn find_current_statement_start(before: &str) -> usize {
-
-    // Walk backwards through lines to find the last statement-starting keyword
-    let statement_starters = [
-        "SELECT", "INSERT", "UPDATE", "DELETE", "EXEC", "EXECUTE",
-        "WITH", "CREATE", "ALTER", "DROP", "DECLARE", "BEGIN",
-    ];
-
-    let mut last_start = 0;
-    let mut pos = 0;
-
-    for line in before.split('\n') {
-        let trimmed = line.trim_start().to_uppercase();
-        for starter in &statement_starters {
-            if trimmed.starts_with(starter) {
-                // Check it's a word boundary (next char is space, newline, or end)
-                let after = &trimmed[starter.len()..];
-                if after.is_empty() || after.starts_with(char::is_whitespace) || after.starts_with('(') {
-                    last_start = pos;
-                }
-            }
+/// Find the char range of the SQL statement containing the cursor: its
+/// statement span, from the last line in it that starts with a
+/// statement-starting keyword (SELECT, INSERT, UPDATE, DELETE, EXEC, WITH,
+/// CREATE, ALTER, DROP), as scratch buffers often don't separate statements
+fn find_current_statement(query: &str, cursor: usize, dialect: DatabaseBackend) -> (usize, usize) {
+    let spans = statement_spans(query, dialect);
+    let Some(span) = spans.iter().find(|s| cursor < s.separator_end.max(s.end + 1)).or(spans.last()) else {
+        return (0, 0);
+    };
+
+    let mut start = span.start;
+    let mut depth = 0;
+    for token in tokenize(query, dialect).iter().filter(|t| t.start > span.start && t.start < cursor) {
+        if token.is_punct("(") {
+            depth += 1;
+        } else if token.is_punct(")") {
+            depth -= 1;
+        } else if depth <= 0 && token.newlines_before > 0 && token.is_any(STATEMENT_STARTERS) {
+            start = token.start;
         }
-        pos += line.len() + 1; // +1 for the '\n'
     }
-
-    last_start
+    (start, span.end.max(cursor))
 }
 
 /// Extract the SQL context at the given cursor position
-pub fn extract_context(query: &str, cursor_pos: usize) -> SqlContext {
+pub fn extract_context(query: &str, cursor_pos: usize, dialect: DatabaseBackend) -> SqlContext {
     let byte_pos = crate::app::App::char_to_byte_index(query, cursor_pos);
     let before_cursor = &query[..byte_pos];
+    let cursor = before_cursor.chars().count();
 
-    let before_upper = before_cursor.to_uppercase();
-    let trimmed = before_cursor.trim_end();
+    // Typing inside a string or comment
+    let (before_tokens, state) = tokenize_from(before_cursor, LexState::Code, dialect);
+    let in_line_comment = before_tokens.last().is_some_and(|t| t.kind == TokenKind::LineComment && t.end() == cursor);
+    if state != LexState::Code || in_line_comment {
+        return SqlContext::Literal;
+    }
 
     // Isolate the current SQL statement to avoid mixing table refs from different statements
-    let stmt_start = find_current_statement_start(before_cursor);
-    let current_statement = &query[stmt_start..];
+    let (stmt_start, stmt_end) = find_current_statement(query, cursor, dialect);
+    let current_statement = &query[crate::app::App::char_to_byte_index(query, stmt_start)..crate::app::App::char_to_byte_index(query, stmt_end)];
 
     // Extract tables referenced only in the current statement
-    let tables = extract_table_references(current_statement);
-    
+    let tables = extract_table_references(current_statement, dialect);
+
+    // The statement's tokens before the cursor, and those before the word
+    // being typed
+    let before: Vec<Token> = before_tokens.into_iter().filter(|t| t.start >= stmt_start && !t.is_comment()).collect();
+    let typing = before.last().is_some_and(|t| t.end() == cursor && matches!(t.kind, TokenKind::Word | TokenKind::QuotedName));
+    let complete = &before[..before.len() - usize::from(typing)];
+
     // === PRIORITY -1: Variable context (@) ===
     // If the user is typing after @, suggest declared variables
-    if is_in_variable_context(before_cursor) {
+    if is_in_variable_context(&before, cursor) {
         let variables = extract_declared_variables(query);
         return SqlContext::AfterAt { variables };
     }
 
     // === PRIORITY 0: INSERT INTO columns context ===
     // Check this FIRST because INSERT INTO table( should suggest columns, not dot context
-    if let Some(table_ref) = extract_insert_table_in_columns(&before_upper, before_cursor) {
+    if let Some(table_ref) = extract_insert_table_in_columns(&before) {
         return SqlContext::AfterInsertIntoColumns { table_ref };
     }
     
     // === PRIORITY 1: Dot contexts (schema.| or alias.|) ===
     // These take precedence over most other contexts
-    if let Some(dot_context) = check_dot_context(before_cursor, &before_upper, &tables) {
+    if let Some(dot_context) = check_dot_context(&before, cursor, &tables) {
         return dot_context;
     }
     
     // === PRIORITY 2: Detect current clause based on last significant keyword ===
     // This is the key insight: find what clause we're IN, not just what keyword we're AFTER
-    let current_clause = detect_current_clause(&before_upper);
+    let current_clause = detect_current_clause(complete);
     
     match current_clause {
         CurrentClause::Exec => {
@@ -285,7 +289,7 @@ pub fn extract_context(query: &str, cursor_pos: usize) -> SqlContext {
         }
         CurrentClause::From | CurrentClause::Join => {
             // Check if we already have a table name (then suggest clauses)
-            if is_after_table_name(&before_upper, trimmed) {
+            if is_after_table_name(complete, cursor, dialect) {
                 return SqlContext::AfterTableName;
             }
             // Otherwise still typing table name - fall through to General
@@ -296,7 +300,7 @@ pub fn extract_context(query: &str, cursor_pos: usize) -> SqlContext {
         }
         CurrentClause::Set => {
             // SET expects column names - check if we're in UPDATE context
-            if let Some(table_ref) = extract_update_table(&before_upper, before_cursor) {
+            if let Some(table_ref) = extract_update_table(complete) {
                 return SqlContext::AfterUpdateSet { table_ref };
             }
             // Fallback to AfterWhere if we can't extract the table
@@ -321,202 +325,79 @@ pub fn extract_context(query: &str, cursor_pos: usize) -> SqlContext {
 
 /// Extract table from INSERT INTO table( context
 /// Returns Some(TableRef) if we're inside the column list parentheses
-fn extract_insert_table_in_columns(upper_text: &str, original_text: &str) -> Option<TableRef> {
+fn extract_insert_table_in_columns(tokens: &[Token]) -> Option<TableRef> {
     // Pattern: INSERT INTO [schema.]table (
-    // We need to find the table name and check if we're inside ( )
-    
-    // Find "INSERT INTO " position
-    let insert_pos = upper_text.rfind("INSERT INTO ")?;
-    let after_insert = insert_pos + "INSERT INTO ".len();
-    
-    // Check if there's an open parenthesis after the table name
-    let text_after = &original_text[after_insert..];
-    let paren_pos = text_after.find('(')?;
-    
-    // Extract the table name (between INSERT INTO and the parenthesis)
-    let table_part = text_after[..paren_pos].trim();
-    if table_part.is_empty() {
+    let insert = (0..tokens.len()).rev().find(|&i| tokens[i].is("INSERT") && tokens.get(i + 1).is_some_and(|t| t.is("INTO")))?;
+    let (table_ref, paren) = parse_table_name(tokens, insert + 2)?;
+    if !tokens.get(paren)?.is_punct("(") {
         return None;
     }
-    
+
     // Check if we haven't closed the parenthesis yet (still inside column list)
-    let after_paren = &text_after[paren_pos + 1..];
-    if after_paren.contains(')') {
-        // Already closed, not in column list anymore
+    if tokens[paren + 1..].iter().any(|t| t.is_punct(")")) {
         return None;
     }
-    
-    // Parse the table reference
-    parse_simple_table_reference(table_part)
+    Some(table_ref)
 }
 
 /// Extract table from UPDATE table SET context
-fn extract_update_table(upper_text: &str, original_text: &str) -> Option<TableRef> {
+fn extract_update_table(tokens: &[Token]) -> Option<TableRef> {
     // Pattern: UPDATE [schema.]table SET
-    
-    // Find "UPDATE " position
-    let update_pos = upper_text.rfind("UPDATE ")?;
-    let after_update = update_pos + "UPDATE ".len();
-    
-    // Find "SET " after UPDATE
-    let set_pos = upper_text[after_update..].find(" SET ")?;
-    
-    // Extract table name between UPDATE and SET
-    let table_part = original_text[after_update..after_update + set_pos].trim();
-    if table_part.is_empty() {
-        return None;
-    }
-    
-    // Parse the table reference
-    parse_simple_table_reference(table_part)
+    let update = tokens.iter().rposition(|t| t.is("UPDATE"))?;
+    let (table_ref, _) = parse_table_name(tokens, update + 1)?;
+    tokens[update + 1..].iter().any(|t| t.is("SET")).then_some(table_ref)
 }
 
-/// Parse a simple table reference like "schema.table" or "table"
-fn parse_simple_table_reference(text: &str) -> Option<TableRef> {
-    let text = text.trim().trim_matches(|c| c == '[' || c == ']');
-    if text.is_empty() {
+/// Parse a dotted name starting at token `i` (`pmt.Contas`, `[dbo].[My
+/// Table]`, `db.dbo.t`) as a table reference without alias; also returns
+/// the index of the token after it. None for a name ending in a dot.
+fn parse_table_name(tokens: &[Token], mut i: usize) -> Option<(TableRef, usize)> {
+    let mut parts = vec![tokens.get(i)?.name()?];
+    i += 1;
+    while tokens.get(i).is_some_and(|t| t.is_punct("."))
+        && let Some(part) = tokens.get(i + 1).and_then(|t| t.name())
+    {
+        parts.push(part);
+        i += 2;
+    }
+    // `pmt.` is still being typed
+    if tokens.get(i).is_some_and(|t| t.is_punct(".")) {
         return None;
     }
-    
-    // Check for schema.table format
-    if let Some(dot_pos) = text.find('.') {
-        let schema = text[..dot_pos].trim().trim_matches(|c| c == '[' || c == ']');
-        let table = text[dot_pos + 1..].trim().trim_matches(|c| c == '[' || c == ']');
-        Some(TableRef {
-            schema: Some(schema.to_string()),
-            table: table.to_string(),
-            alias: None,
-        })
-    } else {
-        Some(TableRef {
-            schema: None,
-            table: text.to_string(),
-            alias: None,
-        })
-    }
+    let table = parts.pop()?.to_string();
+    let schema = parts.pop().map(str::to_string);
+    Some((TableRef { schema, table, alias: None }, i))
 }
 
 /// Extract table references from the query (FROM and JOIN clauses)
-fn extract_table_references(query: &str) -> Vec<TableRef> {
-    let mut tables = Vec::new();
-    
-    // Normalize whitespace: replace all whitespace sequences with single spaces
-    // This handles cases where FROM/JOIN is followed by newlines instead of spaces
-    let normalized: String = query.chars()
-        .map(|c| if c.is_whitespace() { ' ' } else { c })
-        .collect();
-    let upper = normalized.to_uppercase();
-    
-    // Collapse multiple spaces into one for easier matching
-    let mut collapsed = String::with_capacity(upper.len());
-    let mut prev_space = false;
-    for c in upper.chars() {
-        if c == ' ' {
-            if !prev_space {
-                collapsed.push(c);
-            }
-            prev_space = true;
-        } else {
-            collapsed.push(c);
-            prev_space = false;
-        }
-    }
-    
-    // Also need a collapsed version of the original query (preserving case)
-    let mut normalized_collapsed = String::with_capacity(normalized.len());
-    prev_space = false;
-    for c in normalized.chars() {
-        if c == ' ' {
-            if !prev_space {
-                normalized_collapsed.push(c);
-            }
-            prev_space = true;
-        } else {
-            normalized_collapsed.push(c);
-            prev_space = false;
-        }
-    }
-    
-    // Simple regex-like parsing for FROM and JOIN clauses
+fn extract_table_references(query: &str, dialect: DatabaseBackend) -> Vec<TableRef> {
+    let tokens: Vec<Token> = tokenize(query, dialect).into_iter().filter(|t| !t.is_comment()).collect();
+    let mut tables: Vec<TableRef> = Vec::new();
+
     // Pattern: FROM|JOIN schema.table [AS] alias
     //          FROM|JOIN table [AS] alias
-    
-    let keywords = ["FROM ", "JOIN ", "INNER JOIN ", "LEFT JOIN ", "RIGHT JOIN ", "FULL JOIN ", "CROSS JOIN "];
-    
-    for keyword in keywords {
-        let mut search_pos = 0;
-        while let Some(kw_pos) = collapsed[search_pos..].find(keyword) {
-            let start = search_pos + kw_pos + keyword.len();
-            if let Some(table_ref) = parse_table_reference(&normalized_collapsed[start..]) {
-                // Avoid duplicates
-                if !tables.iter().any(|t: &TableRef| {
-                    t.table.eq_ignore_ascii_case(&table_ref.table) && t.schema == table_ref.schema
-                }) {
-                    tables.push(table_ref);
-                }
-            }
-            search_pos = start;
+    for (i, token) in tokens.iter().enumerate() {
+        if !token.is_any(&["FROM", "JOIN"]) || tokens.get(i + 1).is_some_and(|t| t.is_any(NOT_TABLES)) {
+            continue;
         }
-    }
-    
-    tables
-}
+        let Some((mut table_ref, next)) = parse_table_name(&tokens, i + 1) else {
+            continue;
+        };
 
-/// Parse a table reference from text like "schema.table alias" or "table AS alias"
-fn parse_table_reference(text: &str) -> Option<TableRef> {
-    let text = text.trim();
-    if text.is_empty() {
-        return None;
-    }
-    
-    // Split by whitespace to get tokens
-    let tokens: Vec<&str> = text.split_whitespace().take(4).collect();
-    if tokens.is_empty() {
-        return None;
-    }
-    
-    let table_part = tokens[0];
-    
-    // Check for schema.table format
-    let (schema, table) = if let Some(dot_pos) = table_part.find('.') {
-        let schema = table_part[..dot_pos].trim_matches(|c| c == '[' || c == ']');
-        let table = table_part[dot_pos + 1..].trim_matches(|c| c == '[' || c == ']');
-        (Some(schema.to_string()), table.to_string())
-    } else {
-        let table = table_part.trim_matches(|c| c == '[' || c == ']');
-        (None, table.to_string())
-    };
-    
-    // Stop if we hit a keyword
-    let stop_keywords = ["WHERE", "ORDER", "GROUP", "HAVING", "ON", "SET", "VALUES", "(", ")", ","];
-    if stop_keywords.contains(&table.to_uppercase().as_str()) {
-        return None;
-    }
-    
-    // Look for alias (second token, or after AS)
-    let alias = if tokens.len() > 1 {
-        let next = tokens[1].to_uppercase();
-        if next == "AS" && tokens.len() > 2 {
-            // Skip AS, get the alias
-            let alias = tokens[2].trim_matches(|c| c == '[' || c == ']');
-            if !stop_keywords.contains(&alias.to_uppercase().as_str()) {
-                Some(alias.to_string())
-            } else {
-                None
-            }
-        } else if !stop_keywords.contains(&next.as_str()) 
-            && !["WHERE", "ORDER", "GROUP", "HAVING", "ON", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "JOIN", "SET", "AND", "OR"].contains(&next.as_str()) 
-        {
-            let alias = tokens[1].trim_matches(|c| c == '[' || c == ']');
-            Some(alias.to_string())
-        } else {
-            None
+        // Look for alias (the next word, or the one after AS)
+        let alias = if tokens.get(next).is_some_and(|t| t.is("AS")) { next + 1 } else { next };
+        table_ref.alias = tokens.get(alias).and_then(|t| match t.kind {
+            TokenKind::Word if is_keyword(t.text, dialect) => None,
+            _ => t.name().map(str::to_string),
+        });
+
+        // Avoid duplicates
+        if !tables.iter().any(|t| t.table.eq_ignore_ascii_case(&table_ref.table) && t.schema == table_ref.schema) {
+            tables.push(table_ref);
         }
-    } else {
-        None
-    };
-    
-    Some(TableRef { schema, table, alias })
+    }
+
+    tables
 }
 
 /// Find a table reference by its alias
@@ -528,35 +409,6 @@ fn find_table_by_alias(tables: &[TableRef], alias: &str) -> Option<TableRef> {
         .cloned()
 }
 
-/// Extract the word immediately before a dot
-fn extract_word_before_dot(text: &str) -> Option<String> {
-    let text = text.trim_end_matches('.');
-    let chars: Vec<char> = text.chars().collect();
-    
-    if chars.is_empty() {
-        return None;
-    }
-    
-    let end = chars.len();
-    let mut start = end;
-    
-    // Walk backwards to find word boundaries
-    for i in (0..chars.len()).rev() {
-        let c = chars[i];
-        if c.is_alphanumeric() || c == '_' {
-            start = i;
-        } else {
-            break;
-        }
-    }
-    
-    if start < end {
-        Some(chars[start..end].iter().collect())
-    } else {
-        None
-    }
-}
-
 /// Extract the current word being typed (for filtering)
 fn extract_current_word(text: &str) -> String {
     let chars: Vec<char> = text.chars().collect();
@@ -580,112 +432,78 @@ fn extract_current_word(text: &str) -> String {
     chars[start..].iter().collect()
 }
 
-/// Check if text contains EXEC context
-fn contains_exec_context(upper_text: &str) -> bool {
-    // Find the last occurrence of EXEC or EXECUTE
-    let exec_pos = upper_text.rfind("EXEC ");
-    let execute_pos = upper_text.rfind("EXECUTE ");
-    
-    // Check if FROM/SELECT came after EXEC (which would mean we're not in EXEC context)
-    let from_pos = upper_text.rfind("FROM ");
-    let select_pos = upper_text.rfind("SELECT ");
-    
-    match (exec_pos.or(execute_pos), from_pos.max(select_pos)) {
+/// Check if tokens contain EXEC context
+fn contains_exec_context(tokens: &[Token]) -> bool {
+    // Check if FROM/SELECT came after the last EXEC (which would mean we're not in EXEC context)
+    let last = |words: &[&str]| tokens.iter().rposition(|t| t.is_any(words));
+    match (last(&["EXEC", "EXECUTE"]), last(&["FROM", "SELECT"])) {
         (Some(exec), Some(other)) => exec > other,
         (Some(_), None) => true,
         _ => false,
     }
 }
 
-/// Check if cursor is right after a table name (for suggesting WHERE, JOIN, etc.)
-fn is_after_table_name(upper_text: &str, trimmed: &str) -> bool {
-    // Pattern: FROM something | or JOIN something |
-    // where | is the cursor and something is a table reference
-    
-    // Simple heuristic: if we end with a space after what looks like an identifier
-    // and FROM or JOIN is in the query
-    
-    if !trimmed.ends_with(char::is_whitespace) {
-        return false;
-    }
-    
-    let has_from_or_join = upper_text.contains("FROM ") || upper_text.contains("JOIN ");
-    if !has_from_or_join {
-        return false;
-    }
-    
-    // Check if we haven't started a WHERE/ORDER yet
-    let last_keyword_pos = ["WHERE ", "ORDER ", "GROUP ", "HAVING "]
-        .iter()
-        .filter_map(|kw| upper_text.rfind(kw))
-        .max();
-    
-    let last_from_join = ["FROM ", "JOIN "]
-        .iter()
-        .filter_map(|kw| upper_text.rfind(kw))
-        .max();
-    
-    match (last_from_join, last_keyword_pos) {
-        (Some(fj), Some(kw)) => fj > kw,
-        (Some(_), None) => true,
-        _ => false,
-    }
+/// Check if cursor is after a table name (for suggesting WHERE, JOIN, etc.)
+/// Example: SELECT * FROM Customers c |
+fn is_after_table_name(tokens: &[Token], cursor: usize, dialect: DatabaseBackend) -> bool {
+    // A name or alias, then whitespace
+    tokens.last().is_some_and(|t| {
+        t.end() < cursor
+            && match t.kind {
+                TokenKind::QuotedName => true,
+                TokenKind::Word => !is_keyword(t.text, dialect),
+                _ => false,
+            }
+    })
 }
 
 /// Check if cursor is in a variable context (after @ with optional partial name)
-fn is_in_variable_context(before_cursor: &str) -> bool {
-    let chars: Vec<char> = before_cursor.chars().collect();
-    if chars.is_empty() {
-        return false;
-    }
-
-    // Walk backwards: if we find @ before hitting a non-identifier char, we're in variable context
-    for i in (0..chars.len()).rev() {
-        let c = chars[i];
-        if c == '@' {
-            return true;
-        }
-        if c.is_alphanumeric() || c == '_' {
-            continue;
-        }
-        // Hit a non-identifier, non-@ character
-        return false;
-    }
-    false
+fn is_in_variable_context(tokens: &[Token], cursor: usize) -> bool {
+    tokens.last().is_some_and(|t| {
+        t.end() == cursor && (t.is_punct("@") || (t.kind == TokenKind::Word && t.text.starts_with('@')))
+    })
 }
 
 /// Extract all declared variables from the entire query text.
 /// Looks for DECLARE @VarName patterns (with or without type).
 pub fn extract_declared_variables(query: &str) -> Vec<String> {
-    let mut vars = Vec::new();
-    let upper = query.to_uppercase();
-    let mut search_pos = 0;
+    let tokens: Vec<Token> =
+        tokenize(query, DatabaseBackend::SqlServer).into_iter().filter(|t| !t.is_comment()).collect();
+    let mut vars: Vec<String> = Vec::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if !token.is("DECLARE") {
+            continue;
+        }
 
-    while let Some(decl_pos) = upper[search_pos..].find("DECLARE") {
-        let abs_pos = search_pos + decl_pos + "DECLARE".len();
         // After DECLARE, there may be multiple variables separated by commas
         // DECLARE @a INT, @b VARCHAR(50), @c INT
-        let remaining = &query[abs_pos..];
-
-        for part in remaining.split(',') {
-            let trimmed = part.trim();
-            if let Some(at_pos) = trimmed.find('@') {
-                let after_at = &trimmed[at_pos..];
-                // Extract @VarName (stops at space, comma, =, or end)
-                let var_name: String = after_at
-                    .chars()
-                    .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '@')
-                    .collect();
-                if var_name.len() > 1 && !vars.contains(&var_name) {
-                    vars.push(var_name);
-                }
-            } else {
-                // No more @ in this part, stop scanning this DECLARE
-                break;
+        // The list ends at `;` or at a line that doesn't end with a comma;
+        // commas inside parentheses belong to the type
+        let mut j = i + 1;
+        // Not a variable (e.g. a cursor): stop scanning this DECLARE
+        while let Some(var) = tokens.get(j).filter(|t| t.kind == TokenKind::Word && t.text.starts_with('@') && t.text.len() > 1) {
+            if !vars.iter().any(|v| v == var.text) {
+                vars.push(var.text.to_string());
             }
+            let mut depth = 0;
+            j += 1;
+            let next = loop {
+                let Some(t) = tokens.get(j) else { break None };
+                if depth <= 0 && (t.is_punct(";") || (t.newlines_before > 0 && !tokens[j - 1].is_punct(","))) {
+                    break None;
+                }
+                match t.text {
+                    "(" if t.kind == TokenKind::Punct => depth += 1,
+                    ")" if t.kind == TokenKind::Punct => depth -= 1,
+                    "," if depth <= 0 => break Some(j + 1),
+                    _ => {}
+                }
+                j += 1;
+            };
+            let Some(next) = next else { break };
+            j = next;
         }
-
-        search_pos = abs_pos;
     }
 
     vars
@@ -695,21 +513,23 @@ pub fn extract_declared_variables(query: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    const TSQL: DatabaseBackend = DatabaseBackend::SqlServer;
+
     #[test]
     fn test_after_schema_dot() {
-        let ctx = extract_context("SELECT * FROM pmt.", 18);
+        let ctx = extract_context("SELECT * FROM pmt.", 18, TSQL);
         assert!(matches!(ctx, SqlContext::AfterSchemaDot { schema, .. } if schema == "pmt"));
     }
 
     #[test]
     fn test_after_exec() {
-        let ctx = extract_context("EXEC ", 5);
+        let ctx = extract_context("EXEC ", 5, TSQL);
         assert!(matches!(ctx, SqlContext::AfterExec));
     }
 
     #[test]
     fn test_exec_schema_dot() {
-        let ctx = extract_context("EXEC pmt.", 9);
+        let ctx = extract_context("EXEC pmt.", 9, TSQL);
         assert!(matches!(
             ctx,
             SqlContext::AfterSchemaDot { schema, object_hint: ObjectHint::Procedure } if schema == "pmt"
@@ -718,7 +538,7 @@ mod tests {
 
     #[test]
     fn test_general() {
-        let ctx = extract_context("SEL", 3);
+        let ctx = extract_context("SEL", 3, TSQL);
         assert!(matches!(ctx, SqlContext::General { prefix } if prefix == "SEL"));
     }
 
@@ -726,7 +546,7 @@ mod tests {
     fn test_alias_after_from_with_newline() {
         // FROM followed by newline instead of space
         let query = "SELECT\n    c.\nFROM\n    pmt.Contas c";
-        let ctx = extract_context(query, 13); // Position after "c."
+        let ctx = extract_context(query, 13, TSQL); // Position after "c."
         
         assert!(matches!(
             &ctx,
@@ -745,7 +565,7 @@ mod tests {
         let cursor_pos = query.find("nc.").unwrap() + 3;
         
         // Check alias 'nc' is resolved
-        let ctx = extract_context(query, cursor_pos);
+        let ctx = extract_context(query, cursor_pos, TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterTableAliasDot { alias, table_ref: Some(tref) } 
@@ -756,7 +576,7 @@ mod tests {
     #[test]
     fn test_extract_table_refs_from_with_newline() {
         let query = "SELECT * FROM\n    pmt.Contas c\njoin pmt.NegociacoesContas nc ON x = y";
-        let tables = extract_table_references(query);
+        let tables = extract_table_references(query, TSQL);
         
         assert_eq!(tables.len(), 2, "Expected 2 tables, got {:?}", tables);
         
@@ -779,7 +599,7 @@ mod tests {
     fn test_insert_into_columns_context() {
         // INSERT INTO pmt.Contas(| - should suggest columns
         let query = "INSERT INTO pmt.Contas(";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterInsertIntoColumns { table_ref } 
@@ -791,7 +611,7 @@ mod tests {
     fn test_insert_into_columns_partial() {
         // INSERT INTO Contas(Nome, | - still in column list
         let query = "INSERT INTO Contas(Nome, ";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterInsertIntoColumns { table_ref } 
@@ -803,7 +623,7 @@ mod tests {
     fn test_insert_into_columns_closed() {
         // INSERT INTO Contas(Nome) VALUES (| - parenthesis closed, not in column list
         let query = "INSERT INTO Contas(Nome) VALUES (";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         // Should NOT be AfterInsertIntoColumns because parens are closed
         assert!(!matches!(ctx, SqlContext::AfterInsertIntoColumns { .. }), 
             "Should not be AfterInsertIntoColumns after closing parens, got {:?}", ctx);
@@ -813,7 +633,7 @@ mod tests {
     fn test_update_set_context() {
         // UPDATE pmt.Contas SET | - should suggest columns
         let query = "UPDATE pmt.Contas SET ";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterUpdateSet { table_ref } 
@@ -825,7 +645,7 @@ mod tests {
     fn test_update_set_with_alias() {
         // UPDATE Contas SET Nome = | - still in SET context
         let query = "UPDATE Contas SET Nome = 'teste', ";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterUpdateSet { table_ref } 
@@ -839,7 +659,7 @@ mod tests {
         // Should resolve alias 'c' to Chargebacks, NOT Contas
         let query = "SELECT *\nFROM pmt.Contas c\nWHERE c.Ativo = 1\n\nSELECT *\nFROM pmt.Chargebacks c\nWHERE c.";
         let cursor_pos = query.len();
-        let ctx = extract_context(query, cursor_pos);
+        let ctx = extract_context(query, cursor_pos, TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterTableAliasDot { alias, table_ref: Some(tref) }
@@ -850,7 +670,7 @@ mod tests {
     #[test]
     fn test_after_at_suggests_variables() {
         let query = "DECLARE @Nome VARCHAR(100), @Idade INT\nSELECT @";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterAt { variables }
@@ -861,7 +681,7 @@ mod tests {
     #[test]
     fn test_after_at_partial_name() {
         let query = "DECLARE @CodConta INT\nWHERE @Cod";
-        let ctx = extract_context(query, query.len());
+        let ctx = extract_context(query, query.len(), TSQL);
         assert!(matches!(
             &ctx,
             SqlContext::AfterAt { variables }
@@ -874,5 +694,37 @@ mod tests {
         let query = "DECLARE @A INT, @B VARCHAR(50)\nDECLARE @C DATETIME";
         let vars = extract_declared_variables(query);
         assert_eq!(vars, vec!["@A".to_string(), "@B".to_string(), "@C".to_string()]);
+
+        let query = "DECLARE @n DECIMAL(10, 2), @t TABLE (\n    a INT,\n    b INT\n);\nSELECT @n, @x\nDECLARE c CURSOR FOR SELECT @y, @z";
+        assert_eq!(extract_declared_variables(query), vec!["@n".to_string(), "@t".to_string()]);
+    }
+
+    #[test]
+    fn test_strings_comments_and_quoted_names() {
+        // Nothing to suggest inside a string or comment
+        for query in ["SELECT * FROM t WHERE a = N'FROM x", "SELECT 1 -- FROM t WHERE ", "SELECT /* multi\nline FROM "] {
+            assert_eq!(extract_context(query, query.chars().count(), TSQL), SqlContext::Literal, "{:?}", query);
+        }
+
+        // Clause keywords in strings and comments don't count
+        let query = "SELECT * FROM pmt.Contas c WHERE c.Nome = 'it''s -- a WHERE' /* ORDER BY */ AND ";
+        assert!(matches!(extract_context(query, query.len(), TSQL), SqlContext::AfterWhere { tables } if tables.len() == 1));
+        let query = "SELECT 'FROM x' AS a, ";
+        assert!(matches!(extract_context(query, query.len(), TSQL), SqlContext::AfterSelect { tables } if tables.is_empty()));
+
+        // Bracketed names resolve like plain ones
+        let query = "SELECT o. FROM [dbo].[Order Details] AS [o]";
+        let ctx = extract_context(query, 9, TSQL);
+        assert!(matches!(
+            &ctx,
+            SqlContext::AfterTableAliasDot { alias, table_ref: Some(tref) }
+            if alias == "o" && tref.table == "Order Details" && tref.schema.as_deref() == Some("dbo")
+        ), "{:?}", ctx);
+
+        // After a complete table reference, clauses are next
+        let query = "SELECT * FROM Contas c ";
+        assert_eq!(extract_context(query, query.len(), TSQL), SqlContext::AfterTableName);
+        let query = "SELECT * FROM ";
+        assert!(matches!(extract_context(query, query.len(), TSQL), SqlContext::General { .. }));
     }
 }
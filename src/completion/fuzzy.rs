@@ -0,0 +1,127 @@
+//! Fuzzy matching of the typed prefix against completion labels
+//!
+//! The prefix matches when its characters appear in the label in order,
+//! ignoring case (`cliend` matches `ClienteEndereco`). Of all the ways to
+//! place them, the best scoring one is kept: characters at the start of a
+//! word (after `_`, `.` or a lower-to-upper case change) and runs of
+//! consecutive characters score more, skipped characters cost a little, and
+//! a label that starts with the whole prefix gets a large bonus.
+
+const SCORE_MATCH: i32 = 16;
+const BONUS_BOUNDARY: i32 = 10;
+const BONUS_CONSECUTIVE: i32 = 8;
+/// Per character skipped between two matched ones
+const PENALTY_GAP: i32 = 2;
+const BONUS_PREFIX: i32 = 100;
+
+/// How well a prefix matches a label, and which chars of the label it matched
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i32,
+    /// Char positions in the label, ascending
+    pub positions: Vec<usize>,
+}
+
+fn fold(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+/// Whether a word starts at `i`
+fn is_boundary(chars: &[char], i: usize) -> bool {
+    let Some(&prev) = i.checked_sub(1).and_then(|p| chars.get(p)) else {
+        return true;
+    };
+    let c = chars[i];
+    !prev.is_alphanumeric()
+        || (prev.is_lowercase() && c.is_uppercase())
+        || (prev.is_ascii_digit() != c.is_ascii_digit())
+}
+
+/// Match `pattern` against `text` as a case-insensitive subsequence; None if
+/// some character is missing. An empty pattern matches anything with score 0.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().map(fold).collect();
+    let chars: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = chars.iter().copied().map(fold).collect();
+    let (m, n) = (pattern.len(), chars.len());
+    if m == 0 {
+        return Some(FuzzyMatch { score: 0, positions: Vec::new() });
+    }
+    if m > n {
+        return None;
+    }
+
+    // best[i][j]: best score with pattern[..=i] placed and pattern[i] at j;
+    // from[i][j]: where pattern[i - 1] was placed on that path
+    let mut best: Vec<Vec<Option<i32>>> = vec![vec![None; n]; m];
+    let mut from = vec![vec![0usize; n]; m];
+    for i in 0..m {
+        // Best earlier placement of pattern[i - 1] that leaves a gap, as its
+        // score plus PENALTY_GAP * position so the gap cost can be added later
+        let mut gapped: Option<(i32, usize)> = None;
+        for j in i..n {
+            if i > 0
+                && j >= 2
+                && let Some(score) = best[i - 1][j - 2]
+            {
+                let value = score + PENALTY_GAP * (j - 2) as i32;
+                if gapped.is_none_or(|(b, _)| value > b) {
+                    gapped = Some((value, j - 2));
+                }
+            }
+            if lower[j] != pattern[i] {
+                continue;
+            }
+            let previous = if i == 0 {
+                Some((0, 0))
+            } else {
+                let consecutive = best[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+                let gapped = gapped.map(|(value, k)| (value - PENALTY_GAP * (j - 1) as i32, k));
+                match (consecutive, gapped) {
+                    (Some(c), Some(g)) if g.0 > c.0 => Some(g),
+                    (Some(c), _) => Some(c),
+                    (None, g) => g,
+                }
+            };
+            if let Some((score, k)) = previous {
+                let bonus = if is_boundary(&chars, j) { BONUS_BOUNDARY } else { 0 };
+                best[i][j] = Some(score + SCORE_MATCH + bonus);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut score, mut j) = (0..n).filter_map(|j| best[m - 1][j].map(|s| (s, j))).rev().max_by_key(|(s, _)| *s)?;
+    let mut positions = vec![0; m];
+    for i in (0..m).rev() {
+        positions[i] = j;
+        j = from[i][j];
+    }
+    if lower.starts_with(&pattern) {
+        score += BONUS_PREFIX;
+    }
+    Some(FuzzyMatch { score, positions })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(pattern: &str, text: &str) -> i32 {
+        fuzzy_match(pattern, text).map_or(i32::MIN, |m| m.score)
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        // Word starts win over the first occurrence
+        let m = fuzzy_match("cliend", "ClienteEndereco").unwrap();
+        assert_eq!(m.positions, vec![0, 1, 2, 7, 8, 9]);
+        assert_eq!(fuzzy_match("ci", "data_criacao").unwrap().positions, vec![5, 7]);
+        assert_eq!(fuzzy_match("xyz", "ClienteEndereco"), None);
+        assert_eq!(fuzzy_match("ab", "a"), None);
+        // Prefixes first, then word starts, then runs
+        assert!(score("cli", "Clientes") > score("cli", "TipoCliente"));
+        assert!(score("ce", "ClienteEndereco") > score("ce", "Cidade"));
+        assert!(score("sel", "SELECT") > score("sel", "SessionLog"));
+    }
+}
@@ -4,12 +4,15 @@
 //! - Schema context (tables, views, procedures after schema.)
 //! - SQL keywords (SELECT, FROM, WHERE, etc.)
 //! - Statement context (EXEC suggests procedures, FROM suggests tables)
+//! - Fuzzy matching of the typed prefix, boosted by use in the query history
 
 mod context;
 mod candidates;
+mod fuzzy;
 
-pub use context::{SqlContext, ObjectHint, TableRef, extract_context};
-pub use candidates::{get_candidates, get_candidates_with_columns};
+pub use context::{SqlContext, ObjectHint, TableRef, extract_context, extract_declared_variables};
+pub use candidates::{get_candidates, get_candidates_with_columns, rank_candidates, snippet_candidates};
+pub use fuzzy::fuzzy_match;
 
 /// Completion state for the query editor
 #[derive(Clone, Debug, Default)]
@@ -71,15 +74,18 @@ impl CompletionState {
         self.items.get(self.selected)
     }
 
-    /// Filter items by prefix
+    /// Filter items by prefix (fuzzy, best matches first; items that
+    /// score the same keep their order)
     pub fn filter(&mut self, prefix: &str) {
         self.prefix = prefix.to_string();
-        let prefix_lower = prefix.to_lowercase();
         
         // Keep only items that match the prefix
-        self.items.retain(|item| {
-            item.label.to_lowercase().starts_with(&prefix_lower)
-        });
+        let mut scored: Vec<(i32, CompletionItem)> = std::mem::take(&mut self.items)
+            .into_iter()
+            .filter_map(|item| Some((fuzzy_match(prefix, &item.label)?.score, item)))
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.items = scored.into_iter().map(|(_, item)| item).collect();
         
         // Hide if no matches
         self.visible = !self.items.is_empty();
@@ -154,6 +160,8 @@ pub enum CompletionKind {
     Function,
     /// SQL variable (@Var)
     Variable,
+    /// Named snippet from the config
+    Snippet,
 }
 
 impl CompletionKind {
@@ -169,6 +177,7 @@ impl CompletionKind {
             CompletionKind::Column => " ",
             CompletionKind::Function => "󰊕 ",
             CompletionKind::Variable => "󰫧 ",
+            CompletionKind::Snippet => " ",
         }
     }
 
@@ -184,6 +193,7 @@ impl CompletionKind {
             CompletionKind::Column => "cl",
             CompletionKind::Function => "fn",
             CompletionKind::Variable => "vr",
+            CompletionKind::Snippet => "sn",
         }
     }
 }
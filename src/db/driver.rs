@@ -7,8 +7,9 @@ use anyhow::Result;
 use async_trait::async_trait;
 
 /// Which database backend is in use
-#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum DatabaseBackend {
+    #[default]
     SqlServer,
     Sqlite,
 }
@@ -22,12 +23,6 @@ impl std::fmt::Display for DatabaseBackend {
     }
 }
 
-impl Default for DatabaseBackend {
-    fn default() -> Self {
-        DatabaseBackend::SqlServer
-    }
-}
-
 /// Trait that all database drivers must implement.
 ///
 /// All methods are async because the caller (App) lives in a tokio runtime.
@@ -52,6 +47,12 @@ pub trait DatabaseDriver: Send + Sync {
     /// Get the name of the current database / file
     fn database_name(&self) -> String;
 
+    /// SSH jump host the connection goes through and whether the tunnel
+    /// is up (None without a tunnel)
+    fn tunnel_status(&self) -> Option<(String, bool)> {
+        None
+    }
+
     // --- Schema exploration ---
 
     /// List available databases (SQL Server) or attached databases (SQLite)
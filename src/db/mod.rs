@@ -5,6 +5,7 @@ mod query;
 mod schema;
 pub mod sqlserver;
 pub mod sqlite;
+pub mod tunnel;
 
 pub use driver::*;
 pub use query::*;
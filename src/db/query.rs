@@ -58,6 +58,20 @@ impl QueryResult {
             messages: Vec::new(),
         }
     }
+
+    /// Combine the results of a script's pieces run one after another: the
+    /// last rows returned are kept, times and affected rows add up
+    pub fn then(mut self, mut next: QueryResult) -> QueryResult {
+        let affected_rows = match (self.affected_rows, next.affected_rows) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+        };
+        let execution_time = self.execution_time + next.execution_time;
+        let mut messages = std::mem::take(&mut self.messages);
+        messages.append(&mut next.messages);
+        let shown = if next.columns.is_empty() && !self.columns.is_empty() { self } else { next };
+        QueryResult { columns: shown.columns, rows: shown.rows, row_count: shown.row_count, execution_time, affected_rows, messages }
+    }
 }
 
 // Helper for hex encoding binary data
@@ -6,9 +6,10 @@
 use crate::db::driver::{DatabaseBackend, DatabaseDriver};
 use crate::db::query::{CellValue, ColumnInfo, QueryResult};
 use crate::db::schema::{ColumnDef, DatabaseObject, ObjectType};
+use crate::sql::statements;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
-use rusqlite::{Connection, types::ValueRef};
+use rusqlite::{Connection, OpenFlags, types::ValueRef};
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
@@ -18,24 +19,121 @@ use tokio::sync::Mutex;
 pub struct SqliteDriver {
     conn: Arc<Mutex<Connection>>,
     pub path: PathBuf,
+    /// Opened with SQLITE_OPEN_READ_ONLY
+    pub read_only: bool,
 }
 
 impl SqliteDriver {
     /// Open (or create) a SQLite database file
     pub async fn new(path: PathBuf) -> Result<Self> {
+        Self::open(path, false).await
+    }
+
+    /// Open a SQLite database file, read-only if asked (the file must exist)
+    pub async fn open(path: PathBuf, read_only: bool) -> Result<Self> {
         let p = path.clone();
-        let conn = tokio::task::spawn_blocking(move || {
-            Connection::open(&p).context("Failed to open SQLite database")
-        })
-        .await??;
+        let conn = tokio::task::spawn_blocking(move || open_connection(&p, read_only))
+            .await?
+            .context("Failed to open SQLite database")?;
 
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             path,
+            read_only,
         })
     }
 }
 
+/// Run one statement
+fn execute_statement(conn: &Connection, query: &str) -> Result<QueryResult> {
+    let start = Instant::now();
+
+    // Try as a query that returns rows first
+    let mut stmt = conn.prepare(query)?;
+    let col_count = stmt.column_count();
+
+    if col_count == 0 {
+        // Statement doesn't return rows (INSERT/UPDATE/DELETE/CREATE/etc.);
+        // a failure stops the statements after it
+        drop(stmt);
+        conn.execute_batch(query)?;
+        return Ok(QueryResult {
+            columns: Vec::new(),
+            rows: Vec::new(),
+            row_count: 0,
+            execution_time: start.elapsed(),
+            affected_rows: Some(0),
+            messages: Vec::new(),
+        });
+    }
+
+    // Build column info
+    let mut columns: Vec<ColumnInfo> = (0..col_count)
+        .map(|i| {
+            let name = stmt.column_name(i).unwrap_or("?").to_string();
+            let max_w = name.len().max(4);
+            ColumnInfo {
+                name,
+                type_name: "TEXT".to_string(), // will be refined per-row
+                max_width: max_w,
+            }
+        })
+        .collect();
+
+    let mut rows: Vec<Vec<CellValue>> = Vec::new();
+    let mut raw_rows = stmt.query([])?;
+
+    while let Some(row) = raw_rows.next()? {
+        let mut row_data = Vec::with_capacity(col_count);
+        for i in 0..col_count {
+            let val = match row.get_ref(i)? {
+                ValueRef::Null => CellValue::Null,
+                ValueRef::Integer(v) => CellValue::Int(v),
+                ValueRef::Real(v) => CellValue::Float(v),
+                ValueRef::Text(v) => {
+                    let s = String::from_utf8_lossy(v).to_string();
+                    CellValue::String(s)
+                }
+                ValueRef::Blob(v) => CellValue::Binary(v.to_vec()),
+            };
+            let val_len = val.to_string().len();
+            if i < columns.len() {
+                columns[i].max_width = columns[i].max_width.max(val_len);
+            }
+            // Update type_name based on first non-null value
+            if rows.is_empty() {
+                columns[i].type_name = match &val {
+                    CellValue::Null => "NULL".to_string(),
+                    CellValue::Int(_) => "INTEGER".to_string(),
+                    CellValue::Float(_) => "REAL".to_string(),
+                    CellValue::String(_) => "TEXT".to_string(),
+                    CellValue::Binary(_) => "BLOB".to_string(),
+                    _ => "TEXT".to_string(),
+                };
+            }
+            row_data.push(val);
+        }
+        rows.push(row_data);
+    }
+
+    Ok(QueryResult {
+        row_count: rows.len(),
+        columns,
+        rows,
+        execution_time: start.elapsed(),
+        affected_rows: None,
+        messages: Vec::new(),
+    })
+}
+
+fn open_connection(path: &std::path::Path, read_only: bool) -> rusqlite::Result<Connection> {
+    if read_only {
+        Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX)
+    } else {
+        Connection::open(path)
+    }
+}
+
 #[async_trait]
 impl DatabaseDriver for SqliteDriver {
     fn backend(&self) -> DatabaseBackend {
@@ -56,94 +154,22 @@ impl DatabaseDriver for SqliteDriver {
     }
 
     async fn reconnect(&mut self) -> Result<()> {
-        let p = self.path.clone();
-        let conn = tokio::task::spawn_blocking(move || {
-            Connection::open(&p).context("Failed to reopen SQLite database")
-        })
-        .await??;
+        let (p, read_only) = (self.path.clone(), self.read_only);
+        let conn = tokio::task::spawn_blocking(move || open_connection(&p, read_only))
+            .await?
+            .context("Failed to reopen SQLite database")?;
         *self.conn.lock().await = conn;
         Ok(())
     }
 
     async fn execute_query(&self, query: &str) -> Result<QueryResult> {
         let conn = self.conn.lock().await;
-        let start = Instant::now();
-
-        // Try as a query that returns rows first
-        let mut stmt = conn.prepare(query)?;
-        let col_count = stmt.column_count();
-
-        if col_count == 0 {
-            // Statement doesn't return rows (INSERT/UPDATE/DELETE/CREATE/etc.)
-            drop(stmt);
-            let affected = conn.execute_batch(query);
-            return Ok(QueryResult {
-                columns: Vec::new(),
-                rows: Vec::new(),
-                row_count: 0,
-                execution_time: start.elapsed(),
-                affected_rows: affected.ok().map(|_| 0),
-                messages: Vec::new(),
-            });
+        // prepare() only compiles the first statement: run them one at a time
+        let mut result = QueryResult::empty();
+        for statement in statements::batches(query, DatabaseBackend::Sqlite) {
+            result = result.then(execute_statement(&conn, statement)?);
         }
-
-        // Build column info
-        let mut columns: Vec<ColumnInfo> = (0..col_count)
-            .map(|i| {
-                let name = stmt.column_name(i).unwrap_or("?").to_string();
-                let max_w = name.len().max(4);
-                ColumnInfo {
-                    name,
-                    type_name: "TEXT".to_string(), // will be refined per-row
-                    max_width: max_w,
-                }
-            })
-            .collect();
-
-        let mut rows: Vec<Vec<CellValue>> = Vec::new();
-        let mut raw_rows = stmt.query([])?;
-
-        while let Some(row) = raw_rows.next()? {
-            let mut row_data = Vec::with_capacity(col_count);
-            for i in 0..col_count {
-                let val = match row.get_ref(i)? {
-                    ValueRef::Null => CellValue::Null,
-                    ValueRef::Integer(v) => CellValue::Int(v),
-                    ValueRef::Real(v) => CellValue::Float(v),
-                    ValueRef::Text(v) => {
-                        let s = String::from_utf8_lossy(v).to_string();
-                        CellValue::String(s)
-                    }
-                    ValueRef::Blob(v) => CellValue::Binary(v.to_vec()),
-                };
-                let val_len = val.to_string().len();
-                if i < columns.len() {
-                    columns[i].max_width = columns[i].max_width.max(val_len);
-                }
-                // Update type_name based on first non-null value
-                if rows.is_empty() {
-                    columns[i].type_name = match &val {
-                        CellValue::Null => "NULL".to_string(),
-                        CellValue::Int(_) => "INTEGER".to_string(),
-                        CellValue::Float(_) => "REAL".to_string(),
-                        CellValue::String(_) => "TEXT".to_string(),
-                        CellValue::Binary(_) => "BLOB".to_string(),
-                        _ => "TEXT".to_string(),
-                    };
-                }
-                row_data.push(val);
-            }
-            rows.push(row_data);
-        }
-
-        Ok(QueryResult {
-            row_count: rows.len(),
-            columns,
-            rows,
-            execution_time: start.elapsed(),
-            affected_rows: None,
-            messages: Vec::new(),
-        })
+        Ok(result)
     }
 
     fn database_name(&self) -> String {
@@ -283,3 +309,20 @@ impl DatabaseDriver for SqliteDriver {
         Ok(objects)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_failed_write_is_an_error() {
+        let driver = SqliteDriver {
+            conn: Arc::new(Mutex::new(Connection::open_in_memory().unwrap())),
+            path: PathBuf::from(":memory:"),
+            read_only: false,
+        };
+        driver.execute_query("CREATE TABLE t (id INTEGER PRIMARY KEY)").await.unwrap();
+        assert!(driver.execute_query("INSERT INTO t VALUES (1); INSERT INTO t VALUES (1)").await.is_err());
+        assert!(driver.execute_query("UPDATE missing SET x = 1").await.is_err());
+    }
+}
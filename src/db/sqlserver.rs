@@ -6,16 +6,21 @@
 use crate::db::driver::{DatabaseBackend, DatabaseDriver};
 use crate::db::query::{CellValue, ColumnInfo, QueryResult};
 use crate::db::schema::{ColumnDef, DatabaseObject, ObjectType};
-use anyhow::{Context, Result};
+use crate::db::tunnel::{SshJump, SshTunnel};
+use crate::sql::statements;
+use anyhow::{anyhow, bail, Context, Result};
 use async_trait::async_trait;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use tiberius::time::chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
-use tiberius::{Client, Column, ColumnType, Config, AuthMethod, Row, numeric::Numeric};
+use tiberius::{Client, Column, ColumnType, Config, AuthMethod, EncryptionLevel, Row, SqlBrowser, numeric::Numeric};
 use tokio::net::TcpStream;
 use tokio::sync::Mutex;
 use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
 
+/// Port of the SQL Browser service that resolves named instances
+const SQL_BROWSER_PORT: u16 = 1434;
+
 /// Configuration specific to SQL Server connections
 #[derive(Clone, Debug)]
 pub struct SqlServerConfig {
@@ -24,8 +29,20 @@ pub struct SqlServerConfig {
     pub user: String,
     pub password: String,
     pub database: String,
-    pub encrypt: bool,
+    /// Named instance, resolved to a port through the SQL Browser
+    pub instance: Option<String>,
+    pub encryption: EncryptionLevel,
+    /// Accept any server certificate
     pub trust_cert: bool,
+    /// CA certificate to validate the server against (wins over `trust_cert`)
+    pub ca_file: Option<String>,
+    pub application_name: String,
+    /// Limit for the TCP connection and login
+    pub connect_timeout: Duration,
+    /// Connect with ApplicationIntent=ReadOnly
+    pub read_only: bool,
+    /// Reach the server through an SSH jump host
+    pub ssh: Option<SshJump>,
 }
 
 impl Default for SqlServerConfig {
@@ -36,8 +53,14 @@ impl Default for SqlServerConfig {
             user: std::env::var("DB_USER").unwrap_or_else(|_| "sa".to_string()),
             password: std::env::var("DB_PASSWORD").unwrap_or_else(|_| String::new()),
             database: std::env::var("DB_DATABASE").unwrap_or_else(|_| "master".to_string()),
-            encrypt: false,
+            instance: None,
+            encryption: EncryptionLevel::NotSupported,
             trust_cert: true,
+            ca_file: None,
+            application_name: "sql-tui".to_string(),
+            connect_timeout: Duration::from_secs(15),
+            read_only: false,
+            ssh: None,
         }
     }
 }
@@ -46,40 +69,79 @@ impl Default for SqlServerConfig {
 pub struct SqlServerDriver {
     client: Arc<Mutex<Client<Compat<TcpStream>>>>,
     pub config: SqlServerConfig,
+    /// SSH forward the client connects through (when `config.ssh` is set)
+    tunnel: Option<SshTunnel>,
 }
 
 impl SqlServerDriver {
     /// Create a new SQL Server connection
     pub async fn new(config: SqlServerConfig) -> Result<Self> {
-        let client = Self::connect_internal(&config).await?;
+        let mut tunnel = None;
+        let client = Self::connect_internal(&config, &mut tunnel).await?;
         Ok(Self {
             client: Arc::new(Mutex::new(client)),
             config,
+            tunnel,
         })
     }
 
-    /// Internal TCP + TDS connection
-    async fn connect_internal(cfg: &SqlServerConfig) -> Result<Client<Compat<TcpStream>>> {
+    /// Internal TCP + TDS connection. With an SSH jump host, the TCP stream
+    /// goes to the local end of the tunnel, (re)opened first if needed.
+    async fn connect_internal(cfg: &SqlServerConfig, tunnel: &mut Option<SshTunnel>) -> Result<Client<Compat<TcpStream>>> {
         let mut config = Config::new();
         config.host(&cfg.host);
         config.port(cfg.port);
         config.database(&cfg.database);
         config.authentication(AuthMethod::sql_server(&cfg.user, &cfg.password));
-
-        if cfg.trust_cert {
-            config.trust_cert();
-        }
-        if !cfg.encrypt {
-            config.encryption(tiberius::EncryptionLevel::NotSupported);
+        config.readonly(cfg.read_only);
+        config.application_name(&cfg.application_name);
+        config.encryption(cfg.encryption);
+
+        // tiberius panics when both are set; the CA file is the safer one
+        match &cfg.ca_file {
+            Some(path) => config.trust_cert_ca(path),
+            None if cfg.trust_cert => config.trust_cert(),
+            None => {}
         }
 
-        let tcp = TcpStream::connect(config.get_addr())
-            .await
-            .context("Failed to connect to SQL Server")?;
+        let timeout = cfg.connect_timeout;
+        let timed_out = || anyhow!("Timed out after {}s connecting to SQL Server", timeout.as_secs());
+
+        let tcp = match (&cfg.ssh, &cfg.instance) {
+            // The SQL Browser answers on UDP, which an ssh -L forward can't carry
+            (Some(_), Some(instance)) => {
+                bail!("Named instance '{}' can't be resolved through an SSH tunnel; set its port instead", instance)
+            }
+            (Some(jump), None) => {
+                if !tunnel.as_ref().is_some_and(SshTunnel::is_alive) {
+                    *tunnel = None;
+                    *tunnel = Some(SshTunnel::open(jump, &cfg.host, cfg.port).await?);
+                }
+                let addr = tunnel.as_ref().map(SshTunnel::local_addr).context("SSH tunnel is not open")?;
+                tokio::time::timeout(timeout, TcpStream::connect(addr))
+                    .await
+                    .map_err(|_| timed_out())?
+                    .context("Failed to connect to SQL Server")?
+            }
+            (None, Some(instance)) => {
+                // The browser listens on 1434; it replies with the instance's port
+                config.instance_name(instance);
+                config.port(SQL_BROWSER_PORT);
+                tokio::time::timeout(timeout, TcpStream::connect_named(&config))
+                    .await
+                    .map_err(|_| timed_out())?
+                    .with_context(|| format!("Failed to connect to SQL Server instance '{}'", instance))?
+            }
+            (None, None) => tokio::time::timeout(timeout, TcpStream::connect(config.get_addr()))
+                .await
+                .map_err(|_| timed_out())?
+                .context("Failed to connect to SQL Server")?,
+        };
         tcp.set_nodelay(true)?;
 
-        let client = Client::connect(config, tcp.compat_write())
+        let client = tokio::time::timeout(timeout, Client::connect(config, tcp.compat_write()))
             .await
+            .map_err(|_| timed_out())?
             .context("Failed to authenticate with SQL Server")?;
 
         Ok(client)
@@ -96,9 +158,14 @@ impl SqlServerDriver {
         client: &mut Client<Compat<TcpStream>>,
         query: &str,
     ) -> Result<QueryResult> {
-        let start = Instant::now();
-        let stream = client.simple_query(query).await?;
-        Self::process_results(stream, start).await
+        // GO isn't T-SQL: the batches between GO lines go one at a time
+        let mut result = QueryResult::empty();
+        for batch in statements::batches(query, DatabaseBackend::SqlServer) {
+            let start = Instant::now();
+            let stream = client.simple_query(batch).await?;
+            result = result.then(Self::process_results(stream, start).await?);
+        }
+        Ok(result)
     }
 
     // ---- helpers for query result processing ----
@@ -201,22 +268,24 @@ impl DatabaseDriver for SqlServerDriver {
     }
 
     async fn reconnect(&mut self) -> Result<()> {
-        let client = Self::connect_internal(&self.config).await?;
+        let client = Self::connect_internal(&self.config, &mut self.tunnel).await?;
         *self.client.lock().await = client;
         Ok(())
     }
 
     async fn execute_query(&self, query: &str) -> Result<QueryResult> {
-        let start = Instant::now();
         let mut client = self.client.lock().await;
-        let stream = client.simple_query(query).await?;
-        Self::process_results(stream, start).await
+        Self::execute_query_with_client(&mut client, query).await
     }
 
     fn database_name(&self) -> String {
         self.config.database.clone()
     }
 
+    fn tunnel_status(&self) -> Option<(String, bool)> {
+        self.tunnel.as_ref().map(|t| (t.jump.target(), t.is_alive()))
+    }
+
     async fn get_databases(&self) -> Result<Vec<String>> {
         self.collect_strings("SELECT name FROM sys.databases WHERE state = 0 ORDER BY name").await
     }
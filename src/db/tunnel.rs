@@ -0,0 +1,217 @@
+//! SSH port forwarding through a jump host (bastion)
+//!
+//! Runs the system `ssh` client with `-L`, the same forward people keep
+//! open by hand, so `~/.ssh/config`, agents and known_hosts all apply.
+//! `BatchMode` keeps ssh from prompting on the terminal the UI owns.
+
+use anyhow::{bail, Context, Result};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::process::Stdio;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use tokio::process::{Child, Command};
+
+/// How long to wait for ssh to authenticate and open the forward
+const OPEN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Jump host settings of a connection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SshJump {
+    pub host: String,
+    pub port: Option<u16>,
+    /// Login user (empty = ssh's default)
+    pub user: String,
+    /// Private key file (empty = agent / ssh config)
+    pub key_file: String,
+    /// known_hosts file (empty = ~/.ssh/known_hosts)
+    pub known_hosts: String,
+}
+
+impl SshJump {
+    /// `user@host`, for the header and messages
+    pub fn target(&self) -> String {
+        if self.user.is_empty() {
+            self.host.clone()
+        } else {
+            format!("{}@{}", self.user, self.host)
+        }
+    }
+
+    /// Refuse values ssh would read as options (`-oProxyCommand=...`) or
+    /// split into several
+    fn check(&self) -> Result<()> {
+        for (field, value) in [
+            ("host", &self.host),
+            ("user", &self.user),
+            ("key file", &self.key_file),
+            ("known_hosts file", &self.known_hosts),
+        ] {
+            if value.starts_with('-') || value.chars().any(|c| c.is_whitespace() || c.is_control()) {
+                bail!("Invalid SSH {} '{}': it can't start with '-' or contain spaces or control characters", field, value.escape_debug());
+            }
+        }
+        Ok(())
+    }
+
+    /// Arguments of `ssh` forwarding `local_port` to `remote_host:remote_port`
+    fn args(&self, local_port: u16, remote_host: &str, remote_port: u16) -> Result<Vec<String>> {
+        self.check()?;
+        let mut args: Vec<String> = [
+            "-N",
+            "-T",
+            "-o", "BatchMode=yes",
+            "-o", "ExitOnForwardFailure=yes",
+            "-o", "ServerAliveInterval=30",
+            "-o", "LogLevel=ERROR",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        if let Some(port) = self.port {
+            args.extend(["-p".to_string(), port.to_string()]);
+        }
+        if !self.user.is_empty() {
+            args.extend(["-l".to_string(), self.user.clone()]);
+        }
+        if !self.key_file.is_empty() {
+            args.extend(["-i".to_string(), self.key_file.clone(), "-o".to_string(), "IdentitiesOnly=yes".to_string()]);
+        }
+        if !self.known_hosts.is_empty() {
+            args.extend([
+                "-o".to_string(),
+                format!("UserKnownHostsFile={}", self.known_hosts),
+                "-o".to_string(),
+                "StrictHostKeyChecking=yes".to_string(),
+            ]);
+        }
+        // Brackets keep IPv6 remote hosts apart from the port
+        let remote = if remote_host.contains(':') { format!("[{}]", remote_host) } else { remote_host.to_string() };
+        args.extend(["-L".to_string(), format!("127.0.0.1:{}:{}:{}", local_port, remote, remote_port)]);
+        args.extend(["--".to_string(), self.host.clone()]);
+        Ok(args)
+    }
+}
+
+/// A running `ssh -L` forward; the ssh process is killed when dropped
+pub struct SshTunnel {
+    child: Mutex<Child>,
+    /// Local end of the forward (127.0.0.1)
+    pub local_port: u16,
+    pub jump: SshJump,
+}
+
+impl SshTunnel {
+    /// Start ssh and wait until the local end of the forward accepts
+    /// connections
+    pub async fn open(jump: &SshJump, remote_host: &str, remote_port: u16) -> Result<Self> {
+        let local_port = free_local_port()?;
+        let mut child = Command::new("ssh")
+            .args(jump.args(local_port, remote_host, remote_port)?)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to start ssh")?;
+
+        let started = Instant::now();
+        loop {
+            if let Some(status) = child.try_wait()? {
+                let mut stderr = String::new();
+                if let Some(mut pipe) = child.stderr.take() {
+                    let _ = pipe.read_to_string(&mut stderr).await;
+                }
+                let reason = match stderr.trim() {
+                    "" => status.to_string(),
+                    message => message.to_string(),
+                };
+                bail!("SSH tunnel through {} failed: {}", jump.target(), reason);
+            }
+            if TcpStream::connect((Ipv4Addr::LOCALHOST, local_port)).await.is_ok() {
+                break;
+            }
+            if started.elapsed() > OPEN_TIMEOUT {
+                bail!("SSH tunnel through {} timed out", jump.target());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        Ok(Self { child: Mutex::new(child), local_port, jump: jump.clone() })
+    }
+
+    /// Address to connect to instead of the database server
+    pub fn local_addr(&self) -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::LOCALHOST, self.local_port))
+    }
+
+    /// Is ssh still running?
+    pub fn is_alive(&self) -> bool {
+        let mut child = self.child.lock().unwrap_or_else(|e| e.into_inner());
+        matches!(child.try_wait(), Ok(None))
+    }
+}
+
+/// A local port nobody listens on right now
+fn free_local_port() -> Result<u16> {
+    let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).context("No free local port for the SSH tunnel")?;
+    Ok(listener.local_addr()?.port())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_args() {
+        let jump = SshJump {
+            host: "bastion".into(),
+            port: Some(2222),
+            user: "ops".into(),
+            key_file: "~/.ssh/id_bastion".into(),
+            known_hosts: "/etc/sqltui/known_hosts".into(),
+        };
+        let args = jump.args(40000, "db.internal", 1433).unwrap().join(" ");
+        assert!(args.starts_with("-N -T -o BatchMode=yes"));
+        assert!(args.contains("-p 2222 -l ops -i ~/.ssh/id_bastion -o IdentitiesOnly=yes"));
+        assert!(args.contains("-o UserKnownHostsFile=/etc/sqltui/known_hosts -o StrictHostKeyChecking=yes"));
+        assert!(args.ends_with("-L 127.0.0.1:40000:db.internal:1433 -- bastion"));
+        assert_eq!(jump.target(), "ops@bastion");
+
+        let jump = SshJump { host: "bastion".into(), port: None, user: String::new(), key_file: String::new(), known_hosts: String::new() };
+        assert!(jump.args(1, "::1", 1433).unwrap().join(" ").ends_with("-o LogLevel=ERROR -L 127.0.0.1:1:[::1]:1433 -- bastion"));
+    }
+
+    #[test]
+    fn test_ssh_check() {
+        let jump = |field: &str, value: &str| {
+            let mut jump = SshJump {
+                host: "bastion".into(),
+                port: None,
+                user: "ops".into(),
+                key_file: "~/.ssh/id_bastion".into(),
+                known_hosts: "/etc/sqltui/known_hosts".into(),
+            };
+            match field {
+                "host" => jump.host = value.into(),
+                "user" => jump.user = value.into(),
+                "key file" => jump.key_file = value.into(),
+                _ => jump.known_hosts = value.into(),
+            }
+            jump
+        };
+        for field in ["host", "user", "key file", "known_hosts file"] {
+            for bad in ["-oProxyCommand=touch /tmp/x", "-p", "two words", "tab\there", "line\nbreak", "nul\0", "esc\x1b[0m"] {
+                let err = jump(field, bad).check().unwrap_err().to_string();
+                assert!(err.starts_with(&format!("Invalid SSH {} ", field)), "{}: {}", field, err);
+            }
+            // Dashes inside a value are fine, and empty means ssh's default
+            for good in ["db-jump.example.com", "ops-team", "~/.ssh/id-ed25519", ""] {
+                assert!(jump(field, good).check().is_ok(), "{}: {:?}", field, good);
+            }
+        }
+        // ssh is never started with a bad value
+        assert!(jump("host", "-oProxyCommand=x").args(1, "db", 1433).is_err());
+    }
+}
@@ -9,9 +9,11 @@ extern crate rust_i18n;
 i18n!("locales", fallback = "en");
 
 pub mod app;
+pub mod cli;
 pub mod completion;
 pub mod config;
 pub mod db;
+pub mod secrets;
 pub mod sql;
 pub mod ui;
 pub mod utils;
@@ -3,6 +3,9 @@
 
 use anyhow::Result;
 use sql_tui::app::App;
+use sql_tui::cli::{self, Command};
+use sql_tui::config::AppConfig;
+use sql_tui::secrets;
 use crossterm::{
     cursor::SetCursorStyle,
     event::{DisableMouseCapture, EnableMouseCapture},
@@ -16,7 +19,36 @@ use std::io;
 async fn main() -> Result<()> {
     // Load .env file
     dotenvy::dotenv().ok();
-    
+
+    // Parse command-line arguments
+    let tui_args = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Command::Tui(args)) => args,
+        Ok(Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(Command::Exec(args)) => {
+            // Headless mode: run the query and exit without touching the terminal
+            std::process::exit(cli::run_exec(args).await);
+        }
+        Err(err) => {
+            eprintln!("Error: {err}\n\n{}", cli::USAGE);
+            std::process::exit(cli::EXIT_USAGE);
+        }
+    };
+
+    // Ask about an untrusted project file, unlock the secret vault and offer
+    // to migrate cleartext passwords (prompts on the plain terminal, before
+    // the UI takes it over)
+    let mut config = AppConfig::load();
+    sql_tui::init_locale(config.locale.as_deref());
+    if let Err(err) = config.ask_project_trust() {
+        eprintln!("Error: {err:#}");
+    }
+    if let Err(err) = secrets::startup(&mut config) {
+        eprintln!("Error: {err:#}");
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -25,7 +57,7 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app and run
-    let mut app = App::new().await?;
+    let mut app = App::new(tui_args).await?;
     let res = app.run(&mut terminal).await;
 
     // Restore terminal
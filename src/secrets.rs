@@ -0,0 +1,453 @@
+//! Connection secrets: `${env:VAR}` and `${vault:KEY}` references,
+//! `password_cmd`, and the encrypted vault.
+//!
+//! The vault is `vault.json` next to `config.json`: a map of secrets
+//! encrypted with XChaCha20-Poly1305 under a key derived (Argon2id) from a
+//! master passphrase. It is unlocked once per process, before the UI starts
+//! or, in exec mode, from `SQLTUI_VAULT_PASSPHRASE`.
+
+use crate::config::{AppConfig, ConnectionConfig};
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, rand_core::RngCore};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use rust_i18n::t;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Environment variable read to unlock the vault without a prompt
+pub const PASSPHRASE_ENV: &str = "SQLTUI_VAULT_PASSPHRASE";
+
+/// How long `password_cmd` may run before it's killed
+const PASSWORD_CMD_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Passphrase attempts before starting with the vault locked
+const UNLOCK_ATTEMPTS: usize = 3;
+
+/// The unlocked vault, shared by every connection of the process
+static VAULT: Mutex<Option<Vault>> = Mutex::new(None);
+
+/// Does `value` contain a `${env:..}` or `${vault:..}` reference?
+pub fn has_reference(value: &str) -> bool {
+    value.contains("${env:") || value.contains("${vault:")
+}
+
+/// The reference a secret moved into the vault is replaced with
+pub fn vault_reference(key: &str) -> String {
+    format!("${{vault:{}}}", key)
+}
+
+/// Replace `${env:VAR}` and `${vault:KEY}` references in `value`. Anything
+/// else, including an unclosed `${`, is kept as written.
+pub fn expand(value: &str) -> Result<String> {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            out.push_str(&rest[start..]);
+            return Ok(out);
+        };
+        let reference = &after[..end];
+        if let Some(name) = reference.strip_prefix("env:") {
+            let value = std::env::var(name).map_err(|_| anyhow!(t!("secret_env_missing", name = name).to_string()))?;
+            out.push_str(&value);
+        } else if let Some(key) = reference.strip_prefix("vault:") {
+            out.push_str(&vault_secret(key)?);
+        } else {
+            out.push_str(&rest[start..start + end + 3]);
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Run `password_cmd` through the shell and return the first line it prints
+/// (`pass show` prints the password first, then any notes). It's killed
+/// after `PASSWORD_CMD_TIMEOUT`; callers on the async runtime run this with
+/// `spawn_blocking`.
+pub fn run_password_cmd(cmd: &str) -> Result<String> {
+    run_password_cmd_within(cmd, PASSWORD_CMD_TIMEOUT)
+}
+
+fn run_password_cmd_within(cmd: &str, timeout: Duration) -> Result<String> {
+    let failed = |error: String| anyhow!(t!("secret_password_cmd_failed", error = error).to_string());
+    let mut child = shell(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+    // Drain both pipes while waiting, so a chatty command can't fill one and block
+    let stdout = child.stdout.take().map(read_to_end);
+    let stderr = child.stderr.take().map(read_to_end);
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(failed(t!("secret_password_cmd_timeout", seconds = timeout.as_secs()).to_string()));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let collect = |reader: Option<std::thread::JoinHandle<Vec<u8>>>| reader.and_then(|r| r.join().ok()).unwrap_or_default();
+    let (stdout, stderr) = (collect(stdout), collect(stderr));
+    if !status.success() {
+        let stderr = String::from_utf8_lossy(&stderr);
+        let error = match stderr.trim() {
+            "" => status.to_string(),
+            message => message.to_string(),
+        };
+        return Err(failed(error));
+    }
+    let stdout = String::from_utf8(stdout).context("password_cmd printed invalid UTF-8")?;
+    Ok(stdout.lines().next().unwrap_or("").to_string())
+}
+
+/// Read a pipe to the end on a helper thread
+fn read_to_end(mut pipe: impl Read + Send + 'static) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut bytes = Vec::new();
+        let _ = pipe.read_to_end(&mut bytes);
+        bytes
+    })
+}
+
+#[cfg(windows)]
+fn shell(cmd: &str) -> Command {
+    let mut command = Command::new("cmd");
+    command.args(["/C", cmd]);
+    command
+}
+
+#[cfg(not(windows))]
+fn shell(cmd: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.args(["-c", cmd]);
+    command
+}
+
+/// Look a secret up in the unlocked vault
+fn vault_secret(key: &str) -> Result<String> {
+    let vault = VAULT.lock().unwrap_or_else(|e| e.into_inner());
+    let vault = vault.as_ref().ok_or_else(|| anyhow!(t!("secret_vault_locked", env = PASSPHRASE_ENV).to_string()))?;
+    vault
+        .get(key)
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!(t!("secret_vault_missing", name = key).to_string()))
+}
+
+/// Is the vault unlocked in this process?
+pub fn is_unlocked() -> bool {
+    VAULT.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Store a secret in the unlocked vault and write the vault file. Returns
+/// the reference to save in the config instead of the secret.
+pub fn store(key: &str, secret: &str) -> Result<String> {
+    let mut vault = VAULT.lock().unwrap_or_else(|e| e.into_inner());
+    let vault = vault.as_mut().ok_or_else(|| anyhow!(t!("secret_vault_locked", env = PASSPHRASE_ENV).to_string()))?;
+    vault.set(key, secret);
+    vault.save()?;
+    Ok(vault_reference(key))
+}
+
+/// Unlock the vault for a headless run: from `SQLTUI_VAULT_PASSPHRASE`, or
+/// by asking on the terminal
+pub fn ensure_unlocked() -> Result<()> {
+    if is_unlocked() {
+        return Ok(());
+    }
+    let path = Vault::default_path()?;
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return unlock(&path, &passphrase);
+    }
+    if !std::io::stderr().is_terminal() {
+        bail!(t!("secret_vault_locked", env = PASSPHRASE_ENV).to_string());
+    }
+    if !unlock_interactive(&path)? {
+        bail!(t!("secret_vault_locked", env = PASSPHRASE_ENV).to_string());
+    }
+    Ok(())
+}
+
+/// Before the UI starts: unlock the vault when saved connections use it,
+/// and offer to move cleartext passwords into it
+pub fn startup(config: &mut AppConfig) -> Result<()> {
+    let path = Vault::default_path()?;
+    // Connections of an untrusted project file never get to ask
+    let project_trusted = config.project.as_ref().is_some_and(|project| project.trusted);
+    let uses_vault = config
+        .connections
+        .iter()
+        .filter(|c| !c.project || project_trusted)
+        .any(ConnectionConfig::uses_vault);
+    if uses_vault && path.exists() {
+        if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+            unlock(&path, &passphrase)?;
+        } else if std::io::stdin().is_terminal() {
+            unlock_interactive(&path)?;
+        }
+    }
+
+    let cleartext: Vec<&str> = config
+        .connections
+        .iter()
+        .filter(|c| c.has_cleartext_password())
+        .map(|c| c.name.as_str())
+        .collect();
+    if cleartext.is_empty() || config.skip_vault_migration || !std::io::stdin().is_terminal() {
+        return Ok(());
+    }
+
+    eprint!("{}", t!("vault_migrate_prompt", count = cleartext.len(), names = cleartext.join(", ")));
+    std::io::stderr().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "y" | "yes" | "s" | "sim" => {}
+        "never" | "nunca" => {
+            config.skip_vault_migration = true;
+            return config.save();
+        }
+        _ => return Ok(()),
+    }
+
+    if !is_unlocked() {
+        let unlocked = if path.exists() { unlock_interactive(&path)? } else { create_interactive(&path)? };
+        if !unlocked {
+            return Ok(());
+        }
+    }
+    let mut moved = 0;
+    for conn in config.connections.iter_mut().filter(|c| c.has_cleartext_password()) {
+        conn.password = store(&conn.name, &conn.password)?;
+        moved += 1;
+    }
+    config.save()?;
+    eprintln!("{}", t!("vault_migrated", count = moved, path = path.display()));
+    Ok(())
+}
+
+fn unlock(path: &Path, passphrase: &str) -> Result<()> {
+    let vault = Vault::open(path, passphrase)?;
+    *VAULT.lock().unwrap_or_else(|e| e.into_inner()) = Some(vault);
+    Ok(())
+}
+
+/// Ask for the passphrase until it opens the vault; false when skipped
+fn unlock_interactive(path: &Path) -> Result<bool> {
+    for _ in 0..UNLOCK_ATTEMPTS {
+        let Some(passphrase) = read_hidden(&t!("vault_passphrase_prompt"))? else {
+            return Ok(false);
+        };
+        match unlock(path, &passphrase) {
+            Ok(()) => return Ok(true),
+            Err(e) => eprintln!("{:#}", e),
+        }
+    }
+    Ok(false)
+}
+
+/// Ask for a new passphrase (twice) and create the vault; false when skipped
+fn create_interactive(path: &Path) -> Result<bool> {
+    loop {
+        let Some(passphrase) = read_hidden(&t!("vault_new_passphrase"))? else {
+            return Ok(false);
+        };
+        let Some(repeated) = read_hidden(&t!("vault_repeat_passphrase"))? else {
+            return Ok(false);
+        };
+        if passphrase == repeated {
+            let vault = Vault::create(path, &passphrase)?;
+            *VAULT.lock().unwrap_or_else(|e| e.into_inner()) = Some(vault);
+            return Ok(true);
+        }
+        eprintln!("{}", t!("vault_passphrase_mismatch"));
+    }
+}
+
+/// Read a line from the terminal without echoing it. None when it is left
+/// empty or cancelled with Esc / Ctrl+C.
+fn read_hidden(prompt: &str) -> Result<Option<String>> {
+    eprint!("{}", prompt);
+    std::io::stderr().flush()?;
+    enable_raw_mode()?;
+    let mut input = String::new();
+    let result = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => match key.code {
+                KeyCode::Enter => break Ok((!input.is_empty()).then_some(input)),
+                KeyCode::Esc => break Ok(None),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break Ok(None),
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+    disable_raw_mode()?;
+    eprintln!();
+    result
+}
+
+/// Encrypted form of the vault, as stored on disk
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+/// Decrypted secrets, with the key to write them back
+pub struct Vault {
+    path: PathBuf,
+    salt: Vec<u8>,
+    key: Key,
+    secrets: BTreeMap<String, String>,
+}
+
+impl Vault {
+    /// `vault.json` in the config directory
+    pub fn default_path() -> Result<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("vault.json"))
+    }
+
+    /// Create an empty vault at `path`, encrypted with `passphrase`
+    pub fn create(path: &Path, passphrase: &str) -> Result<Self> {
+        let mut salt = vec![0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+        let vault = Self {
+            path: path.to_path_buf(),
+            key: derive_key(passphrase, &salt)?,
+            salt,
+            secrets: BTreeMap::new(),
+        };
+        vault.save()?;
+        Ok(vault)
+    }
+
+    /// Decrypt the vault at `path`
+    pub fn open(path: &Path, passphrase: &str) -> Result<Self> {
+        let contents = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: VaultFile = serde_json::from_str(&contents).context("Failed to parse the vault file")?;
+        let salt = BASE64.decode(&file.salt).context("Invalid vault salt")?;
+        let nonce = BASE64.decode(&file.nonce).context("Invalid vault nonce")?;
+        let data = BASE64.decode(&file.data).context("Invalid vault data")?;
+        if nonce.len() != 24 {
+            bail!("Invalid vault nonce");
+        }
+
+        let key = derive_key(passphrase, &salt)?;
+        let plain = XChaCha20Poly1305::new(&key)
+            .decrypt(XNonce::from_slice(&nonce), data.as_ref())
+            .map_err(|_| anyhow!(t!("vault_wrong_passphrase").to_string()))?;
+        let secrets = serde_json::from_slice(&plain).context("Failed to parse the vault contents")?;
+        Ok(Self { path: path.to_path_buf(), salt, key, secrets })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.secrets.get(key).map(String::as_str)
+    }
+
+    pub fn set(&mut self, key: &str, secret: &str) {
+        self.secrets.insert(key.to_string(), secret.to_string());
+    }
+
+    /// Encrypt with a fresh nonce and write the vault file (owner-only)
+    pub fn save(&self) -> Result<()> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let plain = serde_json::to_vec(&self.secrets)?;
+        let data = XChaCha20Poly1305::new(&self.key)
+            .encrypt(&nonce, plain.as_ref())
+            .map_err(|_| anyhow!("Failed to encrypt the vault"))?;
+        let file = VaultFile {
+            version: 1,
+            salt: BASE64.encode(&self.salt),
+            nonce: BASE64.encode(nonce),
+            data: BASE64.encode(data),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).context("Failed to create config directory")?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut out = options.open(&self.path).with_context(|| format!("Failed to write {}", self.path.display()))?;
+        out.write_all(serde_json::to_string_pretty(&file)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive the vault key: {}", e))?;
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_references() {
+        let path = std::env::var("PATH").unwrap();
+        assert_eq!(expand("x${env:PATH}y").unwrap(), format!("x{}y", path));
+        assert_eq!(expand("p@${ss}${").unwrap(), "p@${ss}${");
+        assert!(expand("${env:SQLTUI_SURELY_UNSET_VARIABLE}").is_err());
+        assert!(has_reference("${vault:prod}") && !has_reference("plain"));
+
+        // Every field that's expanded counts as using the vault
+        let conn = ConnectionConfig { ssh_user: "${vault:jump}".into(), ..ConnectionConfig::default() };
+        assert!(conn.uses_vault());
+        assert!(!ConnectionConfig::default().uses_vault());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_password_cmd() {
+        assert_eq!(run_password_cmd("printf 'secret\\nnotes\\n'").unwrap(), "secret");
+        assert!(run_password_cmd("exit 3").is_err());
+        assert!(run_password_cmd_within("sleep 5", Duration::from_millis(100)).is_err());
+        // Far more than a pipe holds, on both pipes
+        let noisy = "yes noise | head -n 100000 >&2; echo secret; yes notes | head -n 100000";
+        assert_eq!(run_password_cmd_within(noisy, Duration::from_secs(10)).unwrap(), "secret");
+    }
+
+    #[test]
+    fn test_vault_roundtrip() {
+        let path = std::env::temp_dir().join(format!("sqltui-vault-test-{}.json", std::process::id()));
+        let mut vault = Vault::create(&path, "correct horse").unwrap();
+        vault.set("prod", "s3cret");
+        vault.save().unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains("s3cret"));
+        assert_eq!(Vault::open(&path, "correct horse").unwrap().get("prod"), Some("s3cret"));
+        assert!(Vault::open(&path, "wrong").is_err());
+        let _ = fs::remove_file(&path);
+    }
+}
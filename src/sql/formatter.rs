@@ -1,18 +1,739 @@
-//! SQL formatter - disabled
+//! SQL formatter for T-SQL and SQLite
+//!
+//! Each clause starts a line. The items of SELECT, GROUP BY, ORDER BY, SET
+//! and VALUES get a line each, aligned under the first one. Subqueries, CTE
+//! bodies, BEGIN / END blocks and CREATE TABLE columns are indented, and
+//! AND / OR in WHERE, HAVING and joins start a line. Everything else stays
+//! on one line. Comments, string literals and quoted identifiers are copied
+//! unchanged.
 
-/// Format SQL query (currently disabled - returns input as-is)
-pub fn format_sql_query(sql: &str) -> String {
-    sql.to_string()
+use crate::config::{CommaStyle, FormatConfig, KeywordCase};
+use crate::db::DatabaseBackend;
+
+use super::lexer::{is_keyword, tokenize, Token, TokenKind};
+
+/// Words that start a clause, in both dialects
+const CLAUSES: &[(&[&str], ClauseKind)] = &[
+    (&["SELECT"], ClauseKind::List),
+    (&["FROM"], ClauseKind::Plain),
+    (&["WHERE"], ClauseKind::Condition),
+    (&["GROUP", "BY"], ClauseKind::List),
+    (&["ORDER", "BY"], ClauseKind::List),
+    (&["HAVING"], ClauseKind::Condition),
+    (&["INSERT", "INTO"], ClauseKind::Plain),
+    (&["INSERT"], ClauseKind::Plain),
+    (&["DELETE", "FROM"], ClauseKind::Plain),
+    (&["DELETE"], ClauseKind::Plain),
+    (&["UPDATE"], ClauseKind::Plain),
+    (&["SET"], ClauseKind::List),
+    (&["VALUES"], ClauseKind::List),
+    (&["INTO"], ClauseKind::Plain),
+    (&["JOIN"], ClauseKind::Condition),
+    (&["INNER", "JOIN"], ClauseKind::Condition),
+    (&["LEFT", "JOIN"], ClauseKind::Condition),
+    (&["LEFT", "OUTER", "JOIN"], ClauseKind::Condition),
+    (&["RIGHT", "JOIN"], ClauseKind::Condition),
+    (&["RIGHT", "OUTER", "JOIN"], ClauseKind::Condition),
+    (&["FULL", "JOIN"], ClauseKind::Condition),
+    (&["FULL", "OUTER", "JOIN"], ClauseKind::Condition),
+    (&["CROSS", "JOIN"], ClauseKind::Condition),
+];
+
+const TSQL_CLAUSES: &[(&[&str], ClauseKind)] = &[
+    (&["CROSS", "APPLY"], ClauseKind::Condition),
+    (&["OUTER", "APPLY"], ClauseKind::Condition),
+];
+
+const SQLITE_CLAUSES: &[(&[&str], ClauseKind)] = &[
+    (&["LIMIT"], ClauseKind::Plain),
+    (&["RETURNING"], ClauseKind::List),
+    (&["NATURAL", "JOIN"], ClauseKind::Condition),
+    (&["ON", "CONFLICT"], ClauseKind::Plain),
+    (&["REPLACE", "INTO"], ClauseKind::Plain),
+    (&["INSERT", "OR", "REPLACE", "INTO"], ClauseKind::Plain),
+    (&["INSERT", "OR", "IGNORE", "INTO"], ClauseKind::Plain),
+];
+
+/// Set operators: on a line of their own between two queries
+const SET_OPERATORS: &[&[&str]] = &[&["UNION", "ALL"], &["UNION"], &["EXCEPT"], &["INTERSECT"]];
+
+/// Words that start a statement
+const STATEMENTS: &[(&str, ClauseKind)] = &[
+    ("CREATE", ClauseKind::Create),
+    ("ALTER", ClauseKind::Alter),
+    ("DROP", ClauseKind::Plain),
+    ("COMMIT", ClauseKind::Plain),
+    ("ROLLBACK", ClauseKind::Plain),
+];
+
+const TSQL_STATEMENTS: &[(&str, ClauseKind)] = &[
+    ("DECLARE", ClauseKind::Create),
+    ("IF", ClauseKind::Plain),
+    ("WHILE", ClauseKind::Plain),
+    ("RETURN", ClauseKind::Plain),
+    ("EXEC", ClauseKind::Plain),
+    ("EXECUTE", ClauseKind::Plain),
+    ("PRINT", ClauseKind::Plain),
+    ("USE", ClauseKind::Plain),
+    ("THROW", ClauseKind::Plain),
+    ("RAISERROR", ClauseKind::Plain),
+    ("TRUNCATE", ClauseKind::Plain),
+    ("MERGE", ClauseKind::Plain),
+    ("BREAK", ClauseKind::Plain),
+    ("CONTINUE", ClauseKind::Plain),
+    ("OPEN", ClauseKind::Plain),
+    ("CLOSE", ClauseKind::Plain),
+    ("DEALLOCATE", ClauseKind::Plain),
+];
+
+const SQLITE_STATEMENTS: &[(&str, ClauseKind)] = &[
+    ("PRAGMA", ClauseKind::Plain),
+    ("VACUUM", ClauseKind::Plain),
+    ("ATTACH", ClauseKind::Plain),
+    ("DETACH", ClauseKind::Plain),
+    ("EXPLAIN", ClauseKind::Plain),
+    ("REINDEX", ClauseKind::Plain),
+    ("ANALYZE", ClauseKind::Plain),
+];
+
+/// Object types after which IF belongs to the statement (`DROP TABLE IF EXISTS`)
+const OBJECT_TYPES: &[&str] = &[
+    "TABLE", "VIEW", "INDEX", "PROCEDURE", "PROC", "FUNCTION", "TRIGGER", "SCHEMA", "DATABASE", "TYPE", "SEQUENCE",
+];
+
+/// Words after which INSERT / UPDATE / DELETE / SET are part of a definition
+/// (`ON DELETE SET NULL`, `AFTER INSERT ON t`)
+const DEFINITION_CONTEXT: &[&str] = &["ON", "AFTER", "BEFORE", "OF", "FOR", "INSTEAD", "DELETE", "UPDATE", "MERGE"];
+
+/// Words after BEGIN that make it a transaction rather than a block
+const TRANSACTION_WORDS: &[&str] = &["TRAN", "TRANSACTION", "DISTRIBUTED", "DEFERRED", "IMMEDIATE", "EXCLUSIVE"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ClauseKind {
+    /// SELECT, GROUP BY, ORDER BY, SET, VALUES: an item per line
+    List,
+    /// WITH: a CTE per line
+    With,
+    /// WHERE, HAVING and joins: AND / OR start a line
+    Condition,
+    /// CREATE and DECLARE: a TABLE's columns get a line each
+    Create,
+    Alter,
+    Plain,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Clause {
+    kind: ClauseKind,
+    /// Column the items of a list line up at
+    align: usize,
+    /// A TABLE was named: the next parenthesis holds its columns
+    table: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FrameKind {
+    /// The statement level or a BEGIN / END block
+    Block,
+    Subquery,
+    /// CREATE TABLE columns
+    Columns,
+    /// Any other parenthesis: kept on one line
+    Inline,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    kind: FrameKind,
+    /// Indentation of the lines inside
+    indent: usize,
+    /// Indentation of the line that opened it, for the closing `)` or END
+    outer: usize,
+    clause: Option<Clause>,
+    /// Open CASE expressions
+    case_depth: usize,
+}
+
+impl Frame {
+    fn new(kind: FrameKind, indent: usize, outer: usize) -> Self {
+        Self { kind, indent, outer, clause: None, case_depth: 0 }
+    }
+}
+
+struct Formatter<'a> {
+    config: &'a FormatConfig,
+    tsql: bool,
+    out: String,
+    frames: Vec<Frame>,
+    /// Start a new line at this column before the next token
+    pending: Option<usize>,
+    /// Inside BETWEEN, before its AND
+    between: bool,
+    /// Last token written, and the last one that wasn't a comment
+    prev: Option<Token<'a>>,
+    prev_code: Option<Token<'a>>,
+    /// The last token written was a unary operator
+    unary: bool,
+    /// A leading comma and its column, held back so comments before the
+    /// next list item go above it rather than leave it alone on a line
+    comma: Option<(usize, Token<'a>)>,
+}
+
+/// Format SQL for `dialect` (T-SQL for SQL Server, or SQLite)
+pub fn format_sql_query(sql: &str, dialect: DatabaseBackend, config: &FormatConfig) -> String {
+    let tokens = tokenize(sql, dialect);
+    let mut formatter = Formatter {
+        config,
+        tsql: dialect == DatabaseBackend::SqlServer,
+        out: String::new(),
+        frames: vec![Frame::new(FrameKind::Block, 0, 0)],
+        pending: None,
+        between: false,
+        prev: None,
+        prev_code: None,
+        unary: false,
+        comma: None,
+    };
+    let mut i = 0;
+    while i < tokens.len() {
+        i += formatter.token(&tokens, i);
+    }
+    formatter.flush_comma();
+    formatter.out.trim_end().to_string()
+}
+
+impl<'a> Formatter<'a> {
+    fn top(&self) -> Frame {
+        self.frames[self.frames.len() - 1]
+    }
+
+    fn top_mut(&mut self) -> &mut Frame {
+        let last = self.frames.len() - 1;
+        &mut self.frames[last]
+    }
+
+    fn laid_out(&self) -> bool {
+        self.top().kind != FrameKind::Inline
+    }
+
+    fn is_keyword(&self, word: &str) -> bool {
+        let dialect = if self.tsql { DatabaseBackend::SqlServer } else { DatabaseBackend::Sqlite };
+        is_keyword(word, dialect)
+    }
+
+    fn recase(&self, word: &str) -> String {
+        match self.config.keyword_case {
+            KeywordCase::Upper => word.to_uppercase(),
+            KeywordCase::Lower => word.to_lowercase(),
+            KeywordCase::Preserve => word.to_string(),
+        }
+    }
+
+    /// Leading spaces of the current output line
+    fn line_indent(&self) -> usize {
+        let line = self.out.rsplit('\n').next().unwrap_or("");
+        line.len() - line.trim_start_matches(' ').len()
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.rsplit('\n').next().unwrap_or("").trim().is_empty()
+    }
+
+    /// Where a line continuing the current clause starts
+    fn content_indent(&self) -> usize {
+        let top = self.top();
+        match (top.kind, top.clause) {
+            (FrameKind::Inline, _) => self.line_indent(),
+            (_, Some(Clause { kind: ClauseKind::List, align, .. })) => align,
+            _ => top.indent,
+        }
+    }
+
+    fn newline(&mut self, col: usize) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        self.out.extend(std::iter::repeat_n(' ', col));
+    }
+
+    fn blank_line(&mut self) {
+        let len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(len);
+        if !self.out.is_empty() {
+            while !self.out.ends_with("\n\n") {
+                self.out.push('\n');
+            }
+        }
+    }
+
+    fn needs_space(&self, token: &Token, text: &str) -> bool {
+        let Some(prev) = &self.prev else {
+            return false;
+        };
+        if self.at_line_start() || self.unary {
+            return false;
+        }
+        match (prev.text, text) {
+            (_, "," | ";" | ")" | "." | "::") | ("(" | "." | "::", _) => false,
+            // Keep `COUNT(*)` and `t (a, b)` as written
+            (_, "(") => token.space_before || prev.kind == TokenKind::Punct,
+            _ => true,
+        }
+    }
+
+    fn note(&mut self, token: Token<'a>) {
+        self.unary = false;
+        self.prev = Some(token);
+        if !token.is_comment() {
+            self.prev_code = Some(token);
+        }
+    }
+
+    /// Write a token after a space, or on a new line if one is pending
+    fn emit(&mut self, token: Token<'a>, text: &str) {
+        if let Some(col) = self.pending.take() {
+            self.newline(col);
+        } else if self.needs_space(&token, text) {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.note(token);
+    }
+
+    /// Write a token at the start of a new line
+    fn emit_at(&mut self, col: usize, token: Token<'a>, text: &str) {
+        self.pending = None;
+        self.newline(col);
+        self.out.push_str(text);
+        self.note(token);
+    }
+
+    /// Write a token that sticks to the previous one (`;`), keeping a
+    /// pending line break for the next
+    fn attach(&mut self, token: Token<'a>) {
+        if self.prev.is_some_and(|p| p.kind == TokenKind::LineComment) {
+            self.emit(token, token.text);
+        } else {
+            self.out.push_str(token.text);
+            self.note(token);
+        }
+    }
+
+    /// Handle the token at `i`; returns how many tokens were used
+    fn token(&mut self, tokens: &[Token<'a>], i: usize) -> usize {
+        let token = tokens[i];
+        if token.newlines_before >= 2 && self.laid_out() {
+            self.blank_line();
+            self.pending = Some(self.pending.unwrap_or_else(|| self.content_indent()));
+        }
+        if !token.is_comment() {
+            self.flush_comma();
+        }
+        match token.kind {
+            TokenKind::LineComment | TokenKind::BlockComment => {
+                self.comment(tokens, i);
+                1
+            }
+            TokenKind::Word => self.word(tokens, i),
+            TokenKind::String | TokenKind::Number | TokenKind::QuotedName | TokenKind::Parameter => {
+                self.emit(token, token.text);
+                1
+            }
+            TokenKind::Punct => {
+                self.punct(tokens, i);
+                1
+            }
+        }
+    }
+
+    fn comment(&mut self, tokens: &[Token<'a>], i: usize) {
+        let token = tokens[i];
+        let next_on_new_line = tokens.get(i + 1).is_some_and(|t| t.newlines_before > 0);
+        let breaks_after = token.kind == TokenKind::LineComment || next_on_new_line;
+        if token.newlines_before > 0 || self.out.trim().is_empty() {
+            let col = self.pending.take().unwrap_or_else(|| self.content_indent());
+            self.newline(col);
+            self.out.push_str(token.text);
+            if breaks_after {
+                self.pending = Some(col);
+            }
+        } else {
+            // After the code on its line; a pending break waits until after it
+            if !self.at_line_start() {
+                self.out.push(' ');
+            }
+            self.out.push_str(token.text);
+            if breaks_after {
+                self.pending = Some(self.pending.unwrap_or_else(|| self.content_indent()));
+            }
+        }
+        self.note(token);
+    }
+
+    fn punct(&mut self, tokens: &[Token<'a>], i: usize) {
+        let token = tokens[i];
+        let top = self.top();
+        match token.text {
+            "(" => {
+                let next = tokens[i + 1..].iter().find(|t| !t.is_comment());
+                let opens_query = next.is_some_and(|t| t.is("SELECT") || t.is("WITH"));
+                let columns = self.laid_out() && top.clause.is_some_and(|c| c.table);
+                let outer = self.line_indent();
+                let indent = outer + self.config.indent;
+                self.emit(token, "(");
+                if opens_query || columns {
+                    if let Some(clause) = &mut self.top_mut().clause {
+                        clause.table = false;
+                    }
+                    let kind = if opens_query { FrameKind::Subquery } else { FrameKind::Columns };
+                    self.frames.push(Frame::new(kind, indent, outer));
+                    self.pending = Some(indent);
+                } else {
+                    self.frames.push(Frame::new(FrameKind::Inline, outer, outer));
+                }
+            }
+            ")" => match top.kind {
+                FrameKind::Subquery | FrameKind::Columns => {
+                    self.frames.pop();
+                    self.emit_at(top.outer, token, ")");
+                }
+                FrameKind::Inline => {
+                    self.frames.pop();
+                    self.emit(token, ")");
+                }
+                // Unbalanced
+                FrameKind::Block => self.emit(token, ")"),
+            },
+            "," => {
+                let clause = if self.laid_out() { top.clause.map(|c| c.kind) } else { None };
+                match (top.kind, clause) {
+                    (FrameKind::Columns, _) => self.list_comma(token, top.indent, top.indent.saturating_sub(2)),
+                    (_, Some(ClauseKind::List)) => {
+                        let align = top.clause.map_or(0, |c| c.align);
+                        self.list_comma(token, align, align.saturating_sub(2));
+                    }
+                    (_, Some(ClauseKind::With)) => self.list_comma(token, top.indent, top.indent),
+                    _ => self.emit(token, ","),
+                }
+            }
+            ";" => {
+                self.attach(token);
+                // Whatever was left open ends with the statement
+                while self.frames.len() > 1 && self.top().kind != FrameKind::Block {
+                    self.frames.pop();
+                }
+                let top = self.top_mut();
+                top.clause = None;
+                top.case_depth = 0;
+                self.pending = Some(self.top().indent);
+                self.between = false;
+            }
+            text => {
+                let unary = text == "~" || (matches!(text, "-" | "+") && self.unary_context());
+                self.emit(token, text);
+                self.unary = unary;
+            }
+        }
+    }
+
+    /// A comma between list items: at the end of the line, or starting the next
+    fn list_comma(&mut self, token: Token<'a>, item_col: usize, comma_col: usize) {
+        match self.config.comma_style {
+            CommaStyle::Trailing => {
+                self.emit(token, ",");
+                self.pending = Some(item_col);
+            }
+            CommaStyle::Leading => self.comma = Some((comma_col, token)),
+        }
+    }
+
+    /// Write a held-back leading comma, starting the next item's line
+    fn flush_comma(&mut self) {
+        if let Some((col, token)) = self.comma.take() {
+            self.emit_at(col, token, ",");
+        }
+    }
+
+    /// Whether a `-` or `+` here is a sign rather than an operator
+    fn unary_context(&self) -> bool {
+        match &self.prev_code {
+            None => true,
+            Some(t) => match t.kind {
+                TokenKind::Punct => t.text != ")",
+                TokenKind::Word => self.is_keyword(t.text),
+                _ => false,
+            },
+        }
+    }
+
+    fn prev_is_any(&self, words: &[&str]) -> bool {
+        self.prev_code.is_some_and(|t| t.is_any(words))
+    }
+
+    /// Whether a statement can start here (for WITH)
+    fn at_statement_start(&self) -> bool {
+        match &self.prev_code {
+            None => true,
+            Some(t) => matches!(t.text, ";" | "(") || t.is_any(&["AS", "BEGIN", "ELSE", "END", "GO", "THEN"]),
+        }
+    }
+
+    /// The clause starting at `i`, with how many words it has
+    fn clause_at(&self, tokens: &[Token], i: usize) -> Option<(usize, ClauseKind)> {
+        let dialect = if self.tsql { TSQL_CLAUSES } else { SQLITE_CLAUSES };
+        CLAUSES
+            .iter()
+            .chain(dialect)
+            .filter(|(words, _)| {
+                tokens.len() >= i + words.len() && words.iter().zip(&tokens[i..]).all(|(w, t)| t.is(w))
+            })
+            .max_by_key(|(words, _)| words.len())
+            .map(|(words, kind)| (words.len(), *kind))
+    }
+
+    fn statement_at(&self, token: &Token) -> Option<ClauseKind> {
+        let dialect = if self.tsql { TSQL_STATEMENTS } else { SQLITE_STATEMENTS };
+        STATEMENTS.iter().chain(dialect).find(|(word, _)| token.is(word)).map(|(_, kind)| *kind)
+    }
+
+    /// Write the `len` words at `i` on a new line as one clause keyword
+    fn start_clause(&mut self, tokens: &[Token<'a>], i: usize, len: usize, kind: ClauseKind) {
+        let indent = self.top().indent;
+        let text = tokens[i..i + len].iter().map(|t| self.recase(t.text)).collect::<Vec<_>>().join(" ");
+        self.emit_at(indent, tokens[i + len - 1], &text);
+        let align = indent + text.chars().count() + 1;
+        self.top_mut().clause = Some(Clause { kind, align, table: false });
+        self.between = false;
+    }
+
+    fn word(&mut self, tokens: &[Token<'a>], i: usize) -> usize {
+        let token = tokens[i];
+        let next = tokens.get(i + 1);
+        // Qualified names (`t.select`) are never keywords
+        if self.prev_code.is_some_and(|t| t.text == ".") || next.is_some_and(|t| t.text == ".") {
+            self.emit(token, token.text);
+            return 1;
+        }
+        let text = if self.is_keyword(token.text) { self.recase(token.text) } else { token.text.to_string() };
+        let top = self.top();
+        let laid_out = self.laid_out();
+        let in_case = top.case_depth > 0;
+        let clause = top.clause.map(|c| c.kind);
+
+        // T-SQL batch separator, alone on its line
+        if self.tsql
+            && token.is("GO")
+            && (token.newlines_before > 0 || self.out.is_empty())
+            && next.is_none_or(|t| t.newlines_before > 0)
+        {
+            self.frames.truncate(1);
+            self.frames[0].clause = None;
+            self.frames[0].case_depth = 0;
+            self.emit_at(0, token, &text);
+            self.pending = Some(0);
+            return 1;
+        }
+
+        if token.is("CASE") {
+            self.top_mut().case_depth += 1;
+        } else if token.is("END") && in_case {
+            self.top_mut().case_depth -= 1;
+        } else if token.is("END") && laid_out {
+            return self.end_block(tokens, i, &text);
+        } else if token.is("BEGIN") && laid_out {
+            return self.begin_block(tokens, i, &text);
+        } else if token.is("ELSE") && laid_out && !in_case {
+            self.start_clause(tokens, i, 1, ClauseKind::Plain);
+            return 1;
+        } else if token.is("BETWEEN") {
+            self.between = true;
+        } else if token.is_any(&["AND", "OR"]) {
+            if token.is("AND") && self.between {
+                self.between = false;
+            } else if laid_out && !in_case && clause == Some(ClauseKind::Condition) {
+                self.emit_at(top.indent + self.config.indent, token, &text);
+                return 1;
+            }
+        } else if token.is("TABLE") {
+            if let Some(clause) = &mut self.top_mut().clause
+                && clause.kind == ClauseKind::Create
+            {
+                clause.table = true;
+            }
+        } else if laid_out && !in_case {
+            if token.is("WITH") && self.at_statement_start() {
+                self.start_clause(tokens, i, 1, ClauseKind::With);
+                return 1;
+            }
+            if let Some(words) = SET_OPERATORS
+                .iter()
+                .find(|words| tokens.len() >= i + words.len() && words.iter().zip(&tokens[i..]).all(|(w, t)| t.is(w)))
+            {
+                self.start_clause(tokens, i, words.len(), ClauseKind::Plain);
+                self.top_mut().clause = None;
+                self.pending = Some(top.indent);
+                return words.len();
+            }
+            let in_definition = matches!(clause, Some(ClauseKind::Create | ClauseKind::Alter));
+            if let Some((len, kind)) = self.clause_at(tokens, i) {
+                let part_of_definition = token.is_any(&["INSERT", "UPDATE", "DELETE", "SET", "INTO"])
+                    && (self.prev_is_any(DEFINITION_CONTEXT)
+                        || (in_definition && self.prev_code.is_some_and(|t| t.text == ",")));
+                if !part_of_definition {
+                    self.start_clause(tokens, i, len, kind);
+                    return len;
+                }
+            } else if let Some(kind) = self.statement_at(&token) {
+                let part_of_statement = (token.is("IF") && self.prev_is_any(OBJECT_TYPES))
+                    || (token.is_any(&["ALTER", "DROP"]) && in_definition);
+                if !part_of_statement {
+                    self.start_clause(tokens, i, 1, kind);
+                    return 1;
+                }
+            }
+        }
+        self.emit(token, &text);
+        1
+    }
+
+    /// BEGIN: a block (also BEGIN TRY / CATCH), or a transaction
+    fn begin_block(&mut self, tokens: &[Token<'a>], i: usize, text: &str) -> usize {
+        let next = tokens[i + 1..].iter().find(|t| !t.is_comment());
+        if next.is_none_or(|t| t.text == ";" || t.is_any(TRANSACTION_WORDS)) {
+            self.start_clause(tokens, i, 1, ClauseKind::Plain);
+            return 1;
+        }
+        let indent = self.top().indent;
+        self.emit_at(indent, tokens[i], text);
+        let used = self.block_suffix(tokens, i);
+        self.top_mut().clause = None;
+        self.frames.push(Frame::new(FrameKind::Block, indent + self.config.indent, indent));
+        self.pending = Some(indent + self.config.indent);
+        used
+    }
+
+    /// END of a BEGIN block (also END TRY / CATCH)
+    fn end_block(&mut self, tokens: &[Token<'a>], i: usize, text: &str) -> usize {
+        let top = self.top();
+        let col = if self.frames.len() > 1 && top.kind == FrameKind::Block {
+            self.frames.pop();
+            top.outer
+        } else {
+            top.indent
+        };
+        self.emit_at(col, tokens[i], text);
+        let used = self.block_suffix(tokens, i);
+        self.top_mut().clause = None;
+        self.pending = Some(col);
+        used
+    }
+
+    /// TRY / CATCH after BEGIN or END, on the same line
+    fn block_suffix(&mut self, tokens: &[Token<'a>], i: usize) -> usize {
+        match tokens.get(i + 1) {
+            Some(&t) if self.tsql && t.is_any(&["TRY", "CATCH"]) => {
+                self.emit(t, &self.recase(t.text));
+                2
+            }
+            _ => 1,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn format(sql: &str) -> String {
+        format_sql_query(sql, DatabaseBackend::SqlServer, &FormatConfig::default())
+    }
+
+    #[test]
+    fn test_clauses_and_lists() {
+        let sql = "select a, b.c as x, count(*) from dbo.t b inner join u on u.id = b.id and u.x between 1 and 2 \
+                   where a = -1 or b.c <> N'x' group by a, b.c order by a desc";
+        assert_eq!(
+            format(sql),
+            "SELECT a,\n       b.c AS x,\n       COUNT(*)\nFROM dbo.t b\nINNER JOIN u ON u.id = b.id\n    AND u.x BETWEEN 1 AND 2\n\
+             WHERE a = -1\n    OR b.c <> N'x'\nGROUP BY a,\n         b.c\nORDER BY a DESC"
+        );
+
+        let config = FormatConfig { keyword_case: KeywordCase::Lower, indent: 2, comma_style: CommaStyle::Leading };
+        let sql = "SELECT a,b FROM t WHERE x=1 AND y=2";
+        assert_eq!(
+            format_sql_query(sql, DatabaseBackend::SqlServer, &config),
+            "select a\n     , b\nfrom t\nwhere x = 1\n  and y = 2"
+        );
+        // Comments before an item go above its comma, or after the item before it
+        let sql = "SELECT a,\n  -- the b column\n  b, /* c */ c,\n/* d */ d FROM t";
+        assert_eq!(
+            format_sql_query(sql, DatabaseBackend::SqlServer, &config),
+            "select a\n       -- the b column\n     , b /* c */\n     , c\n       /* d */\n     , d\nfrom t"
+        );
+    }
+
+    #[test]
+    fn test_subqueries_and_ctes() {
+        let sql = "with x as (select id from t where n > 0), y as (select 1 as one) \
+                   select * from x where id in (select id from y)";
+        assert_eq!(
+            format(sql),
+            "WITH x AS (\n    SELECT id\n    FROM t\n    WHERE n > 0\n),\ny AS (\n    SELECT 1 AS one\n)\n\
+             SELECT *\nFROM x\nWHERE id IN (\n    SELECT id\n    FROM y\n)"
+        );
+        // Table hints are not CTEs
+        assert_eq!(format("select a from t with (nolock)"), "SELECT a\nFROM t WITH (NOLOCK)");
+    }
+
+    #[test]
+    fn test_comments_and_literals_preserved() {
+        let sql = "select a, -- first\n  b /* the\n  b */\nfrom t -- done";
+        assert_eq!(format(sql), "SELECT a, -- first\n       b /* the\n  b */\nFROM t -- done");
+
+        let sql = "select 'it''s -- not a comment',n'x', [order  by] from t";
+        assert_eq!(format(sql), "SELECT 'it''s -- not a comment',\n       n'x',\n       [order  by]\nFROM t");
+
+        // A blank line between statements stays
+        assert_eq!(format("select 1\n\n\nselect 2"), "SELECT 1\n\nSELECT 2");
+    }
+
+    #[test]
+    fn test_tsql_blocks() {
+        let sql = "if @x = 1 begin select 1; update t set a = 1, b = 2 where id = @id end \
+                   else begin try exec p end try begin catch print 'e' end catch\ngo\nselect 2";
+        assert_eq!(
+            format(sql),
+            "IF @x = 1\nBEGIN\n    SELECT 1;\n    UPDATE t\n    SET a = 1,\n        b = 2\n    WHERE id = @id\nEND\n\
+             ELSE\nBEGIN TRY\n    EXEC p\nEND TRY\nBEGIN CATCH\n    PRINT 'e'\nEND CATCH\nGO\nSELECT 2"
+        );
+        assert_eq!(
+            format("select case when a = 1 then 'x' else 'y' end from t"),
+            "SELECT CASE WHEN a = 1 THEN 'x' ELSE 'y' END\nFROM t"
+        );
+    }
+
+    #[test]
+    fn test_dialects() {
+        let sqlite = |sql| format_sql_query(sql, DatabaseBackend::Sqlite, &FormatConfig::default());
+        // LIMIT is only a clause in SQLite
+        assert_eq!(sqlite("select a from t limit 5"), "SELECT a\nFROM t\nLIMIT 5");
+        assert_eq!(format("select a from t limit 5"), "SELECT a\nFROM t limit 5");
+        assert_eq!(
+            sqlite("create table if not exists t (id integer primary key, `the name` text)"),
+            "CREATE TABLE IF NOT EXISTS t (\n    id INTEGER PRIMARY KEY,\n    `the name` TEXT\n)"
+        );
+        assert_eq!(format("drop table if exists #t"), "DROP TABLE IF EXISTS #t");
+    }
+
     #[test]
-    fn test_format_returns_input() {
-        let sql = "select * from users where id = 1";
-        let formatted = format_sql_query(sql);
-        assert_eq!(sql, formatted);
+    fn test_format_is_stable() {
+        let sql = "with x as (select a, b from t where a in (1, 2) and b is not null) \
+                   select x.a, -- note\n sum(x.b) total from x group by x.a having sum(x.b) > 10;\n\n\
+                   insert into t (a, b) values (1, 'x'), (2, 'y')";
+        let once = format(sql);
+        assert_eq!(format(&once), once);
+        assert!(once.ends_with("INSERT INTO t (a, b)\nVALUES (1, 'x'),\n       (2, 'y')"));
     }
 }
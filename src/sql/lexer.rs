@@ -0,0 +1,350 @@
+//! SQL tokenizer for T-SQL and SQLite
+//!
+//! Knows the comment, string, quoted identifier, number and parameter
+//! syntax of each dialect, so words inside literals and comments are never
+//! mistaken for keywords. Text can be tokenized whole or a piece at a time:
+//! `tokenize_from` starts in the state the previous piece ended in (inside a
+//! block comment or an unclosed string), which is how the editor highlights
+//! only the lines on screen.
+
+use crate::db::DatabaseBackend;
+
+const TWO_CHAR_OPERATORS: &[&str] = &[
+    "<>", "<=", ">=", "!=", "!<", "!>", "||", "::", "==", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "<<", ">>",
+];
+
+/// Keywords of both dialects
+const KEYWORDS: &[&str] = &[
+    "ADD", "ALL", "ALTER", "AND", "ANY", "AS", "ASC", "AVG", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE",
+    "CAST", "CHAR", "CHECK", "COALESCE", "COLLATE", "COLUMN", "COMMIT", "CONSTRAINT", "COUNT", "CREATE",
+    "CROSS", "CURRENT", "DECIMAL", "DEFAULT", "DELETE", "DESC", "DISTINCT", "DROP", "ELSE", "END",
+    "ESCAPE", "EXCEPT", "EXISTS", "FALSE", "FLOAT", "FOLLOWING", "FOREIGN", "FROM", "FULL", "GROUP",
+    "HAVING", "IF", "IN", "INDEX", "INNER", "INSERT", "INT", "INTEGER", "INTERSECT", "INTO", "IS", "JOIN",
+    "KEY", "LEFT", "LIKE", "MAX", "MIN", "NOT", "NULL", "NULLIF", "NUMERIC", "OFFSET", "ON", "OR", "ORDER",
+    "OUTER", "OVER", "PARTITION", "PRECEDING", "PRIMARY", "RANGE", "REAL", "RECURSIVE", "REFERENCES",
+    "RIGHT", "ROLLBACK", "ROW", "ROWS", "SELECT", "SET", "SOME", "SUM", "TABLE", "THEN", "TRANSACTION",
+    "TRIGGER", "TRUE", "UNBOUNDED", "UNION", "UNIQUE", "UPDATE", "USING", "VALUES", "VARCHAR", "VIEW",
+    "WHEN", "WHERE", "WITH",
+];
+
+const TSQL_KEYWORDS: &[&str] = &[
+    "APPLY", "BIGINT", "BIT", "BREAK", "CATCH", "CLOSE", "CONTINUE", "CONVERT", "CURSOR", "DATEADD",
+    "DATEDIFF", "DATETIME", "DATETIME2", "DEALLOCATE", "DECLARE", "EXEC", "EXECUTE", "FETCH", "FUNCTION",
+    "GETDATE", "GO", "IDENTITY", "IIF", "ISNULL", "LEN", "MATCHED", "MERGE", "MONEY", "NCHAR", "NEXT",
+    "NOCOUNT", "NOLOCK", "NVARCHAR", "ONLY", "OPEN", "OUTPUT", "PERCENT", "PIVOT", "PRINT", "PROC",
+    "PROCEDURE", "RAISERROR", "RETURN", "RETURNS", "SMALLINT", "THROW", "TIES", "TINYINT", "TOP", "TRAN",
+    "TRUNCATE", "TRY", "UNIQUEIDENTIFIER", "UNPIVOT", "USE", "WHILE",
+];
+
+const SQLITE_KEYWORDS: &[&str] = &[
+    "ABORT", "ANALYZE", "ATTACH", "AUTOINCREMENT", "BLOB", "CONFLICT", "DEFERRED", "DETACH", "EXCLUSIVE",
+    "EXPLAIN", "FAIL", "GLOB", "IFNULL", "IGNORE", "IMMEDIATE", "INSTR", "LENGTH", "LIMIT", "NATURAL",
+    "PLAN", "PRAGMA", "QUERY", "REGEXP", "REINDEX", "REPLACE", "RETURNING", "ROWID", "STRICT", "SUBSTR",
+    "TEMP", "TEMPORARY", "TEXT", "VACUUM", "VIRTUAL", "WITHOUT",
+];
+
+/// Whether `word` is a keyword (or built-in function / type name) of the dialect
+pub fn is_keyword(word: &str, dialect: DatabaseBackend) -> bool {
+    let upper = word.to_uppercase();
+    let dialect = if dialect == DatabaseBackend::SqlServer { TSQL_KEYWORDS } else { SQLITE_KEYWORDS };
+    KEYWORDS.contains(&upper.as_str()) || dialect.contains(&upper.as_str())
+}
+
+/// Token classes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TokenKind {
+    /// Keyword, name or variable (`@x`, `#temp`, SQLite `:x` / `$x`)
+    Word,
+    /// `'...'`, T-SQL `N'...'`, SQLite blob `X'...'`
+    String,
+    Number,
+    /// `[name]`, `"name"` or SQLite's `` `name` ``
+    QuotedName,
+    /// SQLite `?` / `?1`
+    Parameter,
+    LineComment,
+    BlockComment,
+    Punct,
+}
+
+/// Where a piece of text starts: in code, inside a block comment (nested
+/// `depth` deep, as T-SQL allows) or inside a string or quoted name
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LexState {
+    #[default]
+    Code,
+    BlockComment {
+        depth: usize,
+    },
+    Quoted {
+        kind: TokenKind,
+        close: char,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Char index of the first character in the input
+    pub start: usize,
+    /// Whether whitespace came before it in the input, and how many newlines
+    pub space_before: bool,
+    pub newlines_before: usize,
+}
+
+impl Token<'_> {
+    pub fn is(&self, word: &str) -> bool {
+        self.kind == TokenKind::Word && self.text.eq_ignore_ascii_case(word)
+    }
+
+    pub fn is_any(&self, words: &[&str]) -> bool {
+        words.iter().any(|w| self.is(w))
+    }
+
+    pub fn is_punct(&self, text: &str) -> bool {
+        self.kind == TokenKind::Punct && self.text == text
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.kind, TokenKind::LineComment | TokenKind::BlockComment)
+    }
+
+    /// String, number, quoted name or parameter: copied as is by the formatter
+    pub fn is_literal(&self) -> bool {
+        matches!(self.kind, TokenKind::String | TokenKind::Number | TokenKind::QuotedName | TokenKind::Parameter)
+    }
+
+    /// The name a word or quoted name stands for, without its quotes
+    pub fn name(&self) -> Option<&str> {
+        match self.kind {
+            TokenKind::Word => Some(self.text),
+            TokenKind::QuotedName if self.text.chars().count() >= 2 => {
+                let close = self.text.chars().next_back()?;
+                Some(&self.text[1..self.text.len() - close.len_utf8()])
+            }
+            _ => None,
+        }
+    }
+
+    /// Char index just past the last character
+    pub fn end(&self) -> usize {
+        self.start + self.text.chars().count()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '$')
+}
+
+/// End (exclusive) of the quoted text from `i`, just past the opening quote,
+/// and whether `close` was found; a doubled closing quote is an escaped one
+fn quote_end(chars: &[char], mut i: usize, close: char) -> (usize, bool) {
+    while i < chars.len() {
+        if chars[i] == close {
+            if chars.get(i + 1) != Some(&close) {
+                return (i + 1, true);
+            }
+            i += 1;
+        }
+        i += 1;
+    }
+    (chars.len(), false)
+}
+
+/// End (exclusive) of the block comment from `i`, already `depth` deep, and
+/// the depth still open there (0 once closed). Block comments nest in T-SQL.
+fn comment_end(chars: &[char], mut i: usize, mut depth: usize, tsql: bool) -> (usize, usize) {
+    while let Some(&c) = chars.get(i) {
+        if c == '/' && chars.get(i + 1) == Some(&'*') && (tsql || depth == 0) {
+            depth += 1;
+            i += 2;
+        } else if c == '*' && chars.get(i + 1) == Some(&'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return (i, 0);
+            }
+        } else {
+            i += 1;
+        }
+    }
+    (chars.len(), depth)
+}
+
+/// Split `sql` into tokens; whitespace is dropped and recorded on the token
+/// that follows it
+pub fn tokenize(sql: &str, dialect: DatabaseBackend) -> Vec<Token<'_>> {
+    tokenize_from(sql, LexState::Code, dialect).0
+}
+
+/// Tokenize `text` starting in `state`, and return the state it ends in. A
+/// comment or quote still open at the start becomes the first token.
+pub fn tokenize_from(text: &str, state: LexState, dialect: DatabaseBackend) -> (Vec<Token<'_>>, LexState) {
+    let tsql = dialect == DatabaseBackend::SqlServer;
+    let offsets: Vec<usize> = text.char_indices().map(|(b, _)| b).collect();
+    let chars: Vec<char> = text.chars().collect();
+    let at = |i: usize| chars.get(i).copied();
+    let mut tokens = Vec::new();
+    let (mut i, mut space_before, mut newlines_before) = (0, false, 0);
+    let mut state = state;
+
+    while i < chars.len() {
+        let start = i;
+        let kind = match state {
+            LexState::BlockComment { depth } => {
+                let (end, open) = comment_end(&chars, i, depth, tsql);
+                i = end;
+                state = if open > 0 { LexState::BlockComment { depth: open } } else { LexState::Code };
+                TokenKind::BlockComment
+            }
+            LexState::Quoted { kind, close } => {
+                let (end, closed) = quote_end(&chars, i, close);
+                i = end;
+                if closed {
+                    state = LexState::Code;
+                }
+                kind
+            }
+            LexState::Code => {
+                let c = chars[i];
+                if c.is_whitespace() {
+                    space_before = true;
+                    newlines_before += usize::from(c == '\n');
+                    i += 1;
+                    continue;
+                }
+                let next = at(i + 1);
+                // Opening quote of a string or quoted name at `i + skip`
+                let mut quoted = |skip: usize, kind: TokenKind| {
+                    let close = if chars[i + skip] == '[' { ']' } else { chars[i + skip] };
+                    let (end, closed) = quote_end(&chars, i + skip + 1, close);
+                    if !closed {
+                        state = LexState::Quoted { kind, close };
+                    }
+                    (end, kind)
+                };
+                let (end, kind) = match c {
+                    '-' if next == Some('-') => {
+                        let end = (i..chars.len()).find(|&j| chars[j] == '\n').unwrap_or(chars.len());
+                        (end, TokenKind::LineComment)
+                    }
+                    '/' if next == Some('*') => {
+                        let (end, open) = comment_end(&chars, i, 0, tsql);
+                        if open > 0 {
+                            state = LexState::BlockComment { depth: open };
+                        }
+                        (end, TokenKind::BlockComment)
+                    }
+                    '\'' => quoted(0, TokenKind::String),
+                    '"' | '[' => quoted(0, TokenKind::QuotedName),
+                    '`' if !tsql => quoted(0, TokenKind::QuotedName),
+                    'N' | 'n' if tsql && next == Some('\'') => quoted(1, TokenKind::String),
+                    'X' | 'x' if !tsql && next == Some('\'') => quoted(1, TokenKind::String),
+                    c if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) => {
+                        let mut end = i + 1;
+                        while let Some(c) = at(end) {
+                            let exponent_sign = matches!(c, '+' | '-') && matches!(at(end - 1), Some('e' | 'E'));
+                            if c.is_alphanumeric() || c == '.' || c == '_' || exponent_sign {
+                                end += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                        (end, TokenKind::Number)
+                    }
+                    '?' if !tsql => {
+                        let end = (i + 1..chars.len()).find(|&j| !chars[j].is_ascii_digit()).unwrap_or(chars.len());
+                        (end, TokenKind::Parameter)
+                    }
+                    c if c.is_alphabetic()
+                        || c == '_'
+                        || (matches!(c, '@' | '#') && next.is_some_and(is_word_char))
+                        || (!tsql && matches!(c, ':' | '$') && next.is_some_and(char::is_alphabetic)) =>
+                    {
+                        let end = (i + 1..chars.len()).find(|&j| !is_word_char(chars[j])).unwrap_or(chars.len());
+                        (end, TokenKind::Word)
+                    }
+                    _ => {
+                        let pair: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+                        let len = if TWO_CHAR_OPERATORS.contains(&pair.as_str()) { 2 } else { 1 };
+                        (i + len, TokenKind::Punct)
+                    }
+                };
+                i = end;
+                kind
+            }
+        };
+        let end = offsets.get(i).copied().unwrap_or(text.len());
+        tokens.push(Token { kind, text: &text[offsets[start]..end], start, space_before, newlines_before });
+        space_before = false;
+        newlines_before = 0;
+    }
+    (tokens, state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str, dialect: DatabaseBackend) -> Vec<(TokenKind, &str)> {
+        tokenize(sql, dialect).into_iter().map(|t| (t.kind, t.text)).collect()
+    }
+
+    #[test]
+    fn test_literals_and_comments() {
+        use TokenKind::*;
+        let tsql = DatabaseBackend::SqlServer;
+        assert_eq!(
+            kinds("SELECT N'it''s -- not a comment', [Order Details].[Qty], \"x\"\"y\" -- done", tsql),
+            vec![
+                (Word, "SELECT"),
+                (String, "N'it''s -- not a comment'"),
+                (Punct, ","),
+                (QuotedName, "[Order Details]"),
+                (Punct, "."),
+                (QuotedName, "[Qty]"),
+                (Punct, ","),
+                (QuotedName, "\"x\"\"y\""),
+                (LineComment, "-- done"),
+            ]
+        );
+        assert_eq!(kinds("[a]]b] 1.5e-3 @x", tsql), vec![(QuotedName, "[a]]b]"), (Number, "1.5e-3"), (Word, "@x")]);
+        // N'...' is a T-SQL string; SQLite has blobs and backquoted names instead
+        let sqlite = DatabaseBackend::Sqlite;
+        assert_eq!(kinds("N'x'", sqlite), vec![(Word, "N"), (String, "'x'")]);
+        assert_eq!(kinds("X'00' `t` ?1", sqlite), vec![(String, "X'00'"), (QuotedName, "`t`"), (Parameter, "?1")]);
+        // T-SQL block comments nest, SQLite's don't
+        assert_eq!(kinds("/* a /* b */ c */ x", tsql), vec![(BlockComment, "/* a /* b */ c */"), (Word, "x")]);
+        assert_eq!(kinds("/* a /* b */ x", sqlite), vec![(BlockComment, "/* a /* b */"), (Word, "x")]);
+        assert_eq!(tokenize("[My Table]", tsql)[0].name(), Some("My Table"));
+    }
+
+    #[test]
+    fn test_state_across_lines() {
+        use TokenKind::*;
+        let tsql = DatabaseBackend::SqlServer;
+        let lines = ["SELECT 1 /* starts", "SELECT still comment", "*/ SELECT 'open", "it''s -- in the string", "done' FROM t"];
+        let mut state = LexState::Code;
+        let mut all = Vec::new();
+        for line in lines {
+            let (tokens, next) = tokenize_from(line, state, tsql);
+            all.push(tokens.into_iter().map(|t| (t.kind, t.text)).collect::<Vec<_>>());
+            state = next;
+        }
+        assert_eq!(state, LexState::Code);
+        assert_eq!(all[0], vec![(Word, "SELECT"), (Number, "1"), (BlockComment, "/* starts")]);
+        assert_eq!(all[1], vec![(BlockComment, "SELECT still comment")]);
+        assert_eq!(all[2], vec![(BlockComment, "*/"), (Word, "SELECT"), (String, "'open")]);
+        assert_eq!(all[3], vec![(String, "it''s -- in the string")]);
+        assert_eq!(all[4], vec![(String, "done'"), (Word, "FROM"), (Word, "t")]);
+
+        // Nested comments stay open until the outer one closes
+        let (_, state) = tokenize_from("/* a /* b */", LexState::Code, tsql);
+        assert_eq!(state, LexState::BlockComment { depth: 1 });
+        let (_, state) = tokenize_from("[open", LexState::Code, tsql);
+        assert_eq!(state, LexState::Quoted { kind: QuotedName, close: ']' });
+        let (tokens, state) = tokenize_from("name] x", state, tsql);
+        assert_eq!((tokens[0].kind, tokens[1].text, state), (QuotedName, "x", LexState::Code));
+    }
+}
@@ -0,0 +1,726 @@
+//! SQL linter
+//!
+//! Flags statements that are probably wrong or risky: UPDATE and DELETE
+//! without WHERE, `SELECT *` in procedure bodies, comparisons with `= NULL`,
+//! comma (implicit cross) joins, tables and columns missing from the loaded
+//! schema, T-SQL variables that are never declared and parentheses without
+//! a partner. The checks work on
+//! tokens, so comments and string literals never trigger them.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::completion::extract_declared_variables;
+use crate::db::DatabaseBackend;
+
+use super::lexer::{tokenize, Token, TokenKind};
+use super::pairs::Pairs;
+use super::statements::ACTION_CONTEXT;
+
+/// Words that begin a new statement (T-SQL doesn't need `;`)
+const STATEMENT_STARTS: &[&str] = &[
+    "SELECT", "INSERT", "UPDATE", "DELETE", "MERGE", "CREATE", "ALTER", "DROP", "TRUNCATE", "DECLARE", "IF",
+    "ELSE", "WHILE", "BEGIN", "END", "EXEC", "EXECUTE", "RETURN", "PRINT", "USE", "COMMIT", "ROLLBACK", "THROW",
+    "RAISERROR", "OPEN", "CLOSE", "DEALLOCATE", "REPLACE", "PRAGMA", "VACUUM", "ATTACH", "DETACH",
+];
+
+/// Words that end the table list of a FROM clause
+const FROM_END: &[&str] = &[
+    "WHERE", "GROUP", "ORDER", "HAVING", "UNION", "EXCEPT", "INTERSECT", "LIMIT", "WINDOW", "OPTION", "INTO",
+    "FOR", "OUTPUT", "VALUES", "RETURNING", "SET",
+];
+
+/// Keywords an `=` is compared under, as opposed to assigned under
+const CONDITION_WORDS: &[&str] = &["WHERE", "ON", "HAVING", "WHEN", "IF", "WHILE", "AND", "OR", "NOT", "CHECK"];
+const ASSIGNMENT_WORDS: &[&str] = &[
+    "SET", "DECLARE", "SELECT", "UPDATE", "VALUES", "EXEC", "EXECUTE", "PROC", "PROCEDURE", "FUNCTION",
+];
+
+/// Words that can follow a table name without being its alias
+const NOT_ALIAS: &[&str] = &[
+    "WHERE", "JOIN", "INNER", "LEFT", "RIGHT", "FULL", "CROSS", "OUTER", "NATURAL", "ON", "USING", "APPLY",
+    "GROUP", "ORDER", "HAVING", "UNION", "EXCEPT", "INTERSECT", "SET", "WITH", "OUTPUT", "VALUES", "SELECT",
+    "FROM", "LIMIT", "WINDOW", "OPTION", "INTO", "DEFAULT", "PIVOT", "UNPIVOT", "FOR", "RETURNING", "OFFSET",
+    "TABLESAMPLE", "INDEXED", "NOT",
+];
+
+/// Schemas of system catalogs, which the schema explorer doesn't list
+const SYSTEM_SCHEMAS: &[&str] = &["sys", "information_schema", "temp"];
+
+/// Trigger pseudo-tables
+const PSEUDO_TABLES: &[&str] = &["inserted", "deleted", "new", "old"];
+
+/// How serious a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// What a diagnostic is about
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LintKind {
+    UpdateWithoutWhere,
+    DeleteWithoutWhere,
+    SelectStar,
+    EqualsNull,
+    ImplicitJoin,
+    UnknownTable(String),
+    UnknownColumn { column: String, table: String },
+    UndeclaredVariable(String),
+    UnbalancedParen,
+}
+
+impl LintKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            LintKind::UnknownTable(_)
+            | LintKind::UnknownColumn { .. }
+            | LintKind::UndeclaredVariable(_)
+            | LintKind::UnbalancedParen => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+
+    /// Name of the rule, for `lint.disabled` in the config
+    pub fn rule(&self) -> &'static str {
+        match self {
+            LintKind::UpdateWithoutWhere => "update_without_where",
+            LintKind::DeleteWithoutWhere => "delete_without_where",
+            LintKind::SelectStar => "select_star",
+            LintKind::EqualsNull => "equals_null",
+            LintKind::ImplicitJoin => "implicit_join",
+            LintKind::UnknownTable(_) => "unknown_table",
+            LintKind::UnknownColumn { .. } => "unknown_column",
+            LintKind::UndeclaredVariable(_) => "undeclared_variable",
+            LintKind::UnbalancedParen => "unbalanced_paren",
+        }
+    }
+
+    pub fn message(&self) -> String {
+        match self {
+            LintKind::UpdateWithoutWhere => t!("lint_update_without_where").to_string(),
+            LintKind::DeleteWithoutWhere => t!("lint_delete_without_where").to_string(),
+            LintKind::SelectStar => t!("lint_select_star").to_string(),
+            LintKind::EqualsNull => t!("lint_equals_null").to_string(),
+            LintKind::ImplicitJoin => t!("lint_implicit_join").to_string(),
+            LintKind::UnknownTable(name) => t!("lint_unknown_table", name = name).to_string(),
+            LintKind::UnknownColumn { column, table } => t!("lint_unknown_column", column = column, table = table).to_string(),
+            LintKind::UndeclaredVariable(name) => t!("lint_undeclared_variable", name = name).to_string(),
+            LintKind::UnbalancedParen => t!("lint_unbalanced_paren").to_string(),
+        }
+    }
+}
+
+/// A problem found in the buffer, spanning chars `start..end`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub start: usize,
+    pub end: usize,
+    pub kind: LintKind,
+}
+
+/// Tables and views of the connected database, with the columns loaded so
+/// far. Names are compared case-insensitively; an empty schema (nothing
+/// loaded) turns the name checks off.
+#[derive(Clone, Debug, Default)]
+pub struct LintSchema {
+    /// (schema, object) → the object's name as written, and its column
+    /// names once loaded
+    objects: HashMap<(String, String), (String, Vec<String>)>,
+    /// Object name → the schemas that have it
+    by_name: HashMap<String, Vec<String>>,
+}
+
+impl LintSchema {
+    pub fn add_object(&mut self, schema: &str, name: &str) {
+        let key = (schema.to_lowercase(), name.to_lowercase());
+        if !self.objects.contains_key(&key) {
+            self.by_name.entry(key.1.clone()).or_default().push(key.0.clone());
+            self.objects.insert(key, (name.to_string(), Vec::new()));
+        }
+    }
+
+    pub fn set_columns<'a>(&mut self, schema: &str, name: &str, columns: impl IntoIterator<Item = &'a str>) {
+        self.add_object(schema, name);
+        let key = (schema.to_lowercase(), name.to_lowercase());
+        if let Some(object) = self.objects.get_mut(&key) {
+            object.1 = columns.into_iter().map(str::to_lowercase).collect();
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// The object a one or two part name refers to; a bare name prefers the
+    /// default schema
+    fn resolve(&self, parts: &[String]) -> Option<(String, String)> {
+        match parts {
+            [name] => {
+                let schemas = self.by_name.get(name)?;
+                let schema = schemas
+                    .iter()
+                    .find(|s| *s == "dbo" || *s == "main")
+                    .unwrap_or(&schemas[0]);
+                Some((schema.clone(), name.clone()))
+            }
+            [schema, name] => {
+                let key = (schema.clone(), name.clone());
+                self.objects.contains_key(&key).then_some(key)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A table named after FROM, JOIN, UPDATE or INSERT INTO
+struct TableRef {
+    /// Lowercased, unquoted name parts
+    parts: Vec<String>,
+    /// Token range of the name
+    first: usize,
+    last: usize,
+    /// UPDATE targets may be aliases of a table in the FROM clause
+    is_update_target: bool,
+    alias: Option<String>,
+}
+
+/// Lint `sql`, returning diagnostics sorted by position
+pub fn lint(sql: &str, dialect: DatabaseBackend, schema: &LintSchema) -> Vec<Diagnostic> {
+    let tsql = dialect == DatabaseBackend::SqlServer;
+    let tokens: Vec<Token> = tokenize(sql, dialect).into_iter().filter(|t| !t.is_comment()).collect();
+
+    let mut diagnostics = Vec::new();
+    let mut batch_start = 0;
+    for i in 0..=tokens.len() {
+        let go = tsql && tokens.get(i).is_some_and(|t| t.is("GO") && (i == 0 || t.newlines_before > 0));
+        if i == tokens.len() || go {
+            if batch_start < i {
+                Linter::new(sql, &tokens[batch_start..i], tsql, schema).run(&mut diagnostics);
+            }
+            batch_start = i + 1;
+        }
+    }
+    for start in Pairs::from_tokens(&tokens, dialect).unmatched {
+        diagnostics.push(Diagnostic { start, end: start + 1, kind: LintKind::UnbalancedParen });
+    }
+    diagnostics.sort_by_key(|d| (d.start, d.end));
+    diagnostics
+}
+
+struct Linter<'s, 't> {
+    sql: &'s str,
+    tokens: &'t [Token<'s>],
+    /// Parenthesis depth of each token (parentheses get their outer depth)
+    depth: Vec<usize>,
+    tsql: bool,
+    schema: &'t LintSchema,
+}
+
+impl<'s, 't> Linter<'s, 't> {
+    fn new(sql: &'s str, tokens: &'t [Token<'s>], tsql: bool, schema: &'t LintSchema) -> Self {
+        let mut depth = Vec::with_capacity(tokens.len());
+        let mut current = 0usize;
+        for token in tokens {
+            if token.is_punct(")") {
+                current = current.saturating_sub(1);
+            }
+            depth.push(current);
+            if token.is_punct("(") {
+                current += 1;
+            }
+        }
+        Self { sql, tokens, depth, tsql, schema }
+    }
+
+    fn run(&self, out: &mut Vec<Diagnostic>) {
+        self.missing_where(out);
+        self.equals_null(out);
+        self.implicit_joins(out);
+        if self.tsql {
+            self.select_star_in_procedures(out);
+            self.undeclared_variables(out);
+        }
+        if !self.schema.is_empty() {
+            self.unknown_names(out);
+        }
+    }
+
+    fn token_text(&self, i: usize) -> &'s str {
+        self.tokens.get(i).map_or("", |t| t.text)
+    }
+
+    fn is(&self, i: usize, word: &str) -> bool {
+        self.tokens.get(i).is_some_and(|t| t.is(word))
+    }
+
+    fn push(&self, out: &mut Vec<Diagnostic>, first: usize, last: usize, kind: LintKind) {
+        out.push(Diagnostic { start: self.tokens[first].start, end: self.tokens[last].end(), kind });
+    }
+
+    /// Whether token `i` is a word starting a statement at its depth
+    fn starts_statement(&self, i: usize) -> bool {
+        // `UPDATE OR REPLACE`, `INSERT OR REPLACE` in SQLite
+        self.tokens[i].is_any(STATEMENT_STARTS) && !(i > 0 && self.is(i - 1, "OR"))
+    }
+
+    /// Whether the UPDATE / DELETE at `i` names an action (`ON DELETE`,
+    /// `GRANT SELECT, UPDATE`) instead of starting a statement
+    fn is_action(&self, i: usize) -> bool {
+        i > 0 && (self.tokens[i - 1].is_any(ACTION_CONTEXT) || self.tokens[i - 1].is_punct(","))
+    }
+
+    /// UPDATE and DELETE statements with no WHERE clause
+    fn missing_where(&self, out: &mut Vec<Diagnostic>) {
+        for (i, token) in self.tokens.iter().enumerate() {
+            let kind = if token.is("UPDATE") {
+                LintKind::UpdateWithoutWhere
+            } else if token.is("DELETE") {
+                LintKind::DeleteWithoutWhere
+            } else {
+                continue;
+            };
+            let action = self.is_action(i);
+            let function = self.token_text(i + 1) == "(" || self.is(i + 1, "STATISTICS");
+            if action || function {
+                continue;
+            }
+
+            let depth = self.depth[i];
+            let (mut cases, mut sets) = (0, 0);
+            let mut has_where = false;
+            for j in i + 1..self.tokens.len() {
+                let t = &self.tokens[j];
+                if self.depth[j] < depth || (self.depth[j] == depth && t.is_punct(")")) {
+                    break;
+                }
+                if self.depth[j] > depth {
+                    continue;
+                }
+                if t.is_punct(";") {
+                    break;
+                } else if t.is("WHERE") {
+                    has_where = true;
+                    break;
+                } else if t.is("CASE") {
+                    cases += 1;
+                } else if t.is("END") && cases > 0 {
+                    cases -= 1;
+                } else if t.is("SET") {
+                    // UPDATE has one SET of its own
+                    sets += 1;
+                    if sets > usize::from(kind == LintKind::UpdateWithoutWhere) {
+                        break;
+                    }
+                } else if self.starts_statement(j) {
+                    break;
+                }
+            }
+            if !has_where {
+                self.push(out, i, i, kind);
+            }
+        }
+    }
+
+    /// `= NULL` in conditions (always false) and `<> NULL` / `!= NULL`
+    fn equals_null(&self, out: &mut Vec<Diagnostic>) {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if token.kind != TokenKind::Punct || !matches!(token.text, "=" | "<>" | "!=") || !self.is(i + 1, "NULL") {
+                continue;
+            }
+            // `SET x = NULL` assigns; `WHERE x = NULL` compares
+            let comparison = token.text != "="
+                || self.tokens[..i]
+                    .iter()
+                    .rev()
+                    .take_while(|t| !t.is_punct(";"))
+                    .find(|t| t.is_any(CONDITION_WORDS) || t.is_any(ASSIGNMENT_WORDS))
+                    .is_some_and(|t| t.is_any(CONDITION_WORDS));
+            if comparison {
+                self.push(out, i, i + 1, LintKind::EqualsNull);
+            }
+        }
+    }
+
+    /// Commas between the tables of a FROM clause
+    fn implicit_joins(&self, out: &mut Vec<Diagnostic>) {
+        for (i, token) in self.tokens.iter().enumerate() {
+            // `IS [NOT] DISTINCT FROM`, `EXTRACT(YEAR FROM d)`
+            if !token.is("FROM") || (i > 0 && self.is(i - 1, "DISTINCT")) {
+                continue;
+            }
+            let depth = self.depth[i];
+            for j in i + 1..self.tokens.len() {
+                let t = &self.tokens[j];
+                if self.depth[j] < depth || (self.depth[j] == depth && t.is_punct(")")) {
+                    break;
+                }
+                if self.depth[j] > depth {
+                    continue;
+                }
+                if t.is_punct(";") || t.is_any(FROM_END) || self.starts_statement(j) {
+                    break;
+                }
+                if t.is_punct(",") && !self.is_function_call(j + 1) && j + 1 < self.tokens.len() {
+                    self.push(out, j, j + 1, LintKind::ImplicitJoin);
+                }
+            }
+        }
+    }
+
+    /// Whether a (possibly qualified) name starting at `i` is called, like
+    /// the table-valued function in `FROM t, dbo.split(t.list)`
+    fn is_function_call(&self, mut i: usize) -> bool {
+        while self.tokens.get(i).is_some_and(|t| t.kind == TokenKind::Word) {
+            if self.token_text(i + 1) != "." {
+                return self.token_text(i + 1) == "(";
+            }
+            i += 2;
+        }
+        false
+    }
+
+    /// Token ranges of procedure headers (parameters) and bodies in the batch
+    fn procedures(&self) -> Vec<(usize, usize)> {
+        let mut found = Vec::new();
+        for (i, token) in self.tokens.iter().enumerate() {
+            if !(token.is("CREATE") || token.is("ALTER")) {
+                continue;
+            }
+            let mut j = i + 1;
+            if self.is(j, "OR") && self.is(j + 1, "ALTER") {
+                j += 2;
+            }
+            if !(self.is(j, "PROC") || self.is(j, "PROCEDURE")) {
+                continue;
+            }
+            // The body starts after the first AS outside parentheses
+            if let Some(body) = (j + 1..self.tokens.len()).find(|&k| self.depth[k] == 0 && self.is(k, "AS")) {
+                found.push((j + 1, body));
+            }
+        }
+        found
+    }
+
+    /// `SELECT *` in procedure bodies, where a changed table silently
+    /// changes the procedure's result
+    fn select_star_in_procedures(&self, out: &mut Vec<Diagnostic>) {
+        let Some(body) = self.procedures().first().map(|&(_, body)| body) else {
+            return;
+        };
+        for i in body + 1..self.tokens.len() {
+            if !self.tokens[i].is_punct("*") {
+                continue;
+            }
+            let prev = |n: usize| if i >= n { self.tokens[i - n] } else { self.tokens[i] };
+            let star = prev(1).is("SELECT")
+                || prev(1).is("DISTINCT")
+                || prev(1).is_punct(",")
+                || (prev(1).is_punct(".") && i >= 2)
+                || (prev(1).is_literal() && prev(2).is("TOP"));
+            // `EXISTS (SELECT * ...)` reads no columns
+            let exists = prev(1).is("SELECT") && prev(2).is_punct("(") && prev(3).is("EXISTS");
+            if star && !exists {
+                let first = if prev(1).is_punct(".") { i - 2 } else { i };
+                self.push(out, first, i, LintKind::SelectStar);
+            }
+        }
+    }
+
+    /// `@variables` not declared in the batch nor parameters of its procedure
+    fn undeclared_variables(&self, out: &mut Vec<Diagnostic>) {
+        let (Some(first), Some(last)) = (self.tokens.first(), self.tokens.last()) else {
+            return;
+        };
+        let text = char_slice(self.sql, first.start, last.end());
+        let mut declared: HashSet<String> =
+            extract_declared_variables(text).into_iter().map(|v| v.to_lowercase()).collect();
+
+        // Procedure and function parameters, and a function's RETURNS @table
+        let mut headers = self.procedures();
+        for (i, token) in self.tokens.iter().enumerate() {
+            if token.is("FUNCTION")
+                && i > 0
+                && (self.is(i - 1, "CREATE") || self.is(i - 1, "ALTER"))
+                && let Some(returns) = (i + 1..self.tokens.len()).find(|&k| self.depth[k] == 0 && self.is(k, "RETURNS"))
+            {
+                headers.push((i + 1, returns + 1));
+            }
+        }
+        for &(start, end) in &headers {
+            for token in &self.tokens[start..=end.min(self.tokens.len() - 1)] {
+                if token.text.starts_with('@') {
+                    declared.insert(token.text.to_lowercase());
+                }
+            }
+        }
+
+        for (i, token) in self.tokens.iter().enumerate() {
+            let name = token.text;
+            if token.kind != TokenKind::Word || !name.starts_with('@') || name.starts_with("@@") || name.len() < 2 {
+                continue;
+            }
+            if declared.contains(&name.to_lowercase()) || self.is_named_argument(i) {
+                continue;
+            }
+            self.push(out, i, i, LintKind::UndeclaredVariable(name.to_string()));
+        }
+    }
+
+    /// `@param = value` passed to EXEC names the procedure's parameter
+    fn is_named_argument(&self, i: usize) -> bool {
+        self.token_text(i + 1) == "="
+            && self.tokens[..i]
+                .iter()
+                .rev()
+                .find(|t| t.is_punct(";") || t.is_any(STATEMENT_STARTS) || t.is("SET"))
+                .is_some_and(|t| t.is("EXEC") || t.is("EXECUTE"))
+    }
+
+    /// The table name at token `i`, if it is a plain one or two part name
+    fn table_ref(&self, i: usize, is_update_target: bool) -> Option<TableRef> {
+        let mut parts = Vec::new();
+        let mut j = i;
+        loop {
+            let token = self.tokens.get(j)?;
+            parts.push(identifier(token)?);
+            if self.token_text(j + 1) != "." {
+                break;
+            }
+            j += 2;
+        }
+        if self.token_text(j + 1) == "(" {
+            return None;
+        }
+
+        let mut next = j + 1;
+        if self.is(next, "AS") {
+            next += 1;
+        }
+        let alias = self
+            .tokens
+            .get(next)
+            .filter(|t| !t.is_any(NOT_ALIAS) && !t.is_any(STATEMENT_STARTS))
+            .and_then(identifier);
+        Some(TableRef { parts, first: i, last: j, is_update_target, alias })
+    }
+
+    /// Tables missing from the schema, and `alias.column` naming a column
+    /// the aliased table doesn't have
+    fn unknown_names(&self, out: &mut Vec<Diagnostic>) {
+        // `USE other_db` makes the loaded schema the wrong one
+        if self.tokens.iter().any(|t| t.is("USE")) {
+            return;
+        }
+
+        // Names created in the batch: CTEs, CREATE TABLE / VIEW, SELECT INTO
+        let mut created: HashSet<String> = HashSet::new();
+        let mut refs = Vec::new();
+        for (i, token) in self.tokens.iter().enumerate() {
+            let prev = |n: usize| i.checked_sub(n).and_then(|k| self.tokens.get(k));
+            let prev_is = |n: usize, word: &str| prev(n).is_some_and(|t| t.is(word));
+
+            if let Some(name) = identifier(token)
+                && (self.is(i + 1, "AS") && self.token_text(i + 2) == "("
+                    || prev_is(1, "TABLE") && prev_is(2, "CREATE")
+                    || prev_is(1, "VIEW") && prev_is(2, "CREATE"))
+            {
+                created.insert(name);
+            }
+
+            let target = if token.is("FROM") || token.is("JOIN") {
+                Some(false)
+            } else if token.is("UPDATE") {
+                (!self.is_action(i)).then_some(true)
+            } else if token.is("INTO") {
+                let insert = ["INSERT", "MERGE", "REPLACE", "IGNORE", "ABORT", "FAIL", "ROLLBACK"]
+                    .iter()
+                    .any(|w| prev_is(1, w));
+                if !insert {
+                    // SELECT ... INTO new_table
+                    if let Some(table) = self.table_ref(i + 1, false) {
+                        created.extend(table.parts.last().cloned());
+                    }
+                    None
+                } else {
+                    Some(false)
+                }
+            } else {
+                None
+            };
+            if let Some(is_update_target) = target
+                && let Some(table) = self.table_ref(i + 1, is_update_target)
+            {
+                refs.push(table);
+            }
+        }
+
+        let aliases: HashSet<&str> = refs.iter().filter_map(|r| r.alias.as_deref()).collect();
+        let mut qualifiers: HashMap<String, Option<(String, String)>> = HashMap::new();
+        let mut in_refs: HashSet<usize> = HashSet::new();
+        for table in &refs {
+            in_refs.extend(table.first..=table.last);
+            let name = table.parts.last().map(String::as_str).unwrap_or_default();
+            let skipped = name.starts_with('#')
+                || name.starts_with('@')
+                || name.starts_with("sqlite_")
+                || table.parts.len() > 2
+                || table.parts.iter().any(String::is_empty)
+                || (table.parts.len() == 2 && SYSTEM_SCHEMAS.contains(&table.parts[0].as_str()))
+                || PSEUDO_TABLES.contains(&name)
+                || created.contains(name)
+                || (table.is_update_target && aliases.contains(name));
+            if skipped {
+                continue;
+            }
+            let resolved = self.schema.resolve(&table.parts);
+            if resolved.is_none() {
+                let display = char_slice(self.sql, self.tokens[table.first].start, self.tokens[table.last].end());
+                self.push(out, table.first, table.last, LintKind::UnknownTable(display.to_string()));
+                continue;
+            }
+            // A qualifier naming two different tables can't be checked
+            let mut qualify = |q: &str| {
+                qualifiers
+                    .entry(q.to_string())
+                    .and_modify(|known| {
+                        if *known != resolved {
+                            *known = None;
+                        }
+                    })
+                    .or_insert_with(|| resolved.clone());
+            };
+            qualify(name);
+            if let Some(alias) = &table.alias {
+                qualify(alias);
+            }
+        }
+
+        for i in 0..self.tokens.len() {
+            if in_refs.contains(&i) || self.token_text(i + 1) != "." || (i > 0 && self.token_text(i - 1) == ".") {
+                continue;
+            }
+            let (Some(qualifier), Some(column_token)) = (identifier(&self.tokens[i]), self.tokens.get(i + 2)) else {
+                continue;
+            };
+            let Some(column) = identifier(column_token) else {
+                continue;
+            };
+            if matches!(self.token_text(i + 3), "(" | ".") {
+                continue;
+            }
+            let Some(Some(key)) = qualifiers.get(&qualifier) else {
+                continue;
+            };
+            let (table, columns) = &self.schema.objects[key];
+            if !columns.is_empty() && !columns.contains(&column) {
+                self.push(
+                    out,
+                    i + 2,
+                    i + 2,
+                    LintKind::UnknownColumn { column: column_token.text.to_string(), table: table.clone() },
+                );
+            }
+        }
+    }
+}
+
+/// The lowercased name of a word or quoted identifier token
+fn identifier(token: &Token) -> Option<String> {
+    token.name().map(str::to_lowercase)
+}
+
+/// The text between char indices `start` and `end`
+fn char_slice(text: &str, start: usize, end: usize) -> &str {
+    let byte = |i: usize| text.char_indices().nth(i).map_or(text.len(), |(b, _)| b);
+    &text[byte(start)..byte(end)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(sql: &str, dialect: DatabaseBackend, schema: &LintSchema) -> Vec<LintKind> {
+        lint(sql, dialect, schema).into_iter().map(|d| d.kind).collect()
+    }
+
+    fn tsql(sql: &str) -> Vec<LintKind> {
+        kinds(sql, DatabaseBackend::SqlServer, &LintSchema::default())
+    }
+
+    #[test]
+    fn test_missing_where() {
+        assert_eq!(tsql("UPDATE t SET a = 1"), vec![LintKind::UpdateWithoutWhere]);
+        assert_eq!(tsql("DELETE FROM t\nSELECT 1 FROM u WHERE x = 1"), vec![LintKind::DeleteWithoutWhere]);
+        assert!(tsql("UPDATE t SET a = CASE WHEN b = 1 THEN 2 END WHERE id = 1").is_empty());
+        assert!(tsql("DELETE t FROM t JOIN u ON u.id = t.id WHERE u.x = 1").is_empty());
+        assert!(tsql("CREATE TABLE t (id INT REFERENCES u ON DELETE CASCADE ON UPDATE NO ACTION)").is_empty());
+        assert!(tsql("CREATE TRIGGER tr ON t AFTER DELETE, UPDATE AS IF UPDATE(a) PRINT 1").is_empty());
+
+        let diagnostics = lint("SELECT 1;\nDELETE FROM t;", DatabaseBackend::Sqlite, &LintSchema::default());
+        assert_eq!((diagnostics[0].start, diagnostics[0].end), (10, 16));
+    }
+
+    #[test]
+    fn test_equals_null_and_implicit_joins() {
+        assert_eq!(tsql("SELECT a FROM t WHERE b = NULL"), vec![LintKind::EqualsNull]);
+        assert_eq!(tsql("SELECT a FROM t WHERE b <> NULL"), vec![LintKind::EqualsNull]);
+        assert!(tsql("UPDATE t SET a = NULL, b = NULL WHERE c IS NULL").is_empty());
+        assert!(tsql("DECLARE @x INT = NULL SELECT @x = NULL").is_empty());
+
+        assert_eq!(tsql("SELECT * FROM a, b WHERE a.id = b.id"), vec![LintKind::ImplicitJoin]);
+        assert!(tsql("SELECT * FROM a JOIN b ON a.id = b.id WHERE a.x IN (1, 2)").is_empty());
+        assert!(kinds("SELECT * FROM t, json_each(t.data)", DatabaseBackend::Sqlite, &LintSchema::default()).is_empty());
+        assert!(tsql("SELECT a, b FROM t ORDER BY a, b").is_empty());
+    }
+
+    #[test]
+    fn test_select_star_in_procedures() {
+        assert!(tsql("SELECT * FROM t").is_empty());
+        let sql = "CREATE PROCEDURE p AS\nBEGIN\n  IF EXISTS (SELECT * FROM t) SELECT t.* FROM t\n  SELECT COUNT(*) FROM t\nEND";
+        assert_eq!(tsql(sql), vec![LintKind::SelectStar]);
+        // Comments and strings are not code
+        assert!(tsql("CREATE PROC p AS SELECT a FROM t -- SELECT *\nWHERE b = 'x = NULL'").is_empty());
+    }
+
+    #[test]
+    fn test_undeclared_variables() {
+        let sql = "DECLARE @a INT = 1\nSELECT @a, @b, @@ROWCOUNT\nEXEC p @arg = @a";
+        assert_eq!(tsql(sql), vec![LintKind::UndeclaredVariable("@b".to_string())]);
+        // Parameters are declared; each GO batch has its own variables
+        let sql = "CREATE PROCEDURE p @Id INT, @Name NVARCHAR(50) = NULL AS SELECT @id, @name\nGO\nSELECT @Id";
+        assert_eq!(tsql(sql), vec![LintKind::UndeclaredVariable("@Id".to_string())]);
+        // SQLite parameters are bound, never declared
+        assert!(kinds("SELECT @a", DatabaseBackend::Sqlite, &LintSchema::default()).is_empty());
+    }
+
+    #[test]
+    fn test_unbalanced_parens() {
+        let diagnostics = lint("SELECT COUNT(*) FROM t WHERE (a = ')'", DatabaseBackend::Sqlite, &LintSchema::default());
+        assert_eq!(diagnostics, vec![Diagnostic { start: 29, end: 30, kind: LintKind::UnbalancedParen }]);
+        assert_eq!(tsql("SELECT 1)"), vec![LintKind::UnbalancedParen]);
+        assert!(tsql("SELECT (1) -- (").is_empty());
+    }
+
+    #[test]
+    fn test_unknown_names() {
+        let mut schema = LintSchema::default();
+        schema.set_columns("dbo", "Orders", ["Id", "CustomerId", "Total"]);
+        schema.add_object("sales", "Customers");
+        let check = |sql: &str| kinds(sql, DatabaseBackend::SqlServer, &schema);
+
+        assert!(check("SELECT o.Total FROM Orders o JOIN sales.Customers c ON c.Id = o.CustomerId WHERE 1 = 1").is_empty());
+        assert_eq!(check("SELECT * FROM Order WHERE 1 = 1"), vec![LintKind::UnknownTable("Order".to_string())]);
+        assert_eq!(
+            check("SELECT o.Totl FROM [dbo].[Orders] AS o WHERE 1 = 1"),
+            vec![LintKind::UnknownColumn { column: "Totl".to_string(), table: "Orders".to_string() }]
+        );
+        // Temp tables, CTEs, functions, aliases as UPDATE targets and other databases
+        assert!(check("WITH x AS (SELECT 1 AS a) SELECT * FROM x, #tmp WHERE 1 = 1").len() == 1);
+        assert!(check("SELECT * FROM dbo.fn(1) f JOIN db.dbo.t ON 1 = 1 WHERE 1 = 1").is_empty());
+        assert!(check("UPDATE o SET Total = 0 FROM Orders o WHERE o.Id = 1").is_empty());
+        assert!(check("SELECT a INTO NewTable FROM Orders WHERE 1 = 1 SELECT * FROM NewTable WHERE 1 = 1").is_empty());
+        // Nothing loaded: nothing to compare with
+        assert!(tsql("SELECT * FROM anything WHERE 1 = 1").is_empty());
+    }
+}
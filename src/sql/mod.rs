@@ -1,5 +1,9 @@
 //! SQL utilities module
 
 mod formatter;
+pub mod lexer;
+pub mod lint;
+pub mod pairs;
+pub mod statements;
 
 pub use formatter::format_sql_query;
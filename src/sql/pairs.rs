@@ -0,0 +1,110 @@
+//! Matching pairs: `(` and `)`, and `BEGIN` / `CASE` and their `END`
+//!
+//! Used by the editor's `%` motion and partner highlighting, and by the
+//! linter to flag parentheses without a partner. Strings, comments and
+//! quoted names are skipped. Parentheses and keyword blocks are matched
+//! separately, so a missing `)` inside a procedure body doesn't break its
+//! `BEGIN ... END`; neither pairs across a T-SQL `GO` line.
+
+use std::ops::Range;
+
+use crate::db::DatabaseBackend;
+
+use super::lexer::{tokenize, Token};
+use super::statements::opens_block;
+
+/// The pairs of a text, as char ranges
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Pairs {
+    /// Both ends of every pair, each with its partner, sorted by position
+    ends: Vec<(Range<usize>, Range<usize>)>,
+    /// Positions of parentheses without a partner, sorted
+    pub unmatched: Vec<usize>,
+}
+
+impl Pairs {
+    pub fn find(sql: &str, dialect: DatabaseBackend) -> Self {
+        let tokens: Vec<Token> = tokenize(sql, dialect).into_iter().filter(|t| !t.is_comment()).collect();
+        Self::from_tokens(&tokens, dialect)
+    }
+
+    /// Pairs of tokens without comments
+    pub fn from_tokens(tokens: &[Token], dialect: DatabaseBackend) -> Self {
+        let tsql = dialect == DatabaseBackend::SqlServer;
+        let mut pairs = Pairs::default();
+        let (mut parens, mut blocks): (Vec<Range<usize>>, Vec<Range<usize>>) = (Vec::new(), Vec::new());
+        for (i, token) in tokens.iter().enumerate() {
+            let range = token.start..token.end();
+            if tsql && token.is("GO") && (i == 0 || token.newlines_before > 0) {
+                pairs.unmatched.extend(parens.drain(..).map(|open| open.start));
+                blocks.clear();
+            } else if token.is_punct("(") {
+                parens.push(range);
+            } else if token.is_punct(")") {
+                match parens.pop() {
+                    Some(open) => pairs.add(open, range),
+                    None => pairs.unmatched.push(range.start),
+                }
+            } else if opens_block(tokens, i) {
+                blocks.push(range);
+            } else if token.is("END") {
+                // SQLite's `END` alone is `COMMIT`, so a spare one isn't flagged
+                if let Some(open) = blocks.pop() {
+                    pairs.add(open, range);
+                }
+            }
+        }
+        pairs.unmatched.extend(parens.into_iter().map(|open| open.start));
+        pairs.unmatched.sort_unstable();
+        pairs.ends.sort_by_key(|(end, _)| end.start);
+        pairs
+    }
+
+    fn add(&mut self, open: Range<usize>, close: Range<usize>) {
+        self.ends.push((open.clone(), close.clone()));
+        self.ends.push((close, open));
+    }
+
+    /// The end of a pair under `pos`, and its partner
+    pub fn at(&self, pos: usize) -> Option<(Range<usize>, Range<usize>)> {
+        let index = self.ends.partition_point(|(end, _)| end.start <= pos).checked_sub(1)?;
+        let (end, partner) = &self.ends[index];
+        end.contains(&pos).then(|| (end.clone(), partner.clone()))
+    }
+
+    /// Like `at`, but when `pos` isn't on a pair, the first end after it
+    /// that starts before `limit` (`%` looks ahead on the line, as in Vim)
+    pub fn at_or_after(&self, pos: usize, limit: usize) -> Option<(Range<usize>, Range<usize>)> {
+        self.at(pos).or_else(|| {
+            let index = self.ends.partition_point(|(end, _)| end.start < pos);
+            self.ends.get(index).filter(|(end, _)| end.start < limit).cloned()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs() {
+        let tsql = DatabaseBackend::SqlServer;
+        let sql = "IF x BEGIN SELECT CASE WHEN (a) = ')' THEN 1 END END";
+        let pairs = Pairs::find(sql, tsql);
+        assert_eq!(pairs.at(5), Some((5..10, 49..52)));
+        assert_eq!(pairs.at(51), Some((49..52, 5..10)));
+        assert_eq!(pairs.at(20), Some((18..22, 45..48)));
+        assert_eq!(pairs.at(28), Some((28..29, 30..31)));
+        assert_eq!(pairs.at(35), None);
+        assert!(pairs.unmatched.is_empty());
+        // From before the pairs, `%` goes to the first one on the line
+        assert_eq!(pairs.at_or_after(0, 4), None);
+        assert_eq!(pairs.at_or_after(0, 52).map(|(end, _)| end), Some(5..10));
+        // Transactions aren't blocks; parentheses don't pair across GO
+        let pairs = Pairs::find("BEGIN TRAN\nSELECT (1\nGO\nSELECT 2)\nCOMMIT", tsql);
+        assert_eq!(pairs, Pairs { ends: Vec::new(), unmatched: vec![18, 32] });
+        let pairs = Pairs::find("BEGIN;\nSELECT ((1);\nEND;", DatabaseBackend::Sqlite);
+        assert_eq!(pairs.unmatched, vec![14]);
+        assert_eq!(pairs.at(15), Some((15..16, 17..18)));
+    }
+}
@@ -0,0 +1,360 @@
+//! Statement boundaries, and the statements that write: used to keep
+//! read-only connections read-only and to confirm destructive statements
+//! before they run
+
+use crate::db::DatabaseBackend;
+
+use super::lexer::{is_keyword, tokenize, Token, TokenKind};
+
+/// Words before UPDATE / DELETE / INSERT when they name an action rather than
+/// start a statement (`ON DELETE CASCADE`, `AFTER UPDATE`, `GRANT DELETE`,
+/// `THEN UPDATE` in MERGE)
+pub(super) const ACTION_CONTEXT: &[&str] = &["ON", "AFTER", "BEFORE", "OF", "FOR", "INSTEAD", "THEN", "GRANT", "DENY", "REVOKE"];
+
+/// Statements that change data
+const DML: &[&str] = &["INSERT", "UPDATE", "DELETE", "MERGE", "REPLACE"];
+
+/// Statements that change the schema or permissions
+const DDL: &[&str] = &["CREATE", "ALTER", "DROP", "TRUNCATE", "GRANT", "REVOKE", "DENY"];
+
+/// T-SQL procedure calls and dynamic SQL, which may write
+const EXEC: &[&str] = &["EXEC", "EXECUTE"];
+
+/// T-SQL server maintenance, counted with DDL: backups write files,
+/// RESTORE replaces a database and DBCC can repair (and lose) data
+const MAINTENANCE: &[&str] = &["BACKUP", "RESTORE", "DBCC"];
+
+/// T-SQL statements that aren't keywords to the highlighter but don't
+/// call a procedure when they open a batch either (`BULK INSERT` is
+/// reported as its INSERT)
+const NOT_PROCEDURES: &[&str] = &["WAITFOR", "GOTO", "REVERT", "SAVE", "CHECKPOINT", "READTEXT", "BULK"];
+
+/// Statements asked about on connections that confirm destructive statements
+/// (MERGE can `WHEN MATCHED THEN DELETE`)
+const DESTRUCTIVE: &[&str] = &["DROP", "TRUNCATE", "ALTER", "DELETE", "UPDATE", "MERGE", "RESTORE"];
+
+/// Words skipped between a statement's verb and the object it writes
+const TARGET_NOISE: &[&str] = &["INTO", "FROM", "TABLE", "IF", "NOT", "EXISTS", "OR", "IGNORE", "ABORT", "FAIL"];
+
+/// Words after BEGIN that start a transaction rather than a block
+const TRANSACTION_WORDS: &[&str] = &["TRAN", "TRANSACTION", "DISTRIBUTED", "DEFERRED", "IMMEDIATE", "EXCLUSIVE"];
+
+/// Where a statement is, in chars: `start..end` is its text without the
+/// whitespace around it, and `separator_end` is past the `;` that ends it
+/// (or at the start of the blank or `GO` line after it)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StatementSpan {
+    pub start: usize,
+    pub end: usize,
+    pub separator_end: usize,
+}
+
+/// A piece of a script found by `split`
+struct Piece {
+    span: StatementSpan,
+    /// Times to run it (`GO 3`)
+    repeat: usize,
+    /// Whether it holds more than comments
+    code: bool,
+}
+
+/// Cut `sql` at T-SQL `GO` lines, and at `;` and blank lines when asked to.
+/// A `;` inside `BEGIN ... END` or `CASE ... END` (a trigger or procedure
+/// body) doesn't end a piece.
+fn split(sql: &str, dialect: DatabaseBackend, at_semicolons: bool, at_blank_lines: bool) -> Vec<Piece> {
+    let tsql = dialect == DatabaseBackend::SqlServer;
+    let tokens = tokenize(sql, dialect);
+    let chars: Vec<char> = sql.chars().collect();
+    let line_start = |pos: usize| (0..pos).rev().find(|&j| chars[j] == '\n').map_or(0, |j| j + 1);
+    let next_line_start = |pos: usize| (pos..chars.len()).find(|&j| chars[j] == '\n').map_or(chars.len(), |j| j + 1);
+
+    let mut pieces = Vec::new();
+    let mut close = |from: Option<usize>, to: usize, separator_end: usize, repeat: usize| {
+        if let Some(from) = from
+            && from < to
+        {
+            let (start, end) = (tokens[from].start, tokens[to - 1].end());
+            let code = tokens[from..to].iter().any(|t| !t.is_comment());
+            pieces.push(Piece { span: StatementSpan { start, end, separator_end }, repeat, code });
+        }
+    };
+    let (mut first, mut depth, mut i) = (None, 0usize, 0);
+    while let Some(token) = tokens.get(i) {
+        let starts_line = i == 0 || token.newlines_before > 0;
+        if tsql && starts_line && token.is("GO") {
+            // Alone on its line, with an optional count
+            let count = tokens.get(i + 1).filter(|t| t.kind == TokenKind::Number && t.newlines_before == 0);
+            let after = i + 1 + usize::from(count.is_some());
+            if tokens.get(after).is_none_or(|t| t.newlines_before > 0 || t.kind == TokenKind::LineComment) {
+                let repeat = count.and_then(|t| t.text.parse().ok()).unwrap_or(1);
+                close(first, i, line_start(token.start), repeat);
+                (first, depth, i) = (None, 0, after);
+                continue;
+            }
+        }
+        if at_blank_lines && i > 0 && token.newlines_before >= 2 {
+            close(first, i, next_line_start(tokens[i - 1].end()), 1);
+            (first, depth) = (None, 0);
+        }
+        if at_semicolons && depth == 0 && token.is_punct(";") {
+            close(first, i, token.end(), 1);
+            first = None;
+            i += 1;
+            continue;
+        }
+        if opens_block(&tokens, i) {
+            depth += 1;
+        } else if token.is("END") {
+            depth = depth.saturating_sub(1);
+        }
+        first = first.or(Some(i));
+        i += 1;
+    }
+    close(first, tokens.len(), chars.len(), 1);
+    pieces
+}
+
+/// Whether the token at `i` opens a block closed by `END`: `CASE`, or a
+/// `BEGIN` that doesn't start a transaction (`BEGIN TRAN`, SQLite `BEGIN;`)
+pub fn opens_block(tokens: &[Token], i: usize) -> bool {
+    let next = tokens.get(i + 1);
+    tokens[i].is("CASE") || (tokens[i].is("BEGIN") && !next.is_some_and(|t| t.is_any(TRANSACTION_WORDS) || t.is_punct(";")))
+}
+
+/// The statements of the editor buffer, comments included: each ends at a
+/// `;`, a blank line or a T-SQL `GO` line
+pub fn statement_spans(sql: &str, dialect: DatabaseBackend) -> Vec<StatementSpan> {
+    split(sql, dialect, true, true).into_iter().map(|piece| piece.span).collect()
+}
+
+/// What to send to the server, one after another: the batches between `GO`
+/// lines on SQL Server (`GO 3` runs a batch three times), and each statement
+/// on SQLite, which prepares one at a time. Pieces of only comments are left
+/// out.
+pub fn batches(sql: &str, dialect: DatabaseBackend) -> Vec<&str> {
+    let offsets: Vec<usize> = sql.char_indices().map(|(b, _)| b).chain([sql.len()]).collect();
+    split(sql, dialect, dialect == DatabaseBackend::Sqlite, false)
+        .into_iter()
+        .filter(|piece| piece.code)
+        .flat_map(|piece| std::iter::repeat_n(&sql[offsets[piece.span.start]..offsets[piece.span.end]], piece.repeat))
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteKind {
+    Dml,
+    Ddl,
+    /// `EXEC` / `EXECUTE`: what it writes can't be known from the text
+    Exec,
+}
+
+/// A statement that writes, found in a batch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WriteStatement {
+    /// The statement's verb, uppercased (`DELETE`, `DROP`, ...); `SELECT INTO`
+    /// is reported as `INTO`
+    pub verb: String,
+    pub kind: WriteKind,
+    /// Char index of the verb
+    pub start: usize,
+}
+
+impl WriteStatement {
+    pub fn is_destructive(&self) -> bool {
+        DESTRUCTIVE.contains(&self.verb.as_str())
+    }
+}
+
+/// The statements of `sql` that write to the database. Writes to T-SQL
+/// temp tables and table variables (`#t`, `@t`) and to SQLite TEMP tables
+/// are left out: they're allowed on read-only connections and harmless.
+/// T-SQL `EXEC` is reported too, since the procedure it runs may write, and
+/// so is a batch that opens with a bare name (`sp_rename 'a', 'b'`), which
+/// T-SQL runs as a procedure call.
+pub fn write_statements(sql: &str, dialect: DatabaseBackend) -> Vec<WriteStatement> {
+    let tsql = dialect == DatabaseBackend::SqlServer;
+    let tokens: Vec<Token> = tokenize(sql, dialect).into_iter().filter(|t| !t.is_comment()).collect();
+    let is = |i: usize, word: &str| tokens.get(i).is_some_and(|t| t.is(word));
+    let text = |i: usize| tokens.get(i).map_or("", |t| t.text);
+
+    let mut writes = Vec::new();
+    let mut batch_start = true;
+    for (i, token) in tokens.iter().enumerate() {
+        let prev = i.checked_sub(1).and_then(|p| tokens.get(p));
+        if tsql && token.is("GO") && (i == 0 || token.newlines_before > 0) {
+            batch_start = true;
+            continue;
+        }
+        // `GO 3`
+        if token.kind == TokenKind::Number && prev.is_some_and(|p| p.is("GO")) {
+            continue;
+        }
+        let first = std::mem::take(&mut batch_start);
+        let bare_call = first
+            && tsql
+            && text(i + 1) != ":"
+            && match token.kind {
+                TokenKind::QuotedName => true,
+                TokenKind::Word => {
+                    !token.text.starts_with('@')
+                        && !is_keyword(token.text, dialect)
+                        && ![DML, DDL, EXEC, MAINTENANCE, NOT_PROCEDURES].iter().any(|words| token.is_any(words))
+                }
+                _ => false,
+            };
+        if bare_call {
+            writes.push(WriteStatement { verb: "EXEC".to_string(), kind: WriteKind::Exec, start: token.start });
+            continue;
+        }
+        if token.kind != TokenKind::Word {
+            continue;
+        }
+        let action = prev.is_some_and(|p| p.is_any(ACTION_CONTEXT) || p.is_punct(","));
+        let kind = if token.is_any(DML) {
+            // `UPDATE(col)` in triggers, SQLite's `replace()`, `INSERT OR REPLACE`
+            if action || text(i + 1) == "(" || prev.is_some_and(|p| p.is("OR")) {
+                continue;
+            }
+            WriteKind::Dml
+        } else if token.is_any(DDL) {
+            // `CREATE OR ALTER`, `WITH GRANT OPTION`
+            if prev.is_some_and(|p| p.is("OR") || p.is("WITH")) || (action && !token.is("GRANT")) {
+                continue;
+            }
+            WriteKind::Ddl
+        } else if tsql && token.is_any(EXEC) {
+            // `GRANT EXECUTE`, `WITH EXECUTE AS OWNER`, `EXECUTE AS USER`
+            if action || prev.is_some_and(|p| p.is("WITH")) || is(i + 1, "AS") {
+                continue;
+            }
+            WriteKind::Exec
+        } else if tsql && token.is_any(MAINTENANCE) {
+            WriteKind::Ddl
+        } else if token.is("INTO") {
+            // SELECT ... INTO creates a table
+            let select_into = prev.is_some_and(|p| !p.is_any(DML) && !p.is_any(TARGET_NOISE))
+                && tokens[..i].iter().rev().take_while(|t| !t.is_punct(";")).any(|t| t.is("SELECT"))
+                && !tokens[..i].iter().rev().take_while(|t| !t.is_punct(";")).any(|t| t.is("FETCH") || t.is_any(DML));
+            if !select_into {
+                continue;
+            }
+            WriteKind::Ddl
+        } else {
+            continue;
+        };
+
+        // The object written: skip noise words and `TOP (n)`
+        let mut j = i + 1;
+        loop {
+            if is(j, "TOP") && text(j + 1) == "(" {
+                j += 2;
+                while j < tokens.len() && text(j) != ")" {
+                    j += 1;
+                }
+                j += 1;
+            } else if tokens.get(j).is_some_and(|t| t.is_any(TARGET_NOISE)) {
+                j += 1;
+            } else {
+                break;
+            }
+        }
+        let temporary = kind != WriteKind::Exec
+            && (text(j).starts_with('#') || text(j).starts_with('@') || is(j, "TEMP") || is(j, "TEMPORARY"));
+        if !temporary {
+            writes.push(WriteStatement { verb: token.text.to_uppercase(), kind, start: token.start });
+        }
+    }
+    writes
+}
+
+/// The distinct verbs of `writes`, in order, for messages
+pub fn verbs<'a>(writes: impl IntoIterator<Item = &'a WriteStatement>) -> Vec<&'a str> {
+    let mut verbs: Vec<&str> = Vec::new();
+    for write in writes {
+        if !verbs.contains(&write.verb.as_str()) {
+            verbs.push(&write.verb);
+        }
+    }
+    verbs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writes(sql: &str) -> Vec<String> {
+        write_statements(sql, DatabaseBackend::SqlServer).into_iter().map(|w| w.verb).collect()
+    }
+
+    #[test]
+    fn test_write_statements() {
+        assert!(writes("SELECT * FROM t WHERE a = 'DROP TABLE t' -- DELETE FROM t").is_empty());
+        assert_eq!(writes("DELETE FROM t WHERE a = 1; TRUNCATE TABLE u"), vec!["DELETE", "TRUNCATE"]);
+        assert_eq!(writes("CREATE OR ALTER PROCEDURE p AS UPDATE t SET a = 1"), vec!["CREATE", "UPDATE"]);
+        assert_eq!(writes("SELECT a INTO archive FROM t"), vec!["INTO"]);
+        assert_eq!(writes("GRANT SELECT, UPDATE ON t TO bob"), vec!["GRANT"]);
+        assert!(writes("CREATE TABLE t (a INT REFERENCES u ON DELETE CASCADE)").len() == 1);
+
+        // Temp tables, table variables and cursors
+        assert!(writes("CREATE TABLE #t (a INT) INSERT INTO #t SELECT 1 DELETE TOP (5) FROM @v DROP TABLE IF EXISTS #t").is_empty());
+        assert!(writes("SELECT a INTO #t FROM u FETCH NEXT FROM c INTO @a").is_empty());
+        let sqlite = write_statements("CREATE TEMP TABLE x (a); INSERT OR REPLACE INTO t VALUES (replace('a', 'b', 'c'))", DatabaseBackend::Sqlite);
+        assert_eq!(verbs(&sqlite), vec!["INSERT"]);
+        assert!(!sqlite[0].is_destructive());
+
+        // Procedures may write, whatever their name; MERGE can delete
+        assert_eq!(writes("EXEC dbo.purge_orders; EXECUTE sp_executesql @sql; EXEC @rc = #p"), vec!["EXEC", "EXECUTE", "EXEC"]);
+        assert!(writes("GRANT EXECUTE ON p TO bob").iter().all(|verb| verb == "GRANT"));
+        assert!(writes("EXECUTE AS USER = 'bob'; SELECT 1; REVERT").is_empty());
+        assert!(write_statements("SELECT exec FROM t", DatabaseBackend::Sqlite).is_empty());
+        let merge = write_statements("MERGE t USING s ON t.id = s.id WHEN MATCHED THEN DELETE;", DatabaseBackend::SqlServer);
+        assert_eq!(verbs(&merge), vec!["MERGE"]);
+        assert!(merge[0].is_destructive());
+
+        // A batch may call a procedure without EXEC
+        assert_eq!(writes("sp_rename 'a', 'b'"), vec!["EXEC"]);
+        assert_eq!(writes("-- nightly\ndbo.purge_orders\nGO\n[dbo].[purge_logs] 30\nGO 2\nSELECT 1"), vec!["EXEC", "EXEC"]);
+        assert!(writes("SELECT 1\nGO\nDECLARE @a INT\nGO\nWAITFOR DELAY '00:00:01'\nGO\nretry: SELECT 1").is_empty());
+        // Only at the start of a batch
+        assert!(writes("SELECT 1 sp_rename").is_empty());
+        assert!(write_statements("pragma_x", DatabaseBackend::Sqlite).is_empty());
+
+        // Server maintenance and bulk loads
+        assert_eq!(writes("BACKUP DATABASE Sales TO DISK = 'x.bak'"), vec!["BACKUP"]);
+        let restore = write_statements("RESTORE DATABASE Sales FROM DISK = 'x.bak'", DatabaseBackend::SqlServer);
+        assert_eq!(verbs(&restore), vec!["RESTORE"]);
+        assert!(restore[0].is_destructive());
+        assert_eq!(writes("DBCC CHECKDB (Sales, REPAIR_ALLOW_DATA_LOSS)"), vec!["DBCC"]);
+        assert_eq!(writes("BULK INSERT dbo.orders FROM 'orders.csv'"), vec!["INSERT"]);
+    }
+
+    #[test]
+    fn test_batches() {
+        let tsql = DatabaseBackend::SqlServer;
+        let script = "DECLARE @a INT = 1; SELECT @a\nGO\n-- only a comment\ngo 2\nPRINT 'GO'\n/*\nGO\n*/\nSELECT [go]\nGO -- done";
+        assert_eq!(batches(script, tsql), vec!["DECLARE @a INT = 1; SELECT @a", "PRINT 'GO'\n/*\nGO\n*/\nSELECT [go]"]);
+        assert_eq!(batches("SELECT 1\nGO 2", tsql), vec!["SELECT 1", "SELECT 1"]);
+
+        let sqlite = DatabaseBackend::Sqlite;
+        let script = "SELECT ';'; -- a\nCREATE TRIGGER t AFTER INSERT ON a BEGIN\n  UPDATE b SET n = CASE WHEN 1 THEN 2 END;\n  DELETE FROM c;\nEND;\nBEGIN; END;\n-- bye";
+        assert_eq!(
+            batches(script, sqlite),
+            vec![
+                "SELECT ';'",
+                "-- a\nCREATE TRIGGER t AFTER INSERT ON a BEGIN\n  UPDATE b SET n = CASE WHEN 1 THEN 2 END;\n  DELETE FROM c;\nEND",
+                "BEGIN",
+                "END",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statement_spans() {
+        let sql = "\n\nSELECT 1;\n\nSELECT 2\n\n\nSELECT 3";
+        let spans = statement_spans(sql, DatabaseBackend::Sqlite);
+        let texts: Vec<&str> = spans.iter().map(|s| &sql[s.start..s.end]).collect();
+        assert_eq!(texts, vec!["SELECT 1", "SELECT 2", "SELECT 3"]);
+        assert_eq!(spans[0].separator_end, 11);
+        assert_eq!(spans[1].separator_end, sql.find("\n\n\n").unwrap() + 1);
+    }
+}
@@ -1,10 +1,11 @@
 //! Layout management
 
-use crate::app::{App, ActivePanel, SPINNER_FRAMES};
-use crate::ui::{DefaultTheme, draw_query_editor, draw_results_table, draw_schema_explorer, draw_history_panel, draw_completion_popup};
+use crate::app::{App, ActivePanel, InputMode, KeyContext, Keymap, SPINNER_FRAMES};
+use crate::ui::{ActiveTheme, draw_buffer_tabs, draw_query_editor, draw_results_table, draw_schema_explorer, draw_history_panel, draw_completion_popup};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph, Clear};
 use rust_i18n::t;
+use unicode_width::UnicodeWidthStr;
 
 /// Draw the main layout
 pub fn draw_layout(f: &mut Frame, app: &mut App, area: Rect) {
@@ -42,91 +43,88 @@ fn draw_header(f: &mut Frame, app: &App, area: Rect) {
     // Logo/Title
     let logo = Paragraph::new(vec![
         Line::from(vec![
-            Span::styled("╔═════════════════╗", DefaultTheme::title()),
+            Span::styled("╔═════════════════╗", ActiveTheme::title()),
         ]),
         Line::from(vec![
-            Span::styled("║ ", DefaultTheme::title()),
-            Span::styled("SQL Terminal UI ", Style::default().fg(DefaultTheme::TEXT)),
-            Span::styled("║", DefaultTheme::title()),
+            Span::styled("║ ", ActiveTheme::title()),
+            Span::styled("SQL Terminal UI ", Style::default().fg(ActiveTheme::colors().text)),
+            Span::styled("║", ActiveTheme::title()),
         ]),
         Line::from(vec![
-            Span::styled("╚═════════════════╝", DefaultTheme::title()),
+            Span::styled("╚═════════════════╝", ActiveTheme::title()),
         ]),
     ])
-    .style(DefaultTheme::header());
+    .style(ActiveTheme::header());
     f.render_widget(logo, header_chunks[0]);
 
     // Connection info
     let conn_info = if let Some(ref db) = app.db {
         let database = db.database_name().replace("Evermart", "Checkout");
         let backend_label = db.backend().to_string();
+        let mut spans = vec![
+            Span::styled("● ", ActiveTheme::success()),
+            Span::styled(database, ActiveTheme::normal_text()),
+            Span::styled(" · ", ActiveTheme::dim_text()),
+            Span::styled(backend_label, ActiveTheme::dim_text()),
+        ];
+        // SSH tunnel the connection goes through
+        if let Some((target, alive)) = db.tunnel_status() {
+            spans.push(Span::styled(" · ", ActiveTheme::dim_text()));
+            if alive {
+                spans.push(Span::styled(format!("⇄ ssh {}", target), ActiveTheme::success()));
+            } else {
+                spans.push(Span::styled(format!("⇄ ssh {} ", target), ActiveTheme::error()));
+                spans.push(Span::styled(t!("tunnel_down").to_string(), ActiveTheme::error()));
+            }
+        }
+        // Connection tag, so the environment is always in sight
+        if let Some(conn) = &app.current_connection {
+            spans.splice(0..0, [
+                Span::styled(format!(" {} ", conn.tag_label()), ActiveTheme::tag_badge(conn.tag_color())),
+                Span::raw(" "),
+                Span::styled(conn.name.clone(), Style::default().fg(ActiveTheme::tag(conn.tag_color()))),
+                Span::styled(" · ", ActiveTheme::dim_text()),
+            ]);
+        }
         Paragraph::new(vec![
             Line::from(""),
-            Line::from(vec![
-                Span::styled("● ", DefaultTheme::success()),
-                Span::styled(database, DefaultTheme::normal_text()),
-                Span::styled(" · ", DefaultTheme::dim_text()),
-                Span::styled(backend_label, DefaultTheme::dim_text()),
-            ]),
+            Line::from(spans),
             Line::from(""),
         ])
     } else {
         Paragraph::new(vec![
             Line::from(""),
             Line::from(vec![
-                Span::styled("○ ", DefaultTheme::dim_text()),
-                Span::styled(t!("disconnected").to_string(), DefaultTheme::dim_text()),
+                Span::styled("○ ", ActiveTheme::dim_text()),
+                Span::styled(t!("disconnected").to_string(), ActiveTheme::dim_text()),
             ]),
             Line::from(""),
         ])
     }
-    .style(DefaultTheme::header());
+    .style(ActiveTheme::header());
     f.render_widget(conn_info, header_chunks[1]);
 
     // Quick hints (instead of mode indicator)
     let hints = Paragraph::new(vec![
         Line::from(""),
         Line::from(vec![
-            Span::styled("Ctrl+E", DefaultTheme::info()),
-            Span::styled(format!(":{} ", t!("execute")), DefaultTheme::dim_text()),
-            Span::styled("F1", DefaultTheme::info()),
-            Span::styled(format!(":{} ", t!("help")), DefaultTheme::dim_text()),
+            Span::styled("Ctrl+E", ActiveTheme::info()),
+            Span::styled(format!(":{} ", t!("execute")), ActiveTheme::dim_text()),
+            Span::styled("F1", ActiveTheme::info()),
+            Span::styled(format!(":{} ", t!("help")), ActiveTheme::dim_text()),
         ]),
         Line::from(""),
     ])
-    .style(DefaultTheme::header())
+    .style(ActiveTheme::header())
     .alignment(Alignment::Right);
     f.render_widget(hints, header_chunks[2]);
 }
 
 /// Draw main content area
 fn draw_content(f: &mut Frame, app: &mut App, area: Rect) {
-    // Horizontal split: left (query + results), right (schema + history)
-    let h_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage(70),  // Main area
-            Constraint::Percentage(30),  // Side panels
-        ])
-        .split(area);
-
-    // Left side: Query editor + Results
-    let left_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(60),  // Query editor
-            Constraint::Percentage(40),  // Results
-        ])
-        .split(h_chunks[0]);
-
-    // Right side: Schema explorer + History
-    let right_chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage(60),  // Schema explorer
-            Constraint::Percentage(40),  // History
-        ])
-        .split(h_chunks[1]);
+    // Panel sizes, visibility and zoom come from the layout settings
+    app.content_area = area;
+    let rects = app.panel_rects();
 
     // Draw each panel - query editor needs mutable access for scroll updates
     let is_query_active = app.active_panel == ActivePanel::QueryEditor;
@@ -134,14 +132,38 @@ fn draw_content(f: &mut Frame, app: &mut App, area: Rect) {
     let is_schema_active = app.active_panel == ActivePanel::SchemaExplorer;
     let is_history_active = app.active_panel == ActivePanel::History;
 
-    draw_query_editor(f, app, left_chunks[0], is_query_active);
-    draw_results_table(f, app, left_chunks[1], is_results_active);
-    draw_schema_explorer(f, app, right_chunks[0], is_schema_active);
-    draw_history_panel(f, app, right_chunks[1], is_history_active);
-    
+    // Tab bar above the editor, only once there is more than one buffer
+    let editor_area = match rects.editor {
+        Some(editor) if app.buffer_count() > 1 => {
+            let editor_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(3)])
+                .split(editor);
+            draw_buffer_tabs(f, app, editor_chunks[0]);
+            Some(editor_chunks[1])
+        }
+        editor => editor,
+    };
+
+    if let Some(editor) = editor_area {
+        draw_query_editor(f, app, editor, is_query_active);
+    }
+    if let Some(results) = rects.results {
+        draw_results_table(f, app, results, is_results_active);
+    }
+    if let Some(schema) = rects.schema {
+        draw_schema_explorer(f, app, schema, is_schema_active);
+    }
+    if let Some(history) = rects.history {
+        draw_history_panel(f, app, history, is_history_active);
+    }
+
     // Draw completion popup over the query editor (must be after query editor)
-    if is_query_active && app.completion.visible {
-        draw_completion_popup(f, app, left_chunks[0]);
+    if let Some(editor) = editor_area
+        && is_query_active
+        && app.completion.visible
+    {
+        draw_completion_popup(f, app, editor);
     }
 }
 
@@ -155,43 +177,61 @@ fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
         ])
         .split(area);
 
+    // Command line replaces the message area while typing a : command or a search
+    if app.input_mode == InputMode::Command {
+        let prompt = match app.search_prompt {
+            Some(search) if search.forward => '/',
+            Some(_) => '?',
+            None => ':',
+        };
+        let line = format!("{}{}", prompt, app.command_buffer);
+        let cursor_x = chunks[0].x + line.width() as u16;
+        f.render_widget(Paragraph::new(line).style(ActiveTheme::status_bar()), chunks[0]);
+        f.set_cursor(cursor_x.min(chunks[0].right().saturating_sub(1)), chunks[0].y);
+    } else {
+        draw_status_message(f, app, chunks[0]);
+    }
+
+    // Status info
+    let status_info = format!(
+        " {} ",
+        app.status
+    );
+    let status = Paragraph::new(status_info)
+        .style(ActiveTheme::status_bar())
+        .alignment(Alignment::Center);
+    f.render_widget(status, chunks[1]);
+}
+
+/// Draw the error/success message (or a hint) on the left of the status bar
+fn draw_status_message(f: &mut Frame, app: &App, area: Rect) {
     // Messages (error or success)
     let message = if let Some(ref err) = app.error {
         Paragraph::new(Span::styled(
             format!("❌ {}", err),
-            DefaultTheme::error(),
+            ActiveTheme::error(),
         ))
     } else if let Some(ref msg) = app.message {
         Paragraph::new(Span::styled(
             format!("✓ {}", msg),
-            DefaultTheme::success(),
+            ActiveTheme::success(),
         ))
     } else if app.is_loading {
         let spinner = SPINNER_FRAMES[app.spinner_frame];
         Paragraph::new(Span::styled(
             format!("{} Executando query...", spinner),
-            DefaultTheme::warning(),
+            ActiveTheme::warning(),
         ))
     } else {
-        Paragraph::new(Span::styled(t!("query_placeholder").to_string(), DefaultTheme::dim_text()))
+        Paragraph::new(Span::styled(t!("query_placeholder").to_string(), ActiveTheme::dim_text()))
     };
 
-    f.render_widget(message.style(DefaultTheme::status_bar()), chunks[0]);
-
-    // Status info
-    let status_info = format!(
-        " {} ",
-        app.status
-    );
-    let status = Paragraph::new(status_info)
-        .style(DefaultTheme::status_bar())
-        .alignment(Alignment::Center);
-    f.render_widget(status, chunks[1]);
+    f.render_widget(message.style(ActiveTheme::status_bar()), area);
 }
 
 /// Draw help popup
-pub fn draw_help_popup(f: &mut Frame, area: Rect) {
-    let popup_area = centered_rect(60, 70, area);
+pub fn draw_help_popup(f: &mut Frame, app: &mut App, area: Rect) {
+    let popup_area = centered_rect(80, 80, area);
 
     // Clear the area
     f.render_widget(Clear, popup_area);
@@ -199,17 +239,17 @@ pub fn draw_help_popup(f: &mut Frame, area: Rect) {
     // Outer block
     let block = Block::default()
         .borders(Borders::ALL)
-        .border_style(DefaultTheme::popup_border())
-        .title(Span::styled(format!(" {} ", t!("help_title")), DefaultTheme::title()))
-        .style(DefaultTheme::popup());
+        .border_style(ActiveTheme::popup_border())
+        .title(Span::styled(format!(" {} ", t!("help_title")), ActiveTheme::title()))
+        .style(ActiveTheme::popup());
     let inner = block.inner(popup_area);
     f.render_widget(block, popup_area);
 
     // Header centered
     let header_area = Rect { height: 2, ..inner };
     let header = Paragraph::new(vec![
-        Line::from(Span::styled(t!("help_header").to_string(), DefaultTheme::title())),
-        Line::from(""),
+        Line::from(Span::styled(t!("help_header").to_string(), ActiveTheme::title())),
+        Line::from(Span::styled(t!("help_hint").to_string(), ActiveTheme::dim_text())),
     ]);
     f.render_widget(header, header_area);
 
@@ -222,63 +262,51 @@ pub fn draw_help_popup(f: &mut Frame, area: Rect) {
     let columns = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .spacing(2)
         .split(content_area);
 
-    // Left column: Global + Query Editor
-    let left_text = vec![
-        Line::from(Span::styled(t!("help_rule_global").to_string(), DefaultTheme::info())),
-        Line::from(""),
-        Line::from(t!("help_quote1").to_string()),
-        Line::from(t!("help_quote2").to_string()),
-        Line::from(t!("help_quote3").to_string()),
-        Line::from(t!("help_quote4").to_string()),
-        Line::from(t!("help_quote5").to_string()),
-        Line::from(t!("help_quote6").to_string()),
-        Line::from(t!("help_quote7").to_string()),
-        Line::from(t!("help_quote8").to_string()),
-        Line::from(t!("help_quote9").to_string()),
-        Line::from(t!("help_quote10").to_string()),
-        Line::from(t!("help_quote11").to_string()),
-        Line::from(t!("help_quote12").to_string()),
-        Line::from(""),
-        Line::from(Span::styled(t!("help_rule_query_editor").to_string(), DefaultTheme::info())),
-        Line::from(""),
-        Line::from(t!("help_quote13").to_string()),
-        Line::from(t!("help_quote14").to_string()),
-        Line::from(t!("help_quote15").to_string()),
-        Line::from(t!("help_quote16").to_string()),
-        Line::from(t!("help_quote17").to_string()),
-        Line::from(t!("help_quote18").to_string()),
-        Line::from(t!("help_quote19").to_string()),
-        Line::from(t!("help_quote20").to_string()),
-        Line::from(t!("help_quote21").to_string()),
-    ];
-    f.render_widget(Paragraph::new(left_text), columns[0]);
+    // Left column: problems in the configured keys, global keys and the
+    // query editor. Both columns come from the keymap in effect.
+    let mut left_text = Vec::new();
+    if !app.keymap.problems.is_empty() {
+        left_text.push(Line::from(Span::styled(t!("help_rule_keymap_problems").to_string(), ActiveTheme::error())));
+        left_text.push(Line::from(""));
+        for problem in &app.keymap.problems {
+            left_text.push(Line::from(Span::styled(problem.clone(), ActiveTheme::error())));
+        }
+        left_text.push(Line::from(""));
+    }
+    for context in [
+        KeyContext::Global,
+        KeyContext::Leader,
+        KeyContext::Normal,
+        KeyContext::Insert,
+        KeyContext::Completion,
+        KeyContext::Visual,
+    ] {
+        help_section(&mut left_text, &app.keymap, context);
+    }
+    left_text.push(Line::from(Span::styled(t!("help_rule_vim").to_string(), ActiveTheme::info())));
+    left_text.push(Line::from(""));
+    for key in [
+        "help_quote13", "help_quote14", "help_quote15", "help_quote20", "help_quote21", "help_quote34", "help_quote35",
+        "help_quote36", "help_quote40", "help_quote41", "help_quote42", "help_quote43", "help_quote44", "help_quote45",
+        "help_quote46", "help_quote47", "help_quote49",
+    ] {
+        left_text.push(Line::from(t!(key).to_string()));
+    }
 
-    // Right column: Results + Schema + History
-    let right_text = vec![
-        Line::from(Span::styled(t!("help_rule_results").to_string(), DefaultTheme::info())),
-        Line::from(""),
-        Line::from(t!("help_quote22").to_string()),
-        Line::from(t!("help_quote23").to_string()),
-        Line::from(t!("help_quote24").to_string()),
-        Line::from(t!("help_quote25").to_string()),
-        Line::from(t!("help_quote26").to_string()),
-        Line::from(t!("help_quote27").to_string()),
-        Line::from(t!("help_quote28").to_string()),
-        Line::from(t!("help_quote29").to_string()),
-        Line::from(""),
-        Line::from(Span::styled(t!("help_rule_schema").to_string(), DefaultTheme::info())),
-        Line::from(""),
-        Line::from(t!("help_quote30").to_string()),
-        Line::from(t!("help_quote31").to_string()),
-        Line::from(t!("help_quote32").to_string()),
-        Line::from(""),
-        Line::from(Span::styled(t!("help_rule_history").to_string(), DefaultTheme::info())),
-        Line::from(""),
-        Line::from(t!("help_quote33").to_string()),
-    ];
-    f.render_widget(Paragraph::new(right_text), columns[1]);
+    // Right column: Results + Schema + History + Connections
+    let mut right_text = Vec::new();
+    for context in [KeyContext::Results, KeyContext::Schema, KeyContext::History, KeyContext::Connections] {
+        help_section(&mut right_text, &app.keymap, context);
+    }
+
+    // Both columns scroll together, until the longer one is at its end
+    let longest = left_text.len().max(right_text.len()) as u16;
+    app.help_scroll = app.help_scroll.min(longest.saturating_sub(content_area.height));
+    f.render_widget(Paragraph::new(left_text).scroll((app.help_scroll, 0)), columns[0]);
+    f.render_widget(Paragraph::new(right_text).scroll((app.help_scroll, 0)), columns[1]);
 
     // Footer
     let footer_area = Rect {
@@ -292,6 +320,21 @@ pub fn draw_help_popup(f: &mut Frame, area: Rect) {
     f.render_widget(footer, footer_area);
 }
 
+/// A help section: "[keys] description" for each binding of a context
+fn help_section(lines: &mut Vec<Line<'static>>, keymap: &Keymap, context: KeyContext) {
+    let entries = keymap.help(context);
+    if entries.is_empty() {
+        return;
+    }
+    lines.push(Line::from(Span::styled(t!(format!("help_rule_{}", context.name())).to_string(), ActiveTheme::info())));
+    lines.push(Line::from(""));
+    for (chords, binding) in entries {
+        let keys: Vec<String> = chords.iter().map(|chord| format!("[{}]", chord)).collect();
+        lines.push(Line::from(format!("{} {}", keys.join(" "), binding.description())));
+    }
+    lines.push(Line::from(""));
+}
+
 /// Helper to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
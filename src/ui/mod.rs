@@ -11,11 +11,15 @@ pub use widgets::*;
 use crate::app::{App, SPINNER_FRAMES};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+use rust_i18n::t;
 
 /// Main draw function
 pub fn draw(f: &mut Frame, app: &mut App) {
     let size = f.size();
 
+    // Theme background (the dark theme keeps the terminal's)
+    f.render_widget(Block::default().style(ActiveTheme::canvas()), size);
+
     // Draw main layout
     draw_layout(f, app, size);
 
@@ -26,13 +30,63 @@ pub fn draw(f: &mut Frame, app: &mut App) {
 
     // Draw help popup if active
     if app.show_help {
-        draw_help_popup(f, size);
+        draw_help_popup(f, app, size);
     }
 
     // Draw connection modal if active
     if app.show_connection_modal {
         draw_connection_modal(f, app, size);
     }
+
+    // Draw destructive-statement confirmation if waiting
+    if app.confirm_prompt.is_some() {
+        draw_confirm_popup(f, app, size);
+    }
+}
+
+/// Draw the typed confirmation asked before a destructive batch
+fn draw_confirm_popup(f: &mut Frame, app: &App, area: Rect) {
+    let (Some(prompt), Some(conn)) = (&app.confirm_prompt, &app.current_connection) else {
+        return;
+    };
+    let popup_width = 60.min(area.width);
+    let popup_height = 8.min(area.height);
+    let popup_area = Rect {
+        x: (area.width.saturating_sub(popup_width)) / 2,
+        y: (area.height.saturating_sub(popup_height)) / 2,
+        width: popup_width,
+        height: popup_height,
+    };
+
+    f.render_widget(Clear, popup_area);
+
+    let text = vec![
+        Line::from(Span::styled(
+            t!("confirm_warning", env = conn.environment.name(), name = conn.name).to_string(),
+            ActiveTheme::warning().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(
+            t!("confirm_statements", verbs = prompt.verbs.join(", ")).to_string(),
+            ActiveTheme::normal_text(),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(t!("confirm_type_name").to_string(), ActiveTheme::dim_text())),
+        Line::from(vec![
+            Span::styled("> ", ActiveTheme::info()),
+            Span::styled(prompt.typed.clone(), ActiveTheme::normal_text()),
+            Span::styled("█", ActiveTheme::dim_text()),
+        ]),
+    ];
+
+    let popup = Paragraph::new(text).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(t!("confirm_title").to_string())
+            .border_style(Style::default().fg(ActiveTheme::colors().error))
+            .style(Style::default().bg(ActiveTheme::colors().panel)),
+    );
+
+    f.render_widget(popup, popup_area);
 }
 
 /// Draw loading spinner popup
@@ -56,7 +110,7 @@ fn draw_loading_popup(f: &mut Frame, app: &App, area: Rect) {
             Span::styled(
                 format!("  {}  Executando query...  ", spinner),
                 Style::default()
-                    .fg(DefaultTheme::GOLD)
+                    .fg(ActiveTheme::colors().accent)
                     .add_modifier(Modifier::BOLD),
             ),
         ]),
@@ -67,8 +121,8 @@ fn draw_loading_popup(f: &mut Frame, app: &App, area: Rect) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(DefaultTheme::PRIMARY))
-                .style(Style::default().bg(DefaultTheme::BG_PANEL)),
+                .border_style(Style::default().fg(ActiveTheme::colors().primary))
+                .style(Style::default().bg(ActiveTheme::colors().panel)),
         )
         .alignment(Alignment::Center);
 
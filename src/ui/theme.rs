@@ -1,208 +1,676 @@
+//! Colour themes
+//!
+//! A theme is a `Palette` of named colours. `dark` (the default), `light`
+//! and `high-contrast` are built in; more are read from TOML files in
+//! `~/.config/sqltui/themes/`, each starting from a built-in theme and
+//! changing some of its colours. `:colorscheme` switches at runtime, and
+//! `NO_COLOR` (https://no-color.org) turns colours off whatever the theme.
+
+use crate::config::{AppConfig, TagColor};
+use crate::sql::lint::Severity;
+use anyhow::{anyhow, bail, Context, Result};
 use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// The colours of a theme
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    // Brand colours: selection, header and status bar backgrounds
+    pub primary: Color,
+    pub primary_light: Color,
+    pub primary_dark: Color,
+    // Titles, active borders, search matches
+    pub accent: Color,
+    pub accent_light: Color,
+    // Backgrounds: the screen (Reset keeps the terminal's own), text on
+    // coloured badges, popups and panels, the cursor line
+    pub canvas: Color,
+    pub background: Color,
+    pub panel: Color,
+    pub highlight: Color,
+    // Text
+    pub text: Color,
+    pub text_dim: Color,
+    pub text_muted: Color,
+    // Status
+    pub success: Color,
+    pub error: Color,
+    pub warning: Color,
+    pub info: Color,
+    // SQL syntax highlighting
+    pub keyword: Color,
+    pub string: Color,
+    pub number: Color,
+    pub comment: Color,
+    pub function: Color,
+    pub operator: Color,
+    // Column data types in the results
+    pub type_int: Color,
+    pub type_float: Color,
+    pub type_string: Color,
+    pub type_datetime: Color,
+    pub type_binary: Color,
+    pub type_bool: Color,
+    // Connection tags without a status colour of their own
+    pub tag_red: Color,
+    pub tag_amber: Color,
+}
+
+impl Palette {
+    pub const DARK: Palette = Palette {
+        primary: Color::Rgb(0, 102, 51),
+        primary_light: Color::Rgb(0, 76, 153),
+        primary_dark: Color::Rgb(0, 0, 102),
+        accent: Color::Rgb(197, 164, 103),
+        accent_light: Color::Rgb(218, 195, 148),
+        canvas: Color::Reset,
+        background: Color::Rgb(18, 18, 24),
+        panel: Color::Rgb(28, 28, 36),
+        highlight: Color::Rgb(38, 38, 48),
+        text: Color::Rgb(230, 230, 230),
+        text_dim: Color::Rgb(150, 150, 160),
+        text_muted: Color::Rgb(100, 100, 110),
+        success: Color::Rgb(80, 200, 120),
+        error: Color::Rgb(255, 100, 100),
+        warning: Color::Rgb(255, 200, 100),
+        info: Color::Rgb(100, 180, 255),
+        keyword: Color::Rgb(197, 134, 192),
+        string: Color::Rgb(206, 145, 120),
+        number: Color::Rgb(181, 206, 168),
+        comment: Color::Rgb(106, 153, 85),
+        function: Color::Rgb(220, 220, 170),
+        operator: Color::Rgb(212, 212, 212),
+        type_int: Color::Rgb(100, 180, 255),
+        type_float: Color::Rgb(181, 206, 168),
+        type_string: Color::Rgb(206, 145, 120),
+        type_datetime: Color::Rgb(197, 134, 192),
+        type_binary: Color::Rgb(150, 150, 150),
+        type_bool: Color::Rgb(255, 200, 100),
+        tag_red: Color::Rgb(230, 70, 70),
+        tag_amber: Color::Rgb(240, 170, 40),
+    };
+
+    pub const LIGHT: Palette = Palette {
+        primary: Color::Rgb(190, 222, 202),
+        primary_light: Color::Rgb(0, 76, 153),
+        primary_dark: Color::Rgb(222, 228, 240),
+        accent: Color::Rgb(140, 95, 10),
+        accent_light: Color::Rgb(196, 150, 50),
+        canvas: Color::Rgb(250, 250, 250),
+        background: Color::Rgb(250, 250, 250),
+        panel: Color::Rgb(238, 238, 242),
+        highlight: Color::Rgb(224, 227, 235),
+        text: Color::Rgb(30, 30, 35),
+        text_dim: Color::Rgb(85, 85, 95),
+        text_muted: Color::Rgb(140, 140, 150),
+        success: Color::Rgb(20, 125, 60),
+        error: Color::Rgb(195, 30, 30),
+        warning: Color::Rgb(165, 105, 0),
+        info: Color::Rgb(0, 95, 185),
+        keyword: Color::Rgb(135, 40, 145),
+        string: Color::Rgb(160, 65, 20),
+        number: Color::Rgb(35, 110, 40),
+        comment: Color::Rgb(95, 130, 85),
+        function: Color::Rgb(115, 95, 0),
+        operator: Color::Rgb(60, 60, 60),
+        type_int: Color::Rgb(0, 95, 185),
+        type_float: Color::Rgb(35, 110, 40),
+        type_string: Color::Rgb(160, 65, 20),
+        type_datetime: Color::Rgb(135, 40, 145),
+        type_binary: Color::Rgb(110, 110, 110),
+        type_bool: Color::Rgb(165, 105, 0),
+        tag_red: Color::Rgb(200, 35, 35),
+        tag_amber: Color::Rgb(200, 125, 0),
+    };
+
+    pub const HIGH_CONTRAST: Palette = Palette {
+        primary: Color::Rgb(0, 60, 200),
+        primary_light: Color::Rgb(0, 200, 255),
+        primary_dark: Color::Rgb(0, 0, 0),
+        accent: Color::Rgb(255, 255, 0),
+        accent_light: Color::Rgb(255, 255, 150),
+        canvas: Color::Rgb(0, 0, 0),
+        background: Color::Rgb(0, 0, 0),
+        panel: Color::Rgb(0, 0, 0),
+        highlight: Color::Rgb(50, 50, 50),
+        text: Color::Rgb(255, 255, 255),
+        text_dim: Color::Rgb(225, 225, 225),
+        text_muted: Color::Rgb(180, 180, 180),
+        success: Color::Rgb(0, 255, 0),
+        error: Color::Rgb(255, 60, 60),
+        warning: Color::Rgb(255, 200, 0),
+        info: Color::Rgb(0, 220, 255),
+        keyword: Color::Rgb(255, 120, 255),
+        string: Color::Rgb(255, 170, 80),
+        number: Color::Rgb(120, 255, 120),
+        comment: Color::Rgb(150, 220, 150),
+        function: Color::Rgb(255, 255, 120),
+        operator: Color::Rgb(255, 255, 255),
+        type_int: Color::Rgb(0, 220, 255),
+        type_float: Color::Rgb(120, 255, 120),
+        type_string: Color::Rgb(255, 170, 80),
+        type_datetime: Color::Rgb(255, 120, 255),
+        type_binary: Color::Rgb(200, 200, 200),
+        type_bool: Color::Rgb(255, 200, 0),
+        tag_red: Color::Rgb(255, 60, 60),
+        tag_amber: Color::Rgb(255, 180, 0),
+    };
+
+    /// Every colour left to the terminal (`NO_COLOR`)
+    pub const NONE: Palette = Palette {
+        primary: Color::Reset,
+        primary_light: Color::Reset,
+        primary_dark: Color::Reset,
+        accent: Color::Reset,
+        accent_light: Color::Reset,
+        canvas: Color::Reset,
+        background: Color::Reset,
+        panel: Color::Reset,
+        highlight: Color::Reset,
+        text: Color::Reset,
+        text_dim: Color::Reset,
+        text_muted: Color::Reset,
+        success: Color::Reset,
+        error: Color::Reset,
+        warning: Color::Reset,
+        info: Color::Reset,
+        keyword: Color::Reset,
+        string: Color::Reset,
+        number: Color::Reset,
+        comment: Color::Reset,
+        function: Color::Reset,
+        operator: Color::Reset,
+        type_int: Color::Reset,
+        type_float: Color::Reset,
+        type_string: Color::Reset,
+        type_datetime: Color::Reset,
+        type_binary: Color::Reset,
+        type_bool: Color::Reset,
+        tag_red: Color::Reset,
+        tag_amber: Color::Reset,
+    };
+
+    /// The colour called `name` in theme files
+    fn slot(&mut self, name: &str) -> Option<&mut Color> {
+        Some(match name {
+            "primary" => &mut self.primary,
+            "primary_light" => &mut self.primary_light,
+            "primary_dark" => &mut self.primary_dark,
+            "accent" => &mut self.accent,
+            "accent_light" => &mut self.accent_light,
+            "canvas" => &mut self.canvas,
+            "background" => &mut self.background,
+            "panel" => &mut self.panel,
+            "highlight" => &mut self.highlight,
+            "text" => &mut self.text,
+            "text_dim" => &mut self.text_dim,
+            "text_muted" => &mut self.text_muted,
+            "success" => &mut self.success,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            "info" => &mut self.info,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "number" => &mut self.number,
+            "comment" => &mut self.comment,
+            "function" => &mut self.function,
+            "operator" => &mut self.operator,
+            "type_int" => &mut self.type_int,
+            "type_float" => &mut self.type_float,
+            "type_string" => &mut self.type_string,
+            "type_datetime" => &mut self.type_datetime,
+            "type_binary" => &mut self.type_binary,
+            "type_bool" => &mut self.type_bool,
+            "tag_red" => &mut self.tag_red,
+            "tag_amber" => &mut self.tag_amber,
+            _ => return None,
+        })
+    }
+}
+
+/// Names of the built-in themes
+pub const BUILTIN_THEMES: [&str; 3] = ["dark", "light", "high-contrast"];
+
+/// A theme file: a built-in theme to start from and the colours it changes
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct ThemeFile {
+    base: Option<String>,
+    colors: BTreeMap<String, String>,
+}
+
+/// A named palette
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Theme {
+    pub name: String,
+    pub colors: Palette,
+}
+
+impl Theme {
+    /// A built-in theme
+    pub fn builtin(name: &str) -> Option<Self> {
+        let colors = match name {
+            "dark" => Palette::DARK,
+            "light" => Palette::LIGHT,
+            "high-contrast" => Palette::HIGH_CONTRAST,
+            _ => return None,
+        };
+        Some(Self { name: name.to_string(), colors })
+    }
+
+    /// Parse a theme file
+    pub fn parse(name: &str, contents: &str) -> Result<Self> {
+        let file: ThemeFile = toml::from_str(contents)?;
+        let base = file.base.as_deref().unwrap_or("dark");
+        let mut colors = Self::builtin(base).ok_or_else(|| anyhow!("unknown base theme '{}'", base))?.colors;
+        for (key, value) in &file.colors {
+            let slot = colors.slot(key).ok_or_else(|| anyhow!("unknown colour '{}'", key))?;
+            *slot = value.parse().map_err(|_| anyhow!("invalid colour '{}' for {}", value, key))?;
+        }
+        Ok(Self { name: name.to_string(), colors })
+    }
+
+    /// Directory of the theme files (~/.config/sqltui/themes)
+    pub fn dir() -> Result<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("themes"))
+    }
+
+    /// A theme file from `dir`, or a built-in theme. Files come first so
+    /// a built-in theme can be adjusted under its own name.
+    pub fn load_from(dir: &Path, name: &str) -> Result<Self> {
+        let path = dir.join(format!("{}.toml", name));
+        if path.is_file() {
+            let contents = fs::read_to_string(&path).context("Failed to read theme")?;
+            return Self::parse(name, &contents).with_context(|| path.display().to_string());
+        }
+        match Self::builtin(name) {
+            Some(theme) => Ok(theme),
+            None => bail!("No such theme: {}", name),
+        }
+    }
+
+    pub fn load(name: &str) -> Result<Self> {
+        Self::load_from(&Self::dir()?, name)
+    }
+
+    /// Built-in themes and the theme files, for completion
+    pub fn available() -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|n| n.to_string()).collect();
+        if let Ok(entries) = Self::dir().and_then(|dir| Ok(fs::read_dir(dir)?)) {
+            for path in entries.flatten().map(|e| e.path()) {
+                if path.extension().is_some_and(|ext| ext == "toml")
+                    && let Some(stem) = path.file_stem()
+                {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// The theme in use and whether `NO_COLOR` turned colours off
+struct Active {
+    theme: Option<Theme>,
+    no_color: bool,
+}
+
+static ACTIVE: RwLock<Active> = RwLock::new(Active { theme: None, no_color: false });
+
+/// Styles of the active theme, used by every widget
+pub struct ActiveTheme;
+
+impl ActiveTheme {
+    /// Switch to `theme` for everything drawn from now on
+    pub fn set(theme: Theme) {
+        ACTIVE.write().unwrap_or_else(|e| e.into_inner()).theme = Some(theme);
+    }
+
+    /// Turn colours off, as `NO_COLOR` asks
+    pub fn set_no_color(no_color: bool) {
+        ACTIVE.write().unwrap_or_else(|e| e.into_inner()).no_color = no_color;
+    }
+
+    /// Is `NO_COLOR` set to a non-empty value?
+    pub fn no_color_requested() -> bool {
+        std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    }
+
+    /// Name of the active theme
+    pub fn name() -> String {
+        let active = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+        active.theme.as_ref().map_or_else(|| "dark".to_string(), |t| t.name.clone())
+    }
+
+    pub fn is_no_color() -> bool {
+        ACTIVE.read().unwrap_or_else(|e| e.into_inner()).no_color
+    }
+
+    /// Colours of the active theme
+    pub fn colors() -> Palette {
+        let active = ACTIVE.read().unwrap_or_else(|e| e.into_inner());
+        if active.no_color {
+            Palette::NONE
+        } else {
+            active.theme.as_ref().map_or(Palette::DARK, |t| t.colors)
+        }
+    }
 
-pub struct DefaultTheme;
-
-impl DefaultTheme {
-    // Primary brand colors
-    pub const PRIMARY: Color = Color::Rgb(0, 102, 51);      // Default Dark
-    pub const PRIMARY_LIGHT: Color = Color::Rgb(0, 76, 153); // Light Default Blue
-    pub const PRIMARY_DARK: Color = Color::Rgb(0, 0, 102);   // Dark Default Blue
-
-    // Secondary colors
-    pub const GOLD: Color = Color::Rgb(197, 164, 103);       // Default Gold
-    pub const GOLD_LIGHT: Color = Color::Rgb(218, 195, 148);
-
-    // UI colors
-    pub const BG_DARK: Color = Color::Rgb(18, 18, 24);       // Dark background
-    pub const BG_PANEL: Color = Color::Rgb(28, 28, 36);      // Panel background
-    pub const BG_HIGHLIGHT: Color = Color::Rgb(38, 38, 48);  // Highlight background
-
-    // Text colors
-    pub const TEXT: Color = Color::Rgb(230, 230, 230);       // Primary text
-    pub const TEXT_DIM: Color = Color::Rgb(150, 150, 160);   // Secondary text
-    pub const TEXT_MUTED: Color = Color::Rgb(100, 100, 110); // Muted text
-
-    // Status colors
-    pub const SUCCESS: Color = Color::Rgb(80, 200, 120);     // Success green
-    pub const ERROR: Color = Color::Rgb(255, 100, 100);      // Error red
-    pub const WARNING: Color = Color::Rgb(255, 200, 100);    // Warning yellow
-    pub const INFO: Color = Color::Rgb(100, 180, 255);       // Info blue
-
-    // SQL Syntax highlighting
-    pub const KEYWORD: Color = Color::Rgb(197, 134, 192);    // Purple for keywords
-    pub const STRING: Color = Color::Rgb(206, 145, 120);     // Orange for strings
-    pub const NUMBER: Color = Color::Rgb(181, 206, 168);     // Green for numbers
-    pub const COMMENT: Color = Color::Rgb(106, 153, 85);     // Gray-green for comments
-    pub const FUNCTION: Color = Color::Rgb(220, 220, 170);   // Yellow for functions
-    pub const OPERATOR: Color = Color::Rgb(212, 212, 212);   // White for operators
+    /// Without colours, states shown by a background are shown reversed
+    fn marked(style: Style) -> Style {
+        if Self::is_no_color() { style.add_modifier(Modifier::REVERSED) } else { style }
+    }
 
     // Styles
+    pub fn canvas() -> Style {
+        let c = Self::colors();
+        Style::default()
+            .fg(c.text)
+            .bg(c.canvas)
+    }
+
     pub fn header() -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::GOLD)
-            .bg(Self::PRIMARY_DARK)
+            .fg(c.accent)
+            .bg(c.primary_dark)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn title() -> Style {
         Style::default()
-            .fg(Self::GOLD)
+            .fg(Self::colors().accent)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn active_border() -> Style {
-        Style::default().fg(Self::GOLD)
+        Style::default().fg(Self::colors().accent)
     }
 
     pub fn inactive_border() -> Style {
-        Style::default().fg(Self::TEXT_MUTED)
+        Style::default().fg(Self::colors().text_muted)
+    }
+
+    // Connection tags: the active panel's border takes the tag colour
+    pub fn tag(color: TagColor) -> Color {
+        let c = Self::colors();
+        match color {
+            TagColor::Red => c.tag_red,
+            TagColor::Amber => c.tag_amber,
+            TagColor::Green => c.success,
+            TagColor::Blue => c.info,
+            TagColor::Purple => c.keyword,
+            TagColor::Gray => c.text_dim,
+        }
+    }
+
+    pub fn tag_badge(color: TagColor) -> Style {
+        Self::marked(
+            Style::default()
+                .fg(Self::colors().background)
+                .bg(Self::tag(color))
+                .add_modifier(Modifier::BOLD),
+        )
+    }
+
+    pub fn panel_border(active: bool, tag: Option<TagColor>) -> Style {
+        match (active, tag) {
+            (true, Some(color)) => Style::default().fg(Self::tag(color)),
+            (true, None) => Self::active_border(),
+            (false, _) => Self::inactive_border(),
+        }
     }
 
     pub fn normal_text() -> Style {
-        Style::default().fg(Self::TEXT)
+        Style::default().fg(Self::colors().text)
     }
 
     pub fn dim_text() -> Style {
-        Style::default().fg(Self::TEXT_DIM)
+        Style::default().fg(Self::colors().text_dim)
     }
 
     pub fn muted_text() -> Style {
-        Style::default().fg(Self::TEXT_MUTED)
+        Style::default().fg(Self::colors().text_muted)
     }
 
     pub fn selected() -> Style {
-        Style::default()
-            .fg(Self::TEXT)
-            .bg(Self::PRIMARY)
-            .add_modifier(Modifier::BOLD)
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .fg(c.text)
+                .bg(c.primary)
+                .add_modifier(Modifier::BOLD),
+        )
     }
 
     pub fn highlighted() -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::TEXT)
-            .bg(Self::BG_HIGHLIGHT)
+            .fg(c.text)
+            .bg(c.highlight)
     }
 
     pub fn success() -> Style {
-        Style::default().fg(Self::SUCCESS)
+        Style::default().fg(Self::colors().success)
     }
 
     pub fn error() -> Style {
         Style::default()
-            .fg(Self::ERROR)
+            .fg(Self::colors().error)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn warning() -> Style {
-        Style::default().fg(Self::WARNING)
+        Style::default().fg(Self::colors().warning)
     }
 
     pub fn info() -> Style {
-        Style::default().fg(Self::INFO)
+        Style::default().fg(Self::colors().info)
     }
 
     pub fn status_bar() -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::TEXT)
-            .bg(Self::PRIMARY_DARK)
+            .fg(c.text)
+            .bg(c.primary_dark)
     }
 
     pub fn mode_normal() -> Style {
-        Style::default()
-            .fg(Color::Black)
-            .bg(Self::PRIMARY_LIGHT)
-            .add_modifier(Modifier::BOLD)
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .fg(c.background)
+                .bg(c.primary_light)
+                .add_modifier(Modifier::BOLD),
+        )
     }
 
     pub fn mode_insert() -> Style {
-        Style::default()
-            .fg(Color::Black)
-            .bg(Self::GOLD)
-            .add_modifier(Modifier::BOLD)
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .fg(c.background)
+                .bg(c.accent)
+                .add_modifier(Modifier::BOLD),
+        )
     }
 
     pub fn mode_command() -> Style {
-        Style::default()
-            .fg(Color::Black)
-            .bg(Self::INFO)
-            .add_modifier(Modifier::BOLD)
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .fg(c.background)
+                .bg(c.info)
+                .add_modifier(Modifier::BOLD),
+        )
     }
 
     pub fn null_value() -> Style {
         Style::default()
-            .fg(Self::TEXT_MUTED)
+            .fg(Self::colors().text_muted)
             .add_modifier(Modifier::ITALIC)
     }
 
     pub fn primary_key() -> Style {
         Style::default()
-            .fg(Self::GOLD)
+            .fg(Self::colors().accent)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn table_header() -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::GOLD)
-            .bg(Self::PRIMARY_DARK)
+            .fg(c.accent)
+            .bg(c.primary_dark)
             .add_modifier(Modifier::BOLD)
     }
 
     pub fn table_row_alt() -> Style {
+        let c = Self::colors();
+        Style::default()
+            .fg(c.text)
+            .bg(c.panel)
+    }
+
+    // Search matches in the query editor (the one at the cursor stands out)
+    pub fn search_match() -> Style {
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .fg(c.background)
+                .bg(c.accent_light),
+        )
+    }
+
+    pub fn search_current() -> Style {
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .fg(c.background)
+                .bg(c.warning)
+                .add_modifier(Modifier::BOLD),
+        )
+    }
+
+    // A bracket or BEGIN / CASE / END under the cursor and its partner
+    pub fn pair_match() -> Style {
+        let c = Self::colors();
+        Self::marked(
+            Style::default()
+                .bg(c.highlight)
+                .add_modifier(Modifier::BOLD),
+        )
+    }
+
+    // Lint diagnostics: gutter marker color and the underline of the span
+    pub fn diagnostic(severity: Severity) -> Style {
+        let c = Self::colors();
+        Style::default().fg(match severity {
+            Severity::Warning => c.warning,
+            Severity::Error => c.error,
+        })
+    }
+
+    pub fn diagnostic_span(severity: Severity) -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::TEXT)
-            .bg(Self::BG_PANEL)
+            .add_modifier(Modifier::UNDERLINED)
+            .underline_color(match severity {
+                Severity::Warning => c.warning,
+                Severity::Error => c.error,
+            })
     }
 
     pub fn popup() -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::TEXT)
-            .bg(Self::BG_PANEL)
+            .fg(c.text)
+            .bg(c.panel)
     }
 
     pub fn popup_border() -> Style {
-        Style::default().fg(Self::GOLD)
+        Style::default().fg(Self::colors().accent)
     }
 
     // Data type colors for column headers
     pub fn type_int() -> Style {
-        Style::default().fg(Color::Rgb(100, 180, 255)) // Blue for integers
+        Style::default().fg(Self::colors().type_int)
     }
 
     pub fn type_float() -> Style {
-        Style::default().fg(Color::Rgb(181, 206, 168)) // Green for floats
+        Style::default().fg(Self::colors().type_float)
     }
 
     pub fn type_string() -> Style {
-        Style::default().fg(Color::Rgb(206, 145, 120)) // Orange for strings
+        Style::default().fg(Self::colors().type_string)
     }
 
     pub fn type_datetime() -> Style {
-        Style::default().fg(Color::Rgb(197, 134, 192)) // Purple for dates
+        Style::default().fg(Self::colors().type_datetime)
     }
 
     pub fn type_binary() -> Style {
-        Style::default().fg(Color::Rgb(150, 150, 150)) // Gray for binary
+        Style::default().fg(Self::colors().type_binary)
     }
 
     pub fn type_bool() -> Style {
-        Style::default().fg(Color::Rgb(255, 200, 100)) // Yellow for bool
+        Style::default().fg(Self::colors().type_bool)
     }
 
     // Row number column
     pub fn row_number() -> Style {
+        let c = Self::colors();
         Style::default()
-            .fg(Self::TEXT_MUTED)
-            .bg(Self::BG_PANEL)
+            .fg(c.text_muted)
+            .bg(c.panel)
     }
 
     // Execution stats
     pub fn stats_label() -> Style {
-        Style::default().fg(Self::TEXT_DIM)
+        Style::default().fg(Self::colors().text_dim)
     }
 
     pub fn stats_value() -> Style {
         Style::default()
-            .fg(Self::SUCCESS)
+            .fg(Self::colors().success)
             .add_modifier(Modifier::BOLD)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_files() {
+        for name in BUILTIN_THEMES {
+            assert_eq!(Theme::builtin(name).unwrap().name, name);
+        }
+
+        let theme = Theme::parse("paper", "base = \"light\"\n[colors]\nkeyword = \"#112233\"\ncomment = \"darkgray\"\n").unwrap();
+        assert_eq!(theme.colors.keyword, Color::Rgb(0x11, 0x22, 0x33));
+        assert_eq!(theme.colors.comment, Color::DarkGray);
+        assert_eq!(theme.colors.text, Palette::LIGHT.text);
+        assert_eq!(Theme::parse("x", "").unwrap().colors, Palette::DARK);
+
+        let error = |contents: &str| Theme::parse("x", contents).unwrap_err().to_string();
+        assert!(error("[colors]\nkeywrd = \"red\"").contains("unknown colour 'keywrd'"));
+        assert!(error("[colors]\nkeyword = \"#12\"").contains("invalid colour"));
+        assert!(error("base = \"solarized\"").contains("unknown base theme"));
+        assert!(Theme::parse("x", "colours = 1").is_err());
+
+        let dir = std::env::temp_dir().join(format!("sqltui-theme-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("dark.toml"), "[colors]\nstring = \"yellow\"\n").unwrap();
+        assert_eq!(Theme::load_from(&dir, "dark").unwrap().colors.string, Color::Yellow);
+        assert_eq!(Theme::load_from(&dir, "light").unwrap().colors, Palette::LIGHT);
+        assert!(Theme::load_from(&dir, "nope").is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
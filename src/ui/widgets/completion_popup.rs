@@ -1,8 +1,8 @@
 //! Completion popup widget for autocomplete suggestions
 
 use crate::app::App;
-use crate::completion::CompletionKind;
-use crate::ui::DefaultTheme;
+use crate::completion::{fuzzy_match, CompletionKind};
+use crate::ui::ActiveTheme;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem};
 
@@ -46,8 +46,7 @@ pub fn draw_completion_popup(f: &mut Frame, app: &App, editor_area: Rect) {
         .unwrap_or(MIN_POPUP_WIDTH as usize);
     
     let popup_width = (max_label_width as u16 + 4)
-        .max(MIN_POPUP_WIDTH)
-        .min(MAX_POPUP_WIDTH);
+        .clamp(MIN_POPUP_WIDTH, MAX_POPUP_WIDTH);
     
     // Calculate popup position
     // Start below the cursor, offset by prefix length
@@ -110,48 +109,46 @@ pub fn draw_completion_popup(f: &mut Frame, app: &App, editor_area: Rect) {
                 CompletionKind::Column => "C",
                 CompletionKind::Function => "F",
                 CompletionKind::Variable => "@",
+                CompletionKind::Snippet => "~",
             };
 
             let kind_color = match item.kind {
-                CompletionKind::ColumnList => DefaultTheme::SUCCESS,
-                CompletionKind::Keyword => DefaultTheme::KEYWORD,
-                CompletionKind::Schema => DefaultTheme::INFO,
-                CompletionKind::Table => DefaultTheme::SUCCESS,
-                CompletionKind::View => DefaultTheme::PRIMARY_LIGHT,
-                CompletionKind::Procedure => DefaultTheme::GOLD,
-                CompletionKind::Column => DefaultTheme::TEXT_DIM,
-                CompletionKind::Function => DefaultTheme::FUNCTION,
-                CompletionKind::Variable => DefaultTheme::GOLD,
+                CompletionKind::ColumnList => ActiveTheme::colors().success,
+                CompletionKind::Keyword => ActiveTheme::colors().keyword,
+                CompletionKind::Schema => ActiveTheme::colors().info,
+                CompletionKind::Table => ActiveTheme::colors().success,
+                CompletionKind::View => ActiveTheme::colors().primary_light,
+                CompletionKind::Procedure => ActiveTheme::colors().accent,
+                CompletionKind::Column => ActiveTheme::colors().text_dim,
+                CompletionKind::Function => ActiveTheme::colors().function,
+                CompletionKind::Variable => ActiveTheme::colors().accent,
+                CompletionKind::Snippet => ActiveTheme::colors().info,
             };
             
             // Create spans for the item
-            let mut spans = vec![
-                Span::styled(
-                    format!("{} ", kind_indicator),
-                    Style::default().fg(kind_color).add_modifier(Modifier::BOLD),
-                ),
-                Span::styled(
-                    &item.label,
-                    if is_selected {
-                        Style::default().fg(DefaultTheme::TEXT).add_modifier(Modifier::BOLD)
-                    } else {
-                        Style::default().fg(DefaultTheme::TEXT)
-                    },
-                ),
-            ];
+            let mut spans = vec![Span::styled(
+                format!("{} ", kind_indicator),
+                Style::default().fg(kind_color).add_modifier(Modifier::BOLD),
+            )];
+            let label_style = if is_selected {
+                Style::default().fg(ActiveTheme::colors().text).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(ActiveTheme::colors().text)
+            };
+            spans.extend(label_spans(&item.label, &app.completion.prefix, label_style));
             
             // Add detail (schema) if present
             if let Some(ref detail) = item.detail {
                 spans.push(Span::styled(
                     format!(" ({})", detail),
-                    Style::default().fg(DefaultTheme::TEXT_MUTED),
+                    Style::default().fg(ActiveTheme::colors().text_muted),
                 ));
             }
             
             let line = Line::from(spans);
             
             if is_selected {
-                ListItem::new(line).style(DefaultTheme::selected())
+                ListItem::new(line).style(ActiveTheme::selected())
             } else {
                 ListItem::new(line)
             }
@@ -174,10 +171,36 @@ pub fn draw_completion_popup(f: &mut Frame, app: &App, editor_area: Rect) {
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(DefaultTheme::popup_border())
-                .style(DefaultTheme::popup())
+                .border_style(ActiveTheme::popup_border())
+                .style(ActiveTheme::popup())
                 .title(title)
         );
     
     f.render_widget(list, popup_area);
 }
+
+/// The label in runs of plain and matched characters, the chars the typed
+/// prefix matched standing out
+fn label_spans<'a>(label: &'a str, prefix: &str, style: Style) -> Vec<Span<'a>> {
+    let positions = fuzzy_match(prefix, label).map(|m| m.positions).unwrap_or_default();
+    if positions.is_empty() {
+        return vec![Span::styled(label, style)];
+    }
+    let matched_style = style
+        .fg(ActiveTheme::colors().accent)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    let mut spans = Vec::new();
+    let mut run_start = 0;
+    let mut run_matched = false;
+    for (i, (byte, _)) in label.char_indices().enumerate() {
+        let matched = positions.binary_search(&i).is_ok();
+        if matched != run_matched && byte > run_start {
+            spans.push(Span::styled(&label[run_start..byte], if run_matched { matched_style } else { style }));
+            run_start = byte;
+        }
+        run_matched = matched;
+    }
+    spans.push(Span::styled(&label[run_start..], if run_matched { matched_style } else { style }));
+    spans
+}
@@ -5,7 +5,7 @@
 
 use crate::app::{App, ConnectionModalFocus};
 use crate::db::DatabaseBackend;
-use crate::ui::DefaultTheme;
+use crate::ui::ActiveTheme;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, Paragraph};
 use rust_i18n::t;
@@ -15,7 +15,7 @@ pub fn draw_connection_modal(f: &mut Frame, app: &App, area: Rect) {
     let modal_area = if app.connection_modal_focus == ConnectionModalFocus::List {
         centered_rect(20, 40, area)
     } else {
-        centered_rect(30, 50, area)
+        centered_rect(30, 70, area)
     };
 
     // Clear the background
@@ -24,9 +24,9 @@ pub fn draw_connection_modal(f: &mut Frame, app: &App, area: Rect) {
     // Modal block
     let modal_block = Block::default()
         .title(" Conexões ")
-        .title_style(DefaultTheme::title())
+        .title_style(ActiveTheme::title())
         .borders(Borders::ALL)
-        .border_style(DefaultTheme::popup_border())
+        .border_style(ActiveTheme::popup_border())
         .title_bottom(Line::from(if app.connection_modal_focus == ConnectionModalFocus::List {
             "[E] Editar [Enter] Conectar".to_string()
         } else {
@@ -36,7 +36,7 @@ pub fn draw_connection_modal(f: &mut Frame, app: &App, area: Rect) {
             };
             format!("[Esc] Voltar {} [Enter] Salvar", backend_hint)
         }).right_aligned())
-        .style(DefaultTheme::popup());
+        .style(ActiveTheme::popup());
 
     let inner = modal_block.inner(modal_area);
     f.render_widget(modal_block, modal_area);
@@ -60,9 +60,9 @@ fn draw_connection_list(f: &mut Frame, app: &App, area: Rect) {
     let block = Block::default()
         .borders(Borders::RIGHT)
         .border_style(if is_focused {
-            DefaultTheme::active_border()
+            ActiveTheme::active_border()
         } else {
-            DefaultTheme::inactive_border()
+            ActiveTheme::inactive_border()
         });
 
     let inner = block.inner(area);
@@ -75,36 +75,41 @@ fn draw_connection_list(f: &mut Frame, app: &App, area: Rect) {
         let prefix = if is_selected { "▶ " } else { "  " };
 
         let style = if is_selected {
-            Style::default().fg(DefaultTheme::GOLD).add_modifier(Modifier::BOLD)
+            Style::default().fg(ActiveTheme::colors().accent).add_modifier(Modifier::BOLD)
         } else {
-            DefaultTheme::normal_text()
+            ActiveTheme::normal_text()
         };
 
         let backend_tag = match conn.backend {
             DatabaseBackend::SqlServer => "",
             DatabaseBackend::Sqlite => " [SQLite]",
         };
+        // Shared through the project's .sqltui.toml
+        let project_tag = if conn.project { " [project]" } else { "" };
 
         items.push(ListItem::new(Line::from(vec![
             Span::styled(prefix, style),
+            Span::styled("● ", Style::default().fg(ActiveTheme::tag(conn.tag_color()))),
             Span::styled(&conn.name, style),
-            Span::styled(backend_tag, DefaultTheme::dim_text()),
+            Span::styled(backend_tag, ActiveTheme::dim_text()),
+            Span::styled(project_tag, ActiveTheme::dim_text()),
+            Span::styled(format!(" {}", conn.tag_label()), Style::default().fg(ActiveTheme::tag(conn.tag_color()))),
         ])));
     }
 
     // Separator
     items.push(ListItem::new(Line::from(Span::styled(
         "───────────────────────",
-        DefaultTheme::dim_text(),
+        ActiveTheme::dim_text(),
     ))));
 
     // "Create new" option
     let create_new_idx = app.app_config.connections.len();
     let is_create_selected = app.connection_list_selected >= create_new_idx;
     let create_style = if is_create_selected {
-        Style::default().fg(DefaultTheme::SUCCESS).add_modifier(Modifier::BOLD)
+        Style::default().fg(ActiveTheme::colors().success).add_modifier(Modifier::BOLD)
     } else {
-        Style::default().fg(DefaultTheme::SUCCESS)
+        Style::default().fg(ActiveTheme::colors().success)
     };
     let create_prefix = if is_create_selected { "▶ " } else { "  " };
 
@@ -123,7 +128,7 @@ fn draw_connection_form(f: &mut Frame, app: &App, area: Rect) {
 
     let block = Block::default()
         .borders(Borders::NONE)
-        .style(DefaultTheme::popup());
+        .style(ActiveTheme::popup());
 
     let inner = block.inner(area);
     f.render_widget(block, area);
@@ -134,7 +139,7 @@ fn draw_connection_form(f: &mut Frame, app: &App, area: Rect) {
             Line::from(""),
             Line::from(Span::styled(
                 t!("select_or_create").to_string(),
-                DefaultTheme::dim_text(),
+                ActiveTheme::dim_text(),
             )),
         ])
         .alignment(Alignment::Center);
@@ -146,23 +151,16 @@ fn draw_connection_form(f: &mut Frame, app: &App, area: Rect) {
     let focus_idx = app.connection_form_focus;
     let num_fields = form.field_count();
 
-    // Layout for form fields
+    // Layout: backend selector, the fields (scrolled to keep the focused
+    // one in view), spacing and hint
     let field_height = 2u16;
-    let mut constraints = Vec::new();
-
-    // Backend selector row
-    constraints.push(Constraint::Length(2));
-
-    for _ in 0..num_fields {
-        constraints.push(Constraint::Length(field_height));
-    }
-    constraints.push(Constraint::Length(2)); // Spacing
-    constraints.push(Constraint::Length(2)); // Hint
-    constraints.push(Constraint::Min(0));    // Remaining space
-
     let field_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(constraints)
+        .constraints([
+            Constraint::Length(2), // Backend selector row
+            Constraint::Min(field_height),
+            Constraint::Length(2), // Hint
+        ])
         .margin(1)
         .split(inner);
 
@@ -171,44 +169,47 @@ fn draw_connection_form(f: &mut Frame, app: &App, area: Rect) {
         DatabaseBackend::SqlServer => "Driver: SQL Server",
         DatabaseBackend::Sqlite => "Driver: SQLite",
     };
-    let backend_style = Style::default().fg(DefaultTheme::GOLD).add_modifier(Modifier::BOLD);
+    let backend_style = Style::default().fg(ActiveTheme::colors().accent).add_modifier(Modifier::BOLD);
     let backend_para = Paragraph::new(backend_label).style(backend_style);
     f.render_widget(backend_para, field_chunks[0]);
 
-    // Draw each field
-    for i in 0..num_fields {
+    // Draw the visible fields
+    let visible = ((field_chunks[1].height / field_height) as usize).max(1);
+    let first = (focus_idx + 1).saturating_sub(visible).min(num_fields.saturating_sub(visible));
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Length(field_height); visible])
+        .split(field_chunks[1]);
+    for (row, i) in (first..num_fields.min(first + visible)).enumerate() {
         draw_form_field(
             f,
             form.get_field_label(i),
             form.get_field(i),
             i == focus_idx && is_focused,
             form.is_password_field(i),
-            field_chunks[i + 1],  // +1 because index 0 is backend selector
+            form.is_option_field(i),
+            rows[row],
         );
     }
 
-    // Draw hint
-    let hint_idx = num_fields + 2; // backend + fields + spacing
-    if hint_idx < field_chunks.len() {
-        let hint_style = if form.is_valid() {
-            DefaultTheme::success()
-        } else {
-            DefaultTheme::dim_text()
-        };
-
-        if !form.is_valid() {
-            let hint_text = t!("fill_required_fields").to_string();
-            let hint = Paragraph::new(Line::from(vec![
-                Span::styled("  ", Style::default()),
-                Span::styled(hint_text, hint_style),
-            ]));
-            f.render_widget(hint, field_chunks[hint_idx]);
-        }
-    }
+    // Draw hint, with a marker when fields are scrolled out of view
+    let more = match (first > 0, first + visible < num_fields) {
+        (true, true) => "▲▼ ",
+        (true, false) => "▲ ",
+        (false, true) => "▼ ",
+        (false, false) => "",
+    };
+    let hint_text = if form.is_valid() { String::new() } else { t!("fill_required_fields").to_string() };
+    let hint = Paragraph::new(Line::from(vec![
+        Span::styled("  ", Style::default()),
+        Span::styled(more, ActiveTheme::info()),
+        Span::styled(hint_text, ActiveTheme::dim_text()),
+    ]));
+    f.render_widget(hint, field_chunks[2]);
 }
 
 /// Draw a single form field
-fn draw_form_field(f: &mut Frame, label: &str, value: &str, is_focused: bool, is_password: bool, area: Rect) {
+fn draw_form_field(f: &mut Frame, label: &str, value: &str, is_focused: bool, is_password: bool, is_option: bool, area: Rect) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([
@@ -218,9 +219,9 @@ fn draw_form_field(f: &mut Frame, label: &str, value: &str, is_focused: bool, is
         .split(area);
 
     let label_style = if is_focused {
-        Style::default().fg(DefaultTheme::GOLD).add_modifier(Modifier::BOLD)
+        Style::default().fg(ActiveTheme::colors().accent).add_modifier(Modifier::BOLD)
     } else {
-        DefaultTheme::normal_text()
+        ActiveTheme::normal_text()
     };
     let label_text = Paragraph::new(format!("{}:", label)).style(label_style);
     f.render_widget(label_text, chunks[0]);
@@ -233,19 +234,21 @@ fn draw_form_field(f: &mut Frame, label: &str, value: &str, is_focused: bool, is
 
     let input_style = if is_focused {
         Style::default()
-            .fg(DefaultTheme::TEXT)
-            .bg(DefaultTheme::BG_HIGHLIGHT)
+            .fg(ActiveTheme::colors().text)
+            .bg(ActiveTheme::colors().highlight)
     } else {
-        Style::default().fg(DefaultTheme::TEXT)
+        Style::default().fg(ActiveTheme::colors().text)
     };
 
     let border_style = if is_focused {
-        DefaultTheme::active_border()
+        ActiveTheme::active_border()
     } else {
-        DefaultTheme::inactive_border()
+        ActiveTheme::inactive_border()
     };
 
-    let display_with_cursor = if is_focused {
+    let display_with_cursor = if is_focused && is_option {
+        format!("{}  [Space]", display_value)
+    } else if is_focused {
         format!("{}▏", display_value)
     } else {
         display_value
@@ -1,17 +1,13 @@
 //! History panel widget
 
 use crate::app::App;
-use crate::ui::DefaultTheme;
+use crate::ui::ActiveTheme;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
 
 /// Draw the history panel
 pub fn draw_history_panel(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     let title = if active { " Histórico [<Cmd>h] ▪ " } else { " Histórico [<Cmd>h] " };
 
@@ -37,9 +33,9 @@ pub fn draw_history_panel(f: &mut Frame, app: &mut App, area: Rect, active: bool
             let row_info = entry.row_count.map(|r| format!(" ({} rows)", r)).unwrap_or_default();
 
             let style = if active && idx == app.history_selected {
-                DefaultTheme::selected()
+                ActiveTheme::selected()
             } else {
-                DefaultTheme::normal_text()
+                ActiveTheme::normal_text()
             };
 
             ListItem::new(format!("{} │ {}{}", time, query_preview, row_info)).style(style)
@@ -53,7 +49,7 @@ pub fn draw_history_panel(f: &mut Frame, app: &mut App, area: Rect, active: bool
                 .border_style(border_style)
                 .title(Span::styled(
                     format!("{} ({}) ", title, app.history.len()),
-                    DefaultTheme::title(),
+                    ActiveTheme::title(),
                 )),
         );
 
@@ -12,6 +12,6 @@ pub use completion_popup::draw_completion_popup;
 pub use connection_modal::draw_connection_modal;
 pub use helpers::{format_cell_value, format_number, get_type_indicator, hex_encode};
 pub use history_list::draw_history_panel;
-pub use query_editor::draw_query_editor;
+pub use query_editor::{draw_buffer_tabs, draw_query_editor};
 pub use results_table::draw_results_table;
 pub use schema_tree::draw_schema_explorer;
@@ -1,52 +1,68 @@
 //! Query editor widget with syntax highlighting
 
+use crate::app::editor::visual::Selection;
 use crate::app::{App, InputMode};
-use crate::ui::DefaultTheme;
+use crate::db::DatabaseBackend;
+use crate::sql::lexer::{is_keyword, tokenize_from, LexState, Token, TokenKind};
+use crate::sql::lint::Severity;
+use crate::ui::ActiveTheme;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Paragraph};
+use rust_i18n::t;
+use std::ops::Range;
 
 /// Line number gutter width (4 chars + 1 separator)
 const LINE_NUMBER_WIDTH: u16 = 5;
 
 /// Draw the query editor panel with line numbers and scrolling
 pub fn draw_query_editor(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     // Title with active and input mode indicator
+    // In Normal mode, show the keys of a pending command ("2d", "ci")
     let mode_indicator = match app.input_mode {
-        InputMode::Insert => "[INSERT]",
-        InputMode::Visual => "[VISUAL]",
-        InputMode::Normal => "",
-        InputMode::Command => "[COMMAND]",
+        InputMode::Insert => "[INSERT]".to_string(),
+        InputMode::Visual => app.visual_kind.label().to_string(),
+        InputMode::Normal if !app.normal_pending.is_empty() => format!("[{}]", app.normal_pending.keys()),
+        InputMode::Normal => String::new(),
+        InputMode::Command => "[COMMAND]".to_string(),
+    };
+    // File name and dirty marker ("report.sql [+]")
+    let file_label = match (app.buffer_name(), app.is_dirty()) {
+        (Some(name), true) => format!(" · {} [+]", name),
+        (Some(name), false) => format!(" · {}", name),
+        (None, true) => " [+]".to_string(),
+        (None, false) => String::new(),
     };
-    let title = format!(" Query [<Cmd>q] {} {} ", if active { "▪" } else { "" }, mode_indicator);
+    let recording = match app.recording {
+        Some((name, _)) => format!("{} ", t!("macro_recording", name = name)),
+        None => String::new(),
+    };
+    let title = format!(" Query{} [<Cmd>q] {} {} {}", file_label, if active { "▪" } else { "" }, mode_indicator, recording);
 
     // Create outer block
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(border_style)
-        .title(Span::styled(title, DefaultTheme::title()));
+        .title(Span::styled(title, ActiveTheme::title()));
 
     let inner_area = block.inner(area);
     f.render_widget(block, area);
 
-    // Split inner area: line numbers | code
-    if inner_area.width > LINE_NUMBER_WIDTH + 2 {
+    // Split inner area: line numbers | code (the gutter can be hidden with :set nonumber)
+    let gutter_width = if app.editor_options.line_numbers { LINE_NUMBER_WIDTH } else { 0 };
+    if inner_area.width > gutter_width + 2 {
         let line_num_area = Rect {
             x: inner_area.x,
             y: inner_area.y,
-            width: LINE_NUMBER_WIDTH,
+            width: gutter_width,
             height: inner_area.height,
         };
 
         let code_area = Rect {
-            x: inner_area.x + LINE_NUMBER_WIDTH,
+            x: inner_area.x + gutter_width,
             y: inner_area.y,
-            width: inner_area.width - LINE_NUMBER_WIDTH,
+            width: inner_area.width - gutter_width,
             height: inner_area.height,
         };
 
@@ -62,6 +78,16 @@ pub fn draw_query_editor(f: &mut Frame, app: &mut App, area: Rect, active: bool)
             app.query.split('\n').collect()
         };
 
+        // Most severe lint diagnostic of each line, marked in the gutter
+        let diagnostic_lines = app.diagnostic_lines();
+        let line_severity = |n: usize| {
+            diagnostic_lines
+                .iter()
+                .filter(|(line, _)| *line == n)
+                .map(|(_, d)| d.kind.severity())
+                .max()
+        };
+
         // Draw line numbers (with vertical scroll)
         let line_numbers: Vec<Line> = query_lines
             .iter()
@@ -69,31 +95,55 @@ pub fn draw_query_editor(f: &mut Frame, app: &mut App, area: Rect, active: bool)
             .skip(app.query_scroll_y)
             .take(visible_height)
             .map(|(n, _)| {
-                Line::from(Span::styled(
-                    format!("{:>3} │", n + 1),
-                    Style::default().fg(DefaultTheme::COMMENT),
-                ))
+                let number = Span::styled(format!("{:>3} ", n + 1), Style::default().fg(ActiveTheme::colors().comment));
+                let marker = match line_severity(n) {
+                    Some(severity) => Span::styled("●", ActiveTheme::diagnostic(severity)),
+                    None => Span::styled("│", Style::default().fg(ActiveTheme::colors().comment)),
+                };
+                Line::from(vec![number, marker])
             })
             .collect();
 
-        let line_num_widget = Paragraph::new(line_numbers);
-        f.render_widget(line_num_widget, line_num_area);
+        if gutter_width > 0 {
+            let line_num_widget = Paragraph::new(line_numbers);
+            f.render_widget(line_num_widget, line_num_area);
+        }
 
         // Get visual selection if in visual mode
         let visual_selection = if app.input_mode == InputMode::Visual {
-            Some(app.get_visual_selection())
+            Some(app.visual_selection())
+        } else {
+            None
+        };
+
+        // The bracket or BEGIN / CASE / END under the cursor and its partner;
+        // in Insert mode the one just typed counts too
+        let pair = if active && app.input_mode != InputMode::Visual {
+            let pairs = app.pairs();
+            pairs.at(app.cursor_pos).or_else(|| {
+                let before = app.cursor_pos.checked_sub(1).filter(|_| app.input_mode == InputMode::Insert)?;
+                pairs.at(before)
+            })
         } else {
             None
         };
 
         // Draw syntax-highlighted code with scrolling
+        let search_matches = app.search_highlights();
+        let diagnostics: Vec<(usize, usize, Severity)> =
+            app.diagnostics.iter().map(|d| (d.start, d.end, d.kind.severity())).collect();
         let highlighted_lines = highlight_sql_with_scroll(
             &app.query,
+            app.sql_dialect(),
             app.query_scroll_x,
             app.query_scroll_y,
             visible_width,
             visible_height,
             visual_selection,
+            &search_matches,
+            &diagnostics,
+            pair,
+            app.cursor_pos,
         );
         let code_widget = Paragraph::new(highlighted_lines);
         f.render_widget(code_widget, code_area);
@@ -109,6 +159,22 @@ pub fn draw_query_editor(f: &mut Frame, app: &mut App, area: Rect, active: bool)
     }
 }
 
+/// Draw the buffer tab bar (" 1 report.sql [+] ") above the editor
+pub fn draw_buffer_tabs(f: &mut Frame, app: &App, area: Rect) {
+    let mut spans: Vec<Span> = Vec::new();
+    for (i, tab) in app.buffer_tabs().into_iter().enumerate() {
+        let dirty = if tab.dirty { " [+]" } else { "" };
+        let style = if tab.active {
+            ActiveTheme::selected()
+        } else {
+            ActiveTheme::dim_text()
+        };
+        spans.push(Span::styled(format!(" {} {}{} ", i + 1, tab.label, dirty), style));
+        spans.push(Span::raw(" "));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
 /// Calculate cursor position with scroll offset
 fn calculate_cursor_position_with_scroll(app: &App, code_area: Rect) -> (u16, u16) {
     let (line, col) = app.get_cursor_line_col();
@@ -123,152 +189,99 @@ fn calculate_cursor_position_with_scroll(app: &App, code_area: Rect) -> (u16, u1
     (x, y)
 }
 
-/// SQL syntax highlighting with scroll support and visual selection
+/// SQL syntax highlighting with scroll support, visual selection, search
+/// matches and lint diagnostics. Lines are tokenized from the top down to
+/// the last one shown, each starting in the lexer state the previous one
+/// ended in, so comments and strings spanning lines highlight right.
+#[allow(clippy::too_many_arguments)]
 fn highlight_sql_with_scroll(
     sql: &str,
+    dialect: DatabaseBackend,
     scroll_x: usize,
     scroll_y: usize,
     visible_width: usize,
     visible_height: usize,
-    visual_selection: Option<(usize, usize)>, // (start, end) char positions
+    visual_selection: Option<Selection>,
+    search_matches: &[(usize, usize)],       // (start, end exclusive) char positions
+    diagnostics: &[(usize, usize, Severity)], // sorted by start, char positions
+    pair: Option<(Range<usize>, Range<usize>)>,
+    cursor_pos: usize,
 ) -> Vec<Line<'static>> {
-    let keywords = [
-        "SELECT", "FROM", "WHERE", "AND", "OR", "NOT", "IN", "LIKE", "BETWEEN",
-        "ORDER", "BY", "ASC", "DESC", "GROUP", "HAVING", "JOIN", "INNER", "LEFT",
-        "RIGHT", "OUTER", "FULL", "CROSS", "ON", "AS", "DISTINCT", "TOP", "WITH",
-        "INSERT", "INTO", "VALUES", "UPDATE", "SET", "DELETE", "CREATE", "TABLE",
-        "ALTER", "DROP", "INDEX", "VIEW", "PROCEDURE", "FUNCTION", "TRIGGER",
-        "BEGIN", "END", "IF", "ELSE", "WHILE", "RETURN", "DECLARE", "EXEC", "EXECUTE",
-        "NULL", "IS", "CASE", "WHEN", "THEN", "UNION", "ALL", "EXISTS", "COUNT",
-        "SUM", "AVG", "MIN", "MAX", "CAST", "CONVERT", "COALESCE", "ISNULL",
-    ];
-
     // Visual selection style (inverted colors)
     let visual_style = Style::default()
-        .fg(DefaultTheme::BG_DARK)
-        .bg(DefaultTheme::PRIMARY);
+        .fg(ActiveTheme::colors().background)
+        .bg(ActiveTheme::colors().primary);
 
-    let source_lines: Vec<&str> = sql.split('\n').collect();
     let mut lines: Vec<Line> = Vec::new();
+    let mut state = LexState::Code;
+    // Absolute character position at the start of the line
+    let mut line_start_pos = 0;
+
+    for (line_idx, line_content) in sql.split('\n').enumerate().take(scroll_y + visible_height) {
+        let (tokens, next_state) = tokenize_from(line_content, state, dialect);
+        let line_len = line_content.chars().count();
+        state = next_state;
+        if line_idx < scroll_y {
+            line_start_pos += line_len + 1;
+            continue;
+        }
 
-    // Calculate absolute character position at the start of each line
-    let mut line_starts: Vec<usize> = vec![0];
-    let mut pos = 0;
-    for line in &source_lines {
-        pos += line.chars().count() + 1; // +1 for newline, use chars count not bytes!
-        line_starts.push(pos);
-    }
-
-    for (line_idx, line_content) in source_lines.iter().enumerate().skip(scroll_y).take(visible_height) {
-        let line_start_pos = line_starts[line_idx];
-        
-        // Apply horizontal scroll
-        let display_content: String = line_content
-            .chars()
-            .skip(scroll_x)
-            .take(visible_width)
-            .collect();
+        // Style of each char of the line
+        let mut styles = vec![ActiveTheme::normal_text(); line_len];
+        for token in &tokens {
+            styles[token.start..token.end()].fill(token_style(token, dialect));
+        }
 
+        // Visible part (horizontal scroll), in runs of one style
         let mut spans: Vec<Span> = Vec::new();
-        let mut current_word = String::new();
-        let mut in_string = false;
-        let mut string_char = ' ';
-
-        let chars: Vec<char> = display_content.chars().collect();
-        let mut i = 0;
-
-        while i < chars.len() {
-            let c = chars[i];
-            // Calculate absolute position in the original string
-            let abs_pos = line_start_pos + scroll_x + i;
-            
-            // Check if this character is in visual selection
-            let in_visual = visual_selection.map_or(false, |(start, end)| {
-                abs_pos >= start && abs_pos <= end
-            });
-
-            // Check for line comment
-            if !in_string && i + 1 < chars.len() && chars[i] == '-' && chars[i + 1] == '-' {
-                if !current_word.is_empty() {
-                    spans.push(colorize_word(&current_word, &keywords));
-                    current_word.clear();
-                }
-                // Rest of line is comment - check if any part is in selection
-                let comment: String = chars[i..].iter().collect();
-                if in_visual {
-                    // Handle comment with visual selection
-                    for (j, ch) in comment.chars().enumerate() {
-                        let ch_abs_pos = line_start_pos + scroll_x + i + j;
-                        let ch_in_visual = visual_selection.map_or(false, |(start, end)| {
-                            ch_abs_pos >= start && ch_abs_pos <= end
-                        });
-                        if ch_in_visual {
-                            spans.push(Span::styled(ch.to_string(), visual_style));
-                        } else {
-                            spans.push(Span::styled(ch.to_string(), Style::default().fg(DefaultTheme::COMMENT)));
-                        }
-                    }
-                } else {
-                    spans.push(Span::styled(comment, Style::default().fg(DefaultTheme::COMMENT)));
-                }
-                break;
-            }
-
-            // If in visual selection, use visual style
-            if in_visual {
-                if !current_word.is_empty() {
-                    spans.push(colorize_word(&current_word, &keywords));
-                    current_word.clear();
-                }
-                spans.push(Span::styled(c.to_string(), visual_style));
-                i += 1;
-                continue;
-            }
-
-            // Handle strings
-            if (c == '\'' || c == '"') && !in_string {
-                if !current_word.is_empty() {
-                    spans.push(colorize_word(&current_word, &keywords));
-                    current_word.clear();
-                }
-                in_string = true;
-                string_char = c;
-                current_word.push(c);
-            } else if in_string && c == string_char {
-                current_word.push(c);
-                spans.push(Span::styled(
-                    current_word.clone(),
-                    Style::default().fg(DefaultTheme::STRING),
-                ));
-                current_word.clear();
-                in_string = false;
-            } else if in_string {
-                current_word.push(c);
-            } else if c.is_whitespace() || "(),;.=<>+-*/[]".contains(c) {
-                if !current_word.is_empty() {
-                    spans.push(colorize_word(&current_word, &keywords));
-                    current_word.clear();
-                }
-                spans.push(Span::styled(
-                    c.to_string(),
-                    Style::default().fg(DefaultTheme::OPERATOR),
-                ));
+        let mut run = String::new();
+        let mut run_style = None;
+        for (col, c) in line_content.chars().enumerate().skip(scroll_x).take(visible_width) {
+            let abs_pos = line_start_pos + col;
+            let style = if visual_selection.is_some_and(|s| s.contains(abs_pos, line_idx, col)) {
+                visual_style
             } else {
-                current_word.push(c);
+                styles[col]
+            };
+            if run_style.is_some_and(|s| s != style) {
+                spans.push(Span::styled(std::mem::take(&mut run), run_style.unwrap_or(style)));
             }
-
-            i += 1;
+            run_style = Some(style);
+            run.push(c);
         }
-
-        if !current_word.is_empty() {
-            if in_string {
-                spans.push(Span::styled(current_word, Style::default().fg(DefaultTheme::STRING)));
-            } else {
-                spans.push(colorize_word(&current_word, &keywords));
-            }
+        if let Some(style) = run_style {
+            spans.push(Span::styled(run, style));
         }
 
+        let spans = if search_matches.is_empty() {
+            spans
+        } else {
+            let selected = |pos: usize| visual_selection.is_some_and(|s| s.contains(pos, line_idx, pos - line_start_pos));
+            overlay_search_matches(spans, line_start_pos + scroll_x, search_matches, selected, cursor_pos)
+        };
+        let spans = match &pair {
+            Some((here, partner)) => {
+                let marked = |pos: usize| (here.contains(&pos) || partner.contains(&pos)).then(ActiveTheme::pair_match);
+                restyle_chars(spans, line_start_pos + scroll_x, marked)
+            }
+            None => spans,
+        };
+        let spans = if diagnostics.is_empty() {
+            spans
+        } else {
+            let underline = |pos: usize| {
+                diagnostics
+                    .iter()
+                    .take_while(|(start, _, _)| *start <= pos)
+                    .filter(|(_, end, _)| pos < *end)
+                    .map(|(_, _, severity)| *severity)
+                    .max()
+                    .map(ActiveTheme::diagnostic_span)
+            };
+            restyle_chars(spans, line_start_pos + scroll_x, underline)
+        };
         lines.push(Line::from(spans));
+        line_start_pos += line_len + 1;
     }
 
     // Pad with empty lines if needed
@@ -279,27 +292,110 @@ fn highlight_sql_with_scroll(
     lines
 }
 
-fn colorize_word(word: &str, keywords: &[&str]) -> Span<'static> {
-    let upper = word.to_uppercase();
-
-    if keywords.contains(&upper.as_str()) {
-        Span::styled(
-            word.to_string(),
-            Style::default()
-                .fg(DefaultTheme::KEYWORD)
-                .add_modifier(Modifier::BOLD),
-        )
-    } else if word.chars().all(|c| c.is_ascii_digit() || c == '.') {
-        Span::styled(
-            word.to_string(),
-            Style::default().fg(DefaultTheme::NUMBER),
-        )
-    } else if word.starts_with('@') || word.starts_with("@@") {
-        Span::styled(
-            word.to_string(),
-            Style::default().fg(DefaultTheme::FUNCTION),
-        )
-    } else {
-        Span::styled(word.to_string(), DefaultTheme::normal_text())
+/// Restyle the characters of a highlighted line that fall inside search
+/// matches (the visual selection keeps its own style). `first_pos` is the
+/// char position of the first character shown.
+fn overlay_search_matches(
+    spans: Vec<Span<'static>>,
+    first_pos: usize,
+    matches: &[(usize, usize)],
+    selected: impl Fn(usize) -> bool,
+    cursor_pos: usize,
+) -> Vec<Span<'static>> {
+    let match_style = |pos: usize| -> Option<Style> {
+        if selected(pos) {
+            return None;
+        }
+        // Matches are sorted and don't overlap
+        let index = matches.partition_point(|(start, _)| *start <= pos).checked_sub(1)?;
+        let (start, end) = matches[index];
+        if pos >= end {
+            return None;
+        }
+        Some(if (start..end).contains(&cursor_pos) {
+            ActiveTheme::search_current()
+        } else {
+            ActiveTheme::search_match()
+        })
+    };
+
+    restyle_chars(spans, first_pos, match_style)
+}
+
+/// Patch the style of each character of `spans` with `style_at` (given its
+/// char position, `first_pos` being that of the first character), merging
+/// equal neighbours back into spans
+fn restyle_chars(
+    spans: Vec<Span<'static>>,
+    first_pos: usize,
+    style_at: impl Fn(usize) -> Option<Style>,
+) -> Vec<Span<'static>> {
+    let mut out: Vec<Span<'static>> = Vec::new();
+    let mut pos = first_pos;
+    for span in spans {
+        let mut run = String::new();
+        let mut run_style = None;
+        for c in span.content.chars() {
+            let style = style_at(pos).map_or(span.style, |s| span.style.patch(s));
+            if run_style.is_some_and(|s| s != style) {
+                out.push(Span::styled(std::mem::take(&mut run), run_style.unwrap_or(span.style)));
+            }
+            run_style = Some(style);
+            run.push(c);
+            pos += 1;
+        }
+        if !run.is_empty() {
+            out.push(Span::styled(run, run_style.unwrap_or(span.style)));
+        }
+    }
+    out
+}
+
+/// Highlighting style of a token
+fn token_style(token: &Token, dialect: DatabaseBackend) -> Style {
+    let colors = ActiveTheme::colors();
+    match token.kind {
+        TokenKind::Word if is_keyword(token.text, dialect) => {
+            Style::default().fg(colors.keyword).add_modifier(Modifier::BOLD)
+        }
+        // Variables and named parameters
+        TokenKind::Word if token.text.starts_with(['@', ':', '$']) => Style::default().fg(colors.function),
+        TokenKind::Word | TokenKind::QuotedName => ActiveTheme::normal_text(),
+        TokenKind::String => Style::default().fg(colors.string),
+        TokenKind::Number => Style::default().fg(colors.number),
+        TokenKind::Parameter => Style::default().fg(colors.function),
+        TokenKind::LineComment | TokenKind::BlockComment => Style::default().fg(colors.comment),
+        TokenKind::Punct => Style::default().fg(colors.operator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Foreground colour of each visible char of each line
+    fn colors(sql: &str, dialect: DatabaseBackend) -> Vec<Vec<(char, Option<Color>)>> {
+        highlight_sql_with_scroll(sql, dialect, 0, 0, 80, 10, None, &[], &[], None, 0)
+            .into_iter()
+            .map(|line| line.spans.iter().flat_map(|s| s.content.chars().map(|c| (c, s.style.fg))).collect())
+            .filter(|line: &Vec<_>| !line.is_empty())
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_across_lines() {
+        let palette = ActiveTheme::colors();
+        let sql = "SELECT /* from\nwhere */ [select], N'it''s\n-- still' FROM t";
+        let lines = colors(sql, DatabaseBackend::SqlServer);
+        let color_of = |line: usize, c: char| lines[line].iter().find(|(ch, _)| *ch == c).and_then(|(_, fg)| *fg);
+        assert_eq!(color_of(0, 'S'), Some(palette.keyword));
+        assert_eq!(color_of(0, 'f'), Some(palette.comment));
+        // The comment goes on; the bracketed name is no keyword
+        assert_eq!(color_of(1, 'w'), Some(palette.comment));
+        assert_eq!(color_of(1, 's'), ActiveTheme::normal_text().fg);
+        // The string goes on past the doubled quote and the dashes
+        assert_eq!(color_of(1, 'N'), Some(palette.string));
+        assert_eq!(color_of(2, '-'), Some(palette.string));
+        assert_eq!(color_of(2, 'F'), Some(palette.keyword));
     }
 }
@@ -2,7 +2,8 @@
 
 use crate::app::{App, ResultsTab};
 use crate::db::CellValue;
-use crate::ui::DefaultTheme;
+use crate::sql::lint::Severity;
+use crate::ui::ActiveTheme;
 use crate::ui::widgets::helpers::{format_cell_value, format_number, get_type_indicator};
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Scrollbar, ScrollbarOrientation, ScrollbarState, Table};
@@ -12,11 +13,7 @@ use rust_i18n::t;
 
 /// Draw the results table panel with tabs
 pub fn draw_results_table(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     // Draw tabs header
     let tabs_area = Rect {
@@ -36,15 +33,21 @@ pub fn draw_results_table(f: &mut Frame, app: &mut App, area: Rect, active: bool
     // Draw tab bar
     draw_results_tabs(f, app, tabs_area, active);
 
+    // Lint diagnostics don't depend on a query having run
+    if app.results_tab == ResultsTab::Problems {
+        draw_results_problems(f, app, content_area, active);
+        return;
+    }
+
     if app.result.columns.is_empty() {
         let help_text = vec![
             Line::from(""),
-            Line::from(Span::styled(t!("no_results").to_string(), DefaultTheme::dim_text())),
+            Line::from(Span::styled(t!("no_results").to_string(), ActiveTheme::dim_text())),
             Line::from(""),
             Line::from(vec![
-                Span::styled(t!("type_query_hint").to_string(), DefaultTheme::dim_text()),
-                Span::styled("Enter", DefaultTheme::info()),
-                Span::styled(t!("to_execute").to_string(), DefaultTheme::dim_text()),
+                Span::styled(t!("type_query_hint").to_string(), ActiveTheme::dim_text()),
+                Span::styled("Enter", ActiveTheme::info()),
+                Span::styled(t!("to_execute").to_string(), ActiveTheme::dim_text()),
             ]),
         ];
         let empty_msg = Paragraph::new(help_text)
@@ -63,6 +66,7 @@ pub fn draw_results_table(f: &mut Frame, app: &mut App, area: Rect, active: bool
         ResultsTab::Data => draw_results_data(f, app, content_area, active),
         ResultsTab::Columns => draw_results_columns(f, app, content_area, active),
         ResultsTab::Stats => draw_results_stats(f, app, content_area, active),
+        ResultsTab::Problems => {}
     }
 }
 
@@ -72,19 +76,20 @@ fn draw_results_tabs(f: &mut Frame, app: &App, area: Rect, active: bool) {
         ("1:Dados", ResultsTab::Data),
         ("2:Colunas", ResultsTab::Columns),
         ("3:Estatísticas", ResultsTab::Stats),
+        ("4:Problemas", ResultsTab::Problems),
     ];
 
     let mut spans: Vec<Span> = vec![Span::raw(" ")];
     for (label, tab) in tabs {
         let style = if app.results_tab == tab {
             Style::default()
-                .fg(DefaultTheme::TEXT)
-                .bg(DefaultTheme::PRIMARY)
+                .fg(ActiveTheme::colors().text)
+                .bg(ActiveTheme::colors().primary)
                 .add_modifier(Modifier::BOLD)
         } else if active {
-            Style::default().fg(DefaultTheme::TEXT_DIM)
+            Style::default().fg(ActiveTheme::colors().text_dim)
         } else {
-            Style::default().fg(DefaultTheme::TEXT_MUTED)
+            Style::default().fg(ActiveTheme::colors().text_muted)
         };
         spans.push(Span::styled(format!(" {} ", label), style));
         spans.push(Span::raw(" "));
@@ -97,22 +102,18 @@ fn draw_results_tabs(f: &mut Frame, app: &App, area: Rect, active: bool) {
             app.result.row_count,
             app.result.columns.len()
         );
-        spans.push(Span::styled(info, DefaultTheme::dim_text()));
+        spans.push(Span::styled(info, ActiveTheme::dim_text()));
     }
 
     let tabs_line = Line::from(spans);
     let tabs_widget = Paragraph::new(tabs_line);
-        // .style(Style::default().bg(DefaultTheme::BG_PANEL));
+        // .style(Style::default().bg(ActiveTheme::colors().panel));
     f.render_widget(tabs_widget, area);
 }
 
 /// Draw the data tab (table rows)
 fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     // Build title with stats
     let exec_time_ms = app.result.execution_time.as_secs_f64() * 1000.0;
@@ -159,7 +160,7 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
 
     // Create header row with row number column and type indicators
     let mut header_cells: Vec<Cell> = vec![
-        Cell::from(" # ").style(DefaultTheme::table_header())
+        Cell::from(" # ").style(ActiveTheme::table_header())
     ];
     header_cells.extend(
         app.result
@@ -176,9 +177,9 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
                 let header_text = format!("{} {}", type_indicator, name);
 
                 let style = if active && i == app.results_col_selected {
-                    DefaultTheme::selected()
+                    ActiveTheme::selected()
                 } else {
-                    DefaultTheme::table_header()
+                    ActiveTheme::table_header()
                 };
                 Cell::from(header_text).style(style)
             })
@@ -206,9 +207,9 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
         .map(|(row_idx, row)| {
             // Row number cell
             let row_num_style = if active && row_idx == app.results_selected {
-                DefaultTheme::selected()
+                ActiveTheme::selected()
             } else {
-                DefaultTheme::row_number()
+                ActiveTheme::row_number()
             };
             let mut cells: Vec<Cell> = vec![
                 Cell::from(format!("{:>width$} ", row_idx + 1, width = row_num_width as usize - 1))
@@ -227,15 +228,15 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
                         let display_value: String = value.chars().take(col_width as usize - 2).collect();
 
                         let style = if active && row_idx == app.results_selected && col_idx == app.results_col_selected {
-                            DefaultTheme::selected()
+                            ActiveTheme::selected()
                         } else if active && row_idx == app.results_selected {
-                            DefaultTheme::highlighted()
+                            ActiveTheme::highlighted()
                         } else if is_null {
-                            DefaultTheme::null_value()
+                            ActiveTheme::null_value()
                         // } else if row_idx % 2 == 1 {
-                            // DefaultTheme::table_row_alt()
+                            // ActiveTheme::table_row_alt()
                         } else {
-                            DefaultTheme::normal_text()
+                            ActiveTheme::normal_text()
                         };
 
                         Cell::from(format!(" {} ", display_value)).style(style)
@@ -251,9 +252,9 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(border_style)
-                .title(Span::styled(title, DefaultTheme::title())),
+                .title(Span::styled(title, ActiveTheme::title())),
         )
-        .highlight_style(DefaultTheme::highlighted());
+        .highlight_style(ActiveTheme::highlighted());
 
     f.render_widget(table, area);
 
@@ -288,7 +289,7 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
         let pos_y = area.y + area.height.saturating_sub(1);
 
         if pos_x > area.x && pos_y < area.y + area.height {
-            let pos_span = Span::styled(pos_text, DefaultTheme::dim_text());
+            let pos_span = Span::styled(pos_text, ActiveTheme::dim_text());
             f.render_widget(
                 Paragraph::new(pos_span),
                 Rect::new(pos_x, pos_y, pos_len, 1),
@@ -299,11 +300,7 @@ fn draw_results_data(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
 
 /// Draw the columns tab (column info)
 fn draw_results_columns(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     let title = format!(" Colunas │ {} total ", app.result.columns.len());
 
@@ -327,18 +324,18 @@ fn draw_results_columns(f: &mut Frame, app: &mut App, area: Rect, active: bool)
         .map(|(idx, col)| {
             let type_indicator = get_type_indicator(&col.type_name);
             let row_style = if active && idx == app.results_selected {
-                DefaultTheme::selected()
+                ActiveTheme::selected()
             } else if idx % 2 == 1 {
-                DefaultTheme::table_row_alt()
+                ActiveTheme::table_row_alt()
             } else {
-                DefaultTheme::normal_text()
+                ActiveTheme::normal_text()
             };
 
             Row::new(vec![
-                Cell::from(format!(" {:>3} ", idx + 1)).style(DefaultTheme::row_number()),
+                Cell::from(format!(" {:>3} ", idx + 1)).style(ActiveTheme::row_number()),
                 Cell::from(format!(" {} ", type_indicator)),
                 Cell::from(format!(" {} ", col.name)).style(row_style),
-                Cell::from(format!(" {} ", col.type_name)).style(DefaultTheme::dim_text()),
+                Cell::from(format!(" {} ", col.type_name)).style(ActiveTheme::dim_text()),
             ])
         })
         .collect();
@@ -351,10 +348,10 @@ fn draw_results_columns(f: &mut Frame, app: &mut App, area: Rect, active: bool)
     ];
 
     let header = Row::new(vec![
-        Cell::from(" # ").style(DefaultTheme::table_header()),
-        Cell::from(" ").style(DefaultTheme::table_header()),
-        Cell::from(" Nome da Coluna ").style(DefaultTheme::table_header()),
-        Cell::from(" Tipo de Dados ").style(DefaultTheme::table_header()),
+        Cell::from(" # ").style(ActiveTheme::table_header()),
+        Cell::from(" ").style(ActiveTheme::table_header()),
+        Cell::from(" Nome da Coluna ").style(ActiveTheme::table_header()),
+        Cell::from(" Tipo de Dados ").style(ActiveTheme::table_header()),
     ])
     .height(1);
 
@@ -364,7 +361,7 @@ fn draw_results_columns(f: &mut Frame, app: &mut App, area: Rect, active: bool)
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(border_style)
-                .title(Span::styled(title, DefaultTheme::title())),
+                .title(Span::styled(title, ActiveTheme::title())),
         );
 
     f.render_widget(table, area);
@@ -387,13 +384,80 @@ fn draw_results_columns(f: &mut Frame, app: &mut App, area: Rect, active: bool)
     }
 }
 
+/// Draw the problems tab (lint diagnostics of the editor buffer)
+fn draw_results_problems(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
+
+    let title = format!(" Problemas │ {} total ", app.diagnostics.len());
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(border_style)
+        .title(Span::styled(title, ActiveTheme::title()));
+
+    if app.diagnostics.is_empty() {
+        let empty_msg = Paragraph::new(vec![
+            Line::from(""),
+            Line::from(Span::styled(t!("lint_no_problems").to_string(), ActiveTheme::dim_text())),
+        ])
+        .block(block)
+        .alignment(Alignment::Center);
+        f.render_widget(empty_msg, area);
+        return;
+    }
+
+    let visible_height = area.height.saturating_sub(3) as usize;
+    app.results_selected = app.results_selected.min(app.diagnostics.len() - 1);
+    if app.results_selected < app.results_scroll {
+        app.results_scroll = app.results_selected;
+    } else if app.results_selected >= app.results_scroll + visible_height {
+        app.results_scroll = app.results_selected.saturating_sub(visible_height.saturating_sub(1));
+    }
+
+    let lines = app.diagnostic_lines();
+    let rows: Vec<Row> = lines
+        .iter()
+        .enumerate()
+        .skip(app.results_scroll)
+        .take(visible_height)
+        .map(|(idx, (line, diagnostic))| {
+            let severity = diagnostic.kind.severity();
+            let label = match severity {
+                Severity::Warning => t!("lint_warning"),
+                Severity::Error => t!("lint_error"),
+            };
+            let row_style = if active && idx == app.results_selected {
+                ActiveTheme::selected()
+            } else {
+                ActiveTheme::normal_text()
+            };
+            Row::new(vec![
+                Cell::from(format!(" {:>4} ", line + 1)).style(ActiveTheme::row_number()),
+                Cell::from(format!(" ● {} ", label)).style(ActiveTheme::diagnostic(severity)),
+                Cell::from(format!(" {} ", diagnostic.kind.message())).style(row_style),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(7),   // Line
+        Constraint::Length(10),  // Severity
+        Constraint::Min(20),     // Message
+    ];
+
+    let header = Row::new(vec![
+        Cell::from(" Linha ").style(ActiveTheme::table_header()),
+        Cell::from(" ").style(ActiveTheme::table_header()),
+        Cell::from(" Mensagem ").style(ActiveTheme::table_header()),
+    ])
+    .height(1);
+
+    let table = Table::new(rows, widths).header(header).block(block);
+    f.render_widget(table, area);
+}
+
 /// Draw the stats tab (query statistics)
 fn draw_results_stats(f: &mut Frame, app: &App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     let exec_time = app.result.execution_time;
     let exec_ms = exec_time.as_secs_f64() * 1000.0;
@@ -439,30 +503,30 @@ fn draw_results_stats(f: &mut Frame, app: &App, area: Rect, active: bool) {
 
     let stats_lines: Vec<Line> = vec![
         Line::from(""),
-        Line::from(Span::styled(t!("stats_header").to_string(), DefaultTheme::info())),
+        Line::from(Span::styled(t!("stats_header").to_string(), ActiveTheme::info())),
         Line::from(""),
         Line::from(vec![
-            Span::styled(pad_label(&labels[0]), DefaultTheme::dim_text()),
-            Span::styled(format!("{:.2} ms", exec_ms), DefaultTheme::success()),
+            Span::styled(pad_label(&labels[0]), ActiveTheme::dim_text()),
+            Span::styled(format!("{:.2} ms", exec_ms), ActiveTheme::success()),
         ]),
         Line::from(vec![
-            Span::styled(pad_label(&labels[1]), DefaultTheme::dim_text()),
-            Span::styled(format_number(app.result.row_count as i64), DefaultTheme::info()),
+            Span::styled(pad_label(&labels[1]), ActiveTheme::dim_text()),
+            Span::styled(format_number(app.result.row_count as i64), ActiveTheme::info()),
         ]),
         Line::from(vec![
-            Span::styled(pad_label(&labels[2]), DefaultTheme::dim_text()),
-            Span::styled(format!("{}", app.result.columns.len()), DefaultTheme::info()),
+            Span::styled(pad_label(&labels[2]), ActiveTheme::dim_text()),
+            Span::styled(format!("{}", app.result.columns.len()), ActiveTheme::info()),
         ]),
         Line::from(vec![
-            Span::styled(pad_label(&labels[3]), DefaultTheme::dim_text()),
-            Span::styled(format_number(total_cells as i64), DefaultTheme::normal_text()),
+            Span::styled(pad_label(&labels[3]), ActiveTheme::dim_text()),
+            Span::styled(format_number(total_cells as i64), ActiveTheme::normal_text()),
         ]),
         Line::from(vec![
-            Span::styled(pad_label(&labels[4]), DefaultTheme::dim_text()),
-            Span::styled(format!("{} ({:.1}%)", format_number(null_count as i64), null_percentage), DefaultTheme::warning()),
+            Span::styled(pad_label(&labels[4]), ActiveTheme::dim_text()),
+            Span::styled(format!("{} ({:.1}%)", format_number(null_count as i64), null_percentage), ActiveTheme::warning()),
         ]),
         // Line::from(""),
-        // Line::from(Span::styled("═══ TIPOS DE DADOS ═══", DefaultTheme::info())),
+        // Line::from(Span::styled("═══ TIPOS DE DADOS ═══", ActiveTheme::info())),
         // Line::from(""),
     ];
 
@@ -473,30 +537,30 @@ fn draw_results_stats(f: &mut Frame, app: &App, area: Rect, active: bool) {
     // for (type_name, count) in type_vec.iter().take(10) {
     //     let indicator = get_type_indicator(type_name);
     //     stats_lines.push(Line::from(vec![
-    //         Span::styled(format!("  {} ", indicator), DefaultTheme::normal_text()),
-    //         Span::styled(format!("{:<20}", type_name), DefaultTheme::dim_text()),
-    //         Span::styled(format!("{:>5} coluna(s)", count), DefaultTheme::normal_text()),
+    //         Span::styled(format!("  {} ", indicator), ActiveTheme::normal_text()),
+    //         Span::styled(format!("{:<20}", type_name), ActiveTheme::dim_text()),
+    //         Span::styled(format!("{:>5} coluna(s)", count), ActiveTheme::normal_text()),
     //     ]));
     // }
 
     // stats_lines.push(Line::from(""));
-    // stats_lines.push(Line::from(Span::styled("═══ ATALHOS ═══", DefaultTheme::info())));
+    // stats_lines.push(Line::from(Span::styled("═══ ATALHOS ═══", ActiveTheme::info())));
     // stats_lines.push(Line::from(""));
     // stats_lines.push(Line::from(vec![
-    //     Span::styled("  Ctrl+E  ", DefaultTheme::info()),
-    //     Span::styled("Exportar para CSV", DefaultTheme::dim_text()),
+    //     Span::styled("  Ctrl+E  ", ActiveTheme::info()),
+    //     Span::styled("Exportar para CSV", ActiveTheme::dim_text()),
     // ]));
     // stats_lines.push(Line::from(vec![
-    //     Span::styled("  Ctrl+S  ", DefaultTheme::info()),
-    //     Span::styled("Exportar para JSON", DefaultTheme::dim_text()),
+    //     Span::styled("  Ctrl+S  ", ActiveTheme::info()),
+    //     Span::styled("Exportar para JSON", ActiveTheme::dim_text()),
     // ]));
     // stats_lines.push(Line::from(vec![
-    //     Span::styled("  Ctrl+I  ", DefaultTheme::info()),
-    //     Span::styled("Copiar linha como INSERT", DefaultTheme::dim_text()),
+    //     Span::styled("  Ctrl+I  ", ActiveTheme::info()),
+    //     Span::styled("Copiar linha como INSERT", ActiveTheme::dim_text()),
     // ]));
     // stats_lines.push(Line::from(vec![
-    //     Span::styled("  Ctrl+Y  ", DefaultTheme::info()),
-    //     Span::styled("Copiar valor da célula", DefaultTheme::dim_text()),
+    //     Span::styled("  Ctrl+Y  ", ActiveTheme::info()),
+    //     Span::styled("Copiar valor da célula", ActiveTheme::dim_text()),
     // ]));
 
     let stats_widget = Paragraph::new(stats_lines)
@@ -504,7 +568,7 @@ fn draw_results_stats(f: &mut Frame, app: &App, area: Rect, active: bool) {
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(border_style)
-                .title(Span::styled(format!(" {} ", t!("stats_title")), DefaultTheme::title())),
+                .title(Span::styled(format!(" {} ", t!("stats_title")), ActiveTheme::title())),
         );
 
     f.render_widget(stats_widget, area);
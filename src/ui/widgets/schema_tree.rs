@@ -1,18 +1,14 @@
 //! Schema explorer tree widget
 
 use crate::app::{App, SchemaNodeType};
-use crate::ui::DefaultTheme;
+use crate::ui::ActiveTheme;
 use ratatui::prelude::*;
 use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
 use rust_i18n::t;
 
 /// Draw the schema explorer panel
 pub fn draw_schema_explorer(f: &mut Frame, app: &mut App, area: Rect, active: bool) {
-    let border_style = if active {
-        DefaultTheme::active_border()
-    } else {
-        DefaultTheme::inactive_border()
-    };
+    let border_style = ActiveTheme::panel_border(active, app.connection_tag());
 
     // Título com indicador de busca ativa
     let title = if !app.schema_search_query.is_empty() {
@@ -37,15 +33,15 @@ pub fn draw_schema_explorer(f: &mut Frame, app: &mut App, area: Rect, active: bo
     // Renderiza o input de busca se ativo
     if let Some(search_area) = search_area {
         let search_input = Paragraph::new(Line::from(vec![
-            Span::styled(" / ", Style::default().fg(DefaultTheme::PRIMARY)),
-            Span::styled(&app.schema_search_query, DefaultTheme::normal_text()),
-            Span::styled("█", Style::default().fg(DefaultTheme::PRIMARY)), // cursor
+            Span::styled(" / ", Style::default().fg(ActiveTheme::colors().primary)),
+            Span::styled(&app.schema_search_query, ActiveTheme::normal_text()),
+            Span::styled("█", Style::default().fg(ActiveTheme::colors().primary)), // cursor
         ]))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(DefaultTheme::PRIMARY))
-                .title(Span::styled(t!("search_prompt").to_string(), DefaultTheme::info())),
+                .border_style(Style::default().fg(ActiveTheme::colors().primary))
+                .title(Span::styled(t!("search_prompt").to_string(), ActiveTheme::info())),
         );
         f.render_widget(search_input, search_area);
     }
@@ -65,15 +61,15 @@ pub fn draw_schema_explorer(f: &mut Frame, app: &mut App, area: Rect, active: bo
             };
 
             let style = if active && idx == app.schema_selected {
-                DefaultTheme::selected()
+                ActiveTheme::selected()
             } else {
                 match node.node_type {
-                    SchemaNodeType::Folder => DefaultTheme::info(),
-                    SchemaNodeType::Table => DefaultTheme::normal_text(),
-                    SchemaNodeType::View => DefaultTheme::dim_text(),
-                    SchemaNodeType::Procedure => DefaultTheme::warning(),
-                    SchemaNodeType::Function => DefaultTheme::warning(),
-                    _ => DefaultTheme::normal_text(),
+                    SchemaNodeType::Folder => ActiveTheme::info(),
+                    SchemaNodeType::Table => ActiveTheme::normal_text(),
+                    SchemaNodeType::View => ActiveTheme::dim_text(),
+                    SchemaNodeType::Procedure => ActiveTheme::warning(),
+                    SchemaNodeType::Function => ActiveTheme::warning(),
+                    _ => ActiveTheme::normal_text(),
                 }
             };
 
@@ -90,7 +86,7 @@ pub fn draw_schema_explorer(f: &mut Frame, app: &mut App, area: Rect, active: bo
             
             if active && idx == app.schema_selected {
                 // Se selecionado, usa o estilo de seleção para todo o texto
-                spans.push(Span::styled(node.name.clone(), DefaultTheme::selected()));
+                spans.push(Span::styled(node.name.clone(), ActiveTheme::selected()));
             } else {
                 spans.extend(name);
             }
@@ -111,9 +107,9 @@ pub fn draw_schema_explorer(f: &mut Frame, app: &mut App, area: Rect, active: bo
             Block::default()
                 .borders(Borders::ALL)
                 .border_style(border_style)
-                .title(Span::styled(block_title, DefaultTheme::title())),
+                .title(Span::styled(block_title, ActiveTheme::title())),
         )
-        .highlight_style(DefaultTheme::selected());
+        .highlight_style(ActiveTheme::selected());
 
     // Calcula a altura visível (área - bordas)
     let visible_height = list_area.height.saturating_sub(2) as usize;
@@ -143,17 +139,17 @@ fn highlight_search_match<'a>(text: &str, query: &str) -> Vec<Span<'a>> {
     if let Some(start) = text_lower.find(&query_lower) {
         let end = start + query.len();
         vec![
-            Span::styled(text[..start].to_string(), DefaultTheme::normal_text()),
+            Span::styled(text[..start].to_string(), ActiveTheme::normal_text()),
             Span::styled(
                 text[start..end].to_string(),
                 Style::default()
-                    .fg(DefaultTheme::TEXT)
-                    .bg(DefaultTheme::PRIMARY)
+                    .fg(ActiveTheme::colors().text)
+                    .bg(ActiveTheme::colors().primary)
                     .add_modifier(Modifier::BOLD),
             ),
-            Span::styled(text[end..].to_string(), DefaultTheme::normal_text()),
+            Span::styled(text[end..].to_string(), ActiveTheme::normal_text()),
         ]
     } else {
-        vec![Span::styled(text.to_string(), DefaultTheme::normal_text())]
+        vec![Span::styled(text.to_string(), ActiveTheme::normal_text())]
     }
 }